@@ -0,0 +1,334 @@
+use std::collections::hash_map::DefaultHasher;
+use std::env::var_os;
+use std::fs::metadata;
+use std::fs::read_dir;
+use std::fs::remove_file;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use dirs::home_dir;
+use serde_derive::Serialize;
+use serde_json::json;
+use serde_json::Value;
+
+use crate::utils::create_private_dir;
+use crate::utils::read_file;
+use crate::utils::write_private_file;
+use crate::utils::Result;
+
+const CACHE_PATH: &'static str = ".azi/cache";
+const STATS_FILE: &'static str = "stats.json";
+
+const CACHE_TTL: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheMode {
+    Enabled,
+    Disabled,
+    Refresh,
+    /// Entries of any age are used and nothing is requested, for `--offline`
+    Offline,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub path: String,
+    pub entries: u64,
+    pub size: u64,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// File based cache for the responses of GET requests
+pub struct Cache {
+    dir: PathBuf,
+    mode: CacheMode,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    /// Modification time of the oldest entry returned, in seconds since the epoch
+    oldest: AtomicU64,
+}
+
+impl Cache {
+    pub fn new(mode: CacheMode) -> Result<Cache> {
+        let dir = if let Some(ref path) = var_os("AZI_CACHE_DIR") {
+            PathBuf::from(path)
+        } else if let Some(ref home_dir) = home_dir() {
+            home_dir.join(CACHE_PATH)
+        } else {
+            return Err(Box::from("home directory not found!"));
+        };
+        Ok(Cache {
+            dir,
+            mode,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            oldest: AtomicU64::new(u64::MAX),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn mode(&self) -> CacheMode {
+        self.mode
+    }
+
+    /// When the oldest of the entries returned so far was cached
+    pub fn oldest_entry(&self) -> Option<SystemTime> {
+        match self.oldest.load(Ordering::Relaxed) {
+            u64::MAX => None,
+            secs => Some(UNIX_EPOCH + Duration::from_secs(secs)),
+        }
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.dir.join(format!("{:016x}.json", hasher.finish()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        if self.mode != CacheMode::Enabled && self.mode != CacheMode::Offline {
+            return None;
+        }
+
+        let path = self.entry_path(key);
+        let modified = metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        let fresh = match self.mode {
+            CacheMode::Offline => modified.is_some(),
+            _ => modified
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .map(|age| age < CACHE_TTL)
+                .unwrap_or(false),
+        };
+
+        if fresh {
+            if let Ok(value) = read_file(&path) {
+                if value["key"] == key {
+                    trace!("Cache hit: {}", key);
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    if let Some(secs) = modified
+                        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                        .map(|since_epoch| since_epoch.as_secs())
+                    {
+                        self.oldest.fetch_min(secs, Ordering::Relaxed);
+                    }
+                    return Some(value["value"].clone());
+                }
+            }
+        }
+
+        trace!("Cache miss: {}", key);
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        None
+    }
+
+    /// Returns the ETag and value of an entry, also when it has expired,
+    /// to revalidate it with a conditional request
+    pub fn get_etag(&self, key: &str) -> Option<(String, Value)> {
+        if self.mode == CacheMode::Disabled || self.mode == CacheMode::Offline {
+            return None;
+        }
+
+        let value = read_file(&self.entry_path(key)).ok()?;
+        if value["key"] != key {
+            return None;
+        }
+        let etag = value["etag"].as_str()?.to_owned();
+        Some((etag, value["value"].clone()))
+    }
+
+    pub fn put(&self, key: &str, value: &Value, etag: Option<&str>) -> Result<()> {
+        if self.mode == CacheMode::Disabled || self.mode == CacheMode::Offline {
+            return Ok(());
+        }
+
+        create_private_dir(&self.dir)?;
+        let entry = match etag {
+            Some(etag) => json!({ "key": key, "value": value, "etag": etag }),
+            None => json!({ "key": key, "value": value }),
+        };
+        write_private_file(&self.entry_path(key), &serde_json::to_vec(&entry)?)?;
+        Ok(())
+    }
+
+    /// Removes the entry, after a request that may have changed it
+    pub fn remove(&self, key: &str) -> Result<()> {
+        let path = self.entry_path(key);
+        if path.exists() {
+            trace!("Cache invalidated: {}", key);
+            remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<CacheStats> {
+        let mut entries = 0;
+        let mut size = 0;
+        if self.dir.exists() {
+            for entry in read_dir(&self.dir)? {
+                let entry = entry?;
+                if entry.file_name() != STATS_FILE {
+                    entries += 1;
+                    size += entry.metadata()?.len();
+                }
+            }
+        }
+
+        let stats = read_file(&self.dir.join(STATS_FILE))?;
+        Ok(CacheStats {
+            path: self.dir.display().to_string(),
+            entries,
+            size,
+            hits: stats["hits"].as_u64().unwrap_or(0),
+            misses: stats["misses"].as_u64().unwrap_or(0),
+        })
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        if self.dir.exists() {
+            for entry in read_dir(&self.dir)? {
+                remove_file(entry?.path())?;
+            }
+        }
+        debug!("Cleared cache: {}", self.dir.display());
+        Ok(())
+    }
+
+    fn save_stats(&self) -> Result<()> {
+        let path = self.dir.join(STATS_FILE);
+        let stats = read_file(&path)?;
+        let hits = stats["hits"].as_u64().unwrap_or(0) + self.hits.load(Ordering::Relaxed);
+        let misses = stats["misses"].as_u64().unwrap_or(0) + self.misses.load(Ordering::Relaxed);
+
+        create_private_dir(&self.dir)?;
+        let stats = json!({ "hits": hits, "misses": misses });
+        write_private_file(&path, &serde_json::to_vec(&stats)?)?;
+        Ok(())
+    }
+}
+
+impl Drop for Cache {
+    fn drop(&mut self) {
+        if *self.hits.get_mut() > 0 || *self.misses.get_mut() > 0 {
+            if let Err(err) = self.save_stats() {
+                debug!("Failed to save cache statistics: {}", err);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::File;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    use serde_json::json;
+
+    use super::Cache;
+    use super::CacheMode;
+
+    fn new_cache(name: &str, mode: CacheMode) -> Cache {
+        Cache {
+            dir: temp_dir().join(name),
+            mode,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            oldest: AtomicU64::new(u64::MAX),
+        }
+    }
+
+    #[test]
+    fn test_get_put() {
+        let cache = new_cache("azi-test-cache", CacheMode::Enabled);
+        cache.clear().unwrap();
+        assert_eq!(None, cache.get("a"));
+        cache.put("a", &json!([1, 2]), None).unwrap();
+        assert_eq!(Some(json!([1, 2])), cache.get("a"));
+        assert_eq!(1, cache.hits.load(Ordering::Relaxed));
+        assert_eq!(1, cache.misses.load(Ordering::Relaxed));
+
+        let refresh = new_cache("azi-test-cache", CacheMode::Refresh);
+        assert_eq!(None, refresh.get("a"));
+
+        cache.remove("a").unwrap();
+        assert_eq!(None, cache.get("a"));
+        cache.remove("a").unwrap();
+        cache.clear().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private() {
+        use std::fs::metadata;
+        use std::os::unix::fs::PermissionsExt;
+
+        let cache = new_cache("azi-test-cache-private", CacheMode::Enabled);
+        cache.put("a", &json!("a"), None).unwrap();
+        let mode = |path| metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(0o700, mode(cache.path()));
+        assert_eq!(0o600, mode(&cache.entry_path("a")));
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_get_etag() {
+        let cache = new_cache("azi-test-cache-etag", CacheMode::Enabled);
+        cache.clear().unwrap();
+        cache.put("a", &json!({ "name": "a" }), None).unwrap();
+        assert_eq!(None, cache.get_etag("a"));
+        cache
+            .put("b", &json!({ "name": "b" }), Some("\"1\""))
+            .unwrap();
+        assert_eq!(
+            Some(("\"1\"".to_owned(), json!({ "name": "b" }))),
+            cache.get_etag("b")
+        );
+
+        let refresh = new_cache("azi-test-cache-etag", CacheMode::Refresh);
+        assert!(refresh.get_etag("b").is_some());
+        let disabled = new_cache("azi-test-cache-etag", CacheMode::Disabled);
+        assert_eq!(None, disabled.get_etag("b"));
+        cache.clear().unwrap();
+    }
+
+    #[test]
+    fn test_offline() {
+        let cache = new_cache("azi-test-cache-offline", CacheMode::Enabled);
+        cache.clear().unwrap();
+        cache.put("a", &json!("a"), None).unwrap();
+        let day_ago = SystemTime::now() - Duration::from_secs(86400);
+        File::options()
+            .write(true)
+            .open(cache.entry_path("a"))
+            .unwrap()
+            .set_modified(day_ago)
+            .unwrap();
+        assert_eq!(None, cache.get("a"));
+
+        let offline = new_cache("azi-test-cache-offline", CacheMode::Offline);
+        assert_eq!(None, offline.oldest_entry());
+        assert_eq!(Some(json!("a")), offline.get("a"));
+        let oldest = offline.oldest_entry().unwrap();
+        assert!(day_ago.duration_since(oldest).unwrap() < Duration::from_secs(1));
+        offline.put("b", &json!("b"), None).unwrap();
+        assert_eq!(None, offline.get("b"));
+        cache.clear().unwrap();
+    }
+}
@@ -1,33 +1,188 @@
+use std::cell::Cell;
 use std::env::args_os;
+use std::env::var;
 use std::error::Error;
+use std::fs::read_to_string;
+use std::io;
 use std::io::stdin;
+use std::io::BufRead;
+use std::io::ErrorKind;
 use std::io::Read;
 use std::io::Write;
+use std::path::Path;
 use std::slice::Iter;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
 use env_logger;
+use env_logger::WriteStyle;
 use log::LevelFilter;
-
+use serde_json::to_value;
+use serde_json::Value;
+
+use crate::auth::AccessTokenFile;
+use crate::auth::TokenStore;
+use crate::cache::Cache;
+use crate::cache::CacheMode;
+use crate::cache::CacheStats;
 use crate::client::Client;
+use crate::client::Cloud;
+use crate::client::LoginMethod;
+use crate::commands::account;
+use crate::commands::audit_key_vault_access;
+use crate::commands::audit_ports;
+use crate::commands::audit_storage_keys;
+use crate::commands::audit_tags;
+use crate::commands::autoscale;
+use crate::commands::bastion;
+use crate::commands::changes;
+use crate::commands::cluster_kubeconfigs;
 use crate::commands::clusters;
+use crate::commands::containerapps;
+use crate::commands::content_type;
 use crate::commands::costs;
+use crate::commands::costs_tree;
 use crate::commands::dns;
 use crate::commands::domains;
+use crate::commands::effective_routes;
+use crate::commands::endpoints;
+use crate::commands::explain;
+use crate::commands::firewall;
 use crate::commands::get;
+use crate::commands::get_each;
+use crate::commands::identity;
+use crate::commands::images;
 use crate::commands::ip;
 use crate::commands::list;
+use crate::commands::login;
+use crate::commands::logout;
+use crate::commands::move_check;
+use crate::commands::page_list_results;
+use crate::commands::peers;
+use crate::commands::plans;
 use crate::commands::post;
+use crate::commands::providers;
+use crate::commands::rbac_minrole;
+use crate::commands::redis;
+use crate::commands::resources;
+use crate::commands::routing;
+use crate::commands::sort_cluster_results;
+use crate::commands::sort_cost_results;
+use crate::commands::sort_ip_results;
+use crate::commands::sort_list_results;
+use crate::commands::sp_usage;
+use crate::commands::sql;
+use crate::commands::stale;
+use crate::commands::storage;
+use crate::commands::subs;
+use crate::commands::tenants;
+use crate::commands::token;
+use crate::commands::use_subscription;
+use crate::commands::AccountResult;
+use crate::commands::AutoscaleResult;
+use crate::commands::BastionResult;
+use crate::commands::ChangesResult;
+use crate::commands::ClusterDetails;
+use crate::commands::ClusterResult;
+use crate::commands::ContainerAppsResult;
 use crate::commands::Context;
+use crate::commands::CostResult;
+use crate::commands::DnsResult;
+use crate::commands::Domain;
+use crate::commands::EffectiveRoutesResult;
+use crate::commands::EndpointResult;
+use crate::commands::ExplainResult;
+use crate::commands::FirewallResult;
+use crate::commands::IdentityResult;
+use crate::commands::ImagesResult;
+use crate::commands::IpResult;
+use crate::commands::KeyVaultAccessResult;
+use crate::commands::ListOptions;
+use crate::commands::ListResult;
+use crate::commands::LoginResult;
+use crate::commands::LogoutResult;
+use crate::commands::MoveCheckResult;
+use crate::commands::Page;
+use crate::commands::PeersResult;
+use crate::commands::PlansResult;
+use crate::commands::PortsResult;
+use crate::commands::PrincipalUsage;
+use crate::commands::ProvidersResult;
+use crate::commands::RedisResult;
+use crate::commands::ResolveResult;
+use crate::commands::Resolver;
+use crate::commands::ResourcesResult;
+use crate::commands::RoleDefinitionResult;
+use crate::commands::RoutingResult;
+use crate::commands::Sort;
+use crate::commands::SqlResult;
+use crate::commands::StaleResult;
+use crate::commands::StorageKeysResult;
+use crate::commands::StorageResult;
+use crate::commands::Stream;
+use crate::commands::SubscriptionInfo;
+use crate::commands::TagsResult;
+use crate::commands::TenantResult;
+use crate::commands::TokenResult;
+use crate::commands::UseResult;
+use crate::config;
+use crate::config::Config;
+use crate::config::ConfigEntry;
+use crate::config::ConfigKey;
+use crate::error::exit_code;
 use crate::error::AppError;
+use crate::error::AppError::AccessDenied;
 use crate::error::AppError::ParseError;
+use crate::error::AppError::RequestFailed;
+use crate::error::EXIT_FAILURE;
+use crate::error::EXIT_PARTIAL;
+use crate::error::EXIT_SUCCESS;
+use crate::error::EXIT_USAGE;
+use crate::http::is_request_id;
+use crate::http::AccessStats;
+use crate::http::Timeouts;
+use crate::http::TimingSummary;
+use crate::http::DEFAULT_MAX_RESPONSE_SIZE;
+use crate::locale::Locale;
+use crate::money::Precision;
+use crate::money::Rounding;
+use crate::money::MINOR_UNIT_DECIMALS;
+use crate::output::print_clusters_csv;
+use crate::output::print_costs_csv;
+use crate::output::print_ip_csv;
+use crate::output::print_list_csv;
+use crate::output::print_tags_csv;
+use crate::output::Envelope;
+use crate::output::JsonLinesOutput;
 use crate::output::JsonOutput;
 use crate::output::Output;
+use crate::output::QuietOutput;
+use crate::output::Scope;
+use crate::output::TableOutput;
 use crate::output::TextOutput;
+use crate::output::WarningLogger;
+use crate::output::CLUSTERS_FIELD_NAMES;
+use crate::output::COSTS_FIELD_NAMES;
+use crate::output::IP_FIELD_NAMES;
+use crate::output::LIST_FIELD_NAMES;
+#[cfg(unix)]
+use crate::pager::Pager;
+use crate::policy::TagPolicy;
+use crate::policy::DEFAULT_OWNER_TAG;
+use crate::query::Query;
+use crate::schema::output_schema;
 use crate::service::Filter;
 use crate::service::Service;
 use crate::service::Timeframe;
+use crate::service::Warning;
+use crate::tenant::read_default_subscription;
 use crate::utils::convert_str;
 use crate::utils::days_of_month;
+use crate::utils::write_private_file;
 use crate::utils::Result;
 
 type Flag = (&'static str, &'static str, bool);
@@ -37,34 +192,318 @@ type Command = (&'static str, &'static str, &'static [Flag]);
 const HELP: Flag = ("-h, --help", "Show this help message and exit", false);
 const VERSION: Flag = ("--version", "Show program's version number and exit", false);
 const DEBUG: Flag = ("--debug", "Show debugging output", false);
+const NO_COLOR: Flag = (
+    "--no-color",
+    "Do not colorize the output, like setting NO_COLOR",
+    false,
+);
 const TRACE: Flag = ("--trace", "Show even more debugging output", false);
+const QUIET: Flag = (
+    "-q, --quiet",
+    "Only print resource names, IP addresses or domains, one per line",
+    false,
+);
 const TENANT: Flag = (
     "-t, --tenant <tenant>",
-    "Set the Active Directory tenant to use",
+    "Set the Active Directory tenant to use, can be given multiple times",
+    true,
+);
+const ALL_TENANTS: Flag = (
+    "--all-tenants",
+    "Query all tenants accessible with the current login",
+    false,
+);
+const SUBSCRIPTION: Flag = (
+    "-s, --subscription <subscription>",
+    "Only use the given subscription ID or name, can be given multiple times",
     true,
 );
 const FILTER: Flag = (
     "-f, --filter <filter>",
-    "Filter subscriptions to display",
+    "Filter subscriptions to display, can be given multiple times, '!' excludes subscriptions, 're:' matches a regex",
+    true,
+);
+const TAG: Flag = (
+    "--tag <key=value>",
+    "Only list subscriptions, resource groups and resources with the given tag, can be given multiple times",
     true,
 );
 const OUTPUT: Flag = (
     "-o, --output <format>",
-    "Set output format, one of 'text' (default) or 'json'",
+    "Set output format, one of 'text' (default), 'json', 'jsonl' (one JSON object per line), 'table' or 'csv' (for list, ip, costs and clusters)",
+    true,
+);
+
+const NO_CACHE: Flag = ("--no-cache", "Do not use cached responses", false);
+const REFRESH: Flag = (
+    "--refresh",
+    "Ignore cached responses, but update the cache",
+    false,
+);
+
+const OFFLINE: Flag = (
+    "--offline",
+    "Only use cached responses of any age, without network access",
+    false,
+);
+
+const SHOW_LIMITS: Flag = (
+    "--show-limits",
+    "Show the lowest remaining request quotas per subscription on stderr",
+    false,
+);
+
+const REQUEST_ID: Flag = (
+    "--request-id <uuid>",
+    "Send this x-ms-client-request-id with all requests, instead of a new one per request",
+    true,
+);
+
+const TIMING: Flag = (
+    "--timing",
+    "Show the number, total time and slowest of the requests on stderr",
+    false,
+);
+
+const MAX_ITEMS: Flag = (
+    "--max-items <count>",
+    "Stop requesting further pages of a list after this many items",
+    true,
+);
+
+const MAX_RETRIES: Flag = (
+    "--max-retries <count>",
+    "Retry throttled or failed requests this many times, 3 by default",
+    true,
+);
+
+const MAX_RESPONSE_SIZE: Flag = (
+    "--max-response-size <megabytes>",
+    "Only keep the items of larger responses up to this size, 50 MB by default",
     true,
 );
 
-const GLOBAL_FLAGS: &[Flag] = &[HELP, VERSION, DEBUG, TRACE, TENANT, FILTER, OUTPUT];
+const NO_TRUNCATE: Flag = (
+    "--no-truncate",
+    "Keep all items of responses larger than the maximum response size",
+    false,
+);
+
+const PARALLEL: Flag = (
+    "-j, --parallel <count>",
+    "Query this many subscriptions or resources at a time, like the parallelism setting",
+    true,
+);
+
+const ORDERED: Flag = (
+    "--ordered",
+    "List subscriptions and other items queried in parallel in a stable order, the default",
+    false,
+);
+
+const UNORDERED: Flag = (
+    "--unordered",
+    "List subscriptions and other items queried in parallel as their requests complete",
+    false,
+);
+
+const CONNECT_TIMEOUT: Flag = (
+    "--connect-timeout <seconds>",
+    "Give up connecting to Azure after this many seconds",
+    true,
+);
+
+const READ_TIMEOUT: Flag = (
+    "--read-timeout <seconds>",
+    "Give up waiting for a response after this many seconds",
+    true,
+);
+
+const CA_FILE: Flag = (
+    "--ca-file <file>",
+    "PEM file with CA certificates to trust in addition to the built-in ones, like AZI_CA_BUNDLE",
+    true,
+);
+
+const NO_PAGER: Flag = (
+    "--no-pager",
+    "Do not pipe the text output through AZI_PAGER, PAGER or less on a terminal",
+    false,
+);
+
+const QUERY: Flag = (
+    "--query <expression>",
+    "JMESPath expression applied to the JSON output, like '[].subscription.displayName', implies -o json",
+    true,
+);
+
+const LOCALE: Flag = (
+    "--locale <locale>",
+    "Format numbers of the text output for a locale like 'de' or 'en-US', like AZI_LOCALE",
+    true,
+);
+
+const PRECISION: Flag = (
+    "--precision <decimals>",
+    "Print costs in the text output with this many decimals, 0 to 6 (default: 2)",
+    true,
+);
+
+const ROUND: Flag = (
+    "--round <mode>",
+    "Round costs in the text output 'half-up' (default) or 'half-even', banker's rounding",
+    true,
+);
+
+const ENVELOPE: Flag = (
+    "--envelope",
+    "Wrap JSON output in an object with the azi version, arguments, tenants, filters and warnings",
+    false,
+);
+
+const GLOBAL_FLAGS: &[Flag] = &[
+    HELP,
+    VERSION,
+    DEBUG,
+    TRACE,
+    QUIET,
+    NO_COLOR,
+    NO_PAGER,
+    TENANT,
+    ALL_TENANTS,
+    SUBSCRIPTION,
+    FILTER,
+    TAG,
+    OUTPUT,
+    QUERY,
+    LOCALE,
+    PRECISION,
+    ROUND,
+    ENVELOPE,
+    NO_CACHE,
+    REFRESH,
+    OFFLINE,
+    SHOW_LIMITS,
+    TIMING,
+    MAX_ITEMS,
+    MAX_RETRIES,
+    MAX_RESPONSE_SIZE,
+    NO_TRUNCATE,
+    REQUEST_ID,
+    PARALLEL,
+    ORDERED,
+    UNORDERED,
+    CONNECT_TIMEOUT,
+    READ_TIMEOUT,
+    CA_FILE,
+];
 
 const LIST: Command = (
     "list",
     "Show resource groups and resources",
-    &[HELP, LIST_ID, LIST_RESOURCES, LIST_FILTER],
+    &[
+        HELP,
+        LIST_ID,
+        LIST_RESOURCES,
+        LIST_FULL,
+        ANNOTATE_LOCKS,
+        ANNOTATE_ZONES,
+        ZONE_AUDIT,
+        LIST_LOCATION,
+        LIST_TYPE,
+        LIST_USE_GRAPH,
+        LIST_FIELDS,
+        SORT,
+        REVERSE,
+        LIMIT,
+        OFFSET,
+        LIST_FILTER,
+    ],
 );
 const LIST_ID: Flag = ("--id", "Also display resource IDs", false);
 const LIST_RESOURCES: Flag = ("-r, --resources", "Also list all resources", false);
+const LIST_FULL: Flag = (
+    "--full",
+    "Fetch the complete properties of each resource, together with --resources",
+    false,
+);
+const ANNOTATE_LOCKS: Flag = (
+    "--annotate-locks",
+    "Mark items protected from deletion by locks or deny assignments",
+    false,
+);
+const ANNOTATE_ZONES: Flag = (
+    "--annotate-zones",
+    "Show availability zones, whether the region supports zones and its paired region",
+    false,
+);
+const ZONE_AUDIT: Flag = (
+    "--zone-audit",
+    "Only list zone-capable resources deployed without zones in regions with zones, combine with --tag or --filter to select production",
+    false,
+);
+const LIST_LOCATION: Flag = (
+    "--location <region>",
+    "Only list resource groups or resources in the given region",
+    true,
+);
+const LIST_TYPE: Flag = (
+    "--type <type>",
+    "Only list resources of the given type, like Microsoft.Storage/storageAccounts",
+    true,
+);
+const LIST_USE_GRAPH: Flag = (
+    "--use-graph",
+    "Query all subscriptions at once with Azure Resource Graph, much faster for many subscriptions",
+    false,
+);
+const LIST_FIELDS: Flag = (
+    "--fields <fields>",
+    "Only print these comma-separated fields, of subscription, resourceGroup, name, type, location, zones and id",
+    true,
+);
+const SORT: Flag = (
+    "--sort <field>",
+    "Sort by this field within each subscription, 'subscription' sorts the subscriptions",
+    true,
+);
+const REVERSE: Flag = ("--reverse", "Sort in descending order", false);
+const LIMIT: Flag = (
+    "--limit <count>",
+    "Only show this many resources or resource groups, fewer subscriptions are queried",
+    true,
+);
+const OFFSET: Flag = (
+    "--offset <count>",
+    "Skip this many resources or resource groups, together with --limit",
+    true,
+);
 const LIST_FILTER: Flag = ("[<filter>]", "Filter resources by name", false);
 
+const RESOURCES: Command = (
+    "resources",
+    "Show resources of any type, with the complete resources for JSON output",
+    &[
+        HELP,
+        RESOURCES_ID,
+        RESOURCES_API_VERSION,
+        LIMIT,
+        OFFSET,
+        RESOURCES_TYPE,
+    ],
+);
+const RESOURCES_ID: Flag = ("--id", "Also display resource IDs", false);
+const RESOURCES_API_VERSION: Flag = (
+    "--api-version <version>",
+    "The API version to request the resources with, the latest by default",
+    true,
+);
+const RESOURCES_TYPE: Flag = (
+    "<type>",
+    "The resource type, like Microsoft.Web/staticSites",
+    false,
+);
+
 const CLUSTERS: Command = (
     "clusters",
     "Show Kubernetes clusters",
@@ -74,6 +513,13 @@ const CLUSTERS: Command = (
         CLUSTERS_AGENT_POOLS,
         CLUSTERS_RESOURCES,
         CLUSTERS_ALL_RESOURCES,
+        CLUSTERS_RESTARTS,
+        CLUSTERS_EXPORT_KUBECONFIGS,
+        CLUSTERS_COSTS,
+        CLUSTERS_PERIOD,
+        CLUSTERS_FIELDS,
+        SORT,
+        REVERSE,
         CLUSTERS_FILTER,
     ],
 );
@@ -85,12 +531,52 @@ const CLUSTERS_ALL_RESOURCES: Flag = (
     "All resources, including Kubernetes system resources",
     false,
 );
+const CLUSTERS_RESTARTS: Flag = (
+    "--restarts",
+    "Summarize container restarts per namespace, highlighting restart storms",
+    false,
+);
+const CLUSTERS_EXPORT_KUBECONFIGS: Flag = (
+    "--export-kubeconfigs <file>",
+    "Write the user kubeconfigs of the clusters to one file, with contexts named subscription/cluster",
+    true,
+);
+const CLUSTERS_COSTS: Flag = (
+    "--costs",
+    "Show the costs of the node resource group of each cluster",
+    false,
+);
+const CLUSTERS_PERIOD: Flag = (
+    "--period <period>",
+    "The billing period for --costs, like the period of the costs command",
+    true,
+);
+const CLUSTERS_FIELDS: Flag = (
+    "--fields <fields>",
+    "Only print these comma-separated fields, of subscription, name, version, distribution, nodes, costs and id",
+    true,
+);
 const CLUSTERS_FILTER: Flag = ("[<filter>]", "Filter clusters by name", false);
 
 const DOMAINS: Command = (
     "domains",
     "Show all domains and hosting resource groups",
-    &[HELP, DOMAIN],
+    &[HELP, ASCII, OWNERS, OWNER_TAG, LIMIT, OFFSET, DOMAIN],
+);
+const OWNERS: Flag = (
+    "--owners",
+    "Show the owner tag of the resource group each domain points to, via public IPs, App Service apps, Traffic Manager profiles and container apps",
+    false,
+);
+const OWNER_TAG: Flag = (
+    "--owner-tag <tag>",
+    "The tag naming the owner for --owners, 'owner' by default",
+    true,
+);
+const ASCII: Flag = (
+    "--ascii",
+    "Show internationalized domain names in punycode instead of Unicode",
+    false,
 );
 const DOMAIN: Flag = (
     "[<domain>]",
@@ -98,37 +584,537 @@ const DOMAIN: Flag = (
     false,
 );
 
-const DNS: Command = ("dns", "Show DNS records and mapped IP addresses", &[HELP]);
+const ENDPOINTS: Command = (
+    "endpoints",
+    "Show private endpoints and their connection state",
+    &[HELP, ENDPOINTS_ID, ENDPOINTS_FILTER],
+);
+const ENDPOINTS_ID: Flag = ("--id", "Also display resource IDs", false);
+const ENDPOINTS_FILTER: Flag = ("[<filter>]", "Filter private endpoints by name", false);
+
+const DNS: Command = (
+    "dns",
+    "Show DNS records and mapped IP addresses",
+    &[HELP, ASCII],
+);
+
+const RESOLVE: Command = (
+    "resolve",
+    "Resolve hostnames against the DNS zones and public IP addresses",
+    &[HELP, HOSTNAMES],
+);
+const HOSTNAMES: Flag = (
+    "<hostname>...",
+    "The hostnames to resolve, or - to read them from stdin, one per line",
+    false,
+);
+
+const IP: Command = (
+    "ip",
+    "Show currently used IP addresses",
+    &[HELP, ANNOTATE_LOCKS, IP_FIELDS, SORT, REVERSE],
+);
+const IP_FIELDS: Flag = (
+    "--fields <fields>",
+    "Only print these comma-separated fields, of subscription, resourceGroup, name, ipAddress and id",
+    true,
+);
 
-const IP: Command = ("ip", "Show currently used IP addresses", &[HELP]);
+const ROUTING: Command = (
+    "routing",
+    "Show route tables and effective routes",
+    &[HELP, ROUTING_ID, ROUTING_EFFECTIVE, ROUTING_FILTER],
+);
+const ROUTING_ID: Flag = ("--id", "Also display resource IDs", false);
+const ROUTING_EFFECTIVE: Flag = (
+    "-e, --effective <nic>",
+    "Show the effective routes of the given network interface, by name or ID",
+    true,
+);
+const ROUTING_FILTER: Flag = ("[<filter>]", "Filter route tables by name", false);
 
-const COSTS: Command = ("costs", "Show the current resource costs", &[HELP, PERIOD]);
+const BASTION: Command = (
+    "bastion",
+    "Show Bastion hosts and virtual machines with public IP addresses not covered by them",
+    &[HELP, BASTION_ID],
+);
+const BASTION_ID: Flag = ("--id", "Also display resource IDs", false);
+
+const AUTOSCALE: Command = (
+    "autoscale",
+    "Show autoscale settings and scale sets or App Service plans without autoscale",
+    &[HELP, AUTOSCALE_ID],
+);
+const AUTOSCALE_ID: Flag = ("--id", "Also display resource IDs", false);
+
+const PLANS: Command = (
+    "plans",
+    "Show App Service plans with their apps, highlighting underused Premium plans",
+    &[HELP, PLANS_ID],
+);
+const PLANS_ID: Flag = ("--id", "Also display resource IDs", false);
+
+const CONTAINERAPPS: Command = (
+    "containerapps",
+    "Show Container Apps environments and apps with revisions, replicas, ingress and scaling",
+    &[HELP, CONTAINERAPPS_ID],
+);
+const CONTAINERAPPS_ID: Flag = ("--id", "Also display resource IDs", false);
+
+const IMAGES: Command = (
+    "images",
+    "Show gallery image definitions and versions and managed images, highlighting expired versions",
+    &[HELP, IMAGES_ID],
+);
+const IMAGES_ID: Flag = ("--id", "Also display resource IDs", false);
+
+const STORAGE: Command = (
+    "storage",
+    "Show storage accounts, optionally with their used capacity and transactions",
+    &[HELP, STORAGE_ID, STORAGE_CAPACITY],
+);
+const STORAGE_ID: Flag = ("--id", "Also display resource IDs", false);
+const STORAGE_CAPACITY: Flag = (
+    "--capacity",
+    "Show GB used and transactions of the last day, from Azure Monitor",
+    false,
+);
+
+const SQL: Command = (
+    "sql",
+    "Show SQL databases, optionally with their size and utilization",
+    &[HELP, SQL_ID, SQL_USAGE],
+);
+const SQL_ID: Flag = ("--id", "Also display resource IDs", false);
+const SQL_USAGE: Flag = (
+    "--usage",
+    "Show size and CPU/DTU utilization of the last day, highlighting over-provisioned databases",
+    false,
+);
+
+const REDIS: Command = (
+    "redis",
+    "Show Azure Cache for Redis instances with SKU, version and TLS settings",
+    &[HELP, REDIS_ID, REDIS_RETIRING],
+);
+const REDIS_ID: Flag = ("--id", "Also display resource IDs", false);
+const REDIS_RETIRING: Flag = (
+    "--retiring",
+    "Only show instances on retired or soon retired versions, Redis 4 or TLS 1.0/1.1",
+    false,
+);
+
+const PEERS: Command = (
+    "peers",
+    "Show ExpressRoute circuits and VPN gateways with their connections and BGP peers",
+    &[HELP, PEERS_ID],
+);
+const PEERS_ID: Flag = ("--id", "Also display resource IDs", false);
+
+const FIREWALL: Command = (
+    "firewall",
+    "Show Azure Firewalls and their rules",
+    &[HELP, FIREWALL_SEARCH, FIREWALL_FILTER],
+);
+const FIREWALL_SEARCH: Flag = (
+    "-s, --search <destination>",
+    "Only show rules matching the given IP address or FQDN",
+    true,
+);
+const FIREWALL_FILTER: Flag = ("[<filter>]", "Filter firewalls by name", false);
+
+const IDENTITY: Command = (
+    "identity",
+    "Show managed identities, the resources using them and their role assignments",
+    &[HELP, IDENTITY_ID, IDENTITY_FILTER],
+);
+const IDENTITY_ID: Flag = ("--id", "Also display resource and principal IDs", false);
+const IDENTITY_FILTER: Flag = ("[<filter>]", "Filter identities by name", false);
+
+const SP_USAGE: Command = (
+    "sp-usage",
+    "Show which applications are behind the service principal IDs used in role assignments, clusters and Key Vaults",
+    &[HELP, SP_USAGE_ID],
+);
+const SP_USAGE_ID: Flag = (
+    "[<id>]",
+    "Only resolve the given object or application ID",
+    false,
+);
+
+const CHANGES: Command = (
+    "changes",
+    "Show resources created, modified and deleted recently, grouped by caller",
+    &[HELP, CHANGES_DAYS],
+);
+const CHANGES_DAYS: Flag = (
+    "-d, --days <days>",
+    "The number of days to show changes for, 7 by default",
+    true,
+);
+
+const STALE: Command = (
+    "stale",
+    "Show resources not modified recently, as candidates for archival or deletion",
+    &[HELP, STALE_ID, STALE_DAYS, LIMIT, OFFSET, STALE_FILTER],
+);
+const STALE_ID: Flag = ("--id", "Also display resource IDs", false);
+const STALE_DAYS: Flag = (
+    "-d, --days <days>",
+    "The number of days without modification, 90 by default",
+    true,
+);
+const STALE_FILTER: Flag = ("[<filter>]", "Filter resources by name", false);
+
+const MOVE_CHECK: Command = (
+    "move-check",
+    "Validate whether the resources of a resource group can be moved to another subscription",
+    &[
+        HELP,
+        MOVE_CHECK_ID,
+        MOVE_CHECK_TARGET_GROUP,
+        MOVE_CHECK_GROUP,
+        MOVE_CHECK_SUBSCRIPTION,
+    ],
+);
+const MOVE_CHECK_ID: Flag = ("--id", "Display resource IDs instead of names", false);
+const MOVE_CHECK_TARGET_GROUP: Flag = (
+    "-g, --target-group <name>",
+    "The target resource group, by default the one with the same name",
+    true,
+);
+const MOVE_CHECK_GROUP: Flag = ("<resource-group>", "The resource group to move", false);
+const MOVE_CHECK_SUBSCRIPTION: Flag = (
+    "<target-subscription>",
+    "The name or ID of the target subscription",
+    false,
+);
+
+const COSTS: Command = (
+    "costs",
+    "Show the current resource costs",
+    &[HELP, COSTS_TREE, COSTS_SORT, REVERSE, PERIOD],
+);
+const COSTS_TREE: Flag = (
+    "--tree",
+    "Show the subscription totals in the management group hierarchy, with subtotals",
+    false,
+);
+const COSTS_SORT: Flag = (
+    "--sort <field>",
+    "Sort by subscription, resourceGroup, date or costs, for example --sort costs --reverse",
+    true,
+);
 const PERIOD: Flag = (
     "[<period>]",
     "The billing period to show costs for, for example 2019 or 201905. By default, the costs for the current month are shown",
     false,
 );
 
-const GET: Command = ("get", "Execute HTTP GET request", &[HELP, REQUEST]);
-const POST: Command = ("post", "Execute HTTP POST request", &[HELP, BODY, REQUEST]);
+const AUDIT: Command = (
+    "audit",
+    "Audit resources for common security issues",
+    &[
+        HELP,
+        AUDIT_POLICY,
+        AUDIT_CSV,
+        AUDIT_MAX_KEY_AGE,
+        AUDIT_CHECK,
+    ],
+);
+const AUDIT_CHECK: Flag = (
+    "<check>",
+    "The check to run: 'ports' lists public IP addresses exposing ports to the internet, 'keyvault-access' lists principals allowed to read Key Vault secrets, 'storage-keys' lists the age of storage account keys, 'tags' lists resources not compliant with the tag policy",
+    false,
+);
+const AUDIT_POLICY: Flag = (
+    "--policy <file>",
+    "The tag policy file for 'tags', listing required tags and allowed values per resource type",
+    true,
+);
+const AUDIT_CSV: Flag = ("--csv", "Print the result of 'tags' as CSV", false);
+const AUDIT_MAX_KEY_AGE: Flag = (
+    "--max-key-age <days>",
+    "Flag storage account keys older than this for 'storage-keys', unless the account has a key policy. Default: 90",
+    true,
+);
+
+const ACCOUNT: Command = (
+    "account",
+    "Show the signed-in user, tenant, token expiry and default subscription",
+    &[HELP],
+);
+
+const SUBS: Command = (
+    "subs",
+    "Show the subscriptions with state, tenant, management groups, spending limit and tags",
+    &[HELP],
+);
+
+const PROVIDERS: Command = (
+    "providers",
+    "Show the registration state of the resource providers azi uses, flagging unregistered ones",
+    &[HELP, PROVIDERS_ALL],
+);
+const PROVIDERS_ALL: Flag = (
+    "-a, --all",
+    "Show all resource providers, also those not used by azi",
+    false,
+);
+
+const TENANTS: Command = (
+    "tenants",
+    "Show the tenants accessible with the current login, to use with --tenant",
+    &[HELP],
+);
+
+const USE: Command = (
+    "use",
+    "Set the default subscription of the Azure CLI, like 'az account set'",
+    &[HELP, USE_SUBSCRIPTION],
+);
+const USE_SUBSCRIPTION: Flag = ("<subscription>", "The subscription ID or name", false);
+
+const TOKEN: Command = (
+    "token",
+    "Print a valid access token, refreshing it if needed",
+    &[HELP, TOKEN_RESOURCE],
+);
+const TOKEN_RESOURCE: Flag = (
+    "-r, --resource <resource>",
+    "The resource to get the token for, by default the Resource Manager (AZI_ARM_RESOURCE)",
+    true,
+);
+
+const LOGIN: Command = (
+    "login",
+    "Log in interactively and store the tokens, for the given tenant or the default one",
+    &[HELP],
+);
+
+const LOGOUT: Command = (
+    "logout",
+    "Remove the stored tokens of the current tenant, or of all tenants if there is none",
+    &[HELP, LOGOUT_PLAINTEXT],
+);
+const LOGOUT_PLAINTEXT: Flag = (
+    "--plaintext",
+    "Only delete ~/.azure/accessTokens.json, also used by the Azure CLI, after its tokens were copied into the keyring or encrypted token store",
+    false,
+);
+
+const EXPLAIN: Command = (
+    "explain",
+    "Show the REST operations and RBAC actions a command uses, without running it",
+    &[HELP, EXPLAIN_COMMAND],
+);
+const EXPLAIN_COMMAND: Flag = ("<command>", "The command to explain", false);
+
+const RBAC_MINROLE: Command = (
+    "rbac-minrole",
+    "Create a custom role definition with only the actions needed to run the given commands",
+    &[
+        HELP,
+        RBAC_MINROLE_NAME,
+        RBAC_MINROLE_SCOPE,
+        RBAC_MINROLE_COMMANDS,
+    ],
+);
+const RBAC_MINROLE_NAME: Flag = (
+    "-n, --name <name>",
+    "The name of the role, by default derived from the commands",
+    true,
+);
+const RBAC_MINROLE_SCOPE: Flag = (
+    "-s, --scope <scope>",
+    "The assignable scope, by default the default subscription of the Azure CLI",
+    true,
+);
+const RBAC_MINROLE_COMMANDS: Flag = (
+    "<command>...",
+    "The commands the role should allow to run",
+    false,
+);
+
+const CONFIG: Command = (
+    "config",
+    "Show or change the default settings in ~/.config/azi/config.toml or ~/.azi/config.toml",
+    &[HELP, CONFIG_ACTION, CONFIG_KEY, CONFIG_VALUE],
+);
+const CONFIG_ACTION: Flag = ("<action>", "One of 'list', 'get' or 'set'", false);
+const CONFIG_KEY: Flag = (
+    "[<key>]",
+    "The setting: 'output', 'tenant', 'filter', 'parallelism', 'login', 'cloud', 'scope' or 'token-store'",
+    false,
+);
+const CONFIG_VALUE: Flag = ("[<value>]", "The new value, when using 'set'", false);
+
+const CACHE: Command = (
+    "cache",
+    "Show or clear the response cache",
+    &[HELP, CACHE_ACTION],
+);
+const CACHE_ACTION: Flag = (
+    "<action>",
+    "One of 'stats' to show cache size and hit rates, 'clear' to remove all cached responses, or 'path' to show the cache directory",
+    false,
+);
+
+const SCHEMA: Command = (
+    "schema",
+    "Show the JSON Schema of the JSON output of a command",
+    &[HELP, SCHEMA_COMMAND, SCHEMA_VARIANT],
+);
+const SCHEMA_COMMAND: Flag = (
+    "<command>",
+    "The command to show the output schema for",
+    false,
+);
+const SCHEMA_VARIANT: Flag = (
+    "[<variant>]",
+    "The check for 'audit', or 'effective' for the effective routes of 'routing'",
+    false,
+);
+
+const GET: Command = (
+    "get",
+    "Execute HTTP GET request",
+    &[
+        HELP,
+        INCLUDE_HEADERS,
+        GET_FILTER,
+        GET_SELECT,
+        GET_TOP,
+        GET_EXPAND,
+        GET_EACH,
+        REQUEST,
+        GET_TEMPLATE,
+    ],
+);
+const INCLUDE_HEADERS: Flag = (
+    "-i, --include-headers",
+    "Also show the response status and headers, in the envelope for JSON output",
+    false,
+);
+const GET_FILTER: Flag = (
+    "--filter <filter>",
+    "OData filter expression, added as $filter",
+    true,
+);
+const GET_SELECT: Flag = (
+    "--select <properties>",
+    "Comma-separated properties to return, added as $select",
+    true,
+);
+const GET_TOP: Flag = (
+    "--top <count>",
+    "Only return the first items of a list, added as $top",
+    true,
+);
+const GET_EXPAND: Flag = (
+    "--expand <properties>",
+    "Properties to expand, added as $expand",
+    true,
+);
+const GET_EACH: Flag = (
+    "--each <expression>",
+    "JMESPath expression selecting items from the result, like 'value[].id', to request the template for",
+    true,
+);
+const GET_TEMPLATE: Flag = (
+    "[<template>]",
+    "With --each, the request executed for each item, with {} replaced by the item",
+    false,
+);
+const POST: Command = (
+    "post",
+    "Execute HTTP POST request",
+    &[HELP, INCLUDE_HEADERS, BODY, DATA_FILE, REQUEST],
+);
 const BODY: Flag = (
     "-d, --data <data>",
     "The POST data, or - to read from stdin",
     true,
 );
+const DATA_FILE: Flag = (
+    "--data-file <path>",
+    "Read the POST data from a file, with the Content-Type from its extension",
+    true,
+);
 const REQUEST: Flag = ("<request>", "The request to execute", false);
 
-const COMMANDS: &[Command] = &[LIST, CLUSTERS, DOMAINS, DNS, IP, COSTS, GET, POST];
+/// Not listed in the help, the generated reference is meant for packagers
+const DOCS: Command = (
+    "docs",
+    "Print the reference of all options and commands as man page or Markdown",
+    &[HELP, DOCS_FORMAT],
+);
+const DOCS_FORMAT: Flag = (
+    "[<format>]",
+    "Either 'man', the default, or 'markdown'",
+    false,
+);
+
+const COMMANDS: &[Command] = &[
+    LIST,
+    RESOURCES,
+    CLUSTERS,
+    DOMAINS,
+    DNS,
+    RESOLVE,
+    IP,
+    ENDPOINTS,
+    ROUTING,
+    FIREWALL,
+    BASTION,
+    AUTOSCALE,
+    PLANS,
+    CONTAINERAPPS,
+    IMAGES,
+    STORAGE,
+    SQL,
+    REDIS,
+    PEERS,
+    IDENTITY,
+    SP_USAGE,
+    CHANGES,
+    STALE,
+    MOVE_CHECK,
+    COSTS,
+    AUDIT,
+    ACCOUNT,
+    SUBS,
+    PROVIDERS,
+    TENANTS,
+    USE,
+    TOKEN,
+    LOGIN,
+    LOGOUT,
+    EXPLAIN,
+    RBAC_MINROLE,
+    CONFIG,
+    CACHE,
+    SCHEMA,
+    GET,
+    POST,
+];
+
+const HIDDEN_COMMANDS: &[Command] = &[DOCS];
 
 const MAX_COLUMN: usize = 80;
 
+const DEFAULT_PARALLELISM: usize = 8;
+
 const PROGRAM_VERSION: &'static str = env!("CARGO_PKG_VERSION");
 
+const PROGRAM_DESCRIPTION: &'static str = "Show Azure information";
+
 macro_rules! parse_error {
     ($($arg:tt)*) => (Box::<dyn Error>::from(ParseError(format!($($arg)*))))
 }
 
-pub fn run() {
+/// Runs the command of the arguments and returns the exit code
+pub fn run() -> i32 {
     let str_args: Vec<String> = args_os().skip(1).map(convert_str).collect();
 
     let args = match Args::parse(str_args.iter().map(AsRef::as_ref).collect()) {
@@ -136,18 +1122,18 @@ pub fn run() {
         Err(err) => {
             eprintln!("error: {}", err);
             Printer::new().print_usage();
-            return;
+            return EXIT_USAGE;
         }
     };
 
     if args.has_global_flag(&HELP) {
         Printer::new().print_help();
-        return;
+        return EXIT_SUCCESS;
     }
 
     if args.has_global_flag(&VERSION) {
         Printer::new().print_version();
-        return;
+        return EXIT_SUCCESS;
     }
 
     let command = match args.command() {
@@ -155,13 +1141,25 @@ pub fn run() {
         Err(err) => {
             eprintln!("error: {}", err);
             Printer::new().print_usage();
-            return;
+            return EXIT_USAGE;
         }
     };
 
     if args.has_command_flag(&HELP) {
         Printer::new().print_command_help(&command);
-        return;
+        return EXIT_SUCCESS;
+    }
+
+    if command == DOCS {
+        match args.get_arg_opt(0).map(String::as_str) {
+            Some("man") | None => print!("{}", man_page()),
+            Some("markdown") => print!("{}", markdown_reference()),
+            Some(format) => {
+                eprintln!("error: unknown format: {}", format);
+                return EXIT_USAGE;
+            }
+        }
+        return EXIT_SUCCESS;
     }
 
     let mut logger = env_logger::Builder::new();
@@ -173,141 +1171,1198 @@ pub fn run() {
         logger.filter(Some("azi"), LevelFilter::Info);
         logger.format(|buf, record| writeln!(buf, "[{}] {}", record.level(), record.args()));
     };
-    logger.init();
+    // Without a terminal, colored already leaves out the colors
+    let no_color = var("NO_COLOR").is_ok_and(|value| !value.is_empty());
+    if args.has_global_flag(&NO_COLOR) || no_color {
+        colored::control::set_override(false);
+        logger.write_style(WriteStyle::Never);
+    }
+    let include_headers = args.has_command_flag(&INCLUDE_HEADERS);
+    let envelope = args.has_global_flag(&ENVELOPE) || include_headers;
+    if let Err(err) = WarningLogger::init(logger.build()) {
+        eprintln!("error: {}", err);
+        return EXIT_FAILURE;
+    }
 
-    let output: &dyn Output = match args.get_global_flag_arg(&OUTPUT) {
-        Some("json") => &JsonOutput {},
-        Some("text") | None => &TextOutput {},
+    let config = match Config::read() {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return exit_code(err.as_ref());
+        }
+    };
+    // --no-color and NO_COLOR take precedence over the configured colors
+    let no_color = no_color || args.has_global_flag(&NO_COLOR);
+    match config.get(&config::COLOR) {
+        Some("never") => colored::control::set_override(false),
+        Some("always") if !no_color => colored::control::set_override(true),
+        _ => {}
+    }
+    let no_color = no_color || config.get(&config::COLOR) == Some("never");
+
+    let query = match args.get_global_flag_arg(&QUERY).map(Query::parse) {
+        Some(Ok(query)) => Some(query),
+        Some(Err(err)) => {
+            eprintln!("error: {}", err);
+            return exit_code(err.as_ref());
+        }
+        None => None,
+    };
+    // The configured output format is ignored in quiet mode, unless it is JSON lines
+    // a query implies JSON output
+    let quiet = args.has_global_flag(&QUIET);
+    let output_format = match args.get_global_flag_arg(&OUTPUT) {
+        Some(format) => Some(format),
+        None if quiet => None,
+        None if query.is_some() => match config.get(&config::OUTPUT) {
+            Some("jsonl") => Some("jsonl"),
+            _ => Some("json"),
+        },
+        None => config.get(&config::OUTPUT),
+    };
+    let json_output = if envelope {
+        JsonOutput::with_envelope(Envelope {
+            version: PROGRAM_VERSION.to_owned(),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            arguments: str_args.clone(),
+            scope: None,
+            response: None,
+            warnings: vec![],
+        })
+    } else {
+        JsonOutput::new()
+    }
+    .with_query(query.clone());
+    let json_lines_output = JsonLinesOutput::new(query.clone());
+    let locale_name = var("AZI_LOCALE").ok();
+    let locale = match args.get_global_flag_arg(&LOCALE).or(locale_name.as_deref()) {
+        Some(name) => match Locale::parse(name) {
+            Ok(locale) => locale,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                return exit_code(err.as_ref());
+            }
+        },
+        None => Locale::default(),
+    };
+    let precision = match parse_precision(&args) {
+        Ok(precision) => precision,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            return exit_code(err.as_ref());
+        }
+    };
+    let text_output = TextOutput::new(locale, precision);
+    let table_output = TableOutput::new(locale, precision);
+    let quiet_output = QuietOutput::new(locale, precision);
+    let fields = args.has_command_flag(&LIST_FIELDS)
+        || args.has_command_flag(&CLUSTERS_FIELDS)
+        || args.has_command_flag(&IP_FIELDS);
+    let output: &dyn Output = match output_format {
+        Some(format) if quiet && format != "text" => {
+            eprintln!("error: --quiet requires text output");
+            return EXIT_USAGE;
+        }
+        _ if query.is_some() && !matches!(output_format, Some("json") | Some("jsonl")) => {
+            eprintln!("error: --query requires JSON output");
+            return EXIT_USAGE;
+        }
+        Some("json") | Some("jsonl") if fields => {
+            eprintln!("error: --fields requires text, table or CSV output");
+            return EXIT_USAGE;
+        }
+        Some("json") => &json_output,
+        Some("jsonl") if args.has_global_flag(&ENVELOPE) => {
+            eprintln!("error: --envelope requires JSON output");
+            return EXIT_USAGE;
+        }
+        Some("jsonl") => &json_lines_output,
+        Some("text") | None if args.has_global_flag(&ENVELOPE) => {
+            eprintln!("error: --envelope requires JSON output");
+            return EXIT_USAGE;
+        }
+        Some("text") | None if quiet => &quiet_output,
+        Some("text") | None => &text_output,
+        Some("table") if args.has_global_flag(&ENVELOPE) => {
+            eprintln!("error: --envelope requires JSON output");
+            return EXIT_USAGE;
+        }
+        Some("table") => &table_output,
+        Some("csv") if !matches!(command, COSTS | LIST | IP | CLUSTERS) => {
+            eprintln!("error: CSV output is only supported by costs, list, ip and clusters");
+            return EXIT_USAGE;
+        }
+        Some("csv") if args.has_global_flag(&ENVELOPE) => {
+            eprintln!("error: --envelope requires JSON output");
+            return EXIT_USAGE;
+        }
+        // Only used for errors, the results are printed by print_costs_csv etc.
+        Some("csv") => &text_output,
         Some(arg) => {
             eprintln!("error: unknown output format: {}", arg);
             Printer::new().print_usage();
-            return;
+            return EXIT_USAGE;
+        }
+    };
+    let csv_output = output_format == Some("csv");
+    // JSON Lines are printed as the results of each subscription complete, unless all results
+    // are needed first to sort or page them or to export their kubeconfigs
+    let collect_all = [
+        &SORT,
+        &COSTS_SORT,
+        &OFFSET,
+        &LIMIT,
+        &CLUSTERS_EXPORT_KUBECONFIGS,
+    ]
+    .iter()
+    .any(|flag| args.has_command_flag(flag));
+    let stream: Option<&dyn Stream> = match output_format {
+        Some("jsonl") if !collect_all => Some(&json_lines_output),
+        _ => None,
+    };
+    // The complete resources are only requested for JSON output
+    let raw_resources = matches!(output_format, Some("json") | Some("jsonl"));
+
+    // The login prompts must not wait behind the pager
+    #[cfg(unix)]
+    let _pager = match output_format {
+        Some("text") | Some("table") | None
+            if !args.has_global_flag(&NO_PAGER) && !matches!(command, LOGIN | LOGOUT) =>
+        {
+            let pager = Pager::start();
+            if pager.is_some() && !no_color {
+                colored::control::set_override(true);
+            }
+            pager
         }
+        _ => None,
     };
 
+    // Set when requests failed or were denied, but the command still succeeded
+    let partial = Cell::new(false);
     let run_command = || -> Result<()> {
-        let client = Client::new(args.get_global_flag_arg(&TENANT))?;
-        let service = Service::new(client, Filter::new(args.get_global_flag_arg(&FILTER)));
+        if command == CONFIG {
+            return run_config(&args, config, output);
+        }
+
+        if command == SCHEMA {
+            return run_schema(&args, output);
+        }
+
+        if command == EXPLAIN {
+            let name = args.get_arg(0, &EXPLAIN_COMMAND)?;
+            if !COMMANDS.iter().any(|command| command.0 == name) {
+                return Err(parse_error!("unknown command: {}", name));
+            }
+            return output.print_explain_result(&explain(name));
+        }
+
+        if command == RBAC_MINROLE {
+            args.get_arg(0, &RBAC_MINROLE_COMMANDS)?;
+            for name in &args.command_args {
+                if !COMMANDS.iter().any(|command| &command.0 == name) {
+                    return Err(parse_error!("unknown command: {}", name));
+                }
+            }
+            let result = rbac_minrole(
+                &args.command_args,
+                args.get_command_flag_arg(&RBAC_MINROLE_NAME),
+                args.get_command_flag_arg(&RBAC_MINROLE_SCOPE),
+            )?;
+            return output.print_role_definition(&result);
+        }
+
+        if command == USE {
+            let result = use_subscription(args.get_arg(0, &USE_SUBSCRIPTION)?)?;
+            return output.print_use_result(&result);
+        }
+
+        let offline = args.has_global_flag(&OFFLINE);
+        if offline && (args.has_global_flag(&NO_CACHE) || args.has_global_flag(&REFRESH)) {
+            return Err(parse_error!("--offline requires the cache"));
+        }
+        let cache_mode = if offline {
+            CacheMode::Offline
+        } else if args.has_global_flag(&NO_CACHE) {
+            CacheMode::Disabled
+        } else if args.has_global_flag(&REFRESH)
+            || args.has_global_flag(&NO_TRUNCATE)
+            || include_headers
+        {
+            CacheMode::Refresh
+        } else {
+            CacheMode::Enabled
+        };
+        let cache = Cache::new(cache_mode)?;
 
-        let context = Context { service: &service };
+        if command == CACHE {
+            return run_cache(&args, &cache, output);
+        }
 
-        match command {
-            LIST => {
-                let id = args.has_command_flag(&LIST_ID);
-                let list_resources = args.has_command_flag(&LIST_RESOURCES);
-                let result = list(&context, list_resources, args.get_arg_opt(0))?;
-                output.print_list_results(&result, id)?;
+        let all_tenants = args.has_global_flag(&ALL_TENANTS);
+        let mut tenant_names = args.get_global_flag_args(&TENANT);
+        if all_tenants && !tenant_names.is_empty() {
+            return Err(parse_error!("--all-tenants cannot be used with --tenant"));
+        } else if tenant_names.is_empty() && !all_tenants {
+            tenant_names.extend(config.get(&config::TENANT));
+        }
+        let mut filters = args.get_global_flag_args(&FILTER);
+        if filters.is_empty() {
+            filters.extend(config.get(&config::FILTER));
+        }
+        let mut subscriptions = args.get_global_flag_args(&SUBSCRIPTION);
+        let tags = args.get_global_flag_args(&TAG);
+        let max_items = match args.get_global_flag_arg(&MAX_ITEMS) {
+            Some(max_items) => Some(
+                max_items
+                    .parse()
+                    .or(Err(parse_error!("invalid number of items: {}", max_items)))?,
+            ),
+            None => None,
+        };
+        let max_retries = match args
+            .get_global_flag_arg(&MAX_RETRIES)
+            .or(config.get(&config::MAX_RETRIES))
+        {
+            Some(max_retries) => Some(max_retries.parse().or(Err(parse_error!(
+                "invalid number of retries: {}",
+                max_retries
+            )))?),
+            None => None,
+        };
+        let max_response_size = match args
+            .get_global_flag_arg(&MAX_RESPONSE_SIZE)
+            .or(config.get(&config::MAX_RESPONSE_SIZE))
+        {
+            _ if args.has_global_flag(&NO_TRUNCATE) => None,
+            Some(megabytes) => match megabytes.parse::<u64>() {
+                Ok(megabytes) if megabytes > 0 => Some(megabytes * 1024 * 1024),
+                _ => return Err(parse_error!("invalid response size: {}", megabytes)),
+            },
+            None => Some(DEFAULT_MAX_RESPONSE_SIZE),
+        };
+        let request_id = match args.get_global_flag_arg(&REQUEST_ID) {
+            Some(request_id) if !is_request_id(request_id) => {
+                return Err(parse_error!("invalid request ID: {}", request_id));
+            }
+            request_id => request_id,
+        };
+        let parse_timeout = |flag: &Flag, key: &ConfigKey| -> Result<Option<Duration>> {
+            match args.get_global_flag_arg(flag).or(config.get(key)) {
+                Some(seconds) => match seconds.parse() {
+                    Ok(seconds) if seconds > 0 => Ok(Some(Duration::from_secs(seconds))),
+                    _ => Err(parse_error!("invalid timeout: {}", seconds)),
+                },
+                None => Ok(None),
+            }
+        };
+        let timeouts = Timeouts {
+            connect: parse_timeout(&CONNECT_TIMEOUT, &config::CONNECT_TIMEOUT)?,
+            read: parse_timeout(&READ_TIMEOUT, &config::READ_TIMEOUT)?,
+        };
+        let ca_file = args.get_global_flag_arg(&CA_FILE);
+        let default_subscription = match config.get(&config::SCOPE) {
+            Some("az") if filters.is_empty() && tenant_names.is_empty() && !all_tenants => {
+                read_default_subscription()?
+                    .and_then(|subscription| subscription["id"].as_str().map(str::to_owned))
+            }
+            _ => None,
+        };
+        if subscriptions.is_empty() {
+            subscriptions.extend(default_subscription.as_deref());
+        }
+        let cloud = match config.get(&config::CLOUD) {
+            Some(name) => {
+                Cloud::from_name(name).ok_or_else(|| parse_error!("invalid cloud: {}", name))?
             }
-            CLUSTERS => {
-                let id = args.has_command_flag(&CLUSTERS_ID);
-                let pools = args.has_command_flag(&CLUSTERS_AGENT_POOLS);
-                let resources = args.has_command_flag(&CLUSTERS_RESOURCES);
-                let all_resources = args.has_command_flag(&CLUSTERS_ALL_RESOURCES);
-                let result = clusters(
-                    &context,
-                    pools,
-                    resources || all_resources,
-                    all_resources,
-                    args.get_arg_opt(0),
-                )?;
-                output.print_clusters(&result, id)?;
+            None => Cloud::AZURE,
+        };
+        let login_method = match config.get(&config::LOGIN) {
+            Some("browser") => LoginMethod::Browser,
+            _ => LoginMethod::DeviceCode,
+        };
+        let token_store = var("AZI_TOKEN_STORE").ok();
+        let token_store = match token_store.as_deref().or(config.get(&config::TOKEN_STORE)) {
+            Some(name) => TokenStore::from_name(name)
+                .ok_or_else(|| parse_error!("invalid token store: {}", name))?,
+            None => TokenStore::File,
+        };
+        if command == LOGOUT && args.has_command_flag(&LOGOUT_PLAINTEXT) {
+            if token_store == TokenStore::File {
+                return Err(parse_error!(
+                    "--plaintext requires the keyring or encrypted token store"
+                ));
             }
-            DOMAINS => {
-                let result = domains(&context, args.get_arg_opt(0))?;
-                output.print_domains(&result)?;
+            return AccessTokenFile::new(token_store)?.remove_plaintext();
+        }
+        let new_service = |tenant: Option<&str>, cache: Cache| -> Result<Service> {
+            let mut client = Client::new(
+                tenant,
+                cache,
+                cloud,
+                login_method,
+                token_store,
+                ca_file,
+                timeouts,
+            )?
+            .with_max_items(max_items)
+            .with_max_response_size(max_response_size);
+            if let Some(max_retries) = max_retries {
+                client = client.with_max_retries(max_retries);
             }
-            DNS => {
-                let result = dns(&context)?;
-                output.print_dns_results(&result)?;
+            if let Some(request_id) = request_id {
+                client = client.with_request_id(request_id.to_owned());
             }
-            IP => {
-                let result = ip(&context)?;
-                output.print_ip_results(&result)?;
+            let filter = Filter::new(&filters)?
+                .with_subscriptions(&subscriptions)
+                .with_tags(&tags);
+            return Ok(Service::new(client, filter));
+        };
+
+        let services = if all_tenants || tenant_names.len() > 1 {
+            let tenants: Vec<(String, String)> = if all_tenants {
+                new_service(None, cache)?
+                    .get_tenants()?
+                    .into_iter()
+                    .map(|tenant| {
+                        let label = tenant.default_domain.or(tenant.display_name);
+                        let id = tenant.tenant_id;
+                        (id.clone(), label.unwrap_or(id))
+                    })
+                    .collect()
+            } else {
+                tenant_names
+                    .iter()
+                    .map(|&tenant| (tenant.to_owned(), tenant.to_owned()))
+                    .collect()
+            };
+            let mut services = vec![];
+            for (tenant, label) in tenants {
+                let service = new_service(Some(&tenant), Cache::new(cache_mode)?)?;
+                services.push(service.with_tenant_label(label));
             }
-            COSTS => {
-                fn parse_period(period: &str) -> Result<Timeframe> {
-                    if period.len() == 4 {
-                        let year: u32 = period.parse()?;
-                        return Ok(Timeframe::Custom {
-                            from: format!("{:04}-01-01", year),
-                            to: format!("{:04}-12-31", year),
-                        });
-                    } else if period.len() == 6 {
-                        let year: u32 = period[0..4].parse()?;
-                        let month: u32 = period[4..6].parse()?;
-                        let days = days_of_month(year, month)?;
-                        return Ok(Timeframe::Custom {
-                            from: format!("{:04}-{:02}-01", year, month),
-                            to: format!("{:04}-{:02}-{:02}", year, month, days),
-                        });
-                    } else if period.len() == 8 {
-                        let year: u32 = period[0..4].parse()?;
-                        let month: u32 = period[4..6].parse()?;
-                        let day: u32 = period[6..8].parse()?;
-                        return Ok(Timeframe::Custom {
-                            from: format!("{:04}-{:02}-{:02}", year, month, day),
-                            to: format!("{:04}-{:02}-{:02}", year, month, day),
-                        });
-                    } else if period.len() == 13 && &period[6..7] == "-" {
-                        let from_year: u32 = period[0..4].parse()?;
-                        let from_month: u32 = period[4..6].parse()?;
-                        let to_year: u32 = period[7..11].parse()?;
-                        let to_month: u32 = period[11..13].parse()?;
-                        let to_days = days_of_month(to_year, to_month)?;
-                        return Ok(Timeframe::Custom {
-                            from: format!("{:04}-{:02}-01", from_year, from_month),
-                            to: format!("{:04}-{:02}-{:02}", to_year, to_month, to_days),
-                        });
+            services
+        } else {
+            vec![new_service(tenant_names.first().copied(), cache)?]
+        };
+
+        if services.is_empty() {
+            return Err(Box::from("no accessible tenants found!"));
+        }
+
+        if envelope {
+            let tenants: Result<Vec<String>> = services
+                .iter()
+                .map(|service| Ok(service.tenant()?.id))
+                .collect();
+            output.set_scope(Scope {
+                tenants: tenants?,
+                subscriptions: subscriptions.iter().map(|s| s.to_string()).collect(),
+                filters: filters.iter().map(|s| s.to_string()).collect(),
+                tags: tags.iter().map(|s| s.to_string()).collect(),
+            });
+        }
+
+        let parallelism = match args
+            .get_global_flag_arg(&PARALLEL)
+            .or(config.get(&config::PARALLELISM))
+        {
+            Some(parallelism) => match parallelism.parse() {
+                Ok(parallelism) if parallelism > 0 => parallelism,
+                _ => return Err(parse_error!("invalid parallelism: {}", parallelism)),
+            },
+            None => DEFAULT_PARALLELISM,
+        };
+
+        if args.has_global_flag(&ORDERED) && args.has_global_flag(&UNORDERED) {
+            return Err(parse_error!(
+                "only one of --ordered and --unordered allowed"
+            ));
+        }
+        let ordered = !args.has_global_flag(&UNORDERED);
+
+        let contexts: Vec<Context> = services
+            .iter()
+            .map(|service| Context {
+                service,
+                parallelism,
+                ordered,
+                stream,
+            })
+            .collect();
+        let context = &contexts[0];
+
+        let run_service_command = || -> Result<()> {
+            match command {
+                LIST => {
+                    let id = args.has_command_flag(&LIST_ID);
+                    let fields = parse_fields(&args, &LIST_FIELDS, LIST_FIELD_NAMES)?;
+                    let sort = parse_sort(&args, &SORT, LIST_FIELD_NAMES)?;
+                    let page = parse_page(&args)?;
+                    let zone_audit = args.has_command_flag(&ZONE_AUDIT);
+                    let resource_type = args.get_command_flag_arg(&LIST_TYPE);
+                    let list_resources = args.has_command_flag(&LIST_RESOURCES)
+                        || zone_audit
+                        || resource_type.is_some();
+                    let full = args.has_command_flag(&LIST_FULL);
+                    if full && !list_resources {
+                        return Err(parse_error!("--full requires --resources"));
+                    }
+                    let options = ListOptions {
+                        resources: list_resources,
+                        full,
+                        annotate_locks: args.has_command_flag(&ANNOTATE_LOCKS),
+                        annotate_zones: args.has_command_flag(&ANNOTATE_ZONES),
+                        zone_audit,
+                        location: args.get_command_flag_arg(&LIST_LOCATION),
+                        resource_type,
+                        use_graph: args.has_command_flag(&LIST_USE_GRAPH),
+                        // All subscriptions are needed to sort before paging
+                        page: match sort {
+                            Some(_) => Page::default(),
+                            None => page,
+                        },
+                    };
+                    let mut result = collect_results(&contexts, |context| {
+                        list(context, &options, args.get_arg_opt(0))
+                    })?;
+                    if let Some(sort) = &sort {
+                        sort_list_results(&mut result, sort);
+                    }
+                    page_list_results(&mut result, &page, list_resources);
+                    if csv_output {
+                        print_list_csv(&result, id, fields.as_deref())?;
+                    } else {
+                        output.print_list_results(&result, id, fields.as_deref())?;
+                    }
+                }
+                RESOURCES => {
+                    let id = args.has_command_flag(&RESOURCES_ID);
+                    let resource_type = args.get_arg(0, &RESOURCES_TYPE)?;
+                    let api_version = args.get_command_flag_arg(&RESOURCES_API_VERSION);
+                    let mut result = collect_results(&contexts, |context| {
+                        resources(context, resource_type, api_version, raw_resources)
+                    })?;
+                    parse_page(&args)?.apply_nested(&mut result, |result| &mut result.resources);
+                    output.print_resources_results(&result, id)?;
+                }
+                CLUSTERS => {
+                    let id = args.has_command_flag(&CLUSTERS_ID);
+                    let fields = parse_fields(&args, &CLUSTERS_FIELDS, CLUSTERS_FIELD_NAMES)?;
+                    let sort = parse_sort(&args, &SORT, CLUSTERS_FIELD_NAMES)?;
+                    let export_file = args.get_command_flag_arg(&CLUSTERS_EXPORT_KUBECONFIGS);
+                    let all_resources = args.has_command_flag(&CLUSTERS_ALL_RESOURCES);
+                    let details = ClusterDetails {
+                        pools: args.has_command_flag(&CLUSTERS_AGENT_POOLS),
+                        resources: args.has_command_flag(&CLUSTERS_RESOURCES) || all_resources,
+                        all_resources,
+                        restarts: args.has_command_flag(&CLUSTERS_RESTARTS),
+                        kubeconfigs: export_file.is_some(),
+                    };
+                    let timeframe = match args.get_command_flag_arg(&CLUSTERS_PERIOD) {
+                        Some(period) => Some(
+                            parse_period(period)
+                                .or(Err(parse_error!("invalid period: {}", period)))?,
+                        ),
+                        None if args.has_command_flag(&CLUSTERS_COSTS) => {
+                            Some(Timeframe::MonthToDate)
+                        }
+                        None => None,
+                    };
+                    let mut result = collect_results(&contexts, |context| {
+                        clusters(context, &details, timeframe.as_ref(), args.get_arg_opt(0))
+                    })?;
+                    if let Some(file) = export_file {
+                        let (count, kubeconfig) = cluster_kubeconfigs(&result)?;
+                        write_private_file(Path::new(file), kubeconfig.as_bytes())?;
+                        info!("Written {} contexts to {}", count, file);
+                    }
+                    if let Some(sort) = &sort {
+                        sort_cluster_results(&mut result, sort);
+                    }
+                    if csv_output {
+                        print_clusters_csv(&result, id, fields.as_deref())?;
                     } else {
-                        return Err(Box::from("invalid period!"));
+                        output.print_clusters(&result, id, fields.as_deref())?;
                     }
                 }
-                let result = match args.get_arg_opt(0) {
-                    Some(period) => {
-                        let timeframe = parse_period(period)
-                            .or(Err(parse_error!("invalid period: {}", period)))?;
-                        costs(&context, &timeframe)?
+                DOMAINS => {
+                    let owner_tag = match args.get_command_flag_arg(&OWNER_TAG) {
+                        Some(tag) => Some(tag),
+                        None if args.has_command_flag(&OWNERS) => Some(DEFAULT_OWNER_TAG),
+                        None => None,
+                    };
+                    let mut result = collect_results(&contexts, |context| {
+                        domains(context, args.get_arg_opt(0), owner_tag)
+                    })?;
+                    parse_page(&args)?.apply(&mut result);
+                    output.print_domains(
+                        &result,
+                        args.has_command_flag(&ASCII),
+                        owner_tag.is_some(),
+                    )?;
+                }
+                RESOLVE => {
+                    let first = args.get_arg(0, &HOSTNAMES)?;
+                    let resolver = Resolver::new(context)?;
+                    if first == "-" {
+                        for line in stdin().lock().lines() {
+                            let line = line?;
+                            let hostname = line.trim();
+                            if !hostname.is_empty() && !hostname.starts_with('#') {
+                                output.print_resolve_result(&resolver.resolve(hostname))?;
+                            }
+                        }
+                    } else {
+                        for hostname in &args.command_args {
+                            output.print_resolve_result(&resolver.resolve(hostname))?;
+                        }
+                    }
+                }
+                DNS => {
+                    let result = collect_results(&contexts, dns)?;
+                    output.print_dns_results(&result, args.has_command_flag(&ASCII))?;
+                }
+                IP => {
+                    let fields = parse_fields(&args, &IP_FIELDS, IP_FIELD_NAMES)?;
+                    let sort = parse_sort(&args, &SORT, IP_FIELD_NAMES)?;
+                    let mut result = collect_results(&contexts, |context| {
+                        ip(context, args.has_command_flag(&ANNOTATE_LOCKS))
+                    })?;
+                    if let Some(sort) = &sort {
+                        sort_ip_results(&mut result, sort);
+                    }
+                    if csv_output {
+                        print_ip_csv(&result, fields.as_deref())?;
+                    } else {
+                        output.print_ip_results(&result, fields.as_deref())?;
+                    }
+                }
+                ENDPOINTS => {
+                    let id = args.has_command_flag(&ENDPOINTS_ID);
+                    let result = collect_results(&contexts, |context| {
+                        endpoints(context, args.get_arg_opt(0))
+                    })?;
+                    output.print_endpoint_results(&result, id)?;
+                }
+                ROUTING => match args.get_command_flag_arg(&ROUTING_EFFECTIVE) {
+                    Some(network_interface) => {
+                        let result = effective_routes(context, network_interface)?;
+                        output.print_effective_routes(&result)?;
+                    }
+                    None => {
+                        let id = args.has_command_flag(&ROUTING_ID);
+                        let result = collect_results(&contexts, |context| {
+                            routing(context, args.get_arg_opt(0))
+                        })?;
+                        output.print_routing_results(&result, id)?;
+                    }
+                },
+                BASTION => {
+                    let id = args.has_command_flag(&BASTION_ID);
+                    let result = collect_results(&contexts, bastion)?;
+                    output.print_bastion_results(&result, id)?;
+                }
+                AUTOSCALE => {
+                    let id = args.has_command_flag(&AUTOSCALE_ID);
+                    let result = collect_results(&contexts, autoscale)?;
+                    output.print_autoscale_results(&result, id)?;
+                }
+                PLANS => {
+                    let id = args.has_command_flag(&PLANS_ID);
+                    let result = collect_results(&contexts, plans)?;
+                    output.print_plans_results(&result, id)?;
+                }
+                CONTAINERAPPS => {
+                    let id = args.has_command_flag(&CONTAINERAPPS_ID);
+                    let result = collect_results(&contexts, containerapps)?;
+                    output.print_containerapps_results(&result, id)?;
+                }
+                IMAGES => {
+                    let id = args.has_command_flag(&IMAGES_ID);
+                    let result = collect_results(&contexts, images)?;
+                    output.print_images_results(&result, id)?;
+                }
+                STORAGE => {
+                    let id = args.has_command_flag(&STORAGE_ID);
+                    let capacity = args.has_command_flag(&STORAGE_CAPACITY);
+                    let result = collect_results(&contexts, |context| storage(context, capacity))?;
+                    output.print_storage_results(&result, id)?;
+                }
+                SQL => {
+                    let id = args.has_command_flag(&SQL_ID);
+                    let usage = args.has_command_flag(&SQL_USAGE);
+                    let result = collect_results(&contexts, |context| sql(context, usage))?;
+                    output.print_sql_results(&result, id)?;
+                }
+                REDIS => {
+                    let id = args.has_command_flag(&REDIS_ID);
+                    let retiring = args.has_command_flag(&REDIS_RETIRING);
+                    let result = collect_results(&contexts, |context| redis(context, retiring))?;
+                    output.print_redis_results(&result, id)?;
+                }
+                PEERS => {
+                    let id = args.has_command_flag(&PEERS_ID);
+                    let result = collect_results(&contexts, peers)?;
+                    output.print_peers_results(&result, id)?;
+                }
+                FIREWALL => {
+                    let search = args.get_command_flag_arg(&FIREWALL_SEARCH);
+                    let result = collect_results(&contexts, |context| {
+                        firewall(context, search, args.get_arg_opt(0))
+                    })?;
+                    output.print_firewall_results(&result)?;
+                }
+                IDENTITY => {
+                    let id = args.has_command_flag(&IDENTITY_ID);
+                    let result = collect_results(&contexts, |context| {
+                        identity(context, args.get_arg_opt(0))
+                    })?;
+                    output.print_identity_results(&result, id)?;
+                }
+                SP_USAGE => {
+                    let result = collect_results(&contexts, |context| {
+                        sp_usage(context, args.get_arg_opt(0))
+                    })?;
+                    output.print_principal_usages(&result)?;
+                }
+                CHANGES => {
+                    let days = match args.get_command_flag_arg(&CHANGES_DAYS) {
+                        Some(days) => days
+                            .parse()
+                            .or(Err(parse_error!("invalid number of days: {}", days)))?,
+                        None => 7,
+                    };
+                    let result = collect_results(&contexts, |context| changes(context, days))?;
+                    output.print_changes(&result)?;
+                }
+                STALE => {
+                    let id = args.has_command_flag(&STALE_ID);
+                    let days = match args.get_command_flag_arg(&STALE_DAYS) {
+                        Some(days) => days
+                            .parse()
+                            .or(Err(parse_error!("invalid number of days: {}", days)))?,
+                        None => 90,
+                    };
+                    let mut result = collect_results(&contexts, |context| {
+                        stale(context, days, args.get_arg_opt(0))
+                    })?;
+                    parse_page(&args)?.apply_nested(&mut result, |result| &mut result.resources);
+                    output.print_stale_results(&result, id)?;
+                }
+                MOVE_CHECK => {
+                    let id = args.has_command_flag(&MOVE_CHECK_ID);
+                    let result = move_check(
+                        context,
+                        args.get_arg(0, &MOVE_CHECK_GROUP)?,
+                        args.get_arg(1, &MOVE_CHECK_SUBSCRIPTION)?,
+                        args.get_command_flag_arg(&MOVE_CHECK_TARGET_GROUP),
+                    )?;
+                    output.print_move_check(&result, id)?;
+                }
+                COSTS => {
+                    let sort = parse_sort(&args, &COSTS_SORT, COSTS_FIELD_NAMES)?;
+                    let timeframe = match args.get_arg_opt(0) {
+                        Some(period) => parse_period(period)
+                            .or(Err(parse_error!("invalid period: {}", period)))?,
+                        None => Timeframe::MonthToDate,
+                    };
+                    if args.has_command_flag(&COSTS_TREE) {
+                        if csv_output {
+                            return Err(parse_error!("--tree does not support CSV output"));
+                        }
+                        if sort.is_some() {
+                            return Err(parse_error!("--tree cannot be combined with --sort"));
+                        }
+                        let result =
+                            collect_results(&contexts, |context| costs_tree(context, &timeframe))?;
+                        output.print_cost_tree(&result)?;
+                        return Ok(());
+                    }
+                    let mut result =
+                        collect_results(&contexts, |context| costs(context, &timeframe))?;
+                    if let Some(sort) = &sort {
+                        sort_cost_results(&mut result, sort);
+                    }
+                    if csv_output {
+                        print_costs_csv(&result)?;
+                    } else {
+                        output.print_cost_results(&result)?;
+                    }
+                }
+                AUDIT => match args.get_arg(0, &AUDIT_CHECK)?.as_str() {
+                    "ports" => {
+                        let result = collect_results(&contexts, audit_ports)?;
+                        output.print_port_results(&result)?;
                     }
-                    None => costs(&context, &Timeframe::MonthToDate)?,
-                };
-                output.print_cost_results(&result)?;
+                    "keyvault-access" => {
+                        let result = collect_results(&contexts, audit_key_vault_access)?;
+                        output.print_key_vault_access_results(&result)?;
+                    }
+                    "storage-keys" => {
+                        let days = match args.get_command_flag_arg(&AUDIT_MAX_KEY_AGE) {
+                            Some(days) => days
+                                .parse()
+                                .or(Err(parse_error!("invalid number of days: {}", days)))?,
+                            None => 90,
+                        };
+                        let result = collect_results(&contexts, |context| {
+                            audit_storage_keys(context, days)
+                        })?;
+                        output.print_storage_keys_results(&result)?;
+                    }
+                    "tags" => {
+                        let path = args
+                            .get_command_flag_arg(&AUDIT_POLICY)
+                            .ok_or_else(|| parse_error!("missing --policy for tags"))?;
+                        let policy = TagPolicy::parse(&read_to_string(path)?)?;
+                        let result =
+                            collect_results(&contexts, |context| audit_tags(context, &policy))?;
+                        if args.has_command_flag(&AUDIT_CSV) {
+                            print_tags_csv(&result)?;
+                        } else {
+                            output.print_tags_results(&result)?;
+                        }
+                    }
+                    check => return Err(parse_error!("unknown check: {}", check)),
+                },
+                ACCOUNT => {
+                    let result = account(context)?;
+                    output.print_account(&result)?;
+                }
+                SUBS => {
+                    let result = collect_results(&contexts, subs)?;
+                    output.print_subscriptions(&result)?;
+                }
+                PROVIDERS => {
+                    let all = args.has_command_flag(&PROVIDERS_ALL);
+                    let result = collect_results(&contexts, |context| providers(context, all))?;
+                    output.print_providers(&result)?;
+                }
+                TENANTS => {
+                    let result = tenants(context)?;
+                    output.print_tenants(&result)?;
+                }
+                TOKEN => {
+                    let resource = args.get_command_flag_arg(&TOKEN_RESOURCE);
+                    let result = token(context, resource)?;
+                    output.print_token(&result)?;
+                }
+                LOGIN => {
+                    for context in &contexts {
+                        let result = login(context)?;
+                        output.print_login_result(&result)?;
+                    }
+                }
+                LOGOUT => {
+                    for context in &contexts {
+                        let result = logout(context)?;
+                        output.print_logout_result(&result)?;
+                    }
+                }
+                GET => {
+                    let request = args.get_arg(0, &REQUEST)?;
+                    let mut query = vec![];
+                    for (flag, name) in &[
+                        (GET_FILTER, "$filter"),
+                        (GET_SELECT, "$select"),
+                        (GET_TOP, "$top"),
+                        (GET_EXPAND, "$expand"),
+                    ] {
+                        if let Some(value) = args.get_command_flag_arg(flag) {
+                            query.push((*name, value));
+                        }
+                    }
+                    let each = args.get_command_flag_arg(&GET_EACH);
+                    if each.is_none() && args.get_arg_opt(1).is_some() {
+                        return Err(parse_error!("the template requires --each"));
+                    }
+                    if let Some(each) = each {
+                        if include_headers {
+                            return Err(parse_error!(
+                                "only one of --each and --include-headers allowed"
+                            ));
+                        }
+                        let each = Query::parse(each)?;
+                        let template = args.get_arg(1, &GET_TEMPLATE)?;
+                        let results = get_each(context, request, &query, &each, template)?;
+                        output.print_value(&to_value(&results)?)?;
+                    } else {
+                        let result = get(context, request, &query)?;
+                        if include_headers {
+                            output.print_response(context.service.last_response(), &result)?;
+                        } else {
+                            output.print_value(&result)?;
+                        }
+                    }
+                }
+                POST => {
+                    let request = args.get_arg(0, &REQUEST)?;
+                    let body = args.get_command_flag_arg(&BODY);
+                    let data_file = args.get_command_flag_arg(&DATA_FILE);
+                    if body.is_some() && data_file.is_some() {
+                        return Err(parse_error!("only one of --data and --data-file allowed"));
+                    }
+                    let buffer = if let Some(path) = data_file {
+                        read_to_string(path)?
+                    } else if body.is_some() && body.unwrap() == "-" {
+                        let mut buffer = String::new();
+                        stdin().read_to_string(&mut buffer)?;
+                        buffer
+                    } else {
+                        body.unwrap_or("").to_owned()
+                    };
+                    let content_type = data_file.map(content_type);
+                    let result = post(context, request, &buffer, content_type)?;
+                    if include_headers {
+                        output.print_response(context.service.last_response(), &result)?;
+                    } else {
+                        output.print_value(&result)?;
+                    }
+                }
+                _ => return Err(parse_error!("unknown command!")),
             }
-            GET => {
-                let request = args.get_arg(0, &REQUEST)?;
-                let result = get(&context, request)?;
-                output.print_value(&result)?;
+            return Ok(());
+        };
+
+        let start = Instant::now();
+        let result = run_service_command();
+        if args.has_global_flag(&TIMING) {
+            let timings = services
+                .iter()
+                .flat_map(|service| service.timings())
+                .collect();
+            output.print_timings(&TimingSummary::new(timings, start.elapsed()))?;
+        }
+        if args.has_global_flag(&SHOW_LIMITS) {
+            let rate_limits = services
+                .iter()
+                .flat_map(|service| service.rate_limits())
+                .collect();
+            output.print_rate_limits(&rate_limits)?;
+        }
+        if offline {
+            let oldest = services
+                .iter()
+                .filter_map(|service| service.oldest_cached())
+                .min();
+            if let Some(oldest) = oldest {
+                let age = SystemTime::now().duration_since(oldest).unwrap_or_default();
+                info!(
+                    "Offline, showing data cached {} ago, at {}",
+                    format_age(age),
+                    DateTime::<Utc>::from(oldest).to_rfc3339_opts(SecondsFormat::Secs, true)
+                );
             }
-            POST => {
-                let request = args.get_arg(0, &REQUEST)?;
-                let body = args.get_command_flag_arg(&BODY);
-                let buffer = if body.is_some() && body.unwrap() == "-" {
-                    let mut buffer = String::new();
-                    stdin().read_to_string(&mut buffer)?;
-                    buffer
-                } else {
-                    body.unwrap_or("").to_owned()
-                };
-                let result = post(&context, request, &buffer)?;
-                output.print_value(&result)?;
+        }
+        let warnings: Vec<Warning> = services
+            .iter()
+            .flat_map(|service| service.warnings())
+            .collect();
+        if !warnings.is_empty() {
+            output.print_warnings(&warnings)?;
+        }
+        for service in &services {
+            let access_stats = service.access_stats();
+            if access_stats.all_denied() {
+                let guidance = access_guidance(service, &access_stats);
+                match result {
+                    // The guidance replaces the error of the first denied request
+                    Err(_) => return Err(AccessDenied(guidance).into()),
+                    Ok(_) => {
+                        eprintln!("warning: {}", guidance);
+                        partial.set(true);
+                    }
+                }
+            }
+        }
+        if services.iter().any(|service| service.failed_requests() > 0) {
+            partial.set(true);
+        }
+        if let Err(err) = &result {
+            let request_id = services
+                .iter()
+                .find_map(|service| service.failed_request_id());
+            let parse_error = matches!(err.downcast_ref::<AppError>(), Some(ParseError(_)));
+            if let Some(request_id) = request_id.filter(|_| !parse_error) {
+                return Err(RequestFailed {
+                    error: err.to_string(),
+                    exit_code: exit_code(err.as_ref()),
+                    request_id: request_id.to_owned(),
+                }
+                .into());
             }
-            _ => return Err(parse_error!("unknown command!")),
         }
-        return Ok(());
+        return result;
     };
 
     match run_command() {
-        Ok(_) => (),
+        Ok(_) if partial.get() => EXIT_PARTIAL,
+        Ok(_) => EXIT_SUCCESS,
+        // The reader of the output quit early, like `head`, so it is not shown an error
+        Err(err)
+            if err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == ErrorKind::BrokenPipe) =>
+        {
+            EXIT_FAILURE
+        }
         Err(err) => {
             eprintln!("error: {}", err);
-            if let Ok(app_err) = err.downcast::<AppError>() {
-                if let ParseError(_) = *app_err {
-                    Printer::new().print_command_usage(&command);
-                }
+            if let Some(ParseError(_)) = err.downcast_ref::<AppError>() {
+                Printer::new().print_command_usage(&command);
             }
+            exit_code(err.as_ref())
+        }
+    }
+}
+
+/// Explains a run in which every request was denied, with the principal from the token claims
+fn access_guidance(service: &Service, access_stats: &AccessStats) -> String {
+    let scope = access_stats
+        .scope
+        .as_deref()
+        .unwrap_or("/subscriptions/<id>");
+    let mut guidance = format!(
+        "access was denied for all {} requests, azi needs at least the Reader role\n  scope: {}",
+        access_stats.denied, scope
+    );
+    if let Ok(token_set) = service.token_set(None) {
+        let token = &token_set.access_token;
+        guidance.push_str(&format!(
+            "\n  principal: {} (object ID {}, tenant {})\n  \
+             assign it with: az role assignment create --assignee {} --role Reader --scope {}",
+            token.unique_name, token.oid, token.tenant.id, token.oid, scope
+        ));
+    }
+    guidance
+}
+
+/// Runs a command for each tenant and concatenates the results
+fn collect_results<T>(
+    contexts: &[Context],
+    function: impl Fn(&Context) -> Result<Vec<T>>,
+) -> Result<Vec<T>> {
+    let mut results = vec![];
+    for context in contexts {
+        results.extend(function(context)?);
+    }
+    return Ok(results);
+}
+
+/// Parses `--offset` and `--limit`, all items are shown without them
+fn parse_page(args: &Args) -> Result<Page> {
+    let parse_count = |flag: &Flag| -> Result<Option<usize>> {
+        match args.get_command_flag_arg(flag) {
+            Some(count) => Ok(Some(
+                count
+                    .parse()
+                    .or(Err(parse_error!("invalid count: {}", count)))?,
+            )),
+            None => Ok(None),
+        }
+    };
+    Ok(Page {
+        offset: parse_count(&OFFSET)?.unwrap_or(0),
+        limit: parse_count(&LIMIT)?,
+    })
+}
+
+/// Formats the age of cached data in the largest whole unit, like `3 hours`
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (count, unit) = match secs {
+        0..=59 => (secs, "second"),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86399 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    match count {
+        1 => format!("1 {}", unit),
+        count => format!("{} {}s", count, unit),
+    }
+}
+
+/// Parses `--precision` and `--round` of the costs in the text output
+fn parse_precision(args: &Args) -> Result<Precision> {
+    let mut precision = Precision::default();
+    if let Some(decimals) = args.get_global_flag_arg(&PRECISION) {
+        precision.decimals = decimals
+            .parse()
+            .ok()
+            .filter(|decimals| *decimals <= MINOR_UNIT_DECIMALS)
+            .ok_or(parse_error!("invalid precision: {}", decimals))?;
+    }
+    if let Some(rounding) = args.get_global_flag_arg(&ROUND) {
+        precision.rounding = Rounding::parse(rounding)?;
+    }
+    Ok(precision)
+}
+
+/// Parses comma-separated field names like `name,location`, ignoring their case
+fn parse_fields(args: &Args, flag: &Flag, names: &[&str]) -> Result<Option<Vec<String>>> {
+    let fields = match args.get_command_flag_arg(flag) {
+        Some(fields) => fields,
+        None => return Ok(None),
+    };
+    let mut result = vec![];
+    for field in fields.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        result.push(parse_field(field, names)?);
+    }
+    if result.is_empty() {
+        return Err(parse_error!("no fields given: {}", fields));
+    }
+    Ok(Some(result))
+}
+
+/// Parses `--sort` and `--reverse`, which requires `--sort`
+fn parse_sort(args: &Args, flag: &Flag, names: &[&str]) -> Result<Option<Sort>> {
+    let reverse = args.has_command_flag(&REVERSE);
+    match args.get_command_flag_arg(flag) {
+        Some(field) => Ok(Some(Sort {
+            field: parse_field(field, names)?,
+            reverse,
+        })),
+        None if reverse => Err(parse_error!("--reverse requires --sort")),
+        None => Ok(None),
+    }
+}
+
+fn parse_field(field: &str, names: &[&str]) -> Result<String> {
+    match names.iter().find(|name| name.eq_ignore_ascii_case(field)) {
+        Some(name) => Ok(name.to_string()),
+        None => Err(parse_error!(
+            "unknown field: {}, expected one of {}",
+            field,
+            names.join(", ")
+        )),
+    }
+}
+
+/// Parses a billing period like 2019, 201905, 20190521 or 201901-201906
+fn parse_period(period: &str) -> Result<Timeframe> {
+    if period.len() == 4 {
+        let year: u32 = period.parse()?;
+        return Ok(Timeframe::Custom {
+            from: format!("{:04}-01-01", year),
+            to: format!("{:04}-12-31", year),
+        });
+    } else if period.len() == 6 {
+        let year: u32 = period[0..4].parse()?;
+        let month: u32 = period[4..6].parse()?;
+        let days = days_of_month(year, month)?;
+        return Ok(Timeframe::Custom {
+            from: format!("{:04}-{:02}-01", year, month),
+            to: format!("{:04}-{:02}-{:02}", year, month, days),
+        });
+    } else if period.len() == 8 {
+        let year: u32 = period[0..4].parse()?;
+        let month: u32 = period[4..6].parse()?;
+        let day: u32 = period[6..8].parse()?;
+        return Ok(Timeframe::Custom {
+            from: format!("{:04}-{:02}-{:02}", year, month, day),
+            to: format!("{:04}-{:02}-{:02}", year, month, day),
+        });
+    } else if period.len() == 13 && &period[6..7] == "-" {
+        let from_year: u32 = period[0..4].parse()?;
+        let from_month: u32 = period[4..6].parse()?;
+        let to_year: u32 = period[7..11].parse()?;
+        let to_month: u32 = period[11..13].parse()?;
+        let to_days = days_of_month(to_year, to_month)?;
+        return Ok(Timeframe::Custom {
+            from: format!("{:04}-{:02}-01", from_year, from_month),
+            to: format!("{:04}-{:02}-{:02}", to_year, to_month, to_days),
+        });
+    } else {
+        return Err(Box::from("invalid period!"));
+    }
+}
+
+fn run_config(args: &Args, mut config: Config, output: &dyn Output) -> Result<()> {
+    match args.get_arg(0, &CONFIG_ACTION)?.as_str() {
+        "list" => output.print_config_entries(&config.entries()),
+        "get" => {
+            let key = args.get_arg(1, &CONFIG_KEY)?;
+            output.print_config_entries(&vec![config.get_entry(key)?])
+        }
+        "set" => {
+            let key = args.get_arg(1, &CONFIG_KEY)?;
+            let value = args.get_arg(2, &CONFIG_VALUE)?;
+            config.set(key, value)?;
+            config.write()
+        }
+        action => Err(parse_error!("unknown action: {}", action)),
+    }
+}
+
+fn run_cache(args: &Args, cache: &Cache, output: &dyn Output) -> Result<()> {
+    match args.get_arg(0, &CACHE_ACTION)?.as_str() {
+        "stats" => output.print_cache_stats(&cache.stats()?),
+        "clear" => cache.clear(),
+        "path" => {
+            println!("{}", cache.path().display());
+            Ok(())
         }
+        action => Err(parse_error!("unknown action: {}", action)),
     }
 }
 
+fn run_schema(args: &Args, output: &dyn Output) -> Result<()> {
+    let name = args.get_arg(0, &SCHEMA_COMMAND)?;
+    let variant = args.get_arg_opt(1).map(String::as_str);
+    let title = match variant {
+        Some(variant) => format!("azi {} {}", name, variant),
+        None => format!("azi {}", name),
+    };
+    let schema = match (name.as_str(), variant) {
+        ("list", None) => output_schema::<Vec<ListResult>>(&title),
+        ("resources", None) => output_schema::<Vec<ResourcesResult>>(&title),
+        ("clusters", None) => output_schema::<Vec<ClusterResult>>(&title),
+        ("domains", None) => output_schema::<Vec<Domain>>(&title),
+        ("dns", None) => output_schema::<Vec<DnsResult>>(&title),
+        ("resolve", None) => output_schema::<ResolveResult>(&title),
+        ("ip", None) => output_schema::<Vec<IpResult>>(&title),
+        ("endpoints", None) => output_schema::<Vec<EndpointResult>>(&title),
+        ("routing", None) => output_schema::<Vec<RoutingResult>>(&title),
+        ("routing", Some("effective")) => output_schema::<EffectiveRoutesResult>(&title),
+        ("firewall", None) => output_schema::<Vec<FirewallResult>>(&title),
+        ("bastion", None) => output_schema::<Vec<BastionResult>>(&title),
+        ("autoscale", None) => output_schema::<Vec<AutoscaleResult>>(&title),
+        ("plans", None) => output_schema::<Vec<PlansResult>>(&title),
+        ("containerapps", None) => output_schema::<Vec<ContainerAppsResult>>(&title),
+        ("images", None) => output_schema::<Vec<ImagesResult>>(&title),
+        ("storage", None) => output_schema::<Vec<StorageResult>>(&title),
+        ("sql", None) => output_schema::<Vec<SqlResult>>(&title),
+        ("redis", None) => output_schema::<Vec<RedisResult>>(&title),
+        ("peers", None) => output_schema::<Vec<PeersResult>>(&title),
+        ("identity", None) => output_schema::<Vec<IdentityResult>>(&title),
+        ("sp-usage", None) => output_schema::<Vec<PrincipalUsage>>(&title),
+        ("changes", None) => output_schema::<Vec<ChangesResult>>(&title),
+        ("stale", None) => output_schema::<Vec<StaleResult>>(&title),
+        ("move-check", None) => output_schema::<MoveCheckResult>(&title),
+        ("costs", None) => output_schema::<Vec<CostResult>>(&title),
+        ("audit", Some("ports")) => output_schema::<Vec<PortsResult>>(&title),
+        ("audit", Some("keyvault-access")) => output_schema::<Vec<KeyVaultAccessResult>>(&title),
+        ("audit", Some("storage-keys")) => output_schema::<Vec<StorageKeysResult>>(&title),
+        ("audit", Some("tags")) => output_schema::<Vec<TagsResult>>(&title),
+        ("account", None) => output_schema::<AccountResult>(&title),
+        ("subs", None) => output_schema::<Vec<SubscriptionInfo>>(&title),
+        ("providers", None) => output_schema::<Vec<ProvidersResult>>(&title),
+        ("tenants", None) => output_schema::<Vec<TenantResult>>(&title),
+        ("use", None) => output_schema::<UseResult>(&title),
+        ("explain", None) => output_schema::<ExplainResult>(&title),
+        ("rbac-minrole", None) => output_schema::<RoleDefinitionResult>(&title),
+        ("token", None) => output_schema::<TokenResult>(&title),
+        ("login", None) => output_schema::<LoginResult>(&title),
+        ("logout", None) => output_schema::<LogoutResult>(&title),
+        ("config", None) => output_schema::<Vec<ConfigEntry>>(&title),
+        ("cache", None) => output_schema::<CacheStats>(&title),
+        ("get", None) | ("post", None) => output_schema::<Value>(&title),
+        _ => return Err(parse_error!("no schema for: {}", title)),
+    };
+    output.print_value(&schema)
+}
+
 fn short_flag(flag: &Flag) -> &str {
     return match flag.0.find(",") {
         Some(pos) => &flag.0[..pos],
@@ -322,6 +2377,14 @@ fn long_flag(flag: &Flag) -> &str {
     };
 }
 
+/// Strips the argument placeholder, as in `--tenant <tenant>`
+fn flag_name(flag: &str) -> &str {
+    return match flag.find(" <") {
+        Some(pos) => &flag[..pos],
+        None => flag,
+    };
+}
+
 #[derive(Debug)]
 struct Args {
     global_flags: Vec<Arg>,
@@ -344,7 +2407,7 @@ impl Args {
         fn parse_flag(flags: &[Flag], arg: &str, it: &mut Iter<&str>) -> Result<Arg> {
             let found = flags
                 .iter()
-                .find(|flag| arg == short_flag(flag) || arg == long_flag(flag));
+                .find(|flag| arg == short_flag(flag) || arg == flag_name(long_flag(flag)));
             if let Some(flag) = found {
                 if flag.2 {
                     if let Some(&arg) = it.next() {
@@ -367,7 +2430,7 @@ impl Args {
             } else if arg == "--" {
                 double_dash = true;
             } else if let Some(command) = command {
-                if arg.starts_with("-") {
+                if arg.starts_with("-") && arg != "-" {
                     command_flags.push(parse_flag(command.2, arg, &mut it)?);
                 } else {
                     command_args.push(arg.to_owned());
@@ -376,7 +2439,10 @@ impl Args {
                 if arg.starts_with("-") {
                     global_flags.push(parse_flag(GLOBAL_FLAGS, arg, &mut it)?);
                 } else {
-                    let found = COMMANDS.iter().find(|command| arg == command.0);
+                    let found = COMMANDS
+                        .iter()
+                        .chain(HIDDEN_COMMANDS)
+                        .find(|command| arg == command.0);
                     if let Some(cmd) = found {
                         command = Some(*cmd);
                     } else {
@@ -425,6 +2491,15 @@ impl Args {
         return None;
     }
 
+    fn get_global_flag_args(&self, flag: &Flag) -> Vec<&str> {
+        return self
+            .global_flags
+            .iter()
+            .filter(|global_flag| &global_flag.0 == flag)
+            .map(|global_flag| global_flag.1.as_str())
+            .collect();
+    }
+
     fn get_command_flag_arg(&self, flag: &Flag) -> Option<&str> {
         for command_flag in &self.command_flags {
             if &command_flag.0 == flag {
@@ -463,7 +2538,7 @@ impl Printer {
         self.print_usage();
 
         self.println();
-        self.print_description("Show Azure information.");
+        self.print_description(&format!("{}.", PROGRAM_DESCRIPTION));
 
         self.println();
         self.print_options(GLOBAL_FLAGS);
@@ -503,15 +2578,7 @@ impl Printer {
 
     fn print_flags(&mut self, flags: &[Flag]) {
         for flag in flags {
-            if flag.0.starts_with("-") {
-                if short_flag(flag).is_empty() {
-                    self.print(&[" [", long_flag(flag), "]"].join(""));
-                } else {
-                    self.print(&[" [", short_flag(flag), "]"].join(""));
-                }
-            } else {
-                self.print(&[" ", flag.0].join(""));
-            }
+            self.print(&[" ", &usage_flag(flag)].join(""));
         }
     }
 
@@ -534,15 +2601,7 @@ impl Printer {
         }
 
         for flag in flags {
-            if flag.0.starts_with("[") {
-                self.print_prefix(&format!(
-                    "  {0:1$}    ",
-                    &flag.0[1..flag.0.len() - 1],
-                    max_len
-                ));
-            } else {
-                self.print_prefix(&format!("  {0:1$}    ", flag.0, max_len));
-            }
+            self.print_prefix(&format!("  {0:1$}    ", option_name(flag), max_len));
             self.print_text(flag.1);
         }
     }
@@ -593,14 +2652,158 @@ impl Printer {
     }
 }
 
+/// The flag as shown in the usage line, like `[-h]` or `<request>`
+fn usage_flag(flag: &Flag) -> String {
+    if flag.0.starts_with("-") {
+        if short_flag(flag).is_empty() {
+            return ["[", long_flag(flag), "]"].join("");
+        } else {
+            return ["[", short_flag(flag), "]"].join("");
+        }
+    }
+    return flag.0.to_owned();
+}
+
+/// The flag as shown in the list of options, optional arguments without brackets
+fn option_name(flag: &Flag) -> &str {
+    if flag.0.starts_with("[") {
+        return &flag.0[1..flag.0.len() - 1];
+    }
+    return flag.0;
+}
+
+fn command_usage(command: &Command) -> String {
+    let flags: Vec<String> = command.2.iter().map(usage_flag).collect();
+    return format!("azi {} {}", command.0, flags.join(" "));
+}
+
+/// Escapes text for roff, so that dashes and leading dots are printed as they are
+fn escape_roff(text: &str) -> String {
+    let text = text.replace('\\', "\\\\").replace('-', "\\-");
+    if text.starts_with('.') || text.starts_with('\'') {
+        return format!("\\&{}", text);
+    }
+    return text;
+}
+
+fn man_options(page: &mut String, flags: &[Flag]) {
+    for flag in flags {
+        page.push_str(&format!(
+            ".TP\n.B {}\n{}\n",
+            escape_roff(option_name(flag)),
+            escape_roff(flag.1)
+        ));
+    }
+}
+
+/// The man page of azi, section 1, without a date so that builds are reproducible
+fn man_page() -> String {
+    let mut page = format!(
+        ".TH AZI 1 \"\" \"azi {}\" \"User Commands\"\n",
+        PROGRAM_VERSION
+    );
+    page.push_str(&format!(".SH NAME\nazi \\- {}\n", PROGRAM_DESCRIPTION));
+    page.push_str(".SH SYNOPSIS\n.B azi\n[\\fIoptions\\fR] \\fIcommand\\fR [\\fIargs\\fR]\n");
+    page.push_str(".SH OPTIONS\n");
+    man_options(&mut page, GLOBAL_FLAGS);
+    page.push_str(".SH COMMANDS\n");
+    for command in COMMANDS {
+        page.push_str(&format!(
+            ".SS {}\n{}\n.PP\n.B {}\n",
+            command.0,
+            escape_roff(command.1),
+            escape_roff(&command_usage(command))
+        ));
+        man_options(&mut page, command.2);
+    }
+    return page;
+}
+
+fn markdown_options(reference: &mut String, flags: &[Flag]) {
+    reference.push_str("| Option | Description |\n| --- | --- |\n");
+    for flag in flags {
+        reference.push_str(&format!(
+            "| `{}` | {} |\n",
+            option_name(flag),
+            flag.1.replace('|', "\\|")
+        ));
+    }
+}
+
+/// The reference of all options and commands in Markdown
+fn markdown_reference() -> String {
+    let mut reference = format!("# azi {}\n\n{}.\n\n", PROGRAM_VERSION, PROGRAM_DESCRIPTION);
+    reference.push_str("Usage: `azi [options] <command> [<args>]`\n\n## Options\n\n");
+    markdown_options(&mut reference, GLOBAL_FLAGS);
+    reference.push_str("\n## Commands\n");
+    for command in COMMANDS {
+        reference.push_str(&format!(
+            "\n### {}\n\n{}\n\nUsage: `{}`\n\n",
+            command.0,
+            command.1,
+            command_usage(command)
+        ));
+        markdown_options(&mut reference, command.2);
+    }
+    return reference;
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
+    use super::escape_roff;
+    use super::format_age;
     use super::long_flag;
+    use super::man_page;
+    use super::markdown_reference;
+    use super::parse_fields;
+    use super::parse_period;
     use super::short_flag;
     use super::Args;
+    use super::AUDIT_POLICY;
     use super::DEBUG;
     use super::GET;
+    use super::GET_FILTER;
+    use super::GET_TOP;
     use super::HELP;
+    use super::IP_FIELDS;
+    use super::RESOLVE;
+    use super::TENANT;
+    use crate::output::IP_FIELD_NAMES;
+    use crate::service::Timeframe;
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!("0 seconds", format_age(Duration::from_secs(0)));
+        assert_eq!("1 minute", format_age(Duration::from_secs(119)));
+        assert_eq!("23 hours", format_age(Duration::from_secs(86399)));
+        assert_eq!("3 days", format_age(Duration::from_secs(3 * 86400 + 5)));
+    }
+
+    #[test]
+    fn test_escape_roff() {
+        assert_eq!(
+            "\\-\\-query <expression>",
+            escape_roff("--query <expression>")
+        );
+        assert_eq!("\\&.hidden", escape_roff(".hidden"));
+        assert_eq!("C:\\\\azi", escape_roff("C:\\azi"));
+    }
+
+    #[test]
+    fn test_docs() {
+        let man = man_page();
+        assert!(man.starts_with(".TH AZI 1"));
+        assert!(man.contains(".SS list\n"));
+        assert!(man.contains(".B \\-\\-query <expression>\n"));
+        assert!(!man.contains(".SS docs"));
+
+        let markdown = markdown_reference();
+        assert!(markdown.contains("\n### get\n"));
+        assert!(markdown.contains("| `<request>` | The request to execute |"));
+        assert!(markdown.contains("Usage: `azi get [-h] [-i]"));
+    }
 
     #[test]
     fn test_short_flag() {
@@ -621,6 +2824,76 @@ mod tests {
         assert_eq!(vec!("test"), args.command_args);
     }
 
+    #[test]
+    fn test_parse_stdin_arg() {
+        let args = Args::parse(vec!["resolve", "-"]).unwrap();
+        assert_eq!(Some(RESOLVE), args.command);
+        assert_eq!(vec!("-"), args.command_args);
+    }
+
+    #[test]
+    fn test_parse_multiple_tenants() {
+        let args = Args::parse(vec!["-t", "a.com", "-t", "b.com", "list"]).unwrap();
+        assert_eq!(vec!("a.com", "b.com"), args.get_global_flag_args(&TENANT));
+        assert_eq!(Some("a.com"), args.get_global_flag_arg(&TENANT));
+    }
+
+    #[test]
+    fn test_parse_long_flag_with_argument() {
+        let args = Args::parse(vec!["audit", "--policy", "tags.toml", "tags"]).unwrap();
+        assert_eq!(Some("tags.toml"), args.get_command_flag_arg(&AUDIT_POLICY));
+        assert_eq!("tags", args.command_args[0]);
+    }
+
+    #[test]
+    fn test_parse_get_query() {
+        let args = Args::parse(vec![
+            "get",
+            "--filter",
+            "resourceType eq 'Microsoft.Storage/storageAccounts'",
+            "--top",
+            "5",
+            "/subscriptions/123/resources",
+        ])
+        .unwrap();
+        assert_eq!(
+            Some("resourceType eq 'Microsoft.Storage/storageAccounts'"),
+            args.get_command_flag_arg(&GET_FILTER)
+        );
+        assert_eq!(Some("5"), args.get_command_flag_arg(&GET_TOP));
+        assert_eq!(vec!("/subscriptions/123/resources"), args.command_args);
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let args = Args::parse(vec!["ip", "--fields", "name, IPADDRESS"]).unwrap();
+        assert_eq!(
+            Some(vec!["name".to_owned(), "ipAddress".to_owned()]),
+            parse_fields(&args, &IP_FIELDS, IP_FIELD_NAMES).unwrap()
+        );
+
+        let args = Args::parse(vec!["ip", "--fields", "name,size"]).unwrap();
+        assert!(parse_fields(&args, &IP_FIELDS, IP_FIELD_NAMES).is_err());
+
+        let args = Args::parse(vec!["ip"]).unwrap();
+        assert_eq!(
+            None,
+            parse_fields(&args, &IP_FIELDS, IP_FIELD_NAMES).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_period() {
+        match parse_period("201902").unwrap() {
+            Timeframe::Custom { from, to } => {
+                assert_eq!("2019-02-01", from);
+                assert_eq!("2019-02-28", to);
+            }
+            timeframe => panic!("unexpected timeframe: {:?}", timeframe),
+        }
+        assert!(parse_period("2019-02").is_err());
+    }
+
     #[test]
     fn test_parse_missing_command() {
         assert_eq!(None, Args::parse(vec!("--debug")).unwrap().command);
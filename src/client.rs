@@ -1,33 +1,75 @@
 use crate::http::Response;
-use std::cell::RefCell;
+use std::env::var;
+use std::fs::read_to_string;
+use std::process::Command;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::thread::scope;
 use std::thread::sleep;
 use std::time::Duration;
+use std::time::SystemTime;
 
 use serde::de::DeserializeOwned;
 use serde_json::from_value;
+use serde_json::json;
 use serde_json::Value;
 use url::Url;
 
 use crate::auth::AccessTokenFile;
+use crate::auth::ClientCertificate;
+use crate::auth::Pkce;
 use crate::auth::TokenSet;
+use crate::auth::TokenStore;
+use crate::cache::Cache;
+use crate::cache::CacheMode;
 use crate::error::AppError::HttpClientError;
+use crate::error::AppError::HttpError;
+use crate::error::AppError::Offline;
+use crate::error::AppError::ServiceError;
 use crate::error::AppError::UnexpectedJson;
+use crate::http::truncated_responses;
 use crate::http::Header;
 use crate::http::Http;
+use crate::http::RateLimit;
+use crate::http::RedirectListener;
+use crate::http::Timeouts;
 use crate::tenant::Tenant;
 use crate::utils::Result;
 
+/// Result of a request, conditional if an ETag was sent
+enum Fetched {
+    /// The value and its ETag, if it can be revalidated
+    Value(Value, Option<String>),
+    NotModified,
+}
+
+impl Fetched {
+    fn into_value(self) -> Result<Value> {
+        match self {
+            Fetched::Value(value, _) => Ok(value),
+            Fetched::NotModified => Err(HttpError(304, Value::Null).into()),
+        }
+    }
+}
+
 pub struct Request<'r> {
     client: &'r Client,
     url: &'r str,
     resource: &'r str,
-    query: (&'r str, &'r str),
+    query: Vec<(&'r str, &'r str)>,
     body: Option<&'r str>,
+    /// JSON if not set
+    content_type: Option<&'r str>,
+    /// Whether the response of a GET request is read from and written to the cache
+    cached: bool,
 }
 
 impl<'r> Request<'r> {
+    /// Adds a query parameter, URL-encoded when the request is executed
     pub fn query(mut self, name: &'r str, value: &'r str) -> Self {
-        self.query = (name, value);
+        self.query.push((name, value));
         return self;
     }
 
@@ -36,6 +78,17 @@ impl<'r> Request<'r> {
         return self;
     }
 
+    pub fn content_type(mut self, content_type: &'r str) -> Self {
+        self.content_type = Some(content_type);
+        return self;
+    }
+
+    /// Bypasses the cache, for requests of the user which should see the current state
+    pub fn uncached(mut self) -> Self {
+        self.cached = false;
+        return self;
+    }
+
     pub fn get_raw(&self) -> Result<Value> {
         return self.client.request(self);
     }
@@ -75,42 +128,223 @@ impl<'r> Request<'r> {
 
 const CLIENT_ID: &'static str = "04b07795-8ddb-461a-bbee-02f9e1bf7b46";
 
+const POLL_INTERVAL: u64 = 2000;
+
+/// Maximum number of requests the Resource Manager accepts in one batch
+const BATCH_SIZE: usize = 20;
+
+const CLIENT_ASSERTION_TYPE: &'static str =
+    "urn%3Aietf%3Aparams%3Aoauth%3Aclient-assertion-type%3Ajwt-bearer";
+
+/// How to log in when no valid refresh token is available
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoginMethod {
+    DeviceCode,
+    Browser,
+}
+
+/// The endpoints of an Azure cloud, selected with the `cloud` setting
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cloud {
+    /// Azure Resource Manager endpoint, can be overridden with `AZI_ARM_ENDPOINT`
+    arm_endpoint: &'static str,
+    /// Token audience for the Resource Manager, can be overridden with `AZI_ARM_RESOURCE`
+    arm_resource: &'static str,
+    /// Authority host of Azure Active Directory
+    login_endpoint: &'static str,
+}
+
+impl Cloud {
+    pub const AZURE: Cloud = Cloud {
+        arm_endpoint: "https://management.azure.com",
+        arm_resource: "https://management.core.windows.net/",
+        login_endpoint: "https://login.microsoftonline.com",
+    };
+
+    pub fn from_name(name: &str) -> Option<Cloud> {
+        match name {
+            "AzureCloud" => Some(Cloud::AZURE),
+            "AzureChinaCloud" => Some(Cloud {
+                arm_endpoint: "https://management.chinacloudapi.cn",
+                arm_resource: "https://management.core.chinacloudapi.cn/",
+                login_endpoint: "https://login.chinacloudapi.cn",
+            }),
+            "AzureUSGovernment" => Some(Cloud {
+                arm_endpoint: "https://management.usgovcloudapi.net",
+                arm_resource: "https://management.core.usgovcloudapi.net/",
+                login_endpoint: "https://login.microsoftonline.us",
+            }),
+            _ => None,
+        }
+    }
+}
+
+enum ClientCredential {
+    Certificate(Box<ClientCertificate>),
+    /// Path of the token file mounted by workload identity federation
+    FederatedToken(String),
+}
+
+/// Service principal configured with the `AZURE_CLIENT_ID` and `AZURE_TENANT_ID` variables,
+/// authenticating with `AZURE_CLIENT_CERTIFICATE_PATH` or `AZURE_FEDERATED_TOKEN_FILE`
+struct ServicePrincipal {
+    client_id: String,
+    credential: ClientCredential,
+}
+
+impl ServicePrincipal {
+    fn from_env() -> Result<Option<ServicePrincipal>> {
+        let credential = if let Ok(path) = var("AZURE_CLIENT_CERTIFICATE_PATH") {
+            let password = var("AZURE_CLIENT_CERTIFICATE_PASSWORD").ok();
+            let certificate = ClientCertificate::read(&path, password.as_deref())?;
+            ClientCredential::Certificate(Box::new(certificate))
+        } else if let Ok(path) = var("AZURE_FEDERATED_TOKEN_FILE") {
+            ClientCredential::FederatedToken(path)
+        } else {
+            return Ok(None);
+        };
+        let client_id = var("AZURE_CLIENT_ID").or(Err(ServiceError(
+            "AZURE_CLIENT_ID is required for service principal authentication!",
+        )))?;
+        debug!("Using service principal authentication for {}", client_id);
+        Ok(Some(ServicePrincipal {
+            client_id,
+            credential,
+        }))
+    }
+
+    fn client_assertion(&self, audience: &str) -> Result<String> {
+        match &self.credential {
+            ClientCredential::Certificate(certificate) => {
+                certificate.client_assertion(&self.client_id, audience)
+            }
+            // the file is rotated regularly, so it is read again for each token request
+            ClientCredential::FederatedToken(path) => Ok(read_to_string(path)?.trim().to_owned()),
+        }
+    }
+}
+
 pub struct Client {
-    tenant: RefCell<Tenant>,
+    tenant: Mutex<Tenant>,
+    service_principal: Option<ServicePrincipal>,
+    login_method: LoginMethod,
     access_token_file: AccessTokenFile,
-    token_sets: RefCell<Vec<TokenSet>>,
+    token_sets: Mutex<Vec<TokenSet>>,
     http: Http,
+    cache: Cache,
+    arm_endpoint: String,
+    arm_resource: String,
+    login_endpoint: &'static str,
+    /// Stop following the `nextLink` of lists after this many items
+    max_items: Option<usize>,
+    /// PEM file with additional CA certificates, from `--ca-file` or `AZI_CA_BUNDLE`
+    ca_bundle: Option<String>,
+    timeouts: Timeouts,
+    /// Failed or denied requests which were only warned about, to exit with `EXIT_PARTIAL`
+    failed_requests: AtomicUsize,
 }
 
 impl Client {
-    pub fn new(tenant: Option<&str>) -> Result<Client> {
-        let http = Http::new();
+    pub fn new(
+        tenant: Option<&str>,
+        cache: Cache,
+        cloud: Cloud,
+        login_method: LoginMethod,
+        token_store: TokenStore,
+        ca_file: Option<&str>,
+        timeouts: Timeouts,
+    ) -> Result<Client> {
+        let ca_bundle = ca_file
+            .map(str::to_owned)
+            .or_else(|| var("AZI_CA_BUNDLE").ok());
+        let http = match &ca_bundle {
+            Some(ca_bundle) => Http::for_ca_bundle(ca_bundle, timeouts)?,
+            None => Http::new(timeouts),
+        }
+        .with_offline(cache.mode() == CacheMode::Offline);
+
+        let service_principal = ServicePrincipal::from_env()?;
+        let tenant_var = var("AZURE_TENANT_ID").ok();
+        let tenant = match service_principal {
+            Some(_) => tenant.or(tenant_var.as_deref()),
+            None => tenant,
+        };
 
         let tenant = match tenant {
-            Some(tenant) => Tenant::from_name(tenant, &http)?,
+            Some(tenant) => tenant_from_name(tenant, cloud.login_endpoint, &http, &cache)?,
             None => Tenant::read_default_tenant()?.unwrap_or(Tenant::common()),
         };
 
-        let access_token_file = AccessTokenFile::new()?;
+        let access_token_file = AccessTokenFile::new(token_store)?;
         let token_sets = access_token_file.read_tokens()?;
 
-        debug!("Client created with tenant: {}", tenant.id);
+        if service_principal.is_some() && tenant.is_common() {
+            return Err(ServiceError(
+                "AZURE_TENANT_ID is required for service principal authentication!",
+            )
+            .into());
+        }
+
+        let arm_endpoint = var("AZI_ARM_ENDPOINT")
+            .map(|endpoint| endpoint.trim_end_matches('/').to_owned())
+            .unwrap_or_else(|_| cloud.arm_endpoint.to_owned());
+        Url::parse(&arm_endpoint)
+            .map_err(|_| ServiceError("AZI_ARM_ENDPOINT is not a valid URL!"))?;
+        let arm_resource =
+            var("AZI_ARM_RESOURCE").unwrap_or_else(|_| cloud.arm_resource.to_owned());
+
+        debug!(
+            "Client created with tenant: {}, endpoint: {}",
+            tenant.id, arm_endpoint
+        );
 
         Ok(Client {
-            tenant: RefCell::new(tenant),
+            tenant: Mutex::new(tenant),
+            service_principal,
+            login_method,
             access_token_file,
-            token_sets: RefCell::new(token_sets),
+            token_sets: Mutex::new(token_sets),
             http,
+            cache,
+            arm_endpoint,
+            arm_resource,
+            login_endpoint: cloud.login_endpoint,
+            max_items: None,
+            ca_bundle,
+            timeouts,
+            failed_requests: AtomicUsize::new(0),
         })
     }
 
+    pub fn with_max_items(mut self, max_items: Option<usize>) -> Self {
+        self.max_items = max_items;
+        self
+    }
+
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.http = self.http.with_max_retries(max_retries);
+        self
+    }
+
+    pub fn with_max_response_size(mut self, max_response_size: Option<u64>) -> Self {
+        self.http = self.http.with_max_response_size(max_response_size);
+        self
+    }
+
+    pub fn with_request_id(mut self, request_id: String) -> Self {
+        self.http = self.http.with_request_id(request_id);
+        self
+    }
+
     pub fn new_request<'c>(&'c self, url: &'c str, resource: &'c str) -> Request<'c> {
         return Request {
             client: &self,
             url,
             resource,
-            query: ("", ""),
+            query: vec![],
             body: None,
+            content_type: None,
+            cached: true,
         };
     }
 
@@ -118,52 +352,368 @@ impl Client {
         &self.http
     }
 
+    /// Base URL of the Resource Manager, without a trailing slash
+    pub fn arm_endpoint(&self) -> &str {
+        &self.arm_endpoint
+    }
+
+    /// Counts a failed request which was only warned about, as the output is incomplete
+    pub fn record_failure(&self) {
+        self.failed_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn failed_requests(&self) -> usize {
+        self.failed_requests.load(Ordering::Relaxed)
+    }
+
+    /// Whether only cached responses are used, with `--offline`
+    pub fn is_offline(&self) -> bool {
+        self.cache.mode() == CacheMode::Offline
+    }
+
+    /// When the oldest of the cached responses used so far was requested
+    pub fn oldest_cached(&self) -> Option<SystemTime> {
+        self.cache.oldest_entry()
+    }
+
+    pub fn ca_bundle(&self) -> Option<&str> {
+        self.ca_bundle.as_deref()
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+
+    pub fn max_response_size(&self) -> Option<u64> {
+        self.http.max_response_size()
+    }
+
+    /// Resource to request Resource Manager tokens for
+    pub fn arm_resource(&self) -> &str {
+        &self.arm_resource
+    }
+
+    /// Executes GET requests for all URLs, at most `parallelism` at a time.
+    /// Failed requests are logged and result in `Value::Null`, denied requests are only counted
+    pub fn get_all(
+        &self,
+        urls: &[String],
+        resource: &str,
+        parallelism: usize,
+    ) -> Result<Vec<Value>> {
+        let token_set = self.get_token_set(CLIENT_ID, resource)?;
+        let token = token_set.access_token.token();
+        let http = &self.http;
+
+        let mut values = Vec::with_capacity(urls.len());
+        let mut denied = 0;
+        for chunk in urls.chunks(parallelism.max(1)) {
+            // The error is `None` if access was denied
+            let responses: Vec<std::result::Result<Value, Option<String>>> = scope(|scope| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|url| {
+                        scope.spawn(move || {
+                            let headers = vec![Header::content_json(), Header::auth_bearer(token)];
+                            match http.execute(url, Some(&headers), None) {
+                                Ok(Response::Success(json)) => Ok(json),
+                                Ok(Response::Error(403, _)) => Err(None),
+                                Ok(Response::Error(status, _)) => {
+                                    Err(Some(format!("HTTP error {}", status)))
+                                }
+                                Ok(Response::Accepted(_)) | Ok(Response::NotModified) => {
+                                    Err(Some("request not completed".to_owned()))
+                                }
+                                Err(err) => Err(Some(err.to_string())),
+                            }
+                        })
+                    })
+                    .collect();
+                handles
+                    .into_iter()
+                    .map(|handle| {
+                        handle
+                            .join()
+                            .unwrap_or_else(|_| Err(Some("thread failed".to_owned())))
+                    })
+                    .collect()
+            });
+
+            for (url, response) in chunk.iter().zip(responses) {
+                match response {
+                    Ok(json) => values.push(json),
+                    Err(Some(err)) => {
+                        warn!("Request failed: {}: {}", url, err);
+                        self.record_failure();
+                        values.push(Value::Null);
+                    }
+                    Err(None) => {
+                        debug!("Access denied: {}", url);
+                        self.record_failure();
+                        denied += 1;
+                        values.push(Value::Null);
+                    }
+                }
+            }
+        }
+        if denied > 0 {
+            warn!("Access denied for {} of {} requests", denied, urls.len());
+        }
+        Ok(values)
+    }
+
+    /// Executes GET requests on the Resource Manager with one round trip for each
+    /// `BATCH_SIZE` requests. Responses are paged and cached like single requests,
+    /// throttled requests and those missing from the batch response are executed separately
+    pub fn get_batch(&self, requests: &[Request]) -> Result<Vec<Result<Value>>> {
+        let mut results: Vec<Option<Result<Value>>> = Vec::with_capacity(requests.len());
+        let mut pending = vec![];
+        for (index, request) in requests.iter().enumerate() {
+            match self.cache.get(&self.cache_key(request)?) {
+                Some(value) => results.push(Some(Ok(value))),
+                None => {
+                    results.push(None);
+                    pending.push(index);
+                }
+            }
+        }
+
+        // Offline, the missing responses fail one by one below
+        if self.is_offline() {
+            pending.clear();
+        }
+        let batch_url = format!("{}/batch?api-version=2020-06-01", self.arm_endpoint);
+        for chunk in pending.chunks(BATCH_SIZE) {
+            let mut batch = Vec::with_capacity(chunk.len());
+            for &index in chunk {
+                batch.push(json!({
+                    "name": index.to_string(),
+                    "httpMethod": "GET",
+                    "url": request_url(&requests[index])?,
+                }));
+            }
+            let body = json!({ "requests": batch }).to_string();
+
+            debug!("Requesting batch of {} requests", chunk.len());
+            let batch_request = self.new_request(&batch_url, &self.arm_resource);
+            let responses = match batch_request.body(&body).post_raw() {
+                Ok(json) => json["responses"].as_array().cloned().unwrap_or_default(),
+                Err(err) => {
+                    debug!("Batch request failed, requesting separately: {}", err);
+                    vec![]
+                }
+            };
+
+            for (position, response) in responses.iter().enumerate() {
+                let index = match batch_index(response, position, chunk) {
+                    Some(index) => index,
+                    None => continue,
+                };
+
+                let request = &requests[index];
+                let content = &response["content"];
+                let status = response["httpStatusCode"].as_u64().unwrap_or(0) as u16;
+                results[index] = match status {
+                    200..=299 => {
+                        let truncated = truncated_responses();
+                        Some(self.get_pages(request, content).and_then(|value| {
+                            if truncated_responses() == truncated {
+                                self.cache.put(&self.cache_key(request)?, &value, None)?;
+                            }
+                            Ok(value)
+                        }))
+                    }
+                    // Repeated as single requests, with retries and token refresh
+                    401 | 429 | 500..=599 => None,
+                    _ => Some(Err(HttpError(status, content.clone()).into())),
+                };
+            }
+        }
+
+        Ok(results
+            .into_iter()
+            .zip(requests)
+            .map(|(result, request)| match result {
+                Some(result) => result,
+                None => self.request(request),
+            })
+            .collect())
+    }
+
+    fn cache_key(&self, request: &Request) -> Result<String> {
+        let tenant = lock(&self.tenant)?;
+        let query: Vec<String> = request
+            .query
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        Ok(match self.max_items {
+            Some(max_items) => format!(
+                "{} {}?{} max-items={}",
+                tenant.id,
+                request.url,
+                query.join("&"),
+                max_items
+            ),
+            None => format!("{} {}?{}", tenant.id, request.url, query.join("&")),
+        })
+    }
+
     fn request(&self, request: &Request) -> Result<Value> {
+        let key = self.cache_key(request)?;
+        if request.body.is_some() {
+            // A cached GET of the same URL may be outdated by the POST
+            self.cache.remove(&key)?;
+            return self.request_uncached(request, None)?.into_value();
+        }
+        if !request.cached {
+            if self.is_offline() {
+                return Err(Offline(request_url(request)?).into());
+            }
+            return self.request_uncached(request, None)?.into_value();
+        }
+
+        if let Some(value) = self.cache.get(&key) {
+            return Ok(value);
+        }
+        if self.is_offline() {
+            return Err(Offline(request_url(request)?).into());
+        }
+
+        // Expired entries with an ETag are revalidated instead of transferred again
+        let cached = self.cache.get_etag(&key);
+        let etag = cached.as_ref().map(|(etag, _)| etag.as_str());
+        let truncated = truncated_responses();
+        match self.request_uncached(request, etag)? {
+            // Not cached when truncated, so that hits don't hide the missing items
+            Fetched::Value(value, _) if truncated_responses() != truncated => Ok(value),
+            Fetched::Value(value, etag) => {
+                self.cache.put(&key, &value, etag.as_deref())?;
+                Ok(value)
+            }
+            Fetched::NotModified => match cached {
+                Some((etag, value)) => {
+                    self.cache.put(&key, &value, Some(&etag))?;
+                    Ok(value)
+                }
+                None => Err(HttpError(304, Value::Null).into()),
+            },
+        }
+    }
+
+    fn request_uncached(&self, request: &Request, etag: Option<&str>) -> Result<Fetched> {
         let token_set = self.get_token_set(CLIENT_ID, request.resource)?;
-        match self.execute_request(request, &token_set)? {
-            Response::Success(json) => self.get_value(&json),
-            Response::Error(_, json) => self.try_rerequest(&token_set, request, &json),
+        match self.execute_conditional(request, &token_set, etag)? {
+            (Response::Success(json), etag) => {
+                // Only single responses, the ETag of a page doesn't match the merged list
+                let etag = match get_next_link(&json) {
+                    Some(_) => None,
+                    None => etag,
+                };
+                Ok(Fetched::Value(self.get_pages(request, &json)?, etag))
+            }
+            (Response::Accepted(location), _) => {
+                Ok(Fetched::Value(self.poll_result(request, &location)?, None))
+            }
+            (Response::NotModified, _) => Ok(Fetched::NotModified),
+            (Response::Error(status, json), _) => Ok(Fetched::Value(
+                self.try_rerequest(&token_set, request, status, &json)?,
+                None,
+            )),
+        }
+    }
+
+    /// Follows the next links of a paged list, merging all pages into one array
+    fn get_pages(&self, request: &Request, json: &Value) -> Result<Value> {
+        let mut value = self.get_value(json)?;
+        if request.query.iter().any(|(name, _)| *name == "$top") {
+            // Only the requested number of items
+            return Ok(value);
+        }
+        if let Some(items) = value.as_array_mut() {
+            let mut next_link = get_next_link(json);
+            while let Some(link) = next_link {
+                if let Some(max_items) = self.max_items {
+                    if items.len() >= max_items {
+                        warn!("Stopping after {} items, more are available", items.len());
+                        break;
+                    }
+                }
+
+                debug!("Requesting next page: {}", link);
+                let token_set = self.get_token_set(CLIENT_ID, request.resource)?;
+                let page = self.new_request(&link, request.resource);
+                let json = self.execute_request(&page, &token_set)?.success()?;
+                if let Value::Array(page_items) = self.get_value(&json)? {
+                    items.extend(page_items);
+                }
+                next_link = get_next_link(&json);
+            }
         }
+        Ok(value)
     }
 
+    /// Waits for the result of a long-running operation
+    fn poll_result(&self, request: &Request, location: &str) -> Result<Value> {
+        loop {
+            sleep(Duration::from_millis(POLL_INTERVAL));
+
+            let token_set = self.get_token_set(CLIENT_ID, request.resource)?;
+            let poll = self.new_request(location, request.resource);
+            match self.execute_request(&poll, &token_set)? {
+                Response::Success(json) => return self.get_value(&json),
+                Response::Accepted(_) => debug!("Operation still in progress..."),
+                Response::NotModified => return Err(HttpError(304, Value::Null).into()),
+                Response::Error(status, json) => return Err(HttpError(status, json).into()),
+            }
+        }
+    }
+
+    /// Retries the request once with a new token for its resource if the token was rejected
     fn try_rerequest(
         &self,
         token_set: &TokenSet,
         request: &Request,
+        status: u16,
         json: &Value,
     ) -> Result<Value> {
+        if is_token_rejected(status, json) {
+            debug!("Auth token rejected for {}!", request.resource);
+            let token_set = self.refresh_token(CLIENT_ID, request.resource, token_set)?;
+            let json = self.execute_request(request, &token_set)?.success()?;
+            return self.get_pages(request, &json);
+        }
         if let Some(code) = json["error"]["code"].as_str() {
-            if code == "ExpiredAuthenticationToken" || code == "AuthenticationFailed" {
-                debug!("Auth token expired!");
-                let token_set = self.refresh_token(CLIENT_ID, request.resource, token_set)?;
-                let json = self.execute_request(request, &token_set)?.success()?;
-                return self.get_value(&json);
-            } else {
-                debug!("Unknown error: {}", code);
-            }
+            debug!("Unknown error: {}", code);
         }
         Err(UnexpectedJson(json.clone()).into())
     }
 
     fn execute_request(&self, request: &Request, tokens: &TokenSet) -> Result<Response> {
-        let (key, value) = request.query;
-        let url = if key.len() > 0 && value.len() > 0 {
-            let mut url = Url::parse(request.url)?;
-            url.query_pairs_mut().append_pair(key, value);
-            url.to_string()
-        } else {
-            request.url.to_owned()
-        };
+        Ok(self.execute_conditional(request, tokens, None)?.0)
+    }
+
+    /// Executes the request with `If-None-Match` if an ETag is given,
+    /// returning the response and its ETag
+    fn execute_conditional(
+        &self,
+        request: &Request,
+        tokens: &TokenSet,
+        etag: Option<&str>,
+    ) -> Result<(Response, Option<String>)> {
+        let url = request_url(request)?;
 
         let access_token = tokens.access_token.token();
-        self.http.execute(
-            &url,
-            Some(&vec![
-                Header::content_json(),
-                Header::auth_bearer(access_token),
-            ]),
-            request.body,
-        )
+        let content_type = match request.content_type {
+            Some(content_type) => Header::new("Content-Type", content_type.to_owned()),
+            None => Header::content_json(),
+        };
+        let mut headers = vec![content_type, Header::auth_bearer(access_token)];
+        if let Some(etag) = etag {
+            headers.push(Header::if_none_match(etag));
+        }
+        self.http
+            .execute_with_etag(&url, Some(&headers), request.body)
     }
 
     fn get_value(&self, json: &Value) -> Result<Value> {
@@ -176,13 +726,17 @@ impl Client {
     }
 
     pub fn get_token_set(&self, client_id: &str, resource: &str) -> Result<TokenSet> {
+        if let Some(service_principal) = &self.service_principal {
+            return self.get_service_principal_token_set(service_principal, resource);
+        }
+
         let authority = {
-            let tenant = self.tenant.try_borrow()?;
+            let tenant = lock(&self.tenant)?;
             tenant.authority()
         };
 
         if let Some(token_set) = {
-            let token_sets = self.token_sets.try_borrow()?;
+            let token_sets = lock(&self.token_sets)?;
             TokenSet::find(&token_sets, client_id, &authority, Some(resource))
         } {
             if token_set.access_token.is_expired() {
@@ -195,7 +749,7 @@ impl Client {
         }
 
         if let Some(token_set) = {
-            let token_sets = self.token_sets.try_borrow()?;
+            let token_sets = lock(&self.token_sets)?;
             TokenSet::find(&token_sets, client_id, &authority, None)
         } {
             debug!("Trying to get access from existing refresh token...");
@@ -206,18 +760,84 @@ impl Client {
         return self.request_new_token(client_id, resource);
     }
 
+    fn get_service_principal_token_set(
+        &self,
+        service_principal: &ServicePrincipal,
+        resource: &str,
+    ) -> Result<TokenSet> {
+        let authority = {
+            let tenant = lock(&self.tenant)?;
+            tenant.authority()
+        };
+
+        if let Some(token_set) = {
+            let token_sets = lock(&self.token_sets)?;
+            TokenSet::find(
+                &token_sets,
+                &service_principal.client_id,
+                &authority,
+                Some(resource),
+            )
+        } {
+            if !token_set.access_token.is_expired() {
+                return Ok(token_set);
+            }
+        }
+
+        return self.request_service_principal_token(service_principal, resource);
+    }
+
+    fn request_service_principal_token(
+        &self,
+        service_principal: &ServicePrincipal,
+        resource: &str,
+    ) -> Result<TokenSet> {
+        debug!(
+            "Requesting token with client assertion for {}",
+            service_principal.client_id
+        );
+
+        let tenant_id = {
+            let tenant = lock(&self.tenant)?;
+            tenant.id.clone()
+        };
+
+        let url = format!("{}/{}/oauth2/token", self.login_endpoint, tenant_id);
+        let assertion = service_principal.client_assertion(&url)?;
+        let body = format!(
+            "grant_type=client_credentials&client_id={}&client_assertion_type={}&client_assertion={}&resource={}",
+            service_principal.client_id, CLIENT_ASSERTION_TYPE, assertion, resource
+        );
+
+        let json = self
+            .http
+            .execute(&url, Some(&vec![Header::content_form()]), Some(&body))?
+            .success()?;
+        let token_set = TokenSet::from_json(&json)?;
+
+        let mut token_sets = lock(&self.token_sets)?;
+        token_sets.retain(|t| !t.matches(&token_set));
+        token_sets.push(token_set.clone());
+
+        return Ok(token_set);
+    }
+
     fn refresh_token(
         &self,
         client_id: &str,
         resource: &str,
         token_set: &TokenSet,
     ) -> Result<TokenSet> {
+        if let Some(service_principal) = &self.service_principal {
+            return self.request_service_principal_token(service_principal, resource);
+        }
+
         debug!("Refreshing token with {} for {}", client_id, resource);
 
         trace!("Current token: {:?}", token_set);
 
         let tenant_id = {
-            let tenant = self.tenant.try_borrow()?;
+            let tenant = lock(&self.tenant)?;
             tenant.id.clone()
         };
 
@@ -225,10 +845,7 @@ impl Client {
             "client_id={}&refresh_token={}&grant_type=refresh_token&resource={}",
             client_id, token_set.refresh_token, resource
         );
-        let refresh_url = format!(
-            "https://login.microsoftonline.com/{}/oauth2/token",
-            tenant_id
-        );
+        let refresh_url = format!("{}/{}/oauth2/token", self.login_endpoint, tenant_id);
 
         match self.http.execute(
             &refresh_url,
@@ -240,6 +857,7 @@ impl Client {
                 self.update_tokens(&token_set)?;
                 return Ok(token_set);
             }
+            Response::Accepted(_) | Response::NotModified => Err(HttpClientError.into()),
             Response::Error(_, json) => {
                 let error = json["error"].as_str();
                 if error == Some("invalid_grant") || error == Some("interaction_required") {
@@ -253,11 +871,96 @@ impl Client {
     }
 
     fn request_new_token(&self, client_id: &str, resource: &str) -> Result<TokenSet> {
-        let tenant = self.tenant.try_borrow()?;
+        match self.login_method {
+            LoginMethod::DeviceCode => self.request_device_code_token(client_id, resource),
+            LoginMethod::Browser => self.request_browser_token(client_id, resource),
+        }
+    }
+
+    /// Authorization code flow with PKCE, receiving the code on a localhost redirect URI
+    fn request_browser_token(&self, client_id: &str, resource: &str) -> Result<TokenSet> {
+        let tenant = lock(&self.tenant)?;
+
+        let listener = RedirectListener::new()?;
+        let redirect_uri = listener.redirect_uri();
+        let pkce = Pkce::new()?;
+
+        let mut url = Url::parse(&format!(
+            "{}/{}/oauth2/authorize",
+            self.login_endpoint, tenant.id
+        ))?;
+        url.query_pairs_mut()
+            .append_pair("client_id", client_id)
+            .append_pair("response_type", "code")
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("resource", resource)
+            .append_pair("prompt", "select_account")
+            .append_pair("code_challenge", &pkce.challenge)
+            .append_pair("code_challenge_method", "S256")
+            .append_pair("state", &pkce.state);
+
+        eprintln!("Opening the browser to sign in: {}", url);
+        if let Err(err) = open_browser(url.as_str()) {
+            debug!("Failed to open the browser: {}", err);
+        }
+
+        let params = listener.receive()?;
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        if param("state") != Some(&pkce.state) {
+            return Err(ServiceError("Invalid state in authorization response!").into());
+        }
+        let code = match param("code") {
+            Some(code) => code,
+            None => {
+                warn!(
+                    "Authorization failed: {}",
+                    param("error_description").or(param("error")).unwrap_or("")
+                );
+                return Err(ServiceError("Authorization failed!").into());
+            }
+        };
+
+        let url = format!("{}/{}/oauth2/token", self.login_endpoint, tenant.id);
+        let mut body = Url::parse("http://localhost")?;
+        body.query_pairs_mut()
+            .append_pair("grant_type", "authorization_code")
+            .append_pair("client_id", client_id)
+            .append_pair("code", code)
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("resource", resource)
+            .append_pair("code_verifier", &pkce.verifier);
+        let body = body.query().unwrap_or("").to_owned();
+
+        let json = self
+            .http
+            .execute(&url, Some(&vec![Header::content_form()]), Some(&body))?
+            .success()?;
+
+        let token_set = TokenSet::from_json(&json)?;
+        self.update_tokens(&token_set)?;
+
+        if tenant.is_common() {
+            drop(tenant);
+            *lock(&self.tenant)? = token_set.access_token.tenant.clone();
+        }
+
+        eprintln!("Authentication successful!");
+
+        return Ok(token_set);
+    }
+
+    fn request_device_code_token(&self, client_id: &str, resource: &str) -> Result<TokenSet> {
+        let tenant = lock(&self.tenant)?;
 
         let url = format!(
-            "https://login.microsoftonline.com/{}/oauth2/devicecode?api-version=1.0",
-            tenant.id
+            "{}/{}/oauth2/devicecode?api-version=1.0",
+            self.login_endpoint, tenant.id
         );
 
         let body = format!("client_id={}&resource={}", client_id, resource);
@@ -275,10 +978,7 @@ impl Client {
         loop {
             sleep(Duration::from_millis(5000));
 
-            let url = format!(
-                "https://login.microsoftonline.com/{}/oauth2/token",
-                tenant.id
-            );
+            let url = format!("{}/{}/oauth2/token", self.login_endpoint, tenant.id);
             let body = format!(
                 "grant_type=device_code&client_id={}&resource={}&code={}",
                 client_id, resource, device_code
@@ -294,13 +994,14 @@ impl Client {
 
                     if tenant.is_common() {
                         drop(tenant);
-                        self.tenant.replace(token_set.access_token.tenant.clone());
+                        *lock(&self.tenant)? = token_set.access_token.tenant.clone();
                     }
 
                     eprintln!("Authentication successful!");
 
                     return Ok(token_set);
                 }
+                Response::Accepted(_) | Response::NotModified => return Err(HttpClientError.into()),
                 Response::Error(_, json) => {
                     if json["error"].as_str() == Some("authorization_pending") {
                         debug!("Authorization pending...");
@@ -313,8 +1014,64 @@ impl Client {
         }
     }
 
+    pub fn rate_limits(&self) -> Vec<RateLimit> {
+        self.http.rate_limits()
+    }
+
+    pub fn tenant(&self) -> Result<Tenant> {
+        Ok(lock(&self.tenant)?.clone())
+    }
+
+    /// Returns the stored token set of the current tenant, without requesting or refreshing tokens
+    pub fn cached_token_set(&self, resource: &str) -> Result<Option<TokenSet>> {
+        let tenant = self.tenant()?;
+        let token_sets = lock(&self.token_sets)?;
+        let token_set = if tenant.is_common() {
+            token_sets
+                .iter()
+                .find(|token_set| {
+                    token_set.access_token.app_id == CLIENT_ID && token_set.resource == resource
+                })
+                .cloned()
+        } else {
+            TokenSet::find(&token_sets, CLIENT_ID, &tenant.authority(), Some(resource))
+        };
+        Ok(token_set)
+    }
+
+    /// Returns a valid token set for the resource, refreshing or requesting it if needed
+    pub fn token_set(&self, resource: &str) -> Result<TokenSet> {
+        self.get_token_set(CLIENT_ID, resource)
+    }
+
+    /// Requests a new token interactively, even if there already is a valid one
+    pub fn login(&self, resource: &str) -> Result<TokenSet> {
+        if let Some(service_principal) = &self.service_principal {
+            return self.get_service_principal_token_set(service_principal, resource);
+        }
+        self.request_new_token(CLIENT_ID, resource)
+    }
+
+    /// Removes the stored tokens of the current tenant, or all tokens for the common tenant
+    pub fn logout(&self) -> Result<usize> {
+        let tenant = self.tenant()?;
+        let tenant = if tenant.is_common() {
+            None
+        } else {
+            Some(tenant)
+        };
+        let removed = self.access_token_file.remove_tokens(tenant.as_ref())?;
+        lock(&self.token_sets)?.retain(|token_set| match &tenant {
+            Some(tenant) => token_set.access_token.tenant != *tenant,
+            None => false,
+        });
+        debug!("Removed {} tokens", removed);
+        Ok(removed)
+    }
+
     fn update_tokens(&self, token_set: &TokenSet) -> Result<()> {
-        let mut token_sets = { self.token_sets.try_borrow()?.clone() };
+        let mut current = lock(&self.token_sets)?;
+        let mut token_sets = current.clone();
         let mut updated = false;
         for mut t in token_sets.iter_mut() {
             if t.matches(token_set) {
@@ -328,7 +1085,142 @@ impl Client {
             token_sets.push(token_set.clone());
         }
         self.access_token_file.update_tokens(&token_sets)?;
-        self.token_sets.replace(token_sets);
+        *current = token_sets;
         Ok(())
     }
 }
+
+/// Locks the mutex, which is only poisoned if another thread panicked
+fn lock<T>(mutex: &Mutex<T>) -> Result<MutexGuard<'_, T>> {
+    mutex
+        .lock()
+        .map_err(|_| ServiceError("lock poisoned by a failed thread!").into())
+}
+
+fn open_browser(url: &str) -> Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(url).spawn()?;
+    Ok(())
+}
+
+/// Looks up the ID of a tenant given by its domain name, cached like the responses
+/// so that it is also known offline
+fn tenant_from_name(
+    name: &str,
+    login_endpoint: &str,
+    http: &Http,
+    cache: &Cache,
+) -> Result<Tenant> {
+    let key = format!("tenant {}", name);
+    if let Some(id) = cache.get(&key).as_ref().and_then(Value::as_str) {
+        return Tenant::from_name(id, login_endpoint, http);
+    }
+    let tenant = Tenant::from_name(name, login_endpoint, http)?;
+    cache.put(&key, &json!(tenant.id), None)?;
+    Ok(tenant)
+}
+
+/// Returns the index of the request a batch response belongs to, by its name
+/// or otherwise its position in the batch
+fn batch_index(response: &Value, position: usize, batch: &[usize]) -> Option<usize> {
+    response["name"]
+        .as_str()
+        .and_then(|name| name.parse::<usize>().ok())
+        .filter(|index| batch.contains(index))
+        .or_else(|| batch.get(position).copied())
+}
+
+/// The URL of the request with its URL-encoded query parameters
+fn request_url(request: &Request) -> Result<String> {
+    if request.query.is_empty() {
+        Ok(request.url.to_owned())
+    } else {
+        let mut url = Url::parse(request.url)?;
+        url.query_pairs_mut().extend_pairs(&request.query);
+        Ok(url.to_string())
+    }
+}
+
+/// The URL of the next page of a list, `nextLink` in ARM and `@odata.nextLink` in Graph
+fn get_next_link(json: &Value) -> Option<String> {
+    json["nextLink"]
+        .as_str()
+        .or_else(|| json["@odata.nextLink"].as_str())
+        .map(str::to_owned)
+}
+
+/// ARM error codes of rejected tokens, data-plane APIs like Key Vault only respond with a 401
+const TOKEN_ERROR_CODES: &[&str] = &[
+    "ExpiredAuthenticationToken",
+    "AuthenticationFailed",
+    "InvalidAuthenticationToken",
+];
+
+fn is_token_rejected(status: u16, json: &Value) -> bool {
+    status == 401
+        || json["error"]["code"]
+            .as_str()
+            .is_some_and(|code| TOKEN_ERROR_CODES.contains(&code))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::batch_index;
+    use super::get_next_link;
+    use super::is_token_rejected;
+
+    #[test]
+    fn test_get_next_link() {
+        assert_eq!(
+            Some("https://management.azure.com/page2".to_owned()),
+            get_next_link(
+                &json!({ "value": [], "nextLink": "https://management.azure.com/page2" })
+            )
+        );
+        assert_eq!(
+            Some("https://graph.microsoft.com/page2".to_owned()),
+            get_next_link(
+                &json!({ "value": [], "@odata.nextLink": "https://graph.microsoft.com/page2" })
+            )
+        );
+        assert_eq!(None, get_next_link(&json!({ "value": [] })));
+    }
+
+    #[test]
+    fn test_batch_index() {
+        let batch = [20, 21, 22];
+        assert_eq!(Some(22), batch_index(&json!({ "name": "22" }), 0, &batch));
+        assert_eq!(
+            Some(21),
+            batch_index(&json!({ "httpStatusCode": 200 }), 1, &batch)
+        );
+        assert_eq!(Some(20), batch_index(&json!({ "name": "5" }), 0, &batch));
+        assert_eq!(None, batch_index(&json!({}), 3, &batch));
+    }
+
+    #[test]
+    fn test_is_token_rejected() {
+        assert!(is_token_rejected(
+            401,
+            &json!({ "errors": [{ "code": "UNAUTHORIZED" }] })
+        ));
+        assert!(is_token_rejected(
+            400,
+            &json!({ "error": { "code": "ExpiredAuthenticationToken" } })
+        ));
+        assert!(!is_token_rejected(
+            403,
+            &json!({ "error": { "code": "AuthorizationFailed" } })
+        ));
+    }
+}
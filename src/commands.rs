@@ -1,24 +1,176 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::net::ToSocketAddrs;
 
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::SecondsFormat;
+use chrono::Utc;
+use serde::Serialize;
 use serde_derive::Serialize;
+use serde_json::to_value;
 use serde_json::Value;
 
+use crate::error::AppError::ParseError;
+use crate::locale::iso_timestamp;
+use crate::money::Money;
+use crate::object::AutoscaleRule;
+use crate::object::ContainerApp;
+use crate::object::ContainerRestarts;
 use crate::object::Costs;
+use crate::object::DenyAssignment;
 use crate::object::DnsRecord;
 use crate::object::DnsRecordEntry;
+use crate::object::EffectiveRoute;
 use crate::object::Identifiable;
 use crate::object::IpAddress;
 use crate::object::KubernetesObject;
+use crate::object::ManagedEnvironment;
+use crate::object::ManagementLock;
+use crate::object::NetworkSecurityGroup;
+use crate::object::Provider;
+use crate::object::RegionInfo;
 use crate::object::Resource;
 use crate::object::ResourceGroup;
+use crate::object::RoleDefinition;
+use crate::object::Route;
+use crate::object::Subnet;
 use crate::object::Subscription;
+use crate::operations::command_operations;
+use crate::operations::provider;
+use crate::operations::provider_commands;
+use crate::operations::rbac_actions;
+use crate::policy::find_tag;
+use crate::policy::TagPolicy;
+use crate::query::Query;
+use crate::service::merge_kubeconfigs;
 use crate::service::Service;
 use crate::service::Timeframe;
 use crate::service::TYPE_DNS_ZONE;
+use crate::service::TYPE_MANAGED_ENVIRONMENT;
+use crate::service::TYPE_TRAFFIC_MANAGER_PROFILE;
+use crate::service::TYPE_WEB_APP;
+use crate::tenant::read_default_subscription;
+use crate::tenant::write_default_subscription;
+use crate::utils::domain_to_ascii;
+use crate::utils::domain_to_unicode;
+use crate::utils::matches_wildcard;
+use crate::utils::parallel_for_each;
+use crate::utils::parallel_map;
+use crate::utils::parallel_map_ordered;
 use crate::utils::Result;
 
 pub struct Context<'c> {
     pub service: &'c Service,
+    pub parallelism: usize,
+    /// Unless set, subscriptions and other items queried in parallel are listed in the order
+    /// their requests complete
+    pub ordered: bool,
+    /// Receives the result of each subscription as soon as it is complete, which is then
+    /// not returned, for output that can print it without the others, like `-o jsonl`
+    pub stream: Option<&'c dyn Stream>,
+}
+
+/// Prints results while the remaining ones are still being requested
+pub trait Stream: Sync {
+    fn print_result(&self, result: &Value) -> Result<()>;
+}
+
+impl Context<'_> {
+    fn parallel_map<I, T, F>(&self, items: &[I], function: F) -> Result<Vec<T>>
+    where
+        I: Sync,
+        T: Send,
+        F: Fn(&I) -> Result<T> + Sync,
+    {
+        parallel_map_ordered(items, self.parallelism, self.ordered, function)
+    }
+
+    /// Like `parallel_map` for the results of a command, where an item can have no result.
+    /// With a stream, the results are passed to it in the order of `ordered` instead
+    fn parallel_results<I, T, F>(&self, items: &[I], function: F) -> Result<Vec<T>>
+    where
+        I: Sync,
+        T: Send + Serialize,
+        F: Fn(&I) -> Result<Option<T>> + Sync,
+    {
+        let mut results = vec![];
+        parallel_for_each(
+            items,
+            self.parallelism,
+            self.ordered,
+            function,
+            |result| match result {
+                Some(result) => self.emit(&mut results, result),
+                None => Ok(()),
+            },
+        )?;
+        return Ok(results);
+    }
+
+    /// Adds a result, or with a stream, passes it on right away
+    fn emit<T: Serialize>(&self, results: &mut Vec<T>, result: T) -> Result<()> {
+        match self.stream {
+            Some(stream) => stream.print_result(&to_value(&result)?),
+            None => {
+                results.push(result);
+                Ok(())
+            }
+        }
+    }
+}
+
+const TYPE_RESOURCE_GROUP: &str = "Microsoft.Resources/subscriptions/resourceGroups";
+const TYPE_PUBLIC_IP_ADDRESS: &str = "Microsoft.Network/publicIPAddresses";
+const TYPE_VIRTUAL_NETWORK_GATEWAY: &str = "Microsoft.Network/virtualNetworkGateways";
+const TYPE_VIRTUAL_MACHINE: &str = "Microsoft.Compute/virtualMachines";
+
+/// Management locks and deny assignments of a subscription, which prevent
+/// the deletion of resources
+struct Protections {
+    locks: Vec<ManagementLock>,
+    deny_assignments: Vec<DenyAssignment>,
+}
+
+impl Protections {
+    fn get(service: &Service, subscription_id: &str) -> Result<Protections> {
+        Ok(Protections {
+            locks: service.get_locks(subscription_id)?,
+            deny_assignments: service.get_deny_assignments(subscription_id)?,
+        })
+    }
+
+    fn of(&self, id: &str, resource_type: &str) -> Vec<String> {
+        let id = id.to_lowercase();
+        let in_scope = |scope: &str| {
+            let scope = scope.to_lowercase();
+            id == scope || id.starts_with(&format!("{}/", scope))
+        };
+
+        let mut protection: Vec<String> = self
+            .locks
+            .iter()
+            .filter(|lock| in_scope(lock.scope()))
+            .map(|lock| lock.properties.level.clone())
+            .collect();
+        protection.sort();
+        protection.dedup();
+
+        let action = format!("{}/delete", resource_type);
+        for deny_assignment in &self.deny_assignments {
+            if in_scope(&deny_assignment.properties.scope) && deny_assignment.denies_action(&action)
+            {
+                protection.push(format!(
+                    "DenyAssignment:{}",
+                    deny_assignment.properties.deny_assignment_name
+                ));
+            }
+        }
+        protection
+    }
 }
 
 #[derive(Serialize)]
@@ -29,28 +181,181 @@ pub struct ListResult {
     pub resources: Vec<Resource>,
 }
 
+pub struct ListOptions<'a> {
+    /// Also list resources, not only resource groups
+    pub resources: bool,
+    /// Fetch the complete properties of each resource
+    pub full: bool,
+    pub annotate_locks: bool,
+    pub annotate_zones: bool,
+    /// Only list resources which could, but don't use availability zones
+    pub zone_audit: bool,
+    /// Only list items in this Azure region
+    pub location: Option<&'a str>,
+    /// Only list resources of this type
+    pub resource_type: Option<&'a str>,
+    /// Query all subscriptions at once with Azure Resource Graph
+    pub use_graph: bool,
+    /// Stop querying further subscriptions once the page is filled
+    pub page: Page,
+}
+
+/// The leaf items to print, from `--offset` and `--limit`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Page {
+    pub offset: usize,
+    pub limit: Option<usize>,
+}
+
+impl Page {
+    /// The number of items needed to fill the page, `None` if all are needed
+    pub fn end(&self) -> Option<usize> {
+        self.limit.map(|limit| self.offset + limit)
+    }
+
+    /// Skips the first `offset` items and keeps at most `limit` of the rest
+    pub fn apply<T>(&self, items: &mut Vec<T>) {
+        items.drain(..self.offset.min(items.len()));
+        if let Some(limit) = self.limit {
+            items.truncate(limit);
+        }
+    }
+
+    /// Like `apply`, for the leaf items of each result,
+    /// removing results without any items left
+    pub fn apply_nested<R, T>(&self, results: &mut Vec<R>, leaves: impl Fn(&mut R) -> &mut Vec<T>) {
+        let mut skip = self.offset;
+        let mut remaining = self.limit.unwrap_or(usize::MAX);
+        results.retain_mut(|result| {
+            let items = leaves(result);
+            let skipped = skip.min(items.len());
+            items.drain(..skipped);
+            skip -= skipped;
+            items.truncate(remaining);
+            remaining -= items.len();
+            !items.is_empty()
+        });
+    }
+}
+
+/// The field to sort results by with `--sort`, within each subscription unless it is `subscription`
+#[derive(Debug, Clone)]
+pub struct Sort {
+    pub field: String,
+    pub reverse: bool,
+}
+
+impl Sort {
+    /// Sorts the items by their key, keeping the order of items with equal keys
+    fn apply<T>(&self, items: &mut [T], key: impl Fn(&T) -> SortKey) {
+        items.sort_by(|a, b| {
+            let ordering = key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal);
+            if self.reverse {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
+
+/// Numbers and IP addresses are compared by value, text ignoring case
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortKey {
+    Number(f64),
+    Ip(IpAddr),
+    Text(String),
+}
+
+impl SortKey {
+    fn text(value: &str) -> SortKey {
+        SortKey::Text(value.to_lowercase())
+    }
+
+    fn ip(value: &str) -> SortKey {
+        match value.parse() {
+            Ok(ip) => SortKey::Ip(ip),
+            Err(_) => SortKey::text(value),
+        }
+    }
+}
+
+/// Compares Azure regions, which may be given by name (`westeurope`) or display name (`West Europe`)
+fn is_location(location: &str, region: &str) -> bool {
+    let normalize = |s: &str| s.replace(' ', "").to_lowercase();
+    normalize(location) == normalize(region)
+}
+
 pub fn list(
     context: &Context,
-    list_resources: bool,
+    options: &ListOptions,
     filter: Option<&String>,
 ) -> Result<Vec<ListResult>> {
     let service = &context.service;
+    let list_resources = options.resources;
 
-    let mut results = vec![];
+    let subscriptions = service.get_subscriptions()?;
 
-    for subscription in service.get_subscriptions()? {
-        let mut resource_groups = service.get_resource_groups(&subscription.subscription_id)?;
+    // A single Resource Graph query instead of requests for each subscription
+    let graph = if options.use_graph {
+        let subscription_ids: Vec<&str> = subscriptions
+            .iter()
+            .map(|subscription| subscription.subscription_id.as_str())
+            .collect();
+        let listing = service
+            .get_graph_resource_groups(&subscription_ids)
+            .and_then(|resource_groups| {
+                let resources = if list_resources {
+                    service.get_graph_resources(&subscription_ids, options.resource_type)?
+                } else {
+                    HashMap::new()
+                };
+                Ok((resource_groups, resources))
+            });
+        match listing {
+            Ok(listing) => Some(listing),
+            Err(err) => {
+                warn!(
+                    "Resource Graph query failed, listing each subscription instead: {}",
+                    err
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let list_subscription = |subscription: &Subscription| -> Result<Option<ListResult>> {
+        let key = subscription.subscription_id.to_lowercase();
+        let mut resource_groups = match &graph {
+            Some((resource_groups, _)) => resource_groups.get(&key).cloned().unwrap_or_default(),
+            None => service.get_resource_groups(&subscription.subscription_id)?,
+        };
         if !list_resources {
             if let Some(filter) = filter {
                 resource_groups.retain(|group| group.name.contains(filter));
             }
+            if let Some(location) = options.location {
+                resource_groups.retain(|group| is_location(&group.location, location));
+            }
         }
 
-        let resources = if list_resources {
-            let mut resources = service.get_resources(&subscription.subscription_id)?;
+        let mut resources = if list_resources {
+            let mut resources = match (&graph, options.resource_type) {
+                (Some((_, resources)), _) => resources.get(&key).cloned().unwrap_or_default(),
+                (None, Some(resource_type)) => {
+                    service.get_resources_by_type(&subscription.subscription_id, resource_type)?
+                }
+                (None, None) => service.get_resources(&subscription.subscription_id)?,
+            };
             if let Some(filter) = filter {
                 resources.retain(|resource| resource.name.contains(filter));
-
+            }
+            if let Some(location) = options.location {
+                resources.retain(|resource| is_location(&resource.location, location));
+            }
+            if filter.is_some() || options.location.is_some() || options.resource_type.is_some() {
                 resource_groups.retain(|group| {
                     for resource in &resources {
                         if let Ok(resource_group) = resource.resource_group() {
@@ -62,25 +367,286 @@ pub fn list(
                     false
                 });
             }
+            if options.full {
+                let properties = service.get_resource_properties(
+                    &subscription.subscription_id,
+                    &resources,
+                    context.parallelism,
+                )?;
+                for (resource, properties) in resources.iter_mut().zip(properties) {
+                    if !properties.is_null() {
+                        resource.properties = Some(properties);
+                    }
+                }
+            }
             resources
         } else {
             vec![]
         };
 
+        retain_tagged(service, subscription, &mut resource_groups, &mut resources);
+
+        if options.annotate_zones || options.zone_audit {
+            let regions: HashMap<String, RegionInfo> = service
+                .get_locations(&subscription.subscription_id)?
+                .iter()
+                .map(|location| (location.name.to_lowercase(), location.region_info()))
+                .collect();
+            for resource_group in &mut resource_groups {
+                resource_group.region = regions
+                    .get(&resource_group.location.to_lowercase())
+                    .cloned();
+            }
+            for resource in &mut resources {
+                resource.region = regions.get(&resource.location.to_lowercase()).cloned();
+            }
+            if options.zone_audit {
+                resources.retain(is_non_zonal);
+                resource_groups.retain(|group| {
+                    resources
+                        .iter()
+                        .any(|resource| match resource.resource_group() {
+                            Ok(name) => name.eq_ignore_ascii_case(&group.name),
+                            Err(_) => false,
+                        })
+                });
+            }
+        }
+
+        if options.annotate_locks {
+            let protections = Protections::get(service, &subscription.subscription_id)?;
+            for resource_group in &mut resource_groups {
+                resource_group.protection = protections.of(&resource_group.id, TYPE_RESOURCE_GROUP);
+            }
+            for resource in &mut resources {
+                resource.protection = protections.of(&resource.id, &resource.resource_type);
+            }
+        }
+
         if (list_resources && !resources.is_empty())
             || (!list_resources && !resource_groups.is_empty())
         {
-            results.push(ListResult {
-                subscription,
+            Ok(Some(ListResult {
+                subscription: subscription.clone(),
                 resource_groups,
                 resources,
-            });
+            }))
+        } else {
+            Ok(None)
+        }
+    };
+
+    // With --limit, only as many subscriptions are queried as needed to fill the page
+    let mut results: Vec<ListResult> = vec![];
+    let chunk_size = match options.page.end() {
+        Some(_) => context.parallelism.max(1),
+        None => subscriptions.len().max(1),
+    };
+    for chunk in subscriptions.chunks(chunk_size) {
+        results.extend(context.parallel_results(chunk, list_subscription)?);
+
+        let items: usize = results
+            .iter()
+            .map(|result| match list_resources {
+                true => result.resources.len(),
+                false => result.resource_groups.len(),
+            })
+            .sum();
+        if options.page.end().is_some_and(|end| items >= end) {
+            debug!("Page filled, not querying further subscriptions");
+            break;
+        }
+    }
+
+    return Ok(results);
+}
+
+/// Pages the resources, or the resource groups if no resources are listed,
+/// keeping only the resource groups of the remaining resources
+pub fn page_list_results(results: &mut Vec<ListResult>, page: &Page, list_resources: bool) {
+    if !list_resources {
+        page.apply_nested(results, |result| &mut result.resource_groups);
+        return;
+    }
+    page.apply_nested(results, |result| &mut result.resources);
+    for result in results {
+        let resources = &result.resources;
+        result.resource_groups.retain(|group| {
+            resources
+                .iter()
+                .any(|resource| match resource.resource_group() {
+                    Ok(name) => name.eq_ignore_ascii_case(&group.name),
+                    Err(_) => false,
+                })
+        });
+    }
+}
+
+/// Sorts the resource groups and resources by one of `LIST_FIELD_NAMES`
+pub fn sort_list_results(results: &mut Vec<ListResult>, sort: &Sort) {
+    if sort.field == "subscription" {
+        return sort.apply(results, |result| SortKey::text(&result.subscription.name));
+    }
+    for result in results {
+        match sort.field.as_str() {
+            "resourceGroup" | "name" => sort.apply(&mut result.resource_groups, |group| {
+                SortKey::text(&group.name)
+            }),
+            "location" => sort.apply(&mut result.resource_groups, |group| {
+                SortKey::text(&group.location)
+            }),
+            "id" => sort.apply(&mut result.resource_groups, |group| {
+                SortKey::text(&group.id)
+            }),
+            _ => (),
         }
+        sort.apply(&mut result.resources, |resource| {
+            match sort.field.as_str() {
+                "resourceGroup" => SortKey::text(resource.resource_group().unwrap_or_default()),
+                "name" => SortKey::text(&resource.name),
+                "type" => SortKey::text(&resource.resource_type),
+                "location" => SortKey::text(&resource.location),
+                "zones" => SortKey::text(&resource.zones.join(",")),
+                _ => SortKey::text(&resource.id),
+            }
+        });
+    }
+}
+
+#[derive(Serialize)]
+pub struct ResourcesResult {
+    pub subscription: Subscription,
+    pub resources: Vec<TypedResource>,
+}
+
+#[derive(Serialize)]
+pub struct TypedResource {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "resourceGroup")]
+    pub resource_group: String,
+    pub location: String,
+    pub tags: HashMap<String, String>,
+    /// The complete resource as returned by its provider, only for JSON output
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub raw: Value,
+}
+
+/// Lists the resources of any type like `Microsoft.Web/staticSites`,
+/// requesting each of them completely if `raw` is set
+pub fn resources(
+    context: &Context,
+    resource_type: &str,
+    api_version: Option<&str>,
+    raw: bool,
+) -> Result<Vec<ResourcesResult>> {
+    if !resource_type.contains('/') {
+        return Err(ParseError(format!(
+            "invalid resource type, expected <provider>/<type>: {}",
+            resource_type
+        ))
+        .into());
     }
 
+    let service = &context.service;
+
+    let subscriptions = service.get_subscriptions()?;
+    let results = context.parallel_results(&subscriptions, |subscription| {
+        let resources =
+            service.get_resources_by_type(&subscription.subscription_id, resource_type)?;
+        if resources.is_empty() {
+            return Ok(None);
+        }
+
+        let values = if raw {
+            service.get_resource_values(
+                &subscription.subscription_id,
+                &resources,
+                api_version,
+                context.parallelism,
+            )?
+        } else {
+            vec![Value::Null; resources.len()]
+        };
+
+        let mut typed = vec![];
+        for (resource, raw) in resources.into_iter().zip(values) {
+            typed.push(TypedResource {
+                resource_group: resource.resource_group()?.to_owned(),
+                id: resource.id,
+                name: resource.name,
+                location: resource.location,
+                tags: resource.tags.unwrap_or_default(),
+                raw,
+            });
+        }
+
+        Ok(Some(ResourcesResult {
+            subscription: subscription.clone(),
+            resources: typed,
+        }))
+    })?;
+
     return Ok(results);
 }
 
+/// Resource types which can be deployed into availability zones
+const ZONAL_RESOURCE_TYPES: &[&str] = &[
+    "Microsoft.Compute/virtualMachines",
+    "Microsoft.Compute/virtualMachineScaleSets",
+    "Microsoft.Compute/disks",
+    "Microsoft.Network/publicIPAddresses",
+    "Microsoft.Network/applicationGateways",
+    "Microsoft.Network/azureFirewalls",
+    "Microsoft.Network/natGateways",
+];
+
+/// Returns `true` for zone-capable resources in a region with zones, but deployed without zones
+fn is_non_zonal(resource: &Resource) -> bool {
+    let zonal_type = ZONAL_RESOURCE_TYPES
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(&resource.resource_type));
+    let zonal_region = resource
+        .region
+        .as_ref()
+        .is_some_and(|region| region.zones_supported);
+    zonal_type && zonal_region && resource.zones.is_empty()
+}
+
+/// Applies the `--tag` filter, keeping resources whose subscription, resource group
+/// or themselves carry the tags, and resource groups containing such resources
+fn retain_tagged(
+    service: &Service,
+    subscription: &Subscription,
+    resource_groups: &mut Vec<ResourceGroup>,
+    resources: &mut Vec<Resource>,
+) {
+    if service.matches_tags(subscription.tags.as_ref()) {
+        return;
+    }
+
+    let tagged_groups: Vec<String> = resource_groups
+        .iter()
+        .filter(|group| service.matches_tags(group.tags.as_ref()))
+        .map(|group| group.name.to_lowercase())
+        .collect();
+    let resource_group = |resource: &Resource| match resource.resource_group() {
+        Ok(group) => group.to_lowercase(),
+        Err(_) => String::new(),
+    };
+
+    resources.retain(|resource| {
+        service.matches_tags(resource.tags.as_ref())
+            || tagged_groups.contains(&resource_group(resource))
+    });
+
+    let groups: Vec<String> = resources.iter().map(resource_group).collect();
+    resource_groups.retain(|group| {
+        let name = group.name.to_lowercase();
+        tagged_groups.contains(&name) || groups.contains(&name)
+    });
+}
+
 #[derive(Serialize)]
 pub struct ClusterResult {
     pub subscription: Subscription,
@@ -92,8 +658,142 @@ pub struct Cluster {
     pub id: String,
     pub name: String,
     pub version: String,
+    /// Only set for Arc-enabled clusters, like `k3s`, `arc` if unknown
+    pub distribution: Option<String>,
     pub agent_pools: Option<Vec<AgentPool>>,
     pub objects: Option<Vec<KubernetesObject>>,
+    /// Only set with `--restarts`
+    pub restarts: Option<Vec<NamespaceRestarts>>,
+    /// Costs of the node resource group, only set with `--costs`
+    pub costs: Option<Costs>,
+    /// Only set with `--export-kubeconfigs`, never printed
+    #[serde(skip)]
+    pub kubeconfig: Option<String>,
+}
+
+/// Container restarts of the pods of a namespace. Kubernetes only records the time of the
+/// last restart of each container, so the recent restarts count containers, not restarts
+#[derive(Serialize)]
+pub struct NamespaceRestarts {
+    pub namespace: String,
+    /// Restarts since the pods were created
+    pub total: u64,
+    /// Containers which last restarted within the last hour
+    #[serde(rename = "lastHour")]
+    pub last_hour: u64,
+    /// Containers which last restarted within the last day
+    #[serde(rename = "lastDay")]
+    pub last_day: u64,
+    #[serde(rename = "crashLooping")]
+    pub crash_looping: u64,
+    /// Containers crash looping, or many of them restarted within the last hour
+    pub storm: bool,
+}
+
+/// Containers restarted within the last hour at which a namespace has a restart storm
+const RESTART_STORM_CONTAINERS: u64 = 5;
+
+/// Sums up the restarts of each namespace, leaving out those without any
+fn namespace_restarts(
+    containers: Vec<ContainerRestarts>,
+    now: &DateTime<Utc>,
+) -> Vec<NamespaceRestarts> {
+    let mut namespaces: BTreeMap<String, NamespaceRestarts> = BTreeMap::new();
+    for container in containers {
+        if container.restart_count == 0 && !container.crash_loop {
+            continue;
+        }
+        let restarts = namespaces
+            .entry(container.namespace.clone())
+            .or_insert_with(|| NamespaceRestarts {
+                namespace: container.namespace.clone(),
+                total: 0,
+                last_hour: 0,
+                last_day: 0,
+                crash_looping: 0,
+                storm: false,
+            });
+        restarts.total += container.restart_count;
+        let age = container
+            .last_restart
+            .as_deref()
+            .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+            .map(|time| *now - time.with_timezone(&Utc));
+        if let Some(age) = age {
+            if age <= Duration::hours(1) {
+                restarts.last_hour += 1;
+            }
+            if age <= Duration::days(1) {
+                restarts.last_day += 1;
+            }
+        }
+        if container.crash_loop {
+            restarts.crash_looping += 1;
+        }
+        restarts.storm =
+            restarts.crash_looping > 0 || restarts.last_hour >= RESTART_STORM_CONTAINERS;
+    }
+    namespaces.into_values().collect()
+}
+
+/// Merges the kubeconfigs requested with `--export-kubeconfigs` into one,
+/// with contexts named like `subscription/cluster`
+pub fn cluster_kubeconfigs(results: &Vec<ClusterResult>) -> Result<(usize, String)> {
+    let mut kubeconfigs = vec![];
+    for result in results {
+        for cluster in &result.clusters {
+            if let Some(kubeconfig) = &cluster.kubeconfig {
+                let name = format!("{}/{}", result.subscription.name, cluster.name);
+                kubeconfigs.push((name, kubeconfig.as_str()));
+            }
+        }
+    }
+    Ok((kubeconfigs.len(), merge_kubeconfigs(&kubeconfigs)?))
+}
+
+/// Requests the Kubernetes resources and pod restarts of a cluster, each only if wanted,
+/// and warns if they are not available
+fn kubernetes_details(
+    service: &Service,
+    cluster_name: &str,
+    kubeconfig: &str,
+    resources: bool,
+    restarts: bool,
+    all_resources: bool,
+) -> (
+    Option<Vec<KubernetesObject>>,
+    Option<Vec<NamespaceRestarts>>,
+) {
+    let objects = if resources {
+        match service.get_kubernetes_objects(kubeconfig, all_resources) {
+            Ok(objects) => Some(objects),
+            Err(err) => {
+                warn!(
+                    "Failed to get Kubernetes resources for {}: {}",
+                    cluster_name, err
+                );
+                service.record_failure();
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let restarts = if restarts {
+        match service.get_kubernetes_restarts(kubeconfig, all_resources) {
+            Ok(containers) => Some(namespace_restarts(containers, &Utc::now())),
+            Err(err) => {
+                warn!("Failed to get pod restarts for {}: {}", cluster_name, err);
+                service.record_failure();
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    (objects, restarts)
 }
 
 #[derive(Serialize)]
@@ -105,94 +805,226 @@ pub struct AgentPool {
     pub vm_size: String,
 }
 
+/// What `clusters` requests in addition to the clusters themselves
+#[derive(Clone, Copy)]
+pub struct ClusterDetails {
+    pub pools: bool,
+    pub resources: bool,
+    /// Including the Kubernetes system resources
+    pub all_resources: bool,
+    pub restarts: bool,
+    /// The user kubeconfigs, for `--export-kubeconfigs`
+    pub kubeconfigs: bool,
+}
+
 pub fn clusters(
     context: &Context,
-    pools: bool,
-    resources: bool,
-    all_resources: bool,
+    details: &ClusterDetails,
+    timeframe: Option<&Timeframe>,
     filter: Option<&String>,
 ) -> Result<Vec<ClusterResult>> {
     let service = &context.service;
+    let ClusterDetails {
+        pools,
+        resources,
+        all_resources,
+        restarts,
+        kubeconfigs,
+    } = *details;
 
-    let mut results = vec![];
-
-    for subscription in service.get_subscriptions()? {
+    let subscriptions = service.get_subscriptions()?;
+    let results = context.parallel_results(&subscriptions, |subscription| {
         let mut managed_clusters = service.get_clusters(&subscription.subscription_id)?;
+        let mut connected_clusters =
+            service.get_connected_clusters(&subscription.subscription_id)?;
         if let Some(filter) = filter {
             managed_clusters.retain(|cluster| cluster.name.contains(filter));
+            connected_clusters.retain(|cluster| cluster.name.contains(filter));
         }
 
-        if !managed_clusters.is_empty() {
-            let clusters: Result<Vec<_>> = managed_clusters
-                .into_iter()
-                .map(|cluster| {
-                    let agent_pools = if pools {
-                        let agent_pools = service
-                            .get_agent_pools(&cluster.id)?
-                            .into_iter()
-                            .map(|agent_pool| {
-                                let profile = cluster
-                                    .properties
-                                    .agent_pool_profiles
-                                    .iter()
-                                    .find(|pool| pool.name == agent_pool.name);
-
-                                AgentPool {
-                                    name: agent_pool.name,
-                                    count: agent_pool.properties.count,
-                                    min_count: profile.and_then(|p| p.min_count),
-                                    max_count: profile.and_then(|p| p.max_count),
-                                    vm_size: agent_pool.properties.vm_size,
-                                }
-                            })
-                            .collect();
-                        Some(agent_pools)
-                    } else {
-                        None
-                    };
+        if managed_clusters.is_empty() && connected_clusters.is_empty() {
+            return Ok(None);
+        }
 
-                    let objects = if resources {
-                        let kubeconfig = service.get_cluster_kubeconfig(&cluster.id)?;
-                        match service.get_kubernetes_objects(&kubeconfig, all_resources) {
-                            Ok(objects) => Some(objects),
-                            Err(err) => {
-                                warn!(
-                                    "Failed to get Kubernetes resources for {}: {}",
-                                    &cluster.name, err
-                                );
-                                None
-                            }
+        let subscription_costs = match timeframe {
+            Some(timeframe) => service.get_costs(&subscription.subscription_id, timeframe)?,
+            None => vec![],
+        };
+
+        // The kubeconfig and the Kubernetes API of each cluster are requested concurrently
+        let mut clusters = context.parallel_map(&managed_clusters, |cluster| {
+            let agent_pools = if pools {
+                let agent_pools = service
+                    .get_agent_pools(&cluster.id)?
+                    .into_iter()
+                    .map(|agent_pool| {
+                        let profile = cluster
+                            .properties
+                            .agent_pool_profiles
+                            .iter()
+                            .find(|pool| pool.name == agent_pool.name);
+
+                        AgentPool {
+                            name: agent_pool.name,
+                            count: agent_pool.properties.count,
+                            min_count: profile.and_then(|p| p.min_count),
+                            max_count: profile.and_then(|p| p.max_count),
+                            vm_size: agent_pool.properties.vm_size,
                         }
-                    } else {
-                        None
-                    };
+                    })
+                    .collect();
+                Some(agent_pools)
+            } else {
+                None
+            };
+
+            let kubeconfig = if resources || restarts || kubeconfigs {
+                Some(service.get_cluster_kubeconfig(&cluster.id)?)
+            } else {
+                None
+            };
+            let (objects, restarts) = match &kubeconfig {
+                Some(kubeconfig) => kubernetes_details(
+                    service,
+                    &cluster.name,
+                    kubeconfig,
+                    resources,
+                    restarts,
+                    all_resources,
+                ),
+                None => (None, None),
+            };
 
-                    Ok(Cluster {
-                        id: cluster.id,
-                        name: cluster.name,
-                        version: cluster.properties.kubernetes_version,
-                        agent_pools,
-                        objects,
+            let costs = match (timeframe, &cluster.properties.node_resource_group) {
+                (Some(_), Some(node_resource_group)) => subscription_costs
+                    .iter()
+                    .find(|costs| {
+                        costs
+                            .resource_group
+                            .eq_ignore_ascii_case(node_resource_group)
                     })
-                })
-                .collect();
+                    .cloned(),
+                _ => None,
+            };
 
-            results.push(ClusterResult {
-                subscription,
-                clusters: clusters?,
-            });
-        }
-    }
+            Ok(Cluster {
+                id: cluster.id.clone(),
+                name: cluster.name.clone(),
+                version: cluster.properties.kubernetes_version.clone(),
+                distribution: None,
+                agent_pools,
+                objects,
+                restarts,
+                costs,
+                kubeconfig: kubeconfig.filter(|_| kubeconfigs),
+            })
+        })?;
+
+        // Workloads of Arc-enabled clusters are only available through cluster connect
+        clusters.extend(context.parallel_map(&connected_clusters, |cluster| {
+            let properties = &cluster.properties;
+            let kubeconfig = if (resources || restarts || kubeconfigs)
+                && properties.connectivity_status.as_deref() == Some("Connected")
+            {
+                match service.get_connected_cluster_kubeconfig(&cluster.id) {
+                    Ok(kubeconfig) => Some(kubeconfig),
+                    Err(err) => {
+                        warn!(
+                            "Failed to get the kubeconfig of {}, is cluster connect enabled? {}",
+                            &cluster.name, err
+                        );
+                        service.record_failure();
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+            let (objects, restarts) = match &kubeconfig {
+                Some(kubeconfig) => kubernetes_details(
+                    service,
+                    &cluster.name,
+                    kubeconfig,
+                    resources,
+                    restarts,
+                    all_resources,
+                ),
+                None => (None, None),
+            };
+
+            Ok(Cluster {
+                id: cluster.id.clone(),
+                name: cluster.name.clone(),
+                version: properties.kubernetes_version.clone().unwrap_or_default(),
+                distribution: Some(properties.distribution.clone().unwrap_or("arc".to_owned())),
+                agent_pools: None,
+                objects,
+                restarts,
+                costs: None,
+                kubeconfig: kubeconfig.filter(|_| kubeconfigs),
+            })
+        })?);
+
+        Ok(Some(ClusterResult {
+            subscription: subscription.clone(),
+            clusters,
+        }))
+    })?;
 
     return Ok(results);
 }
 
+/// Sorts the clusters by one of `CLUSTERS_FIELD_NAMES`
+pub fn sort_cluster_results(results: &mut Vec<ClusterResult>, sort: &Sort) {
+    if sort.field == "subscription" {
+        return sort.apply(results, |result| SortKey::text(&result.subscription.name));
+    }
+    for result in results {
+        sort.apply(&mut result.clusters, |cluster| match sort.field.as_str() {
+            "name" => SortKey::text(&cluster.name),
+            "version" => SortKey::text(&cluster.version),
+            "distribution" => SortKey::text(cluster.distribution.as_deref().unwrap_or("")),
+            "nodes" => {
+                let nodes = match &cluster.agent_pools {
+                    Some(pools) => pools.iter().map(|pool| pool.count).sum::<u64>(),
+                    None => 0,
+                };
+                SortKey::Number(nodes as f64)
+            }
+            "costs" => SortKey::Number(cluster.costs.as_ref().map_or(0.0, |costs| costs.costs)),
+            _ => SortKey::text(&cluster.id),
+        });
+    }
+}
+
 #[derive(Serialize)]
 pub struct Domain {
     pub name: String,
     pub entries: Vec<Option<DnsRecordEntry>>,
     #[serde(rename = "ipAddresses")]
     pub ip_addresses: Vec<DomainIpAddress>,
+    /// The resource group of the App Service app or Traffic Manager profile the name points to
+    #[serde(rename = "resourceGroup", skip_serializing_if = "Option::is_none")]
+    pub resource_group: Option<ResourceGroup>,
+    /// The owner tag of the resource group the name points to, only with `--owners`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+impl Domain {
+    /// The value of the tag on the resource group of the target, or of the first IP address
+    /// whose resource group carries it
+    fn find_owner(&self, owner_tag: &str) -> Option<String> {
+        self.resource_group
+            .iter()
+            .chain(
+                self.ip_addresses
+                    .iter()
+                    .filter_map(|ip| ip.resource_group.as_ref()),
+            )
+            .filter_map(|group| group.tags.as_ref())
+            .find_map(|tags| find_tag(tags, owner_tag).cloned())
+    }
 }
 
 #[derive(Serialize)]
@@ -203,77 +1035,144 @@ pub struct DomainIpAddress {
     pub resource_group: Option<ResourceGroup>,
 }
 
-pub fn domains(context: &Context, filter: Option<&String>) -> Result<Vec<Domain>> {
-    let service = &context.service;
+/// The DNS records of all zones and the resource groups of all public IP addresses
+struct DomainIndex {
+    records: Vec<DnsRecord>,
+    ip_to_group: HashMap<String, ResourceGroup>,
+    /// Ingress FQDNs of container apps, with the static IP of their environment
+    /// and the resource group of the app
+    hosts: HashMap<String, (String, Option<ResourceGroup>)>,
+    /// Default host names of App Service apps and FQDNs of Traffic Manager profiles,
+    /// with their resource group. Only loaded to find the owners of domains
+    targets: HashMap<String, ResourceGroup>,
+}
 
-    let subscriptions = service.get_subscriptions()?;
+impl DomainIndex {
+    fn load(context: &Context, load_targets: bool) -> Result<DomainIndex> {
+        let service = context.service;
+        let subscriptions = service.get_subscriptions()?;
 
-    let mut records: Vec<DnsRecord> = vec![];
-    for subscription in &subscriptions {
-        for zone in service.get_resources_by_type(&subscription.subscription_id, TYPE_DNS_ZONE)? {
-            records.extend(service.get_dns_records(
-                &subscription.subscription_id,
-                zone.resource_group()?,
-                &zone.name,
-            )?);
-        }
-    }
+        // The zones and their records are requested in batches, across all subscriptions
+        let subscription_ids: Vec<&str> = subscriptions
+            .iter()
+            .map(|subscription| subscription.subscription_id.as_str())
+            .collect();
+        let zones: Vec<Resource> = service
+            .get_resources_by_type_batch(&subscription_ids, TYPE_DNS_ZONE)?
+            .into_iter()
+            .flatten()
+            .collect();
+        let records: Vec<DnsRecord> = service
+            .get_dns_records_batch(&zones)?
+            .into_iter()
+            .flatten()
+            .collect();
 
-    let mut ip_to_group: HashMap<String, ResourceGroup> = HashMap::new();
-    for subscription in &subscriptions {
-        let groups = service.get_resource_groups(&subscription.subscription_id)?;
-        let ips = service.get_ip_addresses(&subscription.subscription_id)?;
-        for ip in ips {
-            let group_name = ip.resource_group()?.to_lowercase();
-            let group = groups
-                .iter()
-                .find(|group| group.name.to_lowercase() == group_name);
-            if let Some(group) = group {
-                ip_to_group.insert(ip.ip_address, group.clone());
-            }
-        }
-    }
+        let subscription_data =
+            parallel_map(&subscriptions, context.parallelism, |subscription| {
+                let groups = service.get_resource_groups(&subscription.subscription_id)?;
+                let ips = service.get_ip_addresses(&subscription.subscription_id)?;
 
-    fn equals(fqdn1: &str, fqdn2: &str) -> bool {
-        fqdn1 == fqdn2
-            || (fqdn1.ends_with(".") && &fqdn1[..fqdn1.len() - 1] == fqdn2)
-            || (fqdn2.ends_with(".") && fqdn1 == &fqdn2[..fqdn2.len() - 1])
-    }
+                // Only request container apps if there are any, the provider may not be registered
+                let (environments, apps) = if service
+                    .get_resources_by_type(&subscription.subscription_id, TYPE_MANAGED_ENVIRONMENT)?
+                    .is_empty()
+                {
+                    (vec![], vec![])
+                } else {
+                    (
+                        service.get_managed_environments(&subscription.subscription_id)?,
+                        service.get_container_apps(&subscription.subscription_id)?,
+                    )
+                };
 
-    let mut domain_names: Vec<&String> = (&records).iter().map(|record| &record.fqdn).collect();
+                let mut targets = vec![];
+                if load_targets {
+                    let has_type = |resource_type: &str| -> Result<bool> {
+                        Ok(!service
+                            .get_resources_by_type(&subscription.subscription_id, resource_type)?
+                            .is_empty())
+                    };
+                    if has_type(TYPE_WEB_APP)? {
+                        for app in service.get_web_apps(&subscription.subscription_id)? {
+                            if let Some(host_name) = &app.properties.default_host_name {
+                                targets.push((host_name.clone(), app.resource_group()?.to_owned()));
+                            }
+                        }
+                    }
+                    if has_type(TYPE_TRAFFIC_MANAGER_PROFILE)? {
+                        for profile in
+                            service.get_traffic_manager_profiles(&subscription.subscription_id)?
+                        {
+                            let fqdn = profile
+                                .properties
+                                .dns_config
+                                .as_ref()
+                                .and_then(|dns_config| dns_config.fqdn.clone());
+                            if let Some(fqdn) = fqdn {
+                                targets.push((fqdn, profile.resource_group()?.to_owned()));
+                            }
+                        }
+                    }
+                }
+                Ok((groups, ips, environments, apps, targets))
+            })?;
 
-    if let Some(filter) = filter {
-        domain_names.retain(|domain| domain.contains(filter));
-    } else {
-        for record in &records {
-            match &record.entry {
-                DnsRecordEntry::CNAME(cname) => {
-                    domain_names.retain(|&domain| !equals(domain, cname));
+        let mut ip_to_group: HashMap<String, ResourceGroup> = HashMap::new();
+        let mut hosts: HashMap<String, (String, Option<ResourceGroup>)> = HashMap::new();
+        let mut targets: HashMap<String, ResourceGroup> = HashMap::new();
+        for (groups, ips, environments, apps, target_names) in subscription_data {
+            let find_group = |name: &str| {
+                groups
+                    .iter()
+                    .find(|group| group.name.eq_ignore_ascii_case(name))
+                    .cloned()
+            };
+            for (fqdn, ip, app) in container_app_hosts(&environments, &apps) {
+                let group = app.resource_group().ok().and_then(find_group);
+                hosts.insert(fqdn, (ip, group));
+            }
+            for (host, group_name) in target_names {
+                if let Some(group) = find_group(&group_name) {
+                    targets.insert(host.to_lowercase(), group);
+                }
+            }
+            for ip in ips {
+                let group_name = ip.resource_group()?.to_lowercase();
+                let group = groups
+                    .iter()
+                    .find(|group| group.name.to_lowercase() == group_name);
+                if let Some(group) = group {
+                    ip_to_group.insert(ip.ip_address, group.clone());
                 }
-                _ => (),
             }
         }
-    }
 
-    domain_names.sort();
-
-    const MAX_DEPTH: usize = 5;
+        return Ok(DomainIndex {
+            records,
+            ip_to_group,
+            hosts,
+            targets,
+        });
+    }
 
-    fn resolve_entries<'e>(
-        entries: &'e mut Vec<Option<DnsRecordEntry>>,
-        records: &'e Vec<DnsRecord>,
+    fn resolve_entries(
+        &self,
+        entries: &mut Vec<Option<DnsRecordEntry>>,
         domain_name: &str,
         depth: usize,
     ) {
-        for record in records {
-            if equals(&record.fqdn, domain_name) {
+        const MAX_DEPTH: usize = 5;
+
+        for record in &self.records {
+            if fqdn_equals(&record.fqdn, domain_name) {
                 match &record.entry {
                     DnsRecordEntry::CNAME(cname) => {
                         if depth >= MAX_DEPTH {
                             entries.push(None);
                         } else {
                             entries.push(Some(record.entry.clone()));
-                            resolve_entries(entries, records, cname, depth + 1);
+                            self.resolve_entries(entries, cname, depth + 1);
                         }
                     }
                     DnsRecordEntry::A(_) => {
@@ -284,37 +1183,164 @@ pub fn domains(context: &Context, filter: Option<&String>) -> Result<Vec<Domain>
         }
     }
 
-    let mut domains = vec![];
+    fn ip_address(&self, ip: &str) -> DomainIpAddress {
+        DomainIpAddress {
+            ip_address: ip.to_owned(),
+            resource_group: self.ip_to_group.get(ip).map(|r| r.clone()),
+        }
+    }
 
-    for domain_name in &domain_names {
+    fn resolve(&self, domain_name: &str) -> Domain {
         let mut entries = vec![];
-        resolve_entries(&mut entries, &records, domain_name, 0);
+        self.resolve_entries(&mut entries, domain_name, 0);
 
         let mut ip_addresses = vec![];
+        let mut resource_group = None;
         if let Some(Some(entry)) = entries.last() {
             match entry {
                 DnsRecordEntry::A(ip_addrs) => {
                     for ip in ip_addrs {
+                        ip_addresses.push(self.ip_address(ip));
+                    }
+                }
+                DnsRecordEntry::CNAME(cname) => {
+                    let host = cname.trim_end_matches('.').to_lowercase();
+                    if let Some((ip, group)) = self.hosts.get(&host) {
                         ip_addresses.push(DomainIpAddress {
                             ip_address: ip.clone(),
-                            resource_group: ip_to_group.get(ip).map(|r| r.clone()),
+                            resource_group: group.clone(),
                         });
                     }
+                    resource_group = self.targets.get(&host).cloned();
                 }
-                _ => (),
             }
         }
 
-        domains.push(Domain {
+        Domain {
             name: domain_name.to_string(),
             entries,
             ip_addresses,
+            resource_group,
+            owner: None,
+        }
+    }
+}
+
+fn fqdn_equals(fqdn1: &str, fqdn2: &str) -> bool {
+    fqdn1 == fqdn2
+        || (fqdn1.ends_with(".") && &fqdn1[..fqdn1.len() - 1] == fqdn2)
+        || (fqdn2.ends_with(".") && fqdn1 == &fqdn2[..fqdn2.len() - 1])
+}
+
+/// With an owner tag, the domains are attributed to the owner of the resource group they point to
+pub fn domains(
+    context: &Context,
+    filter: Option<&String>,
+    owner_tag: Option<&str>,
+) -> Result<Vec<Domain>> {
+    let index = DomainIndex::load(context, owner_tag.is_some())?;
+
+    let mut domain_names: Vec<&String> =
+        (&index.records).iter().map(|record| &record.fqdn).collect();
+
+    if let Some(filter) = filter {
+        // Internationalized names match both in Unicode and in punycode
+        let ascii_filter = domain_to_ascii(filter);
+        domain_names.retain(|domain| {
+            domain.contains(filter)
+                || domain.contains(&ascii_filter)
+                || domain_to_unicode(domain).contains(filter)
         });
+    } else {
+        for record in &index.records {
+            match &record.entry {
+                DnsRecordEntry::CNAME(cname) => {
+                    domain_names.retain(|&domain| !fqdn_equals(domain, cname));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    domain_names.sort();
+
+    let mut domains: Vec<Domain> = domain_names
+        .iter()
+        .map(|domain_name| index.resolve(domain_name))
+        .collect();
+
+    if let Some(owner_tag) = owner_tag {
+        for domain in &mut domains {
+            domain.owner = domain.find_owner(owner_tag);
+        }
     }
 
     return Ok(domains);
 }
 
+#[derive(Serialize)]
+pub struct ResolveResult {
+    pub hostname: String,
+    /// Whether the hostname was found in one of the DNS zones
+    pub zone: bool,
+    pub entries: Vec<Option<DnsRecordEntry>>,
+    #[serde(rename = "ipAddresses")]
+    pub ip_addresses: Vec<DomainIpAddress>,
+}
+
+/// Resolves hostnames against the DNS zones and public IP addresses, loading them only once
+pub struct Resolver {
+    index: DomainIndex,
+    offline: bool,
+}
+
+impl Resolver {
+    pub fn new(context: &Context) -> Result<Resolver> {
+        return Ok(Resolver {
+            index: DomainIndex::load(context, false)?,
+            offline: context.service.is_offline(),
+        });
+    }
+
+    /// Hostnames not found in any zone are resolved with the system resolver,
+    /// to still match them against the public IP addresses, except offline
+    pub fn resolve(&self, hostname: &str) -> ResolveResult {
+        let domain = self.index.resolve(hostname);
+        if !domain.entries.is_empty() {
+            return ResolveResult {
+                hostname: hostname.to_owned(),
+                zone: true,
+                entries: domain.entries,
+                ip_addresses: domain.ip_addresses,
+            };
+        }
+
+        let mut ip_addresses: Vec<DomainIpAddress> = vec![];
+        if self.offline {
+            debug!("Not resolving {} offline", hostname);
+        } else {
+            match (hostname, 0).to_socket_addrs() {
+                Ok(addrs) => {
+                    for addr in addrs {
+                        let ip = addr.ip().to_string();
+                        if !ip_addresses.iter().any(|i| i.ip_address == ip) {
+                            ip_addresses.push(self.index.ip_address(&ip));
+                        }
+                    }
+                }
+                Err(err) => debug!("Failed to resolve {}: {}", hostname, err),
+            }
+        }
+
+        return ResolveResult {
+            hostname: hostname.to_owned(),
+            zone: false,
+            entries: vec![],
+            ip_addresses,
+        };
+    }
+}
+
 #[derive(Serialize)]
 pub struct DnsResult {
     pub zone: Resource,
@@ -325,22 +1351,23 @@ pub fn dns(context: &Context) -> Result<Vec<DnsResult>> {
     let service = &context.service;
 
     let subscriptions = service.get_subscriptions()?;
+    let subscription_ids: Vec<&str> = subscriptions
+        .iter()
+        .map(|subscription| subscription.subscription_id.as_str())
+        .collect();
 
-    let mut zones = vec![];
-    for subscription in &subscriptions {
-        zones.extend(service.get_resources_by_type(&subscription.subscription_id, TYPE_DNS_ZONE)?);
-    }
-
-    let mut results = vec![];
+    let zones: Vec<Resource> = service
+        .get_resources_by_type_batch(&subscription_ids, TYPE_DNS_ZONE)?
+        .into_iter()
+        .flatten()
+        .collect();
+    let records = service.get_dns_records_batch(&zones)?;
 
-    for zone in &zones {
-        let records =
-            service.get_dns_records(zone.subscription_id()?, zone.resource_group()?, &zone.name)?;
-        results.push(DnsResult {
-            zone: zone.clone(),
-            records,
-        });
-    }
+    let results = zones
+        .into_iter()
+        .zip(records)
+        .map(|(zone, records)| DnsResult { zone, records })
+        .collect();
 
     return Ok(results);
 }
@@ -360,17 +1387,26 @@ pub struct IpResultResourceGroup {
     pub ip_addresses: Vec<IpAddress>,
 }
 
-pub fn ip(context: &Context) -> Result<Vec<IpResult>> {
-    let mut result = vec![];
-
+pub fn ip(context: &Context, annotate_locks: bool) -> Result<Vec<IpResult>> {
     let service = &context.service;
     let subscriptions = service.get_subscriptions()?;
-    for subscription in &subscriptions {
+    let results = context.parallel_results(&subscriptions, |subscription| {
         let mut resource_groups = vec![];
 
-        let ip_addrs = service.get_ip_addresses(&subscription.subscription_id)?;
+        let mut ip_addrs = service.get_ip_addresses(&subscription.subscription_id)?;
+
+        let protections = if annotate_locks {
+            Some(Protections::get(service, &subscription.subscription_id)?)
+        } else {
+            None
+        };
+        if let Some(protections) = &protections {
+            for ip in &mut ip_addrs {
+                ip.protection = protections.of(&ip.id, TYPE_PUBLIC_IP_ADDRESS);
+            }
+        }
 
-        for resource_group in service.get_resource_groups(&subscription.subscription_id)? {
+        for mut resource_group in service.get_resource_groups(&subscription.subscription_id)? {
             let mut ip_addresses = vec![];
             for ip in &ip_addrs {
                 if ip.resource_group()? == resource_group.name {
@@ -379,6 +1415,10 @@ pub fn ip(context: &Context) -> Result<Vec<IpResult>> {
             }
 
             if !ip_addresses.is_empty() {
+                if let Some(protections) = &protections {
+                    resource_group.protection =
+                        protections.of(&resource_group.id, TYPE_RESOURCE_GROUP);
+                }
                 resource_groups.push(IpResultResourceGroup {
                     resource_group,
                     ip_addresses,
@@ -386,45 +1426,3953 @@ pub fn ip(context: &Context) -> Result<Vec<IpResult>> {
             }
         }
 
-        if !resource_groups.is_empty() {
-            result.push(IpResult {
+        if resource_groups.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(IpResult {
                 subscription: subscription.clone(),
                 resource_groups,
-            })
+            }))
         }
-    }
+    })?;
+
+    return Ok(results);
+}
 
-    return Ok(result);
+/// Sorts the resource groups or IP addresses by one of `IP_FIELD_NAMES`
+pub fn sort_ip_results(results: &mut Vec<IpResult>, sort: &Sort) {
+    if sort.field == "subscription" {
+        return sort.apply(results, |result| SortKey::text(&result.subscription.name));
+    }
+    for result in results {
+        if sort.field == "resourceGroup" {
+            sort.apply(&mut result.resource_groups, |group| {
+                SortKey::text(&group.resource_group.name)
+            });
+            continue;
+        }
+        for group in &mut result.resource_groups {
+            sort.apply(&mut group.ip_addresses, |ip| match sort.field.as_str() {
+                "name" => SortKey::text(&ip.name),
+                "ipAddress" => SortKey::ip(&ip.ip_address),
+                _ => SortKey::text(&ip.id),
+            });
+        }
+    }
 }
 
 #[derive(Serialize)]
 pub struct CostResult {
     pub subscription: Subscription,
+    /// The billing period as ISO 8601 interval, like `2019-03-01/2019-03-31`
+    pub period: String,
     pub costs: Vec<Costs>,
 }
 
 pub fn costs(context: &Context, timeframe: &Timeframe) -> Result<Vec<CostResult>> {
-    let mut result = vec![];
-
     let service = &context.service;
     let subscriptions = service.get_subscriptions()?;
-    for subscription in &subscriptions {
+    context.parallel_results(&subscriptions, |subscription| {
         let costs = service
             .get_costs(&subscription.subscription_id, timeframe)
             .unwrap_or(vec![]);
-        result.push(CostResult {
+        Ok(Some(CostResult {
             subscription: subscription.clone(),
+            period: timeframe.period(),
             costs,
+        }))
+    })
+}
+
+/// A management group with the subtotal of all subscriptions below it, or a subscription
+#[derive(Debug, Serialize)]
+pub struct CostNode {
+    pub name: String,
+    /// Only set for subscriptions
+    #[serde(rename = "subscriptionId", skip_serializing_if = "Option::is_none")]
+    pub subscription_id: Option<String>,
+    pub costs: f64,
+    pub currency: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<CostNode>,
+}
+
+impl CostNode {
+    /// Sets the costs of management groups to the sum of their children
+    fn sum_up(&mut self) {
+        if self.subscription_id.is_some() {
+            return;
+        }
+        let mut costs = Money::default();
+        for child in &mut self.children {
+            child.sum_up();
+            costs += Money::from_f64(child.costs);
+            if self.currency.is_none() {
+                self.currency = child.currency.clone();
+            }
+        }
+        self.costs = costs.to_f64();
+    }
+}
+
+/// The costs of the subscriptions rolled up their management group hierarchy.
+/// Subscriptions without management groups, like when the query fails, are top-level nodes
+pub fn costs_tree(context: &Context, timeframe: &Timeframe) -> Result<Vec<CostNode>> {
+    // The tree is only complete with the costs of all subscriptions
+    let context = Context {
+        stream: None,
+        ..*context
+    };
+    let results = costs(&context, timeframe)?;
+
+    let ids: Vec<&str> = results
+        .iter()
+        .map(|result| result.subscription.subscription_id.as_str())
+        .collect();
+    let management_groups = match context.service.get_graph_management_groups(&ids) {
+        Ok(management_groups) => management_groups,
+        Err(err) => {
+            warn!("Failed to query management groups: {}", err);
+            context.service.record_failure();
+            HashMap::new()
+        }
+    };
+
+    let subscriptions = results
+        .into_iter()
+        .map(|result| {
+            let path = management_groups
+                .get(&result.subscription.subscription_id.to_lowercase())
+                .cloned()
+                .unwrap_or_default();
+            let node = CostNode {
+                name: result.subscription.name,
+                subscription_id: Some(result.subscription.subscription_id),
+                costs: result
+                    .costs
+                    .iter()
+                    .map(|costs| Money::from_f64(costs.costs))
+                    .sum::<Money>()
+                    .to_f64(),
+                currency: result.costs.first().map(|costs| costs.currency.clone()),
+                children: vec![],
+            };
+            (path, node)
+        })
+        .collect();
+
+    return Ok(cost_tree(subscriptions));
+}
+
+/// Builds the tree from the management groups above each subscription, starting at the root
+fn cost_tree(subscriptions: Vec<(Vec<String>, CostNode)>) -> Vec<CostNode> {
+    let mut roots: Vec<CostNode> = vec![];
+    for (path, subscription) in subscriptions {
+        let mut nodes = &mut roots;
+        for name in path {
+            let index = match nodes
+                .iter()
+                .position(|node| node.subscription_id.is_none() && node.name == name)
+            {
+                Some(index) => index,
+                None => {
+                    nodes.push(CostNode {
+                        name,
+                        subscription_id: None,
+                        costs: 0.0,
+                        currency: None,
+                        children: vec![],
+                    });
+                    nodes.len() - 1
+                }
+            };
+            nodes = &mut nodes[index].children;
+        }
+        nodes.push(subscription);
+    }
+    for root in &mut roots {
+        root.sum_up();
+    }
+    roots
+}
+
+/// Sorts the costs by one of `COSTS_FIELD_NAMES`
+pub fn sort_cost_results(results: &mut Vec<CostResult>, sort: &Sort) {
+    if sort.field == "subscription" {
+        return sort.apply(results, |result| SortKey::text(&result.subscription.name));
+    }
+    for result in results {
+        sort.apply(&mut result.costs, |costs| match sort.field.as_str() {
+            "resourceGroup" => SortKey::text(&costs.resource_group),
+            "date" => SortKey::text(costs.date.as_deref().unwrap_or("")),
+            _ => SortKey::Number(costs.costs),
         });
     }
+}
+
+#[derive(Serialize)]
+pub struct EndpointResult {
+    pub subscription: Subscription,
+    pub endpoints: Vec<Endpoint>,
+}
+
+#[derive(Serialize)]
+pub struct Endpoint {
+    pub id: String,
+    pub name: String,
+    pub connections: Vec<EndpointConnection>,
+    #[serde(rename = "privateDnsZones")]
+    pub private_dns_zones: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct EndpointConnection {
+    pub target: String,
+    #[serde(rename = "groupIds")]
+    pub group_ids: Vec<String>,
+    pub status: String,
+    pub description: Option<String>,
+}
+
+impl EndpointConnection {
+    pub fn is_approved(&self) -> bool {
+        self.status.eq_ignore_ascii_case("Approved")
+    }
+}
+
+pub fn endpoints(context: &Context, filter: Option<&String>) -> Result<Vec<EndpointResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let mut private_endpoints = service.get_private_endpoints(&subscription.subscription_id)?;
+        if let Some(filter) = filter {
+            private_endpoints.retain(|endpoint| endpoint.name.contains(filter));
+        }
+
+        let mut endpoints = vec![];
+        for endpoint in private_endpoints {
+            let private_dns_zones = service
+                .get_private_dns_zone_groups(&endpoint.id)?
+                .into_iter()
+                .flat_map(|group| group.properties.private_dns_zone_configs)
+                .map(|config| config.properties.private_dns_zone_id)
+                .collect();
+
+            let connections = endpoint
+                .properties
+                .private_link_service_connections
+                .into_iter()
+                .chain(endpoint.properties.manual_private_link_service_connections)
+                .map(|connection| EndpointConnection {
+                    target: connection.properties.private_link_service_id,
+                    group_ids: connection.properties.group_ids,
+                    status: connection.properties.state.status,
+                    description: connection.properties.state.description,
+                })
+                .collect();
+
+            endpoints.push(Endpoint {
+                id: endpoint.id,
+                name: endpoint.name,
+                connections,
+                private_dns_zones,
+            });
+        }
+
+        if !endpoints.is_empty() {
+            let result = EndpointResult {
+                subscription,
+                endpoints,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct RoutingResult {
+    pub subscription: Subscription,
+    #[serde(rename = "routeTables")]
+    pub route_tables: Vec<RoutingTable>,
+}
+
+#[derive(Serialize)]
+pub struct RoutingTable {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "bgpRoutePropagation")]
+    pub bgp_route_propagation: bool,
+    pub routes: Vec<Route>,
+    pub subnets: Vec<String>,
+}
+
+pub fn routing(context: &Context, filter: Option<&String>) -> Result<Vec<RoutingResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let mut route_tables = service.get_route_tables(&subscription.subscription_id)?;
+        if let Some(filter) = filter {
+            route_tables.retain(|table| table.name.contains(filter));
+        }
+
+        if !route_tables.is_empty() {
+            let result = RoutingResult {
+                subscription,
+                route_tables: route_tables
+                    .into_iter()
+                    .map(|table| RoutingTable {
+                        id: table.id,
+                        name: table.name,
+                        bgp_route_propagation: !table.properties.disable_bgp_route_propagation,
+                        routes: table.properties.routes,
+                        subnets: table
+                            .properties
+                            .subnets
+                            .into_iter()
+                            .map(|subnet| subnet.id)
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct EffectiveRoutesResult {
+    #[serde(rename = "networkInterface")]
+    pub network_interface: String,
+    pub routes: Vec<EffectiveRoute>,
+}
+
+/// Fetches the effective routes of a network interface, given by name or resource ID
+pub fn effective_routes(
+    context: &Context,
+    network_interface: &str,
+) -> Result<EffectiveRoutesResult> {
+    let service = &context.service;
+
+    let id = if network_interface.starts_with("/subscriptions/") {
+        network_interface.to_owned()
+    } else {
+        let mut found = None;
+        for subscription in service.get_subscriptions()? {
+            found = service
+                .get_network_interfaces(&subscription.subscription_id)?
+                .into_iter()
+                .find(|interface| interface.name == network_interface);
+            if found.is_some() {
+                break;
+            }
+        }
+        found
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "network interface not found: {}",
+                    network_interface
+                ))
+            })?
+            .id
+    };
+
+    let routes = service.get_effective_routes(&id)?;
+
+    return Ok(EffectiveRoutesResult {
+        network_interface: id,
+        routes,
+    });
+}
+
+#[derive(Serialize)]
+pub struct BastionResult {
+    pub subscription: Subscription,
+    pub bastions: Vec<Bastion>,
+    #[serde(rename = "exposedMachines")]
+    pub exposed_machines: Vec<ExposedMachine>,
+}
+
+#[derive(Serialize)]
+pub struct Bastion {
+    pub id: String,
+    pub name: String,
+    pub sku: Option<String>,
+    #[serde(rename = "virtualNetworks")]
+    pub virtual_networks: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExposedMachine {
+    #[serde(rename = "virtualMachine")]
+    pub virtual_machine: String,
+    #[serde(rename = "networkInterface")]
+    pub network_interface: String,
+    #[serde(rename = "ipAddress")]
+    pub ip_address: Option<String>,
+    #[serde(rename = "virtualNetwork")]
+    pub virtual_network: Option<String>,
+}
+
+fn virtual_network_id(subnet_id: &str) -> &str {
+    match subnet_id.to_lowercase().find("/subnets/") {
+        Some(pos) => &subnet_id[..pos],
+        None => subnet_id,
+    }
+}
+
+/// Lists the Bastion hosts with the virtual networks they can reach (their own
+/// and directly peered ones) and the virtual machines with a public IP address
+/// which are outside of all of these networks
+pub fn bastion(context: &Context) -> Result<Vec<BastionResult>> {
+    let service = &context.service;
+
+    let subscriptions = service.get_subscriptions()?;
+
+    let mut networks = vec![];
+    let mut hosts = vec![];
+    for subscription in &subscriptions {
+        networks.extend(service.get_virtual_networks(&subscription.subscription_id)?);
+        hosts.push(service.get_bastion_hosts(&subscription.subscription_id)?);
+    }
+
+    let reachable = |network_id: &str| -> Vec<String> {
+        let network_id = network_id.to_lowercase();
+        let mut ids = vec![network_id.clone()];
+        for network in &networks {
+            let id = network.id.to_lowercase();
+            for peering in &network.properties.virtual_network_peerings {
+                if !peering
+                    .properties
+                    .peering_state
+                    .eq_ignore_ascii_case("Connected")
+                {
+                    continue;
+                }
+                let remote = peering.properties.remote_virtual_network.id.to_lowercase();
+                if id == network_id && !ids.contains(&remote) {
+                    ids.push(remote);
+                } else if remote == network_id && !ids.contains(&id) {
+                    ids.push(id.clone());
+                }
+            }
+        }
+        ids
+    };
+
+    let mut covered: Vec<String> = vec![];
+    let mut bastions_by_subscription = vec![];
+    for bastion_hosts in hosts {
+        let mut bastions = vec![];
+        for host in bastion_hosts {
+            let mut virtual_networks = vec![];
+            for config in &host.properties.ip_configurations {
+                if let Some(subnet) = &config.properties.subnet {
+                    for id in reachable(virtual_network_id(&subnet.id)) {
+                        if !virtual_networks.contains(&id) {
+                            virtual_networks.push(id);
+                        }
+                    }
+                }
+            }
+            covered.extend(virtual_networks.iter().cloned());
+            bastions.push(Bastion {
+                id: host.id,
+                name: host.name,
+                sku: host.sku.map(|sku| sku.name),
+                virtual_networks: virtual_networks
+                    .iter()
+                    .map(|id| {
+                        networks
+                            .iter()
+                            .find(|network| network.id.to_lowercase() == *id)
+                            .map(|network| network.id.clone())
+                            .unwrap_or(id.clone())
+                    })
+                    .collect(),
+            });
+        }
+        bastions_by_subscription.push(bastions);
+    }
+
+    let mut results = vec![];
+
+    for (subscription, bastions) in subscriptions.into_iter().zip(bastions_by_subscription) {
+        let ip_addresses = service.get_ip_addresses(&subscription.subscription_id)?;
+
+        let mut exposed_machines = vec![];
+        for interface in service.get_network_interfaces(&subscription.subscription_id)? {
+            let virtual_machine = match &interface.properties.virtual_machine {
+                Some(virtual_machine) => virtual_machine.id.clone(),
+                None => continue,
+            };
+            for config in &interface.properties.ip_configurations {
+                let public_ip = match &config.properties.public_ip_address {
+                    Some(public_ip) => public_ip.id.to_lowercase(),
+                    None => continue,
+                };
+                let virtual_network = config
+                    .properties
+                    .subnet
+                    .as_ref()
+                    .map(|subnet| virtual_network_id(&subnet.id).to_owned());
+                let is_covered = virtual_network
+                    .as_ref()
+                    .map(|id| covered.contains(&id.to_lowercase()))
+                    .unwrap_or(false);
+                if !is_covered {
+                    exposed_machines.push(ExposedMachine {
+                        virtual_machine: virtual_machine.clone(),
+                        network_interface: interface.id.clone(),
+                        ip_address: ip_addresses
+                            .iter()
+                            .find(|ip| ip.id.to_lowercase() == public_ip)
+                            .map(|ip| ip.ip_address.clone()),
+                        virtual_network,
+                    });
+                }
+            }
+        }
+
+        if !bastions.is_empty() || !exposed_machines.is_empty() {
+            let result = BastionResult {
+                subscription,
+                bastions,
+                exposed_machines,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
 
-    return Ok(result);
+#[derive(Serialize)]
+pub struct FirewallResult {
+    pub subscription: Subscription,
+    pub firewalls: Vec<Firewall>,
 }
 
-pub fn get(context: &Context, request: &str) -> Result<Value> {
-    return context.service.get(request, "");
+#[derive(Serialize)]
+pub struct Firewall {
+    pub id: String,
+    pub name: String,
+    pub policy: Option<String>,
+    pub rules: Vec<FirewallRule>,
 }
 
-pub fn post(context: &Context, request: &str, body: &str) -> Result<Value> {
-    return context.service.post(request, "", body);
+#[derive(Serialize)]
+pub struct FirewallRule {
+    pub group: Option<String>,
+    pub collection: String,
+    pub priority: u32,
+    pub action: String,
+    pub name: String,
+    #[serde(rename = "ruleType")]
+    pub rule_type: String,
+    pub sources: Vec<String>,
+    pub destinations: Vec<String>,
+    pub ports: Vec<String>,
+}
+
+impl FirewallRule {
+    fn from_json(
+        json: &Value,
+        group: Option<&str>,
+        collection: &str,
+        priority: u32,
+        action: &str,
+        rule_type: &str,
+    ) -> FirewallRule {
+        fn strings(json: &Value, names: &[&str]) -> Vec<String> {
+            names
+                .iter()
+                .filter_map(|name| json[name].as_array())
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(str::to_owned)
+                .collect()
+        }
+
+        let mut ports = strings(json, &["destinationPorts"]);
+        if let Some(protocols) = json["protocols"].as_array() {
+            for protocol in protocols {
+                if let (Some(protocol_type), Some(port)) =
+                    (protocol["protocolType"].as_str(), protocol["port"].as_u64())
+                {
+                    ports.push(format!("{}:{}", protocol_type, port));
+                }
+            }
+        }
+
+        FirewallRule {
+            group: group.map(str::to_owned),
+            collection: collection.to_owned(),
+            priority,
+            action: action.to_owned(),
+            name: json["name"].as_str().unwrap_or("").to_owned(),
+            rule_type: json["ruleType"].as_str().unwrap_or(rule_type).to_owned(),
+            sources: strings(json, &["sourceAddresses", "sourceIpGroups"]),
+            destinations: strings(
+                json,
+                &[
+                    "destinationAddresses",
+                    "destinationFqdns",
+                    "destinationIpGroups",
+                    "targetFqdns",
+                    "fqdnTags",
+                ],
+            ),
+            ports,
+        }
+    }
+
+    /// Checks if the given IP address or FQDN is a destination of this rule
+    pub fn matches_destination(&self, destination: &str) -> bool {
+        let ip = destination.parse::<IpAddr>().ok();
+        self.destinations.iter().any(|pattern| match ip {
+            Some(ip) => pattern == "*" || matches_ip(pattern, &ip),
+            None => matches_wildcard(pattern, destination),
+        })
+    }
+}
+
+fn matches_ip(pattern: &str, ip: &IpAddr) -> bool {
+    let (network, prefix) = match pattern.find('/') {
+        Some(pos) => (&pattern[..pos], pattern[pos + 1..].parse::<u32>().ok()),
+        None => (pattern, None),
+    };
+    match (network.parse::<IpAddr>(), ip) {
+        (Ok(IpAddr::V4(network)), IpAddr::V4(ip)) => {
+            let prefix = prefix.unwrap_or(32).min(32);
+            let mask = if prefix == 0 {
+                0
+            } else {
+                u32::MAX << (32 - prefix)
+            };
+            u32::from(network) & mask == u32::from(*ip) & mask
+        }
+        (Ok(network), ip) => prefix.is_none() && &network == ip,
+        _ => false,
+    }
+}
+
+pub fn firewall(
+    context: &Context,
+    search: Option<&str>,
+    filter: Option<&String>,
+) -> Result<Vec<FirewallResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let mut azure_firewalls = service.get_firewalls(&subscription.subscription_id)?;
+        if let Some(filter) = filter {
+            azure_firewalls.retain(|firewall| firewall.name.contains(filter));
+        }
+
+        let mut firewalls = vec![];
+        for azure_firewall in azure_firewalls {
+            let mut rules = vec![];
+
+            let classic_collections = vec![
+                (
+                    "NetworkRule",
+                    &azure_firewall.properties.network_rule_collections,
+                ),
+                (
+                    "ApplicationRule",
+                    &azure_firewall.properties.application_rule_collections,
+                ),
+                ("NatRule", &azure_firewall.properties.nat_rule_collections),
+            ];
+            for (rule_type, collections) in classic_collections {
+                for collection in collections {
+                    let action = collection
+                        .properties
+                        .action
+                        .as_ref()
+                        .map(|action| action.action_type.as_str())
+                        .unwrap_or("");
+                    for rule in &collection.properties.rules {
+                        rules.push(FirewallRule::from_json(
+                            rule,
+                            None,
+                            &collection.name,
+                            collection.properties.priority,
+                            action,
+                            rule_type,
+                        ));
+                    }
+                }
+            }
+
+            if let Some(policy) = &azure_firewall.properties.firewall_policy {
+                let mut groups = service.get_firewall_policy_rule_collection_groups(&policy.id)?;
+                groups.sort_by_key(|group| group.properties.priority);
+                for group in groups {
+                    let mut collections = group.properties.rule_collections;
+                    collections.sort_by_key(|collection| collection.priority);
+                    for collection in collections {
+                        let action = collection
+                            .action
+                            .as_ref()
+                            .map(|action| action.action_type.as_str())
+                            .unwrap_or("");
+                        for rule in &collection.rules {
+                            rules.push(FirewallRule::from_json(
+                                rule,
+                                Some(&group.name),
+                                &collection.name,
+                                collection.priority,
+                                action,
+                                "",
+                            ));
+                        }
+                    }
+                }
+            }
+
+            if let Some(search) = search {
+                rules.retain(|rule| rule.matches_destination(search));
+                if rules.is_empty() {
+                    continue;
+                }
+            }
+
+            firewalls.push(Firewall {
+                id: azure_firewall.id,
+                name: azure_firewall.name,
+                policy: azure_firewall
+                    .properties
+                    .firewall_policy
+                    .map(|policy| policy.id),
+                rules,
+            });
+        }
+
+        if !firewalls.is_empty() {
+            let result = FirewallResult {
+                subscription,
+                firewalls,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct PortsResult {
+    pub subscription: Subscription,
+    pub exposures: Vec<PortExposure>,
+}
+
+#[derive(Serialize)]
+pub struct PortExposure {
+    #[serde(rename = "ipAddress")]
+    pub ip_address: IpAddress,
+    #[serde(rename = "networkInterface")]
+    pub network_interface: String,
+    #[serde(rename = "networkSecurityGroups")]
+    pub network_security_groups: Vec<String>,
+    pub ports: Vec<String>,
+}
+
+pub fn audit_ports(context: &Context) -> Result<Vec<PortsResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let ip_addresses = service.get_ip_addresses(&subscription.subscription_id)?;
+        let interfaces = service.get_network_interfaces(&subscription.subscription_id)?;
+        let groups = service.get_network_security_groups(&subscription.subscription_id)?;
+        let subnets: Vec<Subnet> = service
+            .get_virtual_networks(&subscription.subscription_id)?
+            .into_iter()
+            .flat_map(|network| network.properties.subnets)
+            .collect();
+
+        let mut exposures = vec![];
+
+        for ip_address in ip_addresses {
+            // only addresses attached to a network interface are considered,
+            // load balancer frontends are governed by the backend NICs
+            let ip_configuration = match &ip_address.ip_configuration {
+                Some(id) => id.to_lowercase(),
+                None => continue,
+            };
+            let interface = interfaces.iter().find(|interface| {
+                ip_configuration.starts_with(&format!("{}/", interface.id.to_lowercase()))
+            });
+            let interface = match interface {
+                Some(interface) => interface,
+                None => continue,
+            };
+
+            let mut group_ids = vec![];
+            if let Some(group) = &interface.properties.network_security_group {
+                group_ids.push(group.id.to_lowercase());
+            }
+            for config in &interface.properties.ip_configurations {
+                if config.id.to_lowercase() != ip_configuration {
+                    continue;
+                }
+                let subnet = config.properties.subnet.as_ref().and_then(|subnet| {
+                    subnets
+                        .iter()
+                        .find(|s| s.id.to_lowercase() == subnet.id.to_lowercase())
+                });
+                if let Some(group) =
+                    subnet.and_then(|s| s.properties.network_security_group.as_ref())
+                {
+                    group_ids.push(group.id.to_lowercase());
+                }
+            }
+
+            let applied: Vec<&NetworkSecurityGroup> = groups
+                .iter()
+                .filter(|group| group_ids.contains(&group.id.to_lowercase()))
+                .collect();
+
+            let ports = exposed_ports(&applied);
+            if !ports.is_empty() {
+                exposures.push(PortExposure {
+                    ip_address,
+                    network_interface: interface.name.clone(),
+                    network_security_groups: applied.iter().map(|g| g.name.clone()).collect(),
+                    ports,
+                });
+            }
+        }
+
+        if !exposures.is_empty() {
+            let result = PortsResult {
+                subscription,
+                exposures,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct IdentityResult {
+    pub subscription: Subscription,
+    pub identities: Vec<Identity>,
+}
+
+#[derive(Serialize)]
+pub struct Identity {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "identityType")]
+    pub identity_type: String,
+    #[serde(rename = "principalId")]
+    pub principal_id: String,
+    pub resources: Vec<String>,
+    pub roles: Vec<IdentityRole>,
+}
+
+#[derive(Serialize)]
+pub struct IdentityRole {
+    pub role: String,
+    pub scope: String,
+}
+
+/// Lists user-assigned identities with the resources using them and
+/// system-assigned identities of resources, each with their role assignments
+pub fn identity(context: &Context, filter: Option<&String>) -> Result<Vec<IdentityResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+    let mut role_names: HashMap<String, String> = HashMap::new();
+
+    for subscription in service.get_subscriptions()? {
+        let resources = service.get_resources(&subscription.subscription_id)?;
+
+        let mut identities = vec![];
+
+        for identity in service.get_user_assigned_identities(&subscription.subscription_id)? {
+            let id = identity.id.to_lowercase();
+            let users = resources
+                .iter()
+                .filter(|resource| match &resource.identity {
+                    Some(resource_identity) => resource_identity
+                        .user_assigned_identities
+                        .keys()
+                        .any(|key| key.to_lowercase() == id),
+                    None => false,
+                })
+                .map(|resource| resource.id.clone())
+                .collect();
+            identities.push(Identity {
+                id: identity.id,
+                name: identity.name,
+                identity_type: "UserAssigned".to_owned(),
+                principal_id: identity.properties.principal_id,
+                resources: users,
+                roles: vec![],
+            });
+        }
+
+        for resource in &resources {
+            if let Some(resource_identity) = &resource.identity {
+                if let Some(principal_id) = &resource_identity.principal_id {
+                    if resource_identity.identity_type.contains("SystemAssigned") {
+                        identities.push(Identity {
+                            id: resource.id.clone(),
+                            name: resource.name.clone(),
+                            identity_type: "SystemAssigned".to_owned(),
+                            principal_id: principal_id.clone(),
+                            resources: vec![resource.id.clone()],
+                            roles: vec![],
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(filter) = filter {
+            identities.retain(|identity| identity.name.contains(filter));
+        }
+
+        if identities.is_empty() {
+            continue;
+        }
+
+        let assignments =
+            service.get_subscription_role_assignments(&subscription.subscription_id)?;
+        for identity in identities.iter_mut() {
+            for assignment in &assignments {
+                if assignment.properties.principal_id != identity.principal_id {
+                    continue;
+                }
+                let role_definition_id = &assignment.properties.role_definition_id;
+                if !role_names.contains_key(role_definition_id) {
+                    let role_definition = service.get_role_definition(role_definition_id)?;
+                    role_names.insert(
+                        role_definition_id.clone(),
+                        role_definition.properties.role_name,
+                    );
+                }
+                identity.roles.push(IdentityRole {
+                    role: role_names[role_definition_id].clone(),
+                    scope: assignment.properties.scope.clone(),
+                });
+            }
+        }
+
+        let result = IdentityResult {
+            subscription,
+            identities,
+        };
+        context.emit(&mut results, result)?;
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct PrincipalUsage {
+    pub id: String,
+    #[serde(rename = "appId")]
+    pub app_id: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    pub owners: Vec<String>,
+    pub references: Vec<PrincipalReference>,
+}
+
+#[derive(Serialize)]
+pub struct PrincipalReference {
+    pub source: String,
+    pub resource: String,
+}
+
+/// Resolves the service principal object IDs and application IDs referenced by
+/// role assignments, Kubernetes clusters and Key Vault access policies
+pub fn sp_usage(context: &Context, filter: Option<&String>) -> Result<Vec<PrincipalUsage>> {
+    let service = &context.service;
+
+    // (object ID or application ID, is application ID, reference)
+    let mut references: Vec<(String, bool, PrincipalReference)> = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        for assignment in service.get_subscription_role_assignments(subscription_id)? {
+            if assignment.properties.principal_type.as_deref() == Some("ServicePrincipal") {
+                references.push((
+                    assignment.properties.principal_id,
+                    false,
+                    PrincipalReference {
+                        source: "role assignment".to_owned(),
+                        resource: assignment.properties.scope,
+                    },
+                ));
+            }
+        }
+
+        for cluster in service.get_clusters(subscription_id)? {
+            let mut app_ids = vec![];
+            if let Some(profile) = &cluster.properties.service_principal_profile {
+                if profile.client_id != "msi" {
+                    app_ids.push(profile.client_id.clone());
+                }
+            }
+            if let Some(profile) = &cluster.properties.aad_profile {
+                app_ids.extend(profile.client_app_id.iter().cloned());
+                app_ids.extend(profile.server_app_id.iter().cloned());
+            }
+            for app_id in app_ids {
+                references.push((
+                    app_id,
+                    true,
+                    PrincipalReference {
+                        source: "cluster".to_owned(),
+                        resource: cluster.id.clone(),
+                    },
+                ));
+            }
+        }
+
+        for vault in service.get_key_vaults(subscription_id)? {
+            for policy in &vault.properties.access_policies {
+                let (id, is_app_id) = match &policy.application_id {
+                    Some(application_id) if !application_id.is_empty() => {
+                        (application_id.clone(), true)
+                    }
+                    _ => (policy.object_id.clone(), false),
+                };
+                references.push((
+                    id,
+                    is_app_id,
+                    PrincipalReference {
+                        source: "key vault".to_owned(),
+                        resource: vault.id.clone(),
+                    },
+                ));
+            }
+        }
+    }
+
+    if let Some(filter) = filter {
+        let filter = filter.to_lowercase();
+        references.retain(|(id, _, _)| id.to_lowercase() == filter);
+    }
+
+    let object_ids: Vec<String> = {
+        let mut ids: Vec<String> = references
+            .iter()
+            .filter(|(_, is_app_id, _)| !is_app_id)
+            .map(|(id, _, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    };
+    let objects = if object_ids.is_empty() {
+        vec![]
+    } else {
+        service.get_directory_objects(&object_ids)?
+    };
+
+    let mut usages: Vec<PrincipalUsage> = vec![];
+    let mut owners_cache: HashMap<String, Vec<String>> = HashMap::new();
+
+    for (id, is_app_id, reference) in references {
+        if let Some(usage) = usages
+            .iter_mut()
+            .find(|usage| usage.id == id || (is_app_id && usage.app_id.as_ref() == Some(&id)))
+        {
+            usage.references.push(reference);
+            continue;
+        }
+
+        let object = if is_app_id {
+            service.get_service_principal_by_app_id(&id)?
+        } else {
+            match objects.iter().find(|object| object.id == id) {
+                Some(object) => Some(object.clone()),
+                None => continue,
+            }
+        };
+
+        // object IDs of users and groups are not of interest here
+        if let Some(object) = &object {
+            if let Some(object_type) = &object.object_type {
+                if object_type != "#microsoft.graph.servicePrincipal" {
+                    continue;
+                }
+            }
+        }
+
+        let owners = match &object {
+            Some(object) => {
+                if !owners_cache.contains_key(&object.id) {
+                    let owners = service
+                        .get_service_principal_owners(&object.id)?
+                        .into_iter()
+                        .filter_map(|owner| owner.user_principal_name.or(owner.display_name))
+                        .collect();
+                    owners_cache.insert(object.id.clone(), owners);
+                }
+                owners_cache[&object.id].clone()
+            }
+            None => vec![],
+        };
+
+        usages.push(PrincipalUsage {
+            id: object.as_ref().map(|o| o.id.clone()).unwrap_or(id.clone()),
+            app_id: match &object {
+                Some(object) => object.app_id.clone(),
+                None => Some(id.clone()),
+            },
+            display_name: object.and_then(|o| o.display_name),
+            owners,
+            references: vec![reference],
+        });
+    }
+
+    usages.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+
+    return Ok(usages);
+}
+
+#[derive(Serialize)]
+pub struct ChangesResult {
+    pub caller: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Serialize)]
+pub struct Change {
+    #[serde(rename = "changeType")]
+    pub change_type: String,
+    pub resource: String,
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub subscription: String,
+    pub operations: u32,
+    #[serde(rename = "lastChange")]
+    pub last_change: String,
+}
+
+/// Whether the ID is of the form `/subscriptions/.../resourceGroups/.../providers/<namespace>/<type>/<name>`,
+/// i.e. a resource which would be contained in the resource list
+fn is_top_level_resource(id: &str) -> bool {
+    let parts: Vec<&str> = id.split('/').collect();
+    parts.len() == 9
+        && parts[3].eq_ignore_ascii_case("resourceGroups")
+        && parts[5].eq_ignore_ascii_case("providers")
+}
+
+fn change_type(deleted: bool, exists: bool, created: bool) -> &'static str {
+    if deleted || !exists {
+        "deleted"
+    } else if created {
+        "created"
+    } else {
+        "modified"
+    }
+}
+
+/// Summarizes the resources created, modified and deleted in the last days, by caller
+pub fn changes(context: &Context, days: u32) -> Result<Vec<ChangesResult>> {
+    let service = &context.service;
+
+    let from = Utc::now() - Duration::days(days as i64);
+    let from_str = from.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut results: Vec<ChangesResult> = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        let events = service.get_activity_log(subscription_id, &from_str)?;
+        if events.is_empty() {
+            continue;
+        }
+
+        let resources: HashMap<String, Resource> = service
+            .get_resources_with_timestamps(subscription_id)?
+            .into_iter()
+            .map(|resource| (resource.id.to_lowercase(), resource))
+            .collect();
+
+        // (caller, resource ID) -> (resource type, deleted, operations, last change)
+        let mut grouped: HashMap<(String, String), (String, bool, u32, String)> = HashMap::new();
+        for event in events {
+            if event.status.value != "Succeeded" {
+                continue;
+            }
+            let operation = event.operation_name.value.to_lowercase();
+            let (resource_type, deleted) = if let Some(t) = operation.strip_suffix("/write") {
+                (t.to_owned(), false)
+            } else if let Some(t) = operation.strip_suffix("/delete") {
+                (t.to_owned(), true)
+            } else {
+                continue;
+            };
+
+            let caller = event.caller.unwrap_or("unknown".to_owned());
+            let entry = grouped
+                .entry((caller, event.resource_id.to_lowercase()))
+                .or_insert((resource_type, false, 0, String::new()));
+            entry.1 |= deleted;
+            entry.2 += 1;
+            if event.event_timestamp > entry.3 {
+                entry.3 = event.event_timestamp;
+            }
+        }
+
+        for ((caller, id), (resource_type, deleted, operations, last_change)) in grouped {
+            let resource = resources.get(&id);
+            let exists = resource.is_some() || !is_top_level_resource(&id);
+            let created = resource
+                .and_then(|resource| resource.created_time.as_ref())
+                .and_then(|created_time| DateTime::parse_from_rfc3339(created_time).ok())
+                .map(|created_time| created_time >= from)
+                .unwrap_or(false);
+
+            let change = Change {
+                change_type: change_type(deleted, exists, created).to_owned(),
+                resource: resource.map(|resource| resource.id.clone()).unwrap_or(id),
+                resource_type,
+                subscription: subscription.name.clone(),
+                operations,
+                last_change: iso_timestamp(&last_change),
+            };
+
+            match results.iter_mut().find(|result| result.caller == caller) {
+                Some(result) => result.changes.push(change),
+                None => results.push(ChangesResult {
+                    caller,
+                    changes: vec![change],
+                }),
+            }
+        }
+    }
+
+    results.sort_by(|a, b| a.caller.cmp(&b.caller));
+    for result in &mut results {
+        result
+            .changes
+            .sort_by(|a, b| b.last_change.cmp(&a.last_change));
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct StaleResult {
+    pub subscription: Subscription,
+    pub resources: Vec<StaleResource>,
+}
+
+#[derive(Serialize)]
+pub struct StaleResource {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    #[serde(rename = "lastModified")]
+    pub last_modified: String,
+    /// Type specific indications that the resource is unused, like a deallocated VM
+    pub hints: Vec<String>,
+}
+
+/// The activity log can only be queried for the last 90 days
+const ACTIVITY_LOG_RETENTION_DAYS: u32 = 90;
+
+/// Returns the ID of the top-level resource containing the given (child) resource
+fn top_level_resource(id: &str) -> String {
+    let parts: Vec<&str> = id.split('/').collect();
+    if parts.len() > 9 && is_top_level_resource(&parts[..9].join("/")) {
+        parts[..9].join("/")
+    } else {
+        id.to_owned()
+    }
+}
+
+/// Lists resources not modified in the last days, as candidates for archival or deletion.
+/// A resource counts as modified if its `changedTime` is recent or the activity log
+/// contains a successful write to the resource or one of its child resources.
+pub fn stale(context: &Context, days: u32, filter: Option<&String>) -> Result<Vec<StaleResult>> {
+    let service = &context.service;
+
+    let from = Utc::now() - Duration::days(days as i64);
+    let log_from = Utc::now() - Duration::days(days.min(ACTIVITY_LOG_RETENTION_DAYS) as i64);
+    let log_from_str = log_from.to_rfc3339_opts(SecondsFormat::Secs, true);
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        let mut resources = service.get_resources_with_timestamps(subscription_id)?;
+        if let Some(filter) = filter {
+            resources.retain(|resource| resource.name.contains(filter));
+        }
+        let mut resource_groups = service.get_resource_groups(subscription_id)?;
+        retain_tagged(service, &subscription, &mut resource_groups, &mut resources);
+
+        let written: HashSet<String> = service
+            .get_activity_log(subscription_id, &log_from_str)?
+            .into_iter()
+            .filter(|event| {
+                event.status.value == "Succeeded"
+                    && event
+                        .operation_name
+                        .value
+                        .to_lowercase()
+                        .ends_with("/write")
+            })
+            .map(|event| top_level_resource(&event.resource_id.to_lowercase()))
+            .collect();
+
+        let mut stale_resources: Vec<(Resource, String)> = resources
+            .into_iter()
+            .filter(|resource| !written.contains(&resource.id.to_lowercase()))
+            .filter_map(|resource| {
+                let last_modified = resource
+                    .changed_time
+                    .as_ref()
+                    .or(resource.created_time.as_ref())?
+                    .clone();
+                match DateTime::parse_from_rfc3339(&last_modified) {
+                    Ok(time) if time < from => Some((resource, last_modified)),
+                    _ => None,
+                }
+            })
+            .collect();
+        if stale_resources.is_empty() {
+            continue;
+        }
+
+        let has_type = |resource_type: &str| {
+            stale_resources
+                .iter()
+                .any(|(resource, _)| resource.resource_type.eq_ignore_ascii_case(resource_type))
+        };
+        let power_states: HashMap<String, String> = if has_type(TYPE_VIRTUAL_MACHINE) {
+            service
+                .get_virtual_machine_statuses(subscription_id)?
+                .iter()
+                .filter_map(|status| {
+                    let power_state = status.power_state()?.to_owned();
+                    Some((status.id.to_lowercase(), power_state))
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let unassociated_ips: HashSet<String> = if has_type(TYPE_PUBLIC_IP_ADDRESS) {
+            service
+                .get_ip_addresses(subscription_id)?
+                .into_iter()
+                .filter(|ip_address| ip_address.ip_configuration.is_none())
+                .map(|ip_address| ip_address.id.to_lowercase())
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        stale_resources.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let resources = stale_resources
+            .into_iter()
+            .map(|(resource, last_modified)| {
+                let id = resource.id.to_lowercase();
+                let mut hints = vec![];
+                if let Some(power_state) = power_states.get(&id) {
+                    if power_state == "stopped" || power_state == "deallocated" {
+                        hints.push(format!("virtual machine {}", power_state));
+                    }
+                }
+                if unassociated_ips.contains(&id) {
+                    hints.push("public IP address not associated".to_owned());
+                }
+                StaleResource {
+                    id: resource.id,
+                    name: resource.name,
+                    resource_type: resource.resource_type,
+                    last_modified: iso_timestamp(&last_modified),
+                    hints,
+                }
+            })
+            .collect();
+
+        let result = StaleResult {
+            subscription,
+            resources,
+        };
+        context.emit(&mut results, result)?;
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct StorageKeysResult {
+    pub subscription: Subscription,
+    pub accounts: Vec<StorageAccountKeys>,
+}
+
+#[derive(Serialize)]
+pub struct StorageAccountKeys {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "sharedKeyAccess")]
+    pub shared_key_access: bool,
+    /// The rotation period of the key policy of the account, otherwise `--max-key-age`
+    #[serde(rename = "maxAgeDays")]
+    pub max_age_days: u32,
+    pub keys: Vec<StorageKey>,
+}
+
+#[derive(Serialize)]
+pub struct StorageKey {
+    pub name: String,
+    #[serde(rename = "creationTime")]
+    pub creation_time: Option<String>,
+    #[serde(rename = "ageDays")]
+    pub age_days: Option<i64>,
+    /// Older than the maximum age while shared key access is allowed
+    pub expired: bool,
+}
+
+/// Lists the storage accounts with the age of their access keys, flagging keys older than
+/// the rotation period of the key policy or `max_age_days`
+pub fn audit_storage_keys(context: &Context, max_age_days: u32) -> Result<Vec<StorageKeysResult>> {
+    let service = &context.service;
+    let now = Utc::now();
+    let subscriptions = service.get_subscriptions()?;
+    let results = context.parallel_results(&subscriptions, |subscription| {
+        let mut accounts: Vec<StorageAccountKeys> = service
+            .get_storage_accounts(&subscription.subscription_id)?
+            .into_iter()
+            .map(|account| {
+                let properties = account.properties;
+                let shared_key_access = properties.allow_shared_key_access != Some(false);
+                let max_age_days = match properties.key_policy {
+                    Some(policy) => policy.key_expiration_period_in_days,
+                    None => max_age_days,
+                };
+                let (key1, key2) = match properties.key_creation_time {
+                    Some(times) => (times.key1, times.key2),
+                    None => (None, None),
+                };
+                let keys = vec![("key1", key1), ("key2", key2)]
+                    .into_iter()
+                    .map(|(name, creation_time)| {
+                        let age_days = creation_time
+                            .as_deref()
+                            .and_then(|time| key_age_days(time, &now));
+                        StorageKey {
+                            name: name.to_owned(),
+                            expired: shared_key_access
+                                && age_days.is_some_and(|age| age > max_age_days as i64),
+                            creation_time,
+                            age_days,
+                        }
+                    })
+                    .collect();
+                StorageAccountKeys {
+                    id: account.id,
+                    name: account.name,
+                    shared_key_access,
+                    max_age_days,
+                    keys,
+                }
+            })
+            .collect();
+        if accounts.is_empty() {
+            return Ok(None);
+        }
+        accounts.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Some(StorageKeysResult {
+            subscription: subscription.clone(),
+            accounts,
+        }))
+    })?;
+
+    return Ok(results);
+}
+
+/// Full days since the key was created
+fn key_age_days(creation_time: &str, now: &DateTime<Utc>) -> Option<i64> {
+    let created = DateTime::parse_from_rfc3339(creation_time).ok()?;
+    Some((*now - created.with_timezone(&Utc)).num_days())
+}
+
+#[derive(Serialize)]
+pub struct KeyVaultAccessResult {
+    pub subscription: Subscription,
+    #[serde(rename = "keyVaults")]
+    pub key_vaults: Vec<KeyVaultAccess>,
+}
+
+#[derive(Serialize)]
+pub struct KeyVaultAccess {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "rbacAuthorization")]
+    pub rbac_authorization: bool,
+    pub principals: Vec<KeyVaultPrincipal>,
+}
+
+#[derive(Serialize)]
+pub struct KeyVaultPrincipal {
+    pub id: String,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    #[serde(rename = "principalType")]
+    pub principal_type: Option<String>,
+    pub source: String,
+}
+
+const SECRET_READ_ACTION: &str = "Microsoft.KeyVault/vaults/secrets/getSecret/action";
+
+/// Lists the principals allowed to read secrets, depending on the permission model
+/// of the vault either from the access policies or from the RBAC role assignments
+pub fn audit_key_vault_access(context: &Context) -> Result<Vec<KeyVaultAccessResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+    let mut role_definitions: HashMap<String, RoleDefinition> = HashMap::new();
+
+    for subscription in service.get_subscriptions()? {
+        let mut key_vaults = vec![];
+
+        for vault in service.get_key_vaults(&subscription.subscription_id)? {
+            let mut principals = vec![];
+
+            if vault.properties.enable_rbac_authorization {
+                for assignment in service.get_role_assignments(&vault.id)? {
+                    let role_definition_id =
+                        assignment.properties.role_definition_id.to_lowercase();
+                    if !role_definitions.contains_key(&role_definition_id) {
+                        let role_definition = service
+                            .get_role_definition(&assignment.properties.role_definition_id)?;
+                        role_definitions.insert(role_definition_id.clone(), role_definition);
+                    }
+                    let role_definition = &role_definitions[&role_definition_id];
+                    if role_definition.allows_data_action(SECRET_READ_ACTION) {
+                        principals.push(KeyVaultPrincipal {
+                            id: assignment.properties.principal_id,
+                            display_name: None,
+                            principal_type: assignment.properties.principal_type,
+                            source: role_definition.properties.role_name.clone(),
+                        });
+                    }
+                }
+            } else {
+                for policy in vault.properties.access_policies {
+                    let readable = policy.permissions.secrets.iter().any(|permission| {
+                        permission.eq_ignore_ascii_case("get")
+                            || permission.eq_ignore_ascii_case("all")
+                    });
+                    if readable {
+                        principals.push(KeyVaultPrincipal {
+                            id: policy.object_id,
+                            display_name: None,
+                            principal_type: None,
+                            source: "access policy".to_owned(),
+                        });
+                    }
+                }
+            }
+
+            key_vaults.push(KeyVaultAccess {
+                id: vault.id,
+                name: vault.name,
+                rbac_authorization: vault.properties.enable_rbac_authorization,
+                principals,
+            });
+        }
+
+        if !key_vaults.is_empty() {
+            results.push(KeyVaultAccessResult {
+                subscription,
+                key_vaults,
+            });
+        }
+    }
+
+    let mut ids: Vec<String> = results
+        .iter()
+        .flat_map(|result| &result.key_vaults)
+        .flat_map(|vault| &vault.principals)
+        .map(|principal| principal.id.clone())
+        .collect();
+    ids.sort();
+    ids.dedup();
+
+    if !ids.is_empty() {
+        match service.get_directory_objects(&ids) {
+            Ok(objects) => {
+                for principal in results
+                    .iter_mut()
+                    .flat_map(|result| &mut result.key_vaults)
+                    .flat_map(|vault| &mut vault.principals)
+                {
+                    if let Some(object) = objects.iter().find(|o| o.id == principal.id) {
+                        principal.display_name = object.display_name.clone();
+                        principal.principal_type = object
+                            .object_type
+                            .as_ref()
+                            .map(|t| t.trim_start_matches("#microsoft.graph.").to_owned());
+                    }
+                }
+            }
+            Err(err) => {
+                warn!("Failed to resolve principal names: {}", err);
+                service.record_failure();
+            }
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct TagsResult {
+    pub subscription: Subscription,
+    pub owners: Vec<TagsOwner>,
+}
+
+#[derive(Serialize)]
+pub struct TagsOwner {
+    /// The value of the owner tag, if set
+    pub owner: Option<String>,
+    pub resources: Vec<TagsViolation>,
+}
+
+#[derive(Serialize)]
+pub struct TagsViolation {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub violations: Vec<String>,
+}
+
+/// Lists resources not compliant with the tag policy, grouped by their owner tag
+pub fn audit_tags(context: &Context, policy: &TagPolicy) -> Result<Vec<TagsResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let mut owners: BTreeMap<Option<String>, Vec<TagsViolation>> = BTreeMap::new();
+        for resource in service.get_resources(&subscription.subscription_id)? {
+            let tags = resource.tags.unwrap_or_default();
+            let violations = policy.check(&resource.resource_type, &tags);
+            if violations.is_empty() {
+                continue;
+            }
+            let owner = find_tag(&tags, &policy.owner_tag).cloned();
+            owners.entry(owner).or_default().push(TagsViolation {
+                id: resource.id,
+                name: resource.name,
+                resource_type: resource.resource_type,
+                violations,
+            });
+        }
+
+        if !owners.is_empty() {
+            let result = TagsResult {
+                subscription,
+                owners: owners
+                    .into_iter()
+                    .map(|(owner, resources)| TagsOwner { owner, resources })
+                    .collect(),
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+type PortRanges = Vec<(u32, u32)>;
+
+const ALL_PORTS: (u32, u32) = (0, 65535);
+
+fn exposed_ports(groups: &[&NetworkSecurityGroup]) -> Vec<String> {
+    let mut ports = vec![];
+    for protocol in &["Tcp", "Udp"] {
+        let mut allowed = vec![ALL_PORTS];
+        for group in groups {
+            allowed = intersect_ports(&allowed, &allowed_ports(group, protocol));
+        }
+        for (from, to) in allowed {
+            let range = if (from, to) == ALL_PORTS {
+                "*".to_owned()
+            } else if from == to {
+                from.to_string()
+            } else {
+                format!("{}-{}", from, to)
+            };
+            ports.push(format!("{}/{}", range, protocol.to_lowercase()));
+        }
+    }
+    ports
+}
+
+fn allowed_ports(group: &NetworkSecurityGroup, protocol: &str) -> PortRanges {
+    fn is_internet(prefix: &str) -> bool {
+        prefix == "*" || prefix == "0.0.0.0/0" || prefix.eq_ignore_ascii_case("Internet")
+    }
+
+    let mut rules: Vec<_> = group
+        .properties
+        .security_rules
+        .iter()
+        .chain(group.properties.default_security_rules.iter())
+        .map(|rule| &rule.properties)
+        .filter(|rule| {
+            rule.direction.eq_ignore_ascii_case("Inbound")
+                && (rule.protocol == "*" || rule.protocol.eq_ignore_ascii_case(protocol))
+                && rule
+                    .source_address_prefix
+                    .iter()
+                    .chain(rule.source_address_prefixes.iter())
+                    .any(|prefix| is_internet(prefix))
+        })
+        .collect();
+    rules.sort_by_key(|rule| rule.priority);
+
+    let mut decided = vec![];
+    let mut allowed = vec![];
+    for rule in rules {
+        let ranges: PortRanges = rule
+            .destination_port_range
+            .iter()
+            .chain(rule.destination_port_ranges.iter())
+            .filter_map(|range| parse_port_range(range))
+            .collect();
+        if rule.access.eq_ignore_ascii_case("Allow") {
+            let undecided = subtract_ports(&ranges, &decided);
+            allowed = merge_ports(allowed.into_iter().chain(undecided).collect());
+        }
+        decided = merge_ports(decided.into_iter().chain(ranges).collect());
+    }
+    allowed
+}
+
+fn parse_port_range(range: &str) -> Option<(u32, u32)> {
+    if range == "*" {
+        return Some(ALL_PORTS);
+    }
+    let mut parts = range.splitn(2, '-');
+    let from = parts.next()?.trim().parse().ok()?;
+    let to = match parts.next() {
+        Some(to) => to.trim().parse().ok()?,
+        None => from,
+    };
+    if from <= to {
+        Some((from, to))
+    } else {
+        None
+    }
+}
+
+fn merge_ports(mut ranges: PortRanges) -> PortRanges {
+    ranges.sort();
+    let mut merged: PortRanges = vec![];
+    for (from, to) in ranges {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 + 1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    merged
+}
+
+fn subtract_ports(ranges: &PortRanges, other: &PortRanges) -> PortRanges {
+    let mut result = vec![];
+    for &(from, to) in ranges {
+        let mut start = from;
+        for &(other_from, other_to) in other {
+            if other_to < start || other_from > to {
+                continue;
+            }
+            if other_from > start {
+                result.push((start, other_from - 1));
+            }
+            start = other_to + 1;
+            if start > to {
+                break;
+            }
+        }
+        if start <= to {
+            result.push((start, to));
+        }
+    }
+    result
+}
+
+fn intersect_ports(ranges: &PortRanges, other: &PortRanges) -> PortRanges {
+    let mut result = vec![];
+    for &(from, to) in ranges {
+        for &(other_from, other_to) in other {
+            let (start, end) = (from.max(other_from), to.min(other_to));
+            if start <= end {
+                result.push((start, end));
+            }
+        }
+    }
+    merge_ports(result)
+}
+
+#[derive(Serialize)]
+pub struct AccountResult {
+    pub user: Option<String>,
+    pub tenant: String,
+    #[serde(rename = "expiresOn")]
+    pub expires_on: Option<String>,
+    pub expired: bool,
+    pub subscription: Option<AccountSubscription>,
+}
+
+#[derive(Serialize)]
+pub struct AccountSubscription {
+    pub id: String,
+    pub name: String,
+}
+
+/// Shows the identity of the stored token and the default subscription, without any requests
+pub fn account(context: &Context) -> Result<AccountResult> {
+    let token_set = context.service.cached_token_set()?;
+    let tenant = match &token_set {
+        Some(token_set) => token_set.access_token.tenant.id.clone(),
+        None => context.service.tenant()?.id,
+    };
+
+    let subscription = read_default_subscription()?.and_then(|subscription| {
+        match (subscription["id"].as_str(), subscription["name"].as_str()) {
+            (Some(id), Some(name)) => Some(AccountSubscription {
+                id: id.to_owned(),
+                name: name.to_owned(),
+            }),
+            _ => None,
+        }
+    });
+
+    return Ok(AccountResult {
+        user: token_set
+            .as_ref()
+            .map(|token_set| token_set.access_token.unique_name.clone()),
+        tenant,
+        expires_on: token_set.as_ref().map(|token_set| token_set.expires_on()),
+        expired: token_set
+            .as_ref()
+            .is_none_or(|token_set| token_set.access_token.is_expired()),
+        subscription,
+    });
+}
+
+#[derive(Serialize)]
+pub struct UseResult {
+    pub id: String,
+    pub name: String,
+    pub tenant: Option<String>,
+}
+
+/// Sets the default subscription of the Azure CLI, like `az account set`
+pub fn use_subscription(subscription: &str) -> Result<UseResult> {
+    let entry = write_default_subscription(subscription)?;
+    let get = |key: &str| entry[key].as_str().map(str::to_owned);
+    return Ok(UseResult {
+        id: get("id").unwrap_or_default(),
+        name: get("name").unwrap_or_default(),
+        tenant: get("tenantId"),
+    });
+}
+
+#[derive(Serialize)]
+pub struct ExplainResult {
+    pub command: String,
+    pub operations: Vec<ExplainOperation>,
+    /// The RBAC actions of all Resource Manager operations, for custom roles
+    pub actions: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExplainOperation {
+    pub method: String,
+    pub provider: String,
+    pub path: String,
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+    pub action: String,
+}
+
+/// Lists the REST operations the command may invoke, without any requests
+pub fn explain(command: &str) -> ExplainResult {
+    let operations = command_operations(command).unwrap_or(&[]);
+    let actions = rbac_actions(operations);
+
+    return ExplainResult {
+        command: command.to_owned(),
+        operations: operations
+            .iter()
+            .map(|operation| ExplainOperation {
+                method: operation.0.to_owned(),
+                provider: provider(operation).to_owned(),
+                path: operation.1.to_owned(),
+                api_version: operation.2.to_owned(),
+                action: operation.3.to_owned(),
+            })
+            .collect(),
+        actions,
+    };
+}
+
+/// Custom role definition, in the format of `az role definition create`
+#[derive(Serialize)]
+pub struct RoleDefinitionResult {
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "IsCustom")]
+    pub is_custom: bool,
+    #[serde(rename = "Description")]
+    pub description: String,
+    #[serde(rename = "Actions")]
+    pub actions: Vec<String>,
+    #[serde(rename = "NotActions")]
+    pub not_actions: Vec<String>,
+    #[serde(rename = "DataActions")]
+    pub data_actions: Vec<String>,
+    #[serde(rename = "NotDataActions")]
+    pub not_data_actions: Vec<String>,
+    #[serde(rename = "AssignableScopes")]
+    pub assignable_scopes: Vec<String>,
+}
+
+/// Creates a role with only the actions needed to run the commands, assignable
+/// to the given scope or the default subscription of the Azure CLI
+pub fn rbac_minrole(
+    commands: &[String],
+    name: Option<&str>,
+    scope: Option<&str>,
+) -> Result<RoleDefinitionResult> {
+    let scope = match scope {
+        Some(scope) => scope.to_owned(),
+        None => read_default_subscription()?
+            .and_then(|subscription| subscription["id"].as_str().map(str::to_owned))
+            .map(|id| format!("/subscriptions/{}", id))
+            .ok_or("no default subscription found, use --scope")?,
+    };
+
+    let operations: Vec<_> = commands
+        .iter()
+        .flat_map(|command| command_operations(command).unwrap_or(&[]))
+        .cloned()
+        .collect();
+
+    return Ok(RoleDefinitionResult {
+        name: match name {
+            Some(name) => name.to_owned(),
+            None => format!("azi {}", commands.join(" ")),
+        },
+        is_custom: true,
+        description: format!("Allows running azi {}", commands.join(", ")),
+        actions: rbac_actions(&operations),
+        not_actions: vec![],
+        data_actions: vec![],
+        not_data_actions: vec![],
+        assignable_scopes: vec![scope],
+    });
+}
+
+#[derive(Serialize)]
+pub struct SubscriptionInfo {
+    pub id: String,
+    #[serde(rename = "subscriptionId")]
+    pub subscription_id: String,
+    #[serde(rename = "displayName")]
+    pub name: String,
+    pub state: Option<String>,
+    #[serde(rename = "tenantId")]
+    pub tenant_id: Option<String>,
+    /// Only set when querying multiple tenants
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Display names of the management groups above the subscription, starting with the root
+    #[serde(rename = "managementGroups")]
+    pub management_groups: Vec<String>,
+    #[serde(rename = "spendingLimit")]
+    pub spending_limit: Option<String>,
+    #[serde(rename = "quotaId")]
+    pub quota_id: Option<String>,
+    pub tags: BTreeMap<String, String>,
+}
+
+/// Lists the subscriptions with their state, policies, tags and management groups
+pub fn subs(context: &Context) -> Result<Vec<SubscriptionInfo>> {
+    let service = &context.service;
+    let subscriptions = service.get_subscriptions()?;
+
+    let ids: Vec<&str> = subscriptions
+        .iter()
+        .map(|subscription| subscription.subscription_id.as_str())
+        .collect();
+    let mut management_groups = match service.get_graph_management_groups(&ids) {
+        Ok(management_groups) => management_groups,
+        Err(err) => {
+            warn!("Failed to query management groups: {}", err);
+            service.record_failure();
+            HashMap::new()
+        }
+    };
+
+    return Ok(subscriptions
+        .into_iter()
+        .map(|subscription| {
+            let policies = subscription.policies;
+            SubscriptionInfo {
+                management_groups: management_groups
+                    .remove(&subscription.subscription_id.to_lowercase())
+                    .unwrap_or_default(),
+                id: subscription.id,
+                subscription_id: subscription.subscription_id,
+                name: subscription.name,
+                state: subscription.state,
+                tenant_id: subscription.tenant_id,
+                tenant: subscription.tenant,
+                spending_limit: policies
+                    .as_ref()
+                    .and_then(|policies| policies.spending_limit.clone()),
+                quota_id: policies.and_then(|policies| policies.quota_id),
+                tags: subscription.tags.unwrap_or_default().into_iter().collect(),
+            }
+        })
+        .collect());
+}
+
+#[derive(Serialize)]
+pub struct ProvidersResult {
+    pub subscription: Subscription,
+    pub providers: Vec<ProviderRegistration>,
+}
+
+#[derive(Serialize)]
+pub struct ProviderRegistration {
+    pub namespace: String,
+    #[serde(rename = "registrationState")]
+    pub registration_state: String,
+    /// The azi commands using the provider
+    #[serde(rename = "requiredBy")]
+    pub required_by: Vec<String>,
+    /// Required by azi commands, but not registered, so that their requests fail with 409 or 400
+    pub missing: bool,
+}
+
+/// Lists the resource providers used by azi commands, or all with `all`
+pub fn providers(context: &Context, all: bool) -> Result<Vec<ProvidersResult>> {
+    let service = &context.service;
+    let subscriptions = service.get_subscriptions()?;
+    return context.parallel_results(&subscriptions, |subscription| {
+        let providers = service.get_providers(&subscription.subscription_id)?;
+        Ok(Some(ProvidersResult {
+            subscription: subscription.clone(),
+            providers: provider_registrations(providers, all),
+        }))
+    });
+}
+
+fn provider_registrations(providers: Vec<Provider>, all: bool) -> Vec<ProviderRegistration> {
+    let mut registrations: Vec<ProviderRegistration> = providers
+        .into_iter()
+        .map(|provider| {
+            let required_by: Vec<String> = provider_commands(&provider.namespace)
+                .into_iter()
+                .map(str::to_owned)
+                .collect();
+            ProviderRegistration {
+                missing: !required_by.is_empty() && provider.registration_state != "Registered",
+                namespace: provider.namespace,
+                registration_state: provider.registration_state,
+                required_by,
+            }
+        })
+        .filter(|registration| all || !registration.required_by.is_empty())
+        .collect();
+    registrations.sort_by_key(|registration| registration.namespace.to_lowercase());
+    registrations
+}
+
+#[derive(Serialize)]
+pub struct TenantResult {
+    pub id: String,
+    pub name: Option<String>,
+    #[serde(rename = "defaultDomain")]
+    pub default_domain: Option<String>,
+    pub domains: Vec<String>,
+    /// Whether this is the tenant azi is currently using
+    pub current: bool,
+}
+
+pub fn tenants(context: &Context) -> Result<Vec<TenantResult>> {
+    let service = &context.service;
+    let tenants = service.get_tenants()?;
+    let current = service.tenant()?;
+
+    let mut results: Vec<TenantResult> = tenants
+        .into_iter()
+        .map(|tenant| TenantResult {
+            current: tenant.tenant_id == current.id,
+            id: tenant.tenant_id,
+            name: tenant.display_name,
+            default_domain: tenant.default_domain,
+            domains: tenant.domains,
+        })
+        .collect();
+    results.sort_by(|a, b| a.name.cmp(&b.name));
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct TokenResult {
+    #[serde(rename = "accessToken")]
+    pub access_token: String,
+    #[serde(rename = "expiresOn")]
+    pub expires_on: String,
+    pub resource: String,
+    pub tenant: String,
+}
+
+pub fn token(context: &Context, resource: Option<&str>) -> Result<TokenResult> {
+    let token_set = context.service.token_set(resource)?;
+    return Ok(TokenResult {
+        access_token: token_set.access_token.token().to_owned(),
+        expires_on: token_set.expires_on(),
+        resource: token_set.resource.clone(),
+        tenant: token_set.access_token.tenant.id.clone(),
+    });
+}
+
+#[derive(Serialize)]
+pub struct LoginResult {
+    pub tenant: String,
+    pub user: String,
+    #[serde(rename = "expiresOn")]
+    pub expires_on: String,
+}
+
+pub fn login(context: &Context) -> Result<LoginResult> {
+    let token_set = context.service.login()?;
+    return Ok(LoginResult {
+        tenant: token_set.access_token.tenant.id.clone(),
+        user: token_set.access_token.unique_name.clone(),
+        expires_on: token_set.expires_on(),
+    });
+}
+
+#[derive(Serialize)]
+pub struct LogoutResult {
+    pub tenant: Option<String>,
+    pub removed: usize,
+}
+
+pub fn logout(context: &Context) -> Result<LogoutResult> {
+    let tenant = context.service.tenant()?;
+    let removed = context.service.logout()?;
+    return Ok(LogoutResult {
+        tenant: if tenant.is_common() {
+            None
+        } else {
+            Some(tenant.id)
+        },
+        removed,
+    });
+}
+
+pub fn get(context: &Context, request: &str, query: &[(&str, &str)]) -> Result<Value> {
+    return context.service.get(request, "", query);
+}
+
+#[derive(Serialize)]
+pub struct EachResult {
+    pub item: Value,
+    pub result: Value,
+}
+
+/// Requests the template once for each item the `--each` query selects from the result
+/// of the first request. Failed follow-up requests are logged as warnings and left out
+pub fn get_each(
+    context: &Context,
+    request: &str,
+    query: &[(&str, &str)],
+    each: &Query,
+    template: &str,
+) -> Result<Vec<EachResult>> {
+    let result = context.service.get(request, "", query)?;
+    let requests = each_requests(&each.apply(&result), template)?;
+    context.parallel_results(&requests, |(item, request)| {
+        match context.service.get(request, "", &[]) {
+            Ok(result) => Ok(Some(EachResult {
+                item: item.clone(),
+                result,
+            })),
+            Err(err) => {
+                warn!("Failed to get {}: {}", request, err);
+                context.service.record_failure();
+                Ok(None)
+            }
+        }
+    })
+}
+
+/// The follow-up requests with `{}` replaced by each item, strings are inserted as they are,
+/// numbers and booleans as JSON and null items are skipped
+fn each_requests(items: &Value, template: &str) -> Result<Vec<(Value, String)>> {
+    if !template.contains("{}") {
+        return Err(ParseError(format!("template without {{}}: {}", template)).into());
+    }
+    let items = match items {
+        Value::Array(items) => items.clone(),
+        Value::Null => vec![],
+        item => vec![item.clone()],
+    };
+    items
+        .into_iter()
+        .filter(|item| !item.is_null())
+        .map(|item| {
+            let value = match &item {
+                Value::String(value) => value.clone(),
+                Value::Number(_) | Value::Bool(_) => item.to_string(),
+                _ => {
+                    return Err(ParseError(format!(
+                        "--each must select strings or numbers, found: {}",
+                        item
+                    ))
+                    .into())
+                }
+            };
+            Ok((item.clone(), template.replace("{}", &value)))
+        })
+        .collect()
+}
+
+/// Posts the body as JSON, unless another content type is given
+pub fn post(
+    context: &Context,
+    request: &str,
+    body: &str,
+    content_type: Option<&str>,
+) -> Result<Value> {
+    return context.service.post(request, "", body, content_type);
+}
+
+/// Content type of a POST body read from a file, by its extension, JSON if unknown
+pub fn content_type(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("xml") => "application/xml",
+        Some("form") => "application/x-www-form-urlencoded",
+        Some("txt") => "text/plain",
+        _ => "application/json",
+    }
+}
+
+const TYPE_SCALE_SET: &str = "Microsoft.Compute/virtualMachineScaleSets";
+const TYPE_APP_SERVICE_PLAN: &str = "Microsoft.Web/serverfarms";
+
+/// Difference between the lowest and highest hourly CPU average, in percentage points,
+/// above which a resource without autoscale setting is considered highly variable
+const VARIABLE_CPU_SPREAD: f64 = 40.0;
+
+#[derive(Serialize)]
+pub struct AutoscaleResult {
+    pub subscription: Subscription,
+    pub settings: Vec<Autoscale>,
+    pub unscaled: Vec<UnscaledResource>,
+}
+
+#[derive(Serialize)]
+pub struct Autoscale {
+    pub id: String,
+    pub name: String,
+    pub target: String,
+    pub enabled: bool,
+    pub profiles: Vec<AutoscaleProfile>,
+}
+
+#[derive(Serialize)]
+pub struct AutoscaleProfile {
+    pub name: String,
+    pub minimum: u32,
+    pub maximum: u32,
+    pub default: u32,
+    pub rules: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct UnscaledResource {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    #[serde(rename = "cpuMin")]
+    pub cpu_min: Option<f64>,
+    #[serde(rename = "cpuMax")]
+    pub cpu_max: Option<f64>,
+    pub variable: bool,
+}
+
+fn describe_rule(rule: &AutoscaleRule) -> String {
+    let trigger = &rule.metric_trigger;
+    let action = &rule.scale_action;
+    format!(
+        "{} {} {} {} over {}: {} {} {}",
+        trigger.metric_name,
+        trigger.time_aggregation,
+        trigger.operator,
+        trigger.threshold,
+        trigger.time_window,
+        action.direction,
+        action.action_type,
+        action.value
+    )
+}
+
+fn value_range(values: &[f64]) -> Option<(f64, f64)> {
+    values.iter().fold(None, |range, &value| match range {
+        Some((min, max)) => Some((value.min(min), value.max(max))),
+        None => Some((value, value)),
+    })
+}
+
+/// Lists autoscale settings and the scale sets and App Service plans without one,
+/// marking those with highly variable CPU usage over the last day
+pub fn autoscale(context: &Context) -> Result<Vec<AutoscaleResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        let settings: Vec<Autoscale> = service
+            .get_autoscale_settings(subscription_id)?
+            .into_iter()
+            .map(|setting| Autoscale {
+                id: setting.id,
+                name: setting.name,
+                target: setting.properties.target_resource_uri,
+                enabled: setting.properties.enabled,
+                profiles: setting
+                    .properties
+                    .profiles
+                    .iter()
+                    .map(|profile| AutoscaleProfile {
+                        name: profile.name.clone(),
+                        minimum: profile.capacity.minimum.parse().unwrap_or(0),
+                        maximum: profile.capacity.maximum.parse().unwrap_or(0),
+                        default: profile.capacity.default.parse().unwrap_or(0),
+                        rules: profile.rules.iter().map(describe_rule).collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let mut unscaled = vec![];
+        for (resource_type, metric) in &[
+            (TYPE_SCALE_SET, "Percentage CPU"),
+            (TYPE_APP_SERVICE_PLAN, "CpuPercentage"),
+        ] {
+            for resource in service.get_resources_by_type(subscription_id, resource_type)? {
+                if settings
+                    .iter()
+                    .any(|setting| setting.target.eq_ignore_ascii_case(&resource.id))
+                {
+                    continue;
+                }
+
+                let values = service.get_hourly_metric(&resource.id, metric)?;
+                let range = value_range(&values);
+                unscaled.push(UnscaledResource {
+                    id: resource.id,
+                    name: resource.name,
+                    resource_type: resource.resource_type,
+                    cpu_min: range.map(|(min, _)| min),
+                    cpu_max: range.map(|(_, max)| max),
+                    variable: range.is_some_and(|(min, max)| max - min >= VARIABLE_CPU_SPREAD),
+                });
+            }
+        }
+
+        if !settings.is_empty() || !unscaled.is_empty() {
+            let result = AutoscaleResult {
+                subscription,
+                settings,
+                unscaled,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+/// Premium plans hosting at most this many running apps are reported as underused
+const UNDERUSED_PLAN_APPS: usize = 1;
+
+#[derive(Serialize)]
+pub struct PlansResult {
+    pub subscription: Subscription,
+    pub plans: Vec<Plan>,
+}
+
+#[derive(Serialize)]
+pub struct Plan {
+    pub id: String,
+    pub name: String,
+    pub sku: Option<String>,
+    pub tier: Option<String>,
+    pub instances: u32,
+    pub apps: Vec<PlanApp>,
+    pub underused: bool,
+}
+
+#[derive(Serialize)]
+pub struct PlanApp {
+    pub name: String,
+    pub kind: Option<String>,
+    pub state: Option<String>,
+}
+
+fn is_underused_plan(tier: Option<&str>, running_apps: usize) -> bool {
+    let premium = match tier {
+        Some(tier) => tier.starts_with("Premium") || tier == "ElasticPremium",
+        None => false,
+    };
+    premium && running_apps <= UNDERUSED_PLAN_APPS
+}
+
+/// Lists App Service plans with the apps hosted on them
+pub fn plans(context: &Context) -> Result<Vec<PlansResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        let app_service_plans = service.get_app_service_plans(subscription_id)?;
+        if app_service_plans.is_empty() {
+            continue;
+        }
+
+        let web_apps = service.get_web_apps(subscription_id)?;
+
+        let mut plans = vec![];
+        for plan in app_service_plans {
+            let apps: Vec<PlanApp> = web_apps
+                .iter()
+                .filter(|app| match &app.properties.server_farm_id {
+                    Some(id) => id.eq_ignore_ascii_case(&plan.id),
+                    None => false,
+                })
+                .map(|app| PlanApp {
+                    name: app.name.clone(),
+                    kind: app.kind.clone(),
+                    state: app.properties.state.clone(),
+                })
+                .collect();
+            let running = apps
+                .iter()
+                .filter(|app| app.state.as_deref() == Some("Running"))
+                .count();
+
+            let (sku, tier, instances) = match plan.sku {
+                Some(sku) => (Some(sku.name), sku.tier, sku.capacity),
+                None => (None, None, 0),
+            };
+
+            plans.push(Plan {
+                id: plan.id,
+                name: plan.name,
+                underused: is_underused_plan(tier.as_deref(), running),
+                sku,
+                tier,
+                instances,
+                apps,
+            });
+        }
+
+        let result = PlansResult {
+            subscription,
+            plans,
+        };
+        context.emit(&mut results, result)?;
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct ContainerAppsResult {
+    pub subscription: Subscription,
+    pub environments: Vec<ContainerAppEnvironment>,
+}
+
+#[derive(Serialize)]
+pub struct ContainerAppEnvironment {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    #[serde(rename = "defaultDomain")]
+    pub default_domain: Option<String>,
+    #[serde(rename = "staticIp")]
+    pub static_ip: Option<String>,
+    pub apps: Vec<ContainerAppResult>,
+}
+
+#[derive(Serialize)]
+pub struct ContainerAppResult {
+    pub id: String,
+    pub name: String,
+    /// FQDN of the ingress, if enabled
+    pub fqdn: Option<String>,
+    /// Whether the ingress is reachable from outside the environment
+    pub external: bool,
+    #[serde(rename = "minReplicas")]
+    pub min_replicas: Option<u32>,
+    #[serde(rename = "maxReplicas")]
+    pub max_replicas: Option<u32>,
+    #[serde(rename = "scaleRules")]
+    pub scale_rules: Vec<ContainerAppScaleRuleResult>,
+    /// Only the active revisions
+    pub revisions: Vec<ContainerAppRevisionResult>,
+}
+
+#[derive(Serialize)]
+pub struct ContainerAppScaleRuleResult {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub rule_type: String,
+}
+
+#[derive(Serialize)]
+pub struct ContainerAppRevisionResult {
+    pub name: String,
+    pub replicas: u32,
+    #[serde(rename = "trafficWeight")]
+    pub traffic_weight: u32,
+    #[serde(rename = "runningState")]
+    pub running_state: Option<String>,
+}
+
+/// Returns the lowercase ingress FQDN of each container app with the static IP of its environment
+fn container_app_hosts<'a>(
+    environments: &[ManagedEnvironment],
+    apps: &'a [ContainerApp],
+) -> Vec<(String, String, &'a ContainerApp)> {
+    let mut hosts = vec![];
+    for app in apps {
+        let fqdn = match app.properties.configuration.as_ref() {
+            Some(configuration) => configuration.ingress.as_ref().and_then(|i| i.fqdn.as_ref()),
+            None => None,
+        };
+        let environment =
+            environments
+                .iter()
+                .find(|environment| match &app.properties.managed_environment_id {
+                    Some(id) => id.eq_ignore_ascii_case(&environment.id),
+                    None => false,
+                });
+        if let (Some(fqdn), Some(environment)) = (fqdn, environment) {
+            if let Some(ip) = &environment.properties.static_ip {
+                hosts.push((fqdn.to_lowercase(), ip.clone(), app));
+            }
+        }
+    }
+    hosts
+}
+
+/// Lists Container Apps environments with their apps, active revisions and scaling rules
+pub fn containerapps(context: &Context) -> Result<Vec<ContainerAppsResult>> {
+    let service = &context.service;
+
+    let subscriptions = service.get_subscriptions()?;
+    let results = context.parallel_results(&subscriptions, |subscription| {
+        let subscription_id = &subscription.subscription_id;
+
+        let managed_environments = service.get_managed_environments(subscription_id)?;
+        if managed_environments.is_empty() {
+            return Ok(None);
+        }
+
+        let container_apps = service.get_container_apps(subscription_id)?;
+        let revisions = parallel_map(&container_apps, context.parallelism, |app| {
+            service.get_container_app_revisions(&app.id)
+        })?;
+
+        let mut environments = vec![];
+        for environment in managed_environments {
+            let mut apps = vec![];
+            for (app, revisions) in container_apps.iter().zip(&revisions) {
+                let in_environment = match &app.properties.managed_environment_id {
+                    Some(id) => id.eq_ignore_ascii_case(&environment.id),
+                    None => false,
+                };
+                if !in_environment {
+                    continue;
+                }
+
+                let ingress = app
+                    .properties
+                    .configuration
+                    .as_ref()
+                    .and_then(|configuration| configuration.ingress.as_ref());
+                let scale = app
+                    .properties
+                    .template
+                    .as_ref()
+                    .and_then(|template| template.scale.as_ref());
+
+                apps.push(ContainerAppResult {
+                    id: app.id.clone(),
+                    name: app.name.clone(),
+                    fqdn: ingress.and_then(|ingress| ingress.fqdn.clone()),
+                    external: ingress.is_some_and(|ingress| ingress.external),
+                    min_replicas: scale.and_then(|scale| scale.min_replicas),
+                    max_replicas: scale.and_then(|scale| scale.max_replicas),
+                    scale_rules: scale
+                        .map(|scale| {
+                            scale
+                                .rules
+                                .iter()
+                                .map(|rule| ContainerAppScaleRuleResult {
+                                    name: rule.name.clone(),
+                                    rule_type: rule.rule_type(),
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    revisions: revisions
+                        .iter()
+                        .filter(|revision| revision.properties.active)
+                        .map(|revision| ContainerAppRevisionResult {
+                            name: revision.name.clone(),
+                            replicas: revision.properties.replicas,
+                            traffic_weight: revision.properties.traffic_weight,
+                            running_state: revision.properties.running_state.clone(),
+                        })
+                        .collect(),
+                });
+            }
+
+            environments.push(ContainerAppEnvironment {
+                id: environment.id,
+                name: environment.name,
+                location: environment.location,
+                default_domain: environment.properties.default_domain,
+                static_ip: environment.properties.static_ip,
+                apps,
+            });
+        }
+
+        Ok(Some(ContainerAppsResult {
+            subscription: subscription.clone(),
+            environments,
+        }))
+    })?;
+
+    return Ok(results);
+}
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Serialize)]
+pub struct StorageResult {
+    pub subscription: Subscription,
+    pub accounts: Vec<StorageAccountResult>,
+}
+
+#[derive(Serialize)]
+pub struct StorageAccountResult {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    pub kind: Option<String>,
+    pub sku: Option<String>,
+    #[serde(rename = "accessTier")]
+    pub access_tier: Option<String>,
+    /// Used capacity in GB, only set with `--capacity`
+    #[serde(rename = "usedGb", skip_serializing_if = "Option::is_none")]
+    pub used_gb: Option<f64>,
+    /// Transactions over the last day, only set with `--capacity`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<f64>,
+}
+
+/// Lists storage accounts, optionally with their used capacity and transactions
+pub fn storage(context: &Context, capacity: bool) -> Result<Vec<StorageResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let storage_accounts = service.get_storage_accounts(&subscription.subscription_id)?;
+        if storage_accounts.is_empty() {
+            continue;
+        }
+
+        let mut accounts = vec![];
+        for account in storage_accounts {
+            let (used_gb, transactions) = if capacity {
+                let used = service.get_hourly_metric(&account.id, "UsedCapacity")?;
+                let transactions =
+                    service.get_hourly_metric_aggregation(&account.id, "Transactions", "Total")?;
+                (
+                    used.last().map(|bytes| bytes / BYTES_PER_GB),
+                    Some(transactions.iter().sum()),
+                )
+            } else {
+                (None, None)
+            };
+
+            accounts.push(StorageAccountResult {
+                id: account.id,
+                name: account.name,
+                location: account.location,
+                kind: account.kind,
+                sku: account.sku.map(|sku| sku.name),
+                access_tier: account.properties.access_tier,
+                used_gb,
+                transactions,
+            });
+        }
+
+        let result = StorageResult {
+            subscription,
+            accounts,
+        };
+        context.emit(&mut results, result)?;
+    }
+
+    return Ok(results);
+}
+
+/// Databases never exceeding this CPU or DTU percentage over the last day are reported as over-provisioned
+const OVERPROVISIONED_UTILIZATION: f64 = 20.0;
+
+#[derive(Serialize)]
+pub struct SqlResult {
+    pub subscription: Subscription,
+    pub databases: Vec<SqlDatabaseResult>,
+}
+
+#[derive(Serialize)]
+pub struct SqlDatabaseResult {
+    pub id: String,
+    pub name: String,
+    pub server: String,
+    pub sku: Option<String>,
+    pub tier: Option<String>,
+    pub capacity: Option<u32>,
+    pub status: Option<String>,
+    #[serde(rename = "maxSizeGb")]
+    pub max_size_gb: Option<f64>,
+    /// Used size in GB, only set with `--usage`
+    #[serde(rename = "usedGb", skip_serializing_if = "Option::is_none")]
+    pub used_gb: Option<f64>,
+    /// Maximum CPU or DTU percentage over the last day, only set with `--usage`
+    #[serde(rename = "utilizationMax", skip_serializing_if = "Option::is_none")]
+    pub utilization_max: Option<f64>,
+    pub overprovisioned: bool,
+}
+
+/// DTU-based tiers report DTU consumption, vCore-based tiers only CPU
+fn utilization_metric(tier: Option<&str>) -> &'static str {
+    match tier {
+        Some("Basic") | Some("Standard") | Some("Premium") => "dtu_consumption_percent",
+        _ => "cpu_percent",
+    }
+}
+
+/// Lists SQL databases, optionally with their size and CPU or DTU utilization
+pub fn sql(context: &Context, usage: bool) -> Result<Vec<SqlResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let mut databases = vec![];
+        for server in service.get_sql_servers(&subscription.subscription_id)? {
+            for database in service.get_sql_databases(&server.id)? {
+                if database.name == "master" {
+                    continue;
+                }
+
+                let (sku, tier, capacity) = match database.sku {
+                    Some(sku) => (Some(sku.name), sku.tier, sku.capacity),
+                    None => (None, None, None),
+                };
+
+                let (used_gb, utilization_max) = if usage {
+                    let used = service.get_hourly_metric_aggregation(
+                        &database.id,
+                        "storage",
+                        "Maximum",
+                    )?;
+                    let metric = utilization_metric(tier.as_deref());
+                    let utilization =
+                        service.get_hourly_metric_aggregation(&database.id, metric, "Maximum")?;
+                    (
+                        used.last().map(|bytes| bytes / BYTES_PER_GB),
+                        value_range(&utilization).map(|(_, max)| max),
+                    )
+                } else {
+                    (None, None)
+                };
+
+                databases.push(SqlDatabaseResult {
+                    id: database.id,
+                    name: database.name,
+                    server: server.name.clone(),
+                    sku,
+                    tier,
+                    capacity,
+                    status: database.properties.status,
+                    max_size_gb: database
+                        .properties
+                        .max_size_bytes
+                        .map(|bytes| bytes as f64 / BYTES_PER_GB),
+                    used_gb,
+                    overprovisioned: utilization_max
+                        .is_some_and(|max| max < OVERPROVISIONED_UTILIZATION),
+                    utilization_max,
+                });
+            }
+        }
+
+        if !databases.is_empty() {
+            let result = SqlResult {
+                subscription,
+                databases,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct RedisResult {
+    pub subscription: Subscription,
+    pub caches: Vec<RedisCacheResult>,
+}
+
+#[derive(Serialize)]
+pub struct RedisCacheResult {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    /// `Basic`, `Standard` or `Premium`
+    pub sku: String,
+    /// The size like `C1` or `P2`
+    pub size: String,
+    #[serde(rename = "sizeGb")]
+    pub size_gb: Option<f64>,
+    pub version: Option<String>,
+    #[serde(rename = "minimumTlsVersion")]
+    pub minimum_tls_version: Option<String>,
+    #[serde(rename = "nonSslPort")]
+    pub non_ssl_port: bool,
+    #[serde(rename = "publicNetworkAccess")]
+    pub public_network_access: Option<String>,
+    /// Retired or soon retired versions the cache still uses
+    pub retiring: Vec<String>,
+}
+
+/// Memory of the cache sizes, C0 to C6 and P1 to P5
+fn redis_size_gb(family: &str, capacity: u32) -> Option<f64> {
+    const BASIC_STANDARD: &[f64] = &[0.25, 1.0, 2.5, 6.0, 13.0, 26.0, 53.0];
+    const PREMIUM: &[f64] = &[6.0, 13.0, 26.0, 53.0, 120.0];
+    match family {
+        "C" => BASIC_STANDARD.get(capacity as usize).copied(),
+        "P" => PREMIUM.get((capacity as usize).checked_sub(1)?).copied(),
+        _ => None,
+    }
+}
+
+/// Redis 4 is retired and TLS 1.0 and 1.1 are no longer supported
+fn redis_retiring(version: Option<&str>, minimum_tls_version: Option<&str>) -> Vec<String> {
+    let mut retiring = vec![];
+    if let Some(version) = version {
+        let major = version
+            .split('.')
+            .next()
+            .and_then(|major| major.parse::<u32>().ok());
+        if major.is_some_and(|major| major < 6) {
+            retiring.push(format!("Redis {}", version));
+        }
+    }
+    if let Some(tls) = minimum_tls_version {
+        if tls == "1.0" || tls == "1.1" {
+            retiring.push(format!("TLS {}", tls));
+        }
+    }
+    retiring
+}
+
+/// Lists Azure Cache for Redis instances, only those on retiring versions with `retiring_only`
+pub fn redis(context: &Context, retiring_only: bool) -> Result<Vec<RedisResult>> {
+    let service = &context.service;
+
+    let subscriptions = service.get_subscriptions()?;
+    let results = context.parallel_results(&subscriptions, |subscription| {
+        let mut caches: Vec<RedisCacheResult> = service
+            .get_redis_caches(&subscription.subscription_id)?
+            .into_iter()
+            .map(|cache| {
+                let properties = cache.properties;
+                let sku = properties.sku;
+                RedisCacheResult {
+                    id: cache.id,
+                    name: cache.name,
+                    location: cache.location,
+                    size: format!("{}{}", sku.family, sku.capacity),
+                    size_gb: redis_size_gb(&sku.family, sku.capacity),
+                    sku: sku.name,
+                    retiring: redis_retiring(
+                        properties.redis_version.as_deref(),
+                        properties.minimum_tls_version.as_deref(),
+                    ),
+                    version: properties.redis_version,
+                    minimum_tls_version: properties.minimum_tls_version,
+                    non_ssl_port: properties.enable_non_ssl_port,
+                    public_network_access: properties.public_network_access,
+                }
+            })
+            .collect();
+        if retiring_only {
+            caches.retain(|cache| !cache.retiring.is_empty());
+        }
+
+        if caches.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(RedisResult {
+                subscription: subscription.clone(),
+                caches,
+            }))
+        }
+    })?;
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct PeersResult {
+    pub subscription: Subscription,
+    pub circuits: Vec<CircuitResult>,
+    pub gateways: Vec<GatewayResult>,
+}
+
+#[derive(Serialize)]
+pub struct CircuitResult {
+    pub id: String,
+    pub name: String,
+    pub provider: Option<String>,
+    #[serde(rename = "peeringLocation")]
+    pub peering_location: Option<String>,
+    #[serde(rename = "bandwidthMbps")]
+    pub bandwidth_mbps: Option<u32>,
+    /// Provisioning state at the service provider
+    pub state: Option<String>,
+    pub peers: Vec<BgpPeerResult>,
+    pub disconnected: bool,
+}
+
+#[derive(Serialize)]
+pub struct GatewayResult {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "gatewayType")]
+    pub gateway_type: Option<String>,
+    pub connections: Vec<GatewayConnectionResult>,
+    pub peers: Vec<BgpPeerResult>,
+}
+
+#[derive(Serialize)]
+pub struct GatewayConnectionResult {
+    pub name: String,
+    #[serde(rename = "connectionType")]
+    pub connection_type: Option<String>,
+    pub status: Option<String>,
+    pub disconnected: bool,
+}
+
+#[derive(Serialize)]
+pub struct BgpPeerResult {
+    /// The ExpressRoute peering, or the neighbor address for gateways
+    pub name: String,
+    pub neighbor: Option<String>,
+    pub asn: Option<u64>,
+    pub state: Option<String>,
+    #[serde(rename = "learnedRoutes")]
+    pub learned_routes: Option<u64>,
+    #[serde(rename = "advertisedRoutes")]
+    pub advertised_routes: Option<u64>,
+    pub disconnected: bool,
+}
+
+const STATE_CONNECTED: &str = "Connected";
+
+/// ExpressRoute route summaries contain the number of received prefixes
+/// once the BGP session is established, the BGP state otherwise
+fn parse_prefixes_received(state: Option<&str>) -> (Option<String>, Option<u64>) {
+    match state.map(|state| (state, state.parse::<u64>())) {
+        Some((_, Ok(count))) => (Some(STATE_CONNECTED.to_owned()), Some(count)),
+        Some((state, Err(_))) => (Some(state.to_owned()), None),
+        None => (None, None),
+    }
+}
+
+/// Lists ExpressRoute circuits and virtual network gateways with their connections and
+/// BGP peers, marking everything not connected
+pub fn peers(context: &Context) -> Result<Vec<PeersResult>> {
+    let service = &context.service;
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        let mut circuits = vec![];
+        for circuit in service.get_express_route_circuits(subscription_id)? {
+            let mut peers = vec![];
+            for peering in &circuit.properties.peerings {
+                if peering.properties.state.as_deref() == Some("Disabled") {
+                    continue;
+                }
+                match service.get_express_route_route_summary(&circuit.id, &peering.name) {
+                    Ok(summaries) => {
+                        for summary in summaries {
+                            let (state, learned_routes) =
+                                parse_prefixes_received(summary.state_prefixes_received.as_deref());
+                            peers.push(BgpPeerResult {
+                                name: peering.name.clone(),
+                                neighbor: summary.neighbor,
+                                asn: summary.asn.or(peering.properties.peer_asn),
+                                disconnected: state.as_deref() != Some(STATE_CONNECTED),
+                                state,
+                                learned_routes,
+                                advertised_routes: None,
+                            });
+                        }
+                    }
+                    Err(err) => {
+                        warn!("Failed to get routes of {}: {}", peering.name, err);
+                        service.record_failure();
+                    }
+                }
+            }
+
+            let (provider, peering_location, bandwidth_mbps) =
+                match circuit.properties.service_provider {
+                    Some(provider) => (
+                        provider.name,
+                        provider.peering_location,
+                        provider.bandwidth_mbps,
+                    ),
+                    None => (None, None, None),
+                };
+            let state = circuit.properties.service_provider_state;
+            circuits.push(CircuitResult {
+                id: circuit.id,
+                name: circuit.name,
+                provider,
+                peering_location,
+                bandwidth_mbps,
+                disconnected: state.as_deref() != Some("Provisioned")
+                    || peers.iter().any(|peer| peer.disconnected),
+                state,
+                peers,
+            });
+        }
+
+        let mut gateways = vec![];
+        for resource in
+            service.get_resources_by_type(subscription_id, TYPE_VIRTUAL_NETWORK_GATEWAY)?
+        {
+            let gateway = service.get_virtual_network_gateway(&resource.id)?;
+
+            let connections = service
+                .get_gateway_connections(&gateway.id)?
+                .into_iter()
+                .map(|connection| {
+                    let status = connection.properties.connection_status;
+                    GatewayConnectionResult {
+                        name: connection.name,
+                        connection_type: connection.properties.connection_type,
+                        disconnected: status.as_deref() != Some(STATE_CONNECTED),
+                        status,
+                    }
+                })
+                .collect();
+
+            let mut peers = vec![];
+            if gateway.properties.enable_bgp {
+                for peer in service.get_bgp_peer_status(&gateway.id)? {
+                    let neighbor = peer.neighbor.unwrap_or_default();
+                    let connected = peer.state.as_deref() == Some(STATE_CONNECTED);
+                    let advertised_routes = if connected {
+                        Some(service.get_advertised_route_count(&gateway.id, &neighbor)? as u64)
+                    } else {
+                        None
+                    };
+                    peers.push(BgpPeerResult {
+                        name: neighbor.clone(),
+                        neighbor: Some(neighbor),
+                        asn: peer.asn,
+                        state: peer.state,
+                        learned_routes: peer.routes_received,
+                        advertised_routes,
+                        disconnected: !connected,
+                    });
+                }
+            }
+
+            gateways.push(GatewayResult {
+                id: gateway.id,
+                name: gateway.name,
+                gateway_type: gateway.properties.gateway_type,
+                connections,
+                peers,
+            });
+        }
+
+        if !circuits.is_empty() || !gateways.is_empty() {
+            let result = PeersResult {
+                subscription,
+                circuits,
+                gateways,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[derive(Serialize)]
+pub struct MoveCheckResult {
+    pub source: String,
+    pub target: String,
+    pub resources: Vec<MoveCheckResource>,
+    /// Validation errors not related to a single resource
+    pub errors: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MoveCheckResource {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    pub movable: bool,
+    pub reasons: Vec<String>,
+}
+
+/// Collects the innermost error details of a validateMoveResources error,
+/// together with the resource they refer to, if any
+fn move_errors(error: &Value, errors: &mut Vec<(Option<String>, String)>) {
+    match error["details"].as_array() {
+        Some(details) if !details.is_empty() => {
+            for detail in details {
+                move_errors(detail, errors);
+            }
+        }
+        _ => {
+            let message = match (error["code"].as_str(), error["message"].as_str()) {
+                (Some(code), Some(message)) => format!("{}: {}", code, message),
+                (Some(code), None) => code.to_owned(),
+                (None, Some(message)) => message.to_owned(),
+                (None, None) => error.to_string(),
+            };
+            let target = error["target"]
+                .as_str()
+                .filter(|target| target.starts_with("/subscriptions/"))
+                .map(|target| target.to_lowercase());
+            errors.push((target, message));
+        }
+    }
+}
+
+/// Checks whether the resources of the resource group can be moved to the target subscription
+pub fn move_check(
+    context: &Context,
+    resource_group: &str,
+    target_subscription: &str,
+    target_resource_group: Option<&str>,
+) -> Result<MoveCheckResult> {
+    let service = &context.service;
+
+    let subscriptions = service.get_subscriptions()?;
+
+    let mut sources = vec![];
+    for subscription in &subscriptions {
+        for group in service.get_resource_groups(&subscription.subscription_id)? {
+            if group.name.eq_ignore_ascii_case(resource_group) {
+                sources.push((subscription, group));
+            }
+        }
+    }
+    let (subscription, group) = match sources.len() {
+        0 => {
+            return Err(Box::from(format!(
+                "resource group not found: {}",
+                resource_group
+            )))
+        }
+        1 => sources.remove(0),
+        _ => {
+            return Err(Box::from(format!(
+                "resource group found in multiple subscriptions, use --filter: {}",
+                resource_group
+            )))
+        }
+    };
+
+    let target_subscription_id = match subscriptions.iter().find(|subscription| {
+        subscription.subscription_id == target_subscription
+            || subscription.name == target_subscription
+    }) {
+        Some(subscription) => subscription.subscription_id.clone(),
+        None => target_subscription.to_owned(),
+    };
+    let target = format!(
+        "/subscriptions/{}/resourceGroups/{}",
+        target_subscription_id,
+        target_resource_group.unwrap_or(&group.name)
+    );
+
+    let resources: Vec<Resource> = service
+        .get_resource_group_resources(&subscription.subscription_id, &group.name)?
+        .into_iter()
+        .filter(|resource| is_top_level_resource(&resource.id))
+        .collect();
+    let ids: Vec<String> = resources
+        .iter()
+        .map(|resource| resource.id.clone())
+        .collect();
+
+    let mut errors = vec![];
+    if !ids.is_empty() {
+        if let Some(error) = service.validate_move_resources(&group.id, &ids, &target)? {
+            move_errors(&error, &mut errors);
+        }
+    }
+
+    let resources: Vec<MoveCheckResource> = resources
+        .into_iter()
+        .map(|resource| {
+            let id = resource.id.to_lowercase();
+            let reasons: Vec<String> = errors
+                .iter()
+                .filter(|(target, _)| match target {
+                    Some(target) => target == &id || target.starts_with(&format!("{}/", id)),
+                    None => false,
+                })
+                .map(|(_, message)| message.clone())
+                .collect();
+            MoveCheckResource {
+                id: resource.id,
+                name: resource.name,
+                resource_type: resource.resource_type,
+                movable: reasons.is_empty(),
+                reasons,
+            }
+        })
+        .collect();
+
+    let errors = errors
+        .into_iter()
+        .filter(|(target, _)| match target {
+            Some(target) => !resources.iter().any(|resource| {
+                let id = resource.id.to_lowercase();
+                target == &id || target.starts_with(&format!("{}/", id))
+            }),
+            None => true,
+        })
+        .map(|(_, message)| message)
+        .collect();
+
+    return Ok(MoveCheckResult {
+        source: group.id,
+        target,
+        resources,
+        errors,
+    });
+}
+
+#[derive(Serialize)]
+pub struct ImagesResult {
+    pub subscription: Subscription,
+    pub galleries: Vec<ImageGallery>,
+    #[serde(rename = "managedImages")]
+    pub managed_images: Vec<ManagedImageResult>,
+}
+
+#[derive(Serialize)]
+pub struct ImageGallery {
+    pub id: String,
+    pub name: String,
+    pub definitions: Vec<ImageDefinition>,
+}
+
+#[derive(Serialize)]
+pub struct ImageDefinition {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "osType")]
+    pub os_type: Option<String>,
+    pub identifier: Option<String>,
+    #[serde(rename = "endOfLife")]
+    pub end_of_life: Option<String>,
+    pub expired: bool,
+    pub versions: Vec<ImageVersion>,
+}
+
+#[derive(Serialize)]
+pub struct ImageVersion {
+    pub name: String,
+    pub regions: Vec<String>,
+    pub published: Option<String>,
+    #[serde(rename = "endOfLife")]
+    pub end_of_life: Option<String>,
+    pub expired: bool,
+}
+
+#[derive(Serialize)]
+pub struct ManagedImageResult {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    #[serde(rename = "osType")]
+    pub os_type: Option<String>,
+    pub source: Option<String>,
+}
+
+/// Returns true if the end-of-life date is before the given time
+fn is_past_end_of_life(end_of_life: Option<&str>, now: &DateTime<Utc>) -> bool {
+    match end_of_life.map(DateTime::parse_from_rfc3339) {
+        Some(Ok(date)) => date < *now,
+        _ => false,
+    }
+}
+
+/// Lists compute gallery image definitions and versions, and managed images
+pub fn images(context: &Context) -> Result<Vec<ImagesResult>> {
+    let service = &context.service;
+    let now = Utc::now();
+
+    let mut results = vec![];
+
+    for subscription in service.get_subscriptions()? {
+        let subscription_id = &subscription.subscription_id;
+
+        let mut galleries = vec![];
+        for gallery in service.get_galleries(subscription_id)? {
+            let mut definitions = vec![];
+            for image in service.get_gallery_images(&gallery.id)? {
+                let versions = service
+                    .get_gallery_image_versions(&image.id)?
+                    .into_iter()
+                    .map(|version| {
+                        let profile = version.properties.publishing_profile;
+                        let end_of_life = profile
+                            .as_ref()
+                            .and_then(|profile| profile.end_of_life_date.clone());
+                        ImageVersion {
+                            name: version.name,
+                            regions: profile
+                                .as_ref()
+                                .map(|profile| {
+                                    profile
+                                        .target_regions
+                                        .iter()
+                                        .map(|region| region.name.clone())
+                                        .collect()
+                                })
+                                .unwrap_or_default(),
+                            published: profile.and_then(|profile| profile.published_date),
+                            expired: is_past_end_of_life(end_of_life.as_deref(), &now),
+                            end_of_life,
+                        }
+                    })
+                    .collect();
+
+                let properties = image.properties;
+                definitions.push(ImageDefinition {
+                    id: image.id,
+                    name: image.name,
+                    os_type: properties.os_type,
+                    identifier: properties.identifier.map(|identifier| {
+                        format!(
+                            "{}:{}:{}",
+                            identifier.publisher, identifier.offer, identifier.sku
+                        )
+                    }),
+                    expired: is_past_end_of_life(properties.end_of_life_date.as_deref(), &now),
+                    end_of_life: properties.end_of_life_date,
+                    versions,
+                });
+            }
+            galleries.push(ImageGallery {
+                id: gallery.id,
+                name: gallery.name,
+                definitions,
+            });
+        }
+
+        let managed_images: Vec<ManagedImageResult> = service
+            .get_managed_images(subscription_id)?
+            .into_iter()
+            .map(|image| ManagedImageResult {
+                id: image.id,
+                name: image.name,
+                location: image.location,
+                os_type: image
+                    .properties
+                    .storage_profile
+                    .and_then(|profile| profile.os_disk)
+                    .and_then(|disk| disk.os_type),
+                source: image
+                    .properties
+                    .source_virtual_machine
+                    .map(|machine| machine.id),
+            })
+            .collect();
+
+        if !galleries.is_empty() || !managed_images.is_empty() {
+            let result = ImagesResult {
+                subscription,
+                galleries,
+                managed_images,
+            };
+            context.emit(&mut results, result)?;
+        }
+    }
+
+    return Ok(results);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::change_type;
+    use super::content_type;
+    use super::cost_tree;
+    use super::each_requests;
+    use super::intersect_ports;
+    use super::is_location;
+    use super::is_past_end_of_life;
+    use super::is_top_level_resource;
+    use super::is_underused_plan;
+    use super::key_age_days;
+    use super::matches_ip;
+    use super::merge_ports;
+    use super::move_errors;
+    use super::namespace_restarts;
+    use super::parse_port_range;
+    use super::parse_prefixes_received;
+    use super::provider_registrations;
+    use super::redis_retiring;
+    use super::redis_size_gb;
+    use super::subtract_ports;
+    use super::top_level_resource;
+    use super::utilization_metric;
+    use super::value_range;
+    use super::CostNode;
+    use super::Domain;
+    use super::DomainIpAddress;
+    use super::Page;
+    use super::Protections;
+    use super::Sort;
+    use super::SortKey;
+    use crate::object::ContainerRestarts;
+    use crate::object::DenyAssignment;
+    use crate::object::ManagementLock;
+    use crate::object::Provider;
+    use crate::object::ResourceGroup;
+    use serde_json::json;
+    use serde_json::Value;
+
+    #[test]
+    fn test_provider_registrations() {
+        let provider = |namespace: &str, state: &str| Provider {
+            namespace: namespace.to_owned(),
+            registration_state: state.to_owned(),
+            resource_types: vec![],
+        };
+        let providers = vec![
+            provider("Microsoft.Network", "Registered"),
+            provider("Microsoft.CostManagement", "NotRegistered"),
+            provider("Microsoft.Blockchain", "NotRegistered"),
+        ];
+
+        let registrations = provider_registrations(providers, false);
+        let states: Vec<(&str, bool)> = registrations
+            .iter()
+            .map(|registration| (registration.namespace.as_str(), registration.missing))
+            .collect();
+        assert_eq!(
+            vec![
+                ("Microsoft.CostManagement", true),
+                ("Microsoft.Network", false)
+            ],
+            states
+        );
+        assert!(registrations[0].required_by.contains(&"costs".to_owned()));
+
+        let providers = vec![provider("Microsoft.Blockchain", "NotRegistered")];
+        let registrations = provider_registrations(providers, true);
+        assert_eq!(1, registrations.len());
+        assert!(!registrations[0].missing);
+    }
+
+    #[test]
+    fn test_matches_ip() {
+        let ip: IpAddr = "10.1.2.3".parse().unwrap();
+        assert!(matches_ip("10.1.2.3", &ip));
+        assert!(matches_ip("10.0.0.0/8", &ip));
+        assert!(matches_ip("0.0.0.0/0", &ip));
+        assert!(!matches_ip("10.2.0.0/16", &ip));
+        assert!(!matches_ip("example.com", &ip));
+    }
+
+    #[test]
+    fn test_parse_port_range() {
+        assert_eq!(Some((22, 22)), parse_port_range("22"));
+        assert_eq!(Some((1000, 2000)), parse_port_range("1000-2000"));
+        assert_eq!(Some((0, 65535)), parse_port_range("*"));
+        assert_eq!(None, parse_port_range("x"));
+    }
+
+    #[test]
+    fn test_merge_ports() {
+        assert_eq!(vec![(1, 5)], merge_ports(vec![(3, 5), (1, 2)]));
+        assert_eq!(vec![(1, 2), (4, 5)], merge_ports(vec![(4, 5), (1, 2)]));
+    }
+
+    #[test]
+    fn test_subtract_ports() {
+        assert_eq!(
+            vec![(0, 21), (23, 65535)],
+            subtract_ports(&vec![(0, 65535)], &vec![(22, 22)])
+        );
+        assert_eq!(
+            Vec::<(u32, u32)>::new(),
+            subtract_ports(&vec![(80, 80)], &vec![(0, 65535)])
+        );
+    }
+
+    #[test]
+    fn test_intersect_ports() {
+        assert_eq!(
+            vec![(22, 22), (443, 443)],
+            intersect_ports(&vec![(0, 1000)], &vec![(22, 22), (443, 443), (3389, 3389)])
+        );
+    }
+
+    #[test]
+    fn test_is_top_level_resource() {
+        assert!(is_top_level_resource(
+            "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/virtualNetworks/vnet"
+        ));
+        assert!(!is_top_level_resource(
+            "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Network/virtualNetworks/vnet/subnets/default"
+        ));
+        assert!(!is_top_level_resource("/subscriptions/1/resourceGroups/rg"));
+    }
+
+    #[test]
+    fn test_top_level_resource() {
+        assert_eq!(
+            "/subscriptions/1/resourcegroups/rg/providers/microsoft.network/virtualnetworks/vnet",
+            top_level_resource(
+                "/subscriptions/1/resourcegroups/rg/providers/microsoft.network/virtualnetworks/vnet/subnets/default"
+            )
+        );
+        assert_eq!(
+            "/subscriptions/1/resourcegroups/rg",
+            top_level_resource("/subscriptions/1/resourcegroups/rg")
+        );
+    }
+
+    #[test]
+    fn test_change_type() {
+        assert_eq!("deleted", change_type(true, true, true));
+        assert_eq!("deleted", change_type(false, false, false));
+        assert_eq!("created", change_type(false, true, true));
+        assert_eq!("modified", change_type(false, true, false));
+    }
+
+    #[test]
+    fn test_protections() {
+        let locks: Vec<ManagementLock> = serde_json::from_value(serde_json::json!([{
+            "id": "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Authorization/locks/lock",
+            "properties": { "level": "CanNotDelete" }
+        }]))
+        .unwrap();
+        let deny_assignments: Vec<DenyAssignment> = serde_json::from_value(serde_json::json!([{
+            "properties": {
+                "denyAssignmentName": "managed",
+                "scope": "/subscriptions/1/resourceGroups/rg2",
+                "permissions": [{ "actions": ["*/delete"], "notActions": [] }]
+            }
+        }]))
+        .unwrap();
+        let protections = Protections {
+            locks,
+            deny_assignments,
+        };
+        assert_eq!(
+            vec!["CanNotDelete"],
+            protections.of(
+                "/subscriptions/1/resourceGroups/RG/providers/Microsoft.Web/sites/app",
+                "Microsoft.Web/sites"
+            )
+        );
+        assert_eq!(
+            vec!["DenyAssignment:managed"],
+            protections.of(
+                "/subscriptions/1/resourceGroups/rg2/providers/Microsoft.Web/sites/app",
+                "Microsoft.Web/sites"
+            )
+        );
+        assert!(protections
+            .of("/subscriptions/1/resourceGroups/rg3", "Microsoft.Web/sites")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_value_range() {
+        assert_eq!(None, value_range(&[]));
+        assert_eq!(Some((5.0, 72.5)), value_range(&[12.0, 5.0, 72.5, 30.0]));
+    }
+
+    #[test]
+    fn test_is_underused_plan() {
+        assert!(is_underused_plan(Some("PremiumV3"), 0));
+        assert!(is_underused_plan(Some("ElasticPremium"), 1));
+        assert!(!is_underused_plan(Some("PremiumV2"), 2));
+        assert!(!is_underused_plan(Some("Standard"), 0));
+        assert!(!is_underused_plan(None, 0));
+    }
+
+    #[test]
+    fn test_key_age_days() {
+        let now = "2023-06-01T12:00:00Z"
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap();
+        assert_eq!(Some(0), key_age_days("2023-06-01T00:00:00Z", &now));
+        assert_eq!(Some(91), key_age_days("2023-03-02T08:15:30.1234567Z", &now));
+        assert_eq!(None, key_age_days("yesterday", &now));
+    }
+
+    #[test]
+    fn test_each_requests() {
+        let items = json!(["/a", null, 2]);
+        let requests = each_requests(&items, "{}/pools?api-version=1").unwrap();
+        assert_eq!(
+            vec![
+                (json!("/a"), "/a/pools?api-version=1".to_owned()),
+                (json!(2), "2/pools?api-version=1".to_owned()),
+            ],
+            requests
+        );
+        assert_eq!(1, each_requests(&json!("/a"), "{}").unwrap().len());
+        assert!(each_requests(&Value::Null, "{}").unwrap().is_empty());
+        assert!(each_requests(&json!([{ "id": "/a" }]), "{}").is_err());
+        assert!(each_requests(&items, "/pools").is_err());
+    }
+
+    #[test]
+    fn test_content_type() {
+        assert_eq!("application/json", content_type("body.json"));
+        assert_eq!("application/xml", content_type("policy.XML"));
+        assert_eq!(
+            "application/x-www-form-urlencoded",
+            content_type("token.form")
+        );
+        assert_eq!("application/json", content_type("body"));
+    }
+
+    #[test]
+    fn test_page() {
+        let page = Page {
+            offset: 2,
+            limit: Some(3),
+        };
+        let mut items: Vec<u32> = (1..=10).collect();
+        page.apply(&mut items);
+        assert_eq!(vec![3, 4, 5], items);
+
+        let mut results = vec![vec![1], vec![2, 3, 4], vec![5, 6, 7], vec![8]];
+        page.apply_nested(&mut results, |result| result);
+        assert_eq!(vec![vec![3, 4], vec![5]], results);
+
+        let mut items: Vec<u32> = (1..=3).collect();
+        Page::default().apply(&mut items);
+        assert_eq!(vec![1, 2, 3], items);
+    }
+
+    #[test]
+    fn test_redis_size_gb() {
+        assert_eq!(Some(0.25), redis_size_gb("C", 0));
+        assert_eq!(Some(53.0), redis_size_gb("C", 6));
+        assert_eq!(Some(6.0), redis_size_gb("P", 1));
+        assert_eq!(None, redis_size_gb("P", 0));
+        assert_eq!(None, redis_size_gb("E", 10));
+    }
+
+    #[test]
+    fn test_redis_retiring() {
+        assert_eq!(
+            vec!["Redis 4.0.14", "TLS 1.0"],
+            redis_retiring(Some("4.0.14"), Some("1.0"))
+        );
+        assert!(redis_retiring(Some("6.0.20"), Some("1.2")).is_empty());
+        assert!(redis_retiring(None, None).is_empty());
+    }
+
+    #[test]
+    fn test_utilization_metric() {
+        assert_eq!(
+            "dtu_consumption_percent",
+            utilization_metric(Some("Standard"))
+        );
+        assert_eq!("cpu_percent", utilization_metric(Some("GeneralPurpose")));
+        assert_eq!("cpu_percent", utilization_metric(None));
+    }
+
+    #[test]
+    fn test_is_location() {
+        assert!(is_location("westeurope", "West Europe"));
+        assert!(is_location("WestEurope", "westeurope"));
+        assert!(!is_location("westeurope", "northeurope"));
+    }
+
+    #[test]
+    fn test_parse_prefixes_received() {
+        assert_eq!(
+            (Some("Connected".to_owned()), Some(42)),
+            parse_prefixes_received(Some("42"))
+        );
+        assert_eq!(
+            (Some("Idle".to_owned()), None),
+            parse_prefixes_received(Some("Idle"))
+        );
+        assert_eq!((None, None), parse_prefixes_received(None));
+    }
+
+    #[test]
+    fn test_move_errors() {
+        let error = serde_json::json!({
+            "code": "ResourceMoveProviderValidationFailed",
+            "message": "Resource move validation failed.",
+            "details": [{
+                "code": "ResourceMoveNotSupported",
+                "target": "/subscriptions/1/resourceGroups/rg/providers/Microsoft.Web/sites/app",
+                "message": "Move not supported."
+            }, {
+                "code": "MissingMoveDependentResources",
+                "target": "Microsoft.Network/virtualNetworks",
+                "message": "Missing dependent resources."
+            }]
+        });
+        let mut errors = vec![];
+        move_errors(&error, &mut errors);
+        assert_eq!(
+            vec![
+                (
+                    Some(
+                        "/subscriptions/1/resourcegroups/rg/providers/microsoft.web/sites/app"
+                            .to_owned()
+                    ),
+                    "ResourceMoveNotSupported: Move not supported.".to_owned()
+                ),
+                (
+                    None,
+                    "MissingMoveDependentResources: Missing dependent resources.".to_owned()
+                ),
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn test_cost_tree() {
+        let subscription = |name: &str, costs| CostNode {
+            name: name.to_owned(),
+            subscription_id: Some(name.to_owned()),
+            costs,
+            currency: Some("EUR".to_owned()),
+            children: vec![],
+        };
+        let path = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        let tree = cost_tree(vec![
+            (path(&["Root", "Prod"]), subscription("prod-eu", 100.0)),
+            (path(&["Root", "Dev"]), subscription("dev", 10.0)),
+            (path(&["Root", "Prod"]), subscription("prod-us", 50.0)),
+            (path(&[]), subscription("unknown", 1.0)),
+        ]);
+
+        assert_eq!(2, tree.len());
+        let root = &tree[0];
+        assert_eq!(("Root", 160.0), (root.name.as_str(), root.costs));
+        assert_eq!(Some("EUR".to_owned()), root.currency);
+        let children: Vec<(&str, f64, usize)> = root
+            .children
+            .iter()
+            .map(|node| (node.name.as_str(), node.costs, node.children.len()))
+            .collect();
+        assert_eq!(vec![("Prod", 150.0, 2), ("Dev", 10.0, 1)], children);
+        assert_eq!(("unknown", 1.0), (tree[1].name.as_str(), tree[1].costs));
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut items = vec![(2.5, "b"), (10.0, "A"), (2.5, "c")];
+        let sort = Sort {
+            field: "costs".to_owned(),
+            reverse: true,
+        };
+        sort.apply(&mut items, |item| SortKey::Number(item.0));
+        assert_eq!(vec![(10.0, "A"), (2.5, "b"), (2.5, "c")], items);
+
+        let sort = Sort {
+            field: "name".to_owned(),
+            reverse: false,
+        };
+        sort.apply(&mut items, |item| SortKey::text(item.1));
+        assert_eq!(vec![(10.0, "A"), (2.5, "b"), (2.5, "c")], items);
+    }
+
+    #[test]
+    fn test_sort_key_ip() {
+        assert!(SortKey::ip("10.0.0.9") < SortKey::ip("10.0.0.10"));
+        assert!(SortKey::ip("10.0.0.9") < SortKey::ip("unknown"));
+    }
+
+    #[test]
+    fn test_namespace_restarts() {
+        let now = "2024-06-01T12:00:00Z"
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap();
+        let container =
+            |namespace: &str, count, last_restart: Option<&str>, crash_loop| ContainerRestarts {
+                namespace: namespace.to_owned(),
+                restart_count: count,
+                last_restart: last_restart.map(str::to_owned),
+                crash_loop,
+            };
+        let restarts = namespace_restarts(
+            vec![
+                container("web", 3, Some("2024-06-01T11:30:00Z"), false),
+                container("web", 7, Some("2024-05-31T18:00:00Z"), false),
+                container("jobs", 40, Some("2024-06-01T11:59:00Z"), true),
+                container("idle", 0, None, false),
+                container("old", 2, Some("2024-01-01T00:00:00Z"), false),
+            ],
+            &now,
+        );
+        let summary: Vec<_> = restarts
+            .iter()
+            .map(|r| {
+                (
+                    r.namespace.as_str(),
+                    r.total,
+                    r.last_hour,
+                    r.last_day,
+                    r.storm,
+                )
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                ("jobs", 40, 1, 1, true),
+                ("old", 2, 0, 0, false),
+                ("web", 10, 1, 2, false),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn test_find_owner() {
+        let group = |name: &str, tags: &[(&str, &str)]| ResourceGroup {
+            id: format!("/subscriptions/1/resourceGroups/{}", name),
+            location: "westeurope".to_owned(),
+            name: name.to_owned(),
+            protection: vec![],
+            tags: Some(
+                tags.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            region: None,
+        };
+        let mut domain = Domain {
+            name: "www.example.com".to_owned(),
+            entries: vec![],
+            ip_addresses: vec![
+                DomainIpAddress {
+                    ip_address: "20.1.2.3".to_owned(),
+                    resource_group: Some(group("shared", &[])),
+                },
+                DomainIpAddress {
+                    ip_address: "20.1.2.4".to_owned(),
+                    resource_group: Some(group("web", &[("Owner", "team-web")])),
+                },
+            ],
+            resource_group: None,
+            owner: None,
+        };
+        assert_eq!(Some("team-web".to_owned()), domain.find_owner("owner"));
+        assert_eq!(None, domain.find_owner("team"));
+
+        domain.resource_group = Some(group("tm", &[("owner", "team-tm")]));
+        assert_eq!(Some("team-tm".to_owned()), domain.find_owner("owner"));
+    }
+
+    #[test]
+    fn test_is_past_end_of_life() {
+        let now = "2024-06-01T00:00:00Z"
+            .parse::<chrono::DateTime<chrono::Utc>>()
+            .unwrap();
+        assert!(is_past_end_of_life(Some("2024-01-01T00:00:00+00:00"), &now));
+        assert!(!is_past_end_of_life(Some("2025-01-01T00:00:00Z"), &now));
+        assert!(!is_past_end_of_life(Some("invalid"), &now));
+        assert!(!is_past_end_of_life(None, &now));
+    }
 }
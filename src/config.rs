@@ -0,0 +1,457 @@
+use std::env::var_os;
+use std::fs::create_dir_all;
+use std::fs::read_to_string;
+use std::fs::write;
+use std::path::PathBuf;
+
+use dirs::home_dir;
+use serde_derive::Serialize;
+
+use crate::error::AppError::InvalidConfig;
+use crate::error::AppError::ParseError;
+use crate::utils::Result;
+
+const CONFIG_PATH: &'static str = ".azi/config.toml";
+const XDG_CONFIG_PATH: &'static str = "azi/config.toml";
+
+pub type ConfigKey = (&'static str, &'static str);
+
+pub const OUTPUT: ConfigKey = (
+    "output",
+    "Default output format, one of 'text', 'json', 'jsonl' or 'table'",
+);
+pub const TENANT: ConfigKey = ("tenant", "Default Active Directory tenant");
+pub const FILTER: ConfigKey = ("filter", "Default subscription filter");
+pub const PARALLELISM: ConfigKey = ("parallelism", "Number of parallel requests");
+pub const MAX_RETRIES: ConfigKey = (
+    "max-retries",
+    "Number of retries of throttled or failed requests, 3 by default",
+);
+pub const MAX_RESPONSE_SIZE: ConfigKey = (
+    "max-response-size",
+    "Size in MB above which responses are truncated, 50 by default",
+);
+pub const CONNECT_TIMEOUT: ConfigKey = (
+    "connect-timeout",
+    "Timeout in seconds for connecting to Azure",
+);
+pub const READ_TIMEOUT: ConfigKey = (
+    "read-timeout",
+    "Timeout in seconds for receiving a response, none by default",
+);
+pub const LOGIN: ConfigKey = (
+    "login",
+    "Interactive login method, one of 'device' (default) or 'browser'",
+);
+pub const CLOUD: ConfigKey = (
+    "cloud",
+    "Azure cloud, one of 'AzureCloud', 'AzureChinaCloud' or 'AzureUSGovernment'",
+);
+
+pub const SCOPE: ConfigKey = (
+    "scope",
+    "Default subscriptions, one of 'all' (default) or 'az' for the default subscription of the Azure CLI",
+);
+
+pub const COLOR: ConfigKey = (
+    "color",
+    "Colorize the text output, one of 'auto' (default, on a terminal), 'always' or 'never'",
+);
+
+pub const TOKEN_STORE: ConfigKey = (
+    "token-store",
+    "Where to store tokens, one of 'file' (default), 'keyring' or 'encrypted'",
+);
+
+pub const KEYS: &[ConfigKey] = &[
+    OUTPUT,
+    TENANT,
+    FILTER,
+    PARALLELISM,
+    MAX_RETRIES,
+    MAX_RESPONSE_SIZE,
+    CONNECT_TIMEOUT,
+    READ_TIMEOUT,
+    LOGIN,
+    CLOUD,
+    SCOPE,
+    COLOR,
+    TOKEN_STORE,
+];
+
+const OUTPUT_FORMATS: &[&str] = &["text", "json", "jsonl", "table"];
+const LOGIN_METHODS: &[&str] = &["device", "browser"];
+const CLOUDS: &[&str] = &["AzureCloud", "AzureChinaCloud", "AzureUSGovernment"];
+const SCOPES: &[&str] = &["all", "az"];
+const COLORS: &[&str] = &["auto", "always", "never"];
+const TOKEN_STORES: &[&str] = &["file", "keyring", "encrypted"];
+
+#[derive(Debug, Serialize)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: String,
+}
+
+/// The `config.toml` file of azi, a flat list of `key = "value"` pairs.
+/// Read from `~/.config/azi/config.toml` if it exists, otherwise `~/.azi/config.toml`
+pub struct Config {
+    path: PathBuf,
+    lines: Vec<Line>,
+}
+
+/// A line of the config file, kept to write the file back with its comments
+#[derive(Debug, PartialEq)]
+enum Line {
+    /// A valid entry, with the line as read unless it was changed
+    Entry {
+        key: String,
+        value: String,
+        text: Option<String>,
+    },
+    /// Comments, blank lines and skipped entries, with the key of skipped entries
+    Other { key: Option<String>, text: String },
+}
+
+impl Config {
+    pub fn read() -> Result<Config> {
+        let path = if let Some(ref path) = var_os("AZI_CONFIG_FILE") {
+            PathBuf::from(path)
+        } else if let Some(path) = xdg_config_path().filter(|path| path.exists()) {
+            path
+        } else if let Some(ref home_dir) = home_dir() {
+            home_dir.join(CONFIG_PATH)
+        } else {
+            return Err(InvalidConfig("home directory not found".to_owned()).into());
+        };
+
+        let lines = if path.exists() {
+            trace!("Reading config from {}", path.display());
+            Self::parse(&read_to_string(&path)?)
+        } else {
+            debug!("Config file not found: {}", path.display());
+            vec![]
+        };
+
+        Ok(Config { path, lines })
+    }
+
+    /// Parses the lines of the file. Invalid lines, unknown keys and invalid values are
+    /// skipped with a warning, so that a broken file can still be fixed with `azi config set`
+    fn parse(content: &str) -> Vec<Line> {
+        let mut lines = vec![];
+        for (i, text) in content.lines().enumerate() {
+            let line = text.trim();
+            let other = |key: Option<&str>| Line::Other {
+                key: key.map(str::to_owned),
+                text: text.to_owned(),
+            };
+            if line.is_empty() || line.starts_with("#") {
+                lines.push(other(None));
+                continue;
+            }
+
+            let (key, value) = match line.find("=") {
+                Some(pos) => (line[..pos].trim(), line[pos + 1..].trim()),
+                None => {
+                    warn!("Ignoring line {} of the config file: {}", i + 1, line);
+                    lines.push(other(None));
+                    continue;
+                }
+            };
+
+            let value = match parse_value(value) {
+                Some(value) => value,
+                None => {
+                    warn!("Ignoring line {} of the config file: {}", i + 1, line);
+                    lines.push(other(Some(key)));
+                    continue;
+                }
+            };
+
+            if let Err(err) = Self::validate(key, &value) {
+                warn!("Ignoring line {} of the config file, {}", i + 1, err);
+                lines.push(other(Some(key)));
+                continue;
+            }
+            lines.push(Line::Entry {
+                key: key.to_owned(),
+                value,
+                text: Some(text.to_owned()),
+            });
+        }
+        lines
+    }
+
+    /// The valid entries, in the order of the file
+    fn values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.lines.iter().filter_map(|line| match line {
+            Line::Entry { key, value, .. } => Some((key.as_str(), value.as_str())),
+            Line::Other { .. } => None,
+        })
+    }
+
+    fn validate(key: &str, value: &str) -> Result<()> {
+        Self::check_key(key)?;
+
+        let valid = match key {
+            "output" => OUTPUT_FORMATS.contains(&value),
+            "parallelism" => value.parse::<u32>().map(|n| n > 0).unwrap_or(false),
+            "max-retries" => value.parse::<u32>().is_ok(),
+            "max-response-size" => value.parse::<u64>().map(|n| n > 0).unwrap_or(false),
+            "connect-timeout" | "read-timeout" => {
+                value.parse::<u64>().map(|n| n > 0).unwrap_or(false)
+            }
+            "login" => LOGIN_METHODS.contains(&value),
+            "cloud" => CLOUDS.contains(&value),
+            "scope" => SCOPES.contains(&value),
+            "color" => COLORS.contains(&value),
+            "token-store" => TOKEN_STORES.contains(&value),
+            _ => !value.is_empty(),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(ParseError(format!("invalid value for {}: {}", key, value)).into())
+        }
+    }
+
+    pub fn get(&self, key: &ConfigKey) -> Option<&str> {
+        self.values().find(|(k, _)| *k == key.0).map(|(_, v)| v)
+    }
+
+    pub fn get_entry(&self, key: &str) -> Result<ConfigEntry> {
+        Self::check_key(key)?;
+        let value = self
+            .values()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.to_owned())
+            .ok_or(ParseError(format!("config value not set: {}", key)))?;
+        Ok(ConfigEntry {
+            key: key.to_owned(),
+            value,
+        })
+    }
+
+    pub fn entries(&self) -> Vec<ConfigEntry> {
+        self.values()
+            .map(|(key, value)| ConfigEntry {
+                key: key.to_owned(),
+                value: value.to_owned(),
+            })
+            .collect()
+    }
+
+    /// Sets the value, replacing the first line with the key, even if it was skipped as invalid
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        Self::validate(key, value)?;
+        let entry = Line::Entry {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            text: None,
+        };
+        let line = self.lines.iter_mut().find(|line| match line {
+            Line::Entry { key: k, .. } => k == key,
+            Line::Other { key: k, .. } => k.as_deref() == Some(key),
+        });
+        match line {
+            Some(line) => *line = entry,
+            None => self.lines.push(entry),
+        }
+        Ok(())
+    }
+
+    /// Writes the entries back, keeping comments and unchanged lines as they are
+    pub fn write(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            create_dir_all(parent)?;
+        }
+
+        let mut content = String::new();
+        for line in &self.lines {
+            match line {
+                Line::Entry {
+                    text: Some(text), ..
+                }
+                | Line::Other { text, .. } => content.push_str(text),
+                Line::Entry { key, value, .. } => {
+                    let numeric = [
+                        PARALLELISM,
+                        MAX_RETRIES,
+                        MAX_RESPONSE_SIZE,
+                        CONNECT_TIMEOUT,
+                        READ_TIMEOUT,
+                    ]
+                    .iter()
+                    .any(|k| k.0 == key);
+                    if numeric {
+                        content.push_str(&format!("{} = {}", key, value));
+                    } else {
+                        content.push_str(&format!("{} = {}", key, quote(value)));
+                    }
+                }
+            }
+            content.push('\n');
+        }
+        write(&self.path, content)?;
+        debug!("Written config: {}", self.path.display());
+
+        Ok(())
+    }
+
+    fn check_key(key: &str) -> Result<()> {
+        if KEYS.iter().any(|k| k.0 == key) {
+            Ok(())
+        } else {
+            Err(ParseError(format!("unknown config key: {}", key)).into())
+        }
+    }
+}
+
+/// Parses a TOML value: a basic string with escapes, a literal string in single quotes,
+/// or a bare value like a number, each optionally followed by a comment
+fn parse_value(value: &str) -> Option<String> {
+    let mut chars = value.chars();
+    let (value, rest) = match chars.next() {
+        Some('"') => {
+            let mut result = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => result.push(match chars.next()? {
+                        '"' => '"',
+                        '\\' => '\\',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        _ => return None,
+                    }),
+                    c => result.push(c),
+                }
+            }
+            (result, chars.as_str())
+        }
+        Some('\'') => {
+            let literal = chars.as_str();
+            let end = literal.find('\'')?;
+            (literal[..end].to_owned(), &literal[end + 1..])
+        }
+        _ => {
+            let end = value.find("#").unwrap_or(value.len());
+            (value[..end].trim().to_owned(), "")
+        }
+    };
+    let rest = rest.trim();
+    if rest.is_empty() || rest.starts_with("#") {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+/// Quotes a value as a TOML basic string
+fn quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// The config file below `XDG_CONFIG_HOME`, `~/.config` by default
+fn xdg_config_path() -> Option<PathBuf> {
+    let config_dir = match var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => home_dir()?.join(".config"),
+    };
+    Some(config_dir.join(XDG_CONFIG_PATH))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env::temp_dir;
+    use std::fs::read_to_string;
+    use std::fs::remove_file;
+
+    use super::Config;
+
+    fn values(config: &Config) -> Vec<(&str, &str)> {
+        config.values().collect()
+    }
+
+    fn parse(content: &str) -> Config {
+        Config {
+            path: temp_dir().join(format!("azi-config-{}.toml", std::process::id())),
+            lines: Config::parse(content),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = parse(
+            "# azi config\noutput = \"json\"\nparallelism = 4 # requests\n\ncloud = 'AzureCloud'\n",
+        );
+        assert_eq!(
+            vec![
+                ("output", "json"),
+                ("parallelism", "4"),
+                ("cloud", "AzureCloud")
+            ],
+            values(&config)
+        );
+
+        let config = parse("tenant = \"a\\\"b\\\\c\" # escaped\nscope = \"az\" rest\n");
+        assert_eq!(vec![("tenant", "a\"b\\c")], values(&config));
+
+        // a broken file is still read, so that it can be fixed with `azi config set`
+        let config = parse("output = \"yaml\"\nunknown = 1\nbroken\ntenant = \"contoso\"\n");
+        assert_eq!(vec![("tenant", "contoso")], values(&config));
+    }
+
+    #[test]
+    fn test_write() {
+        let mut config = parse("# azi config\noutput = \"yaml\" # broken\ncloud = 'AzureCloud'\n");
+        config.set("output", "json").unwrap();
+        config.set("tenant", "a\"b\\c").unwrap();
+        config.set("parallelism", "4").unwrap();
+        config.write().unwrap();
+
+        let content = read_to_string(&config.path).unwrap();
+        remove_file(&config.path).unwrap();
+        assert_eq!(
+            "# azi config\noutput = \"json\"\ncloud = 'AzureCloud'\ntenant = \"a\\\"b\\\\c\"\nparallelism = 4\n",
+            content
+        );
+        assert_eq!(values(&config), values(&parse(&content)));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Config::validate("output", "json").is_ok());
+        assert!(Config::validate("output", "jsonl").is_ok());
+        assert!(Config::validate("output", "yaml").is_err());
+        assert!(Config::validate("parallelism", "0").is_err());
+        assert!(Config::validate("max-retries", "0").is_ok());
+        assert!(Config::validate("max-retries", "-1").is_err());
+        assert!(Config::validate("read-timeout", "30").is_ok());
+        assert!(Config::validate("connect-timeout", "0").is_err());
+        assert!(Config::validate("cloud", "AzureChinaCloud").is_ok());
+        assert!(Config::validate("token-store", "keyring").is_ok());
+        assert!(Config::validate("token-store", "vault").is_err());
+        assert!(Config::validate("scope", "az").is_ok());
+        assert!(Config::validate("scope", "default").is_err());
+        assert!(Config::validate("color", "never").is_ok());
+        assert!(Config::validate("color", "yes").is_err());
+        assert!(Config::validate("max-response-size", "100").is_ok());
+        assert!(Config::validate("max-response-size", "abc").is_err());
+        assert!(Config::validate("unknown", "value").is_err());
+    }
+}
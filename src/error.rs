@@ -3,6 +3,19 @@ use std::fmt;
 
 use serde_json::Value;
 
+/// Exit codes of azi, for shell scripts
+pub const EXIT_SUCCESS: i32 = 0;
+/// Any other error, like an unexpected response or a file that cannot be written
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid arguments or options
+pub const EXIT_USAGE: i32 = 2;
+/// No valid login, or access denied
+pub const EXIT_AUTH: i32 = 3;
+/// Failed requests
+pub const EXIT_HTTP: i32 = 4;
+/// The output was printed, but some requests failed and were only warned about
+pub const EXIT_PARTIAL: i32 = 5;
+
 #[derive(Debug)]
 pub enum AppError {
     AccessTokenFileError,
@@ -21,6 +34,55 @@ pub enum AppError {
     InvalidTenantId(String),
     InvalidIssuer(String),
     InvalidAuthority(String),
+    InvalidConfig(String),
+    KeyringError(String),
+    TokenEncryptionError(String),
+
+    /// A request with `--offline` whose response is not cached
+    Offline(String),
+    /// Every request was denied, with guidance which role is missing
+    AccessDenied(String),
+    /// A failed request with the client request ID to quote to the support,
+    /// keeping the message and exit code of the original error
+    RequestFailed {
+        error: String,
+        exit_code: i32,
+        request_id: String,
+    },
+}
+
+impl AppError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::ParseError(_) => EXIT_USAGE,
+            AppError::AccessTokenFileError
+            | AppError::InvalidAccessToken(_)
+            | AppError::InvalidTenantId(_)
+            | AppError::InvalidIssuer(_)
+            | AppError::InvalidAuthority(_)
+            | AppError::KeyringError(_)
+            | AppError::TokenEncryptionError(_)
+            | AppError::AccessDenied(_)
+            | AppError::HttpError(401, _)
+            | AppError::HttpError(403, _) => EXIT_AUTH,
+            AppError::HttpClientError
+            | AppError::HttpError(_, _)
+            | AppError::InvalidCertificate(_) => EXIT_HTTP,
+            AppError::RequestFailed { exit_code, .. } => *exit_code,
+            _ => EXIT_FAILURE,
+        }
+    }
+}
+
+/// The exit code of any error, `EXIT_FAILURE` for errors other than `AppError` and transport errors
+pub fn exit_code(err: &(dyn error::Error + 'static)) -> i32 {
+    if let Some(err) = err.downcast_ref::<AppError>() {
+        err.exit_code()
+    } else if err.is::<ureq::Error>() {
+        EXIT_HTTP
+    } else {
+        EXIT_FAILURE
+    }
 }
 
 impl error::Error for AppError {}
@@ -52,6 +114,54 @@ impl fmt::Display for AppError {
             AppError::InvalidAuthority(authority) => {
                 f.write_fmt(format_args!("Invalid authority: {}", authority))
             }
+            AppError::InvalidConfig(line) => {
+                f.write_fmt(format_args!("Invalid config file, {}", line))
+            }
+            AppError::KeyringError(err) => f.write_fmt(format_args!("Keyring error: {}", err)),
+            AppError::TokenEncryptionError(err) => {
+                f.write_fmt(format_args!("Token encryption error: {}", err))
+            }
+            AppError::Offline(url) => f.write_fmt(format_args!("Not cached, offline: {}", url)),
+            AppError::AccessDenied(guidance) => f.write_str(guidance),
+            AppError::RequestFailed {
+                error, request_id, ..
+            } => f.write_fmt(format_args!(
+                "{}\n  client request ID: {}",
+                error, request_id
+            )),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use serde_json::Value;
+
+    use super::exit_code;
+    use super::AppError;
+    use super::EXIT_AUTH;
+    use super::EXIT_FAILURE;
+    use super::EXIT_HTTP;
+    use super::EXIT_USAGE;
+
+    #[test]
+    fn test_exit_code() {
+        let code = |err: AppError| exit_code(&err);
+        assert_eq!(EXIT_USAGE, code(AppError::ParseError("invalid".to_owned())));
+        assert_eq!(EXIT_AUTH, code(AppError::HttpError(403, Value::Null)));
+        assert_eq!(EXIT_HTTP, code(AppError::HttpError(500, Value::Null)));
+        assert_eq!(EXIT_FAILURE, code(AppError::ServiceError("failed")));
+
+        let err = AppError::RequestFailed {
+            error: "HTTP error 401".to_owned(),
+            exit_code: EXIT_AUTH,
+            request_id: "123".to_owned(),
+        };
+        assert_eq!(EXIT_AUTH, code(err));
+
+        let err: Box<dyn Error> = Box::from("unknown");
+        assert_eq!(EXIT_FAILURE, exit_code(err.as_ref()));
+    }
+}
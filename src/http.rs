@@ -1,20 +1,66 @@
+use ring::rand::SecureRandom;
+use ring::rand::SystemRandom;
 use rustls::Certificate;
 use rustls::ClientConfig;
+use rustls::OwnedTrustAnchor;
 use rustls::RootCertStore;
 use rustls_pemfile::read_all;
 use rustls_pemfile::Item;
-use serde_json::from_reader;
+use serde_derive::Serialize;
 use serde_json::to_string_pretty;
 use serde_json::Value;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::sleep;
+use std::time::Duration;
+use std::time::Instant;
 use ureq::Agent;
 use ureq::AgentBuilder;
+use url::Url;
+use webpki_roots::TLS_SERVER_ROOTS;
 
 use crate::error::AppError::HttpClientError;
 use crate::error::AppError::HttpError;
 use crate::error::AppError::InvalidCertificate;
+use crate::error::AppError::Offline;
+use crate::truncate::parse_truncated;
 use crate::utils::Result;
 
+const RATE_LIMIT_PREFIX: &'static str = "x-ms-ratelimit-remaining-";
+
+/// Sent with every request, to find it in the logs of Azure support
+const CLIENT_REQUEST_ID: &'static str = "x-ms-client-request-id";
+
+const MAX_REDIRECTS: usize = 5;
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+
+pub const DEFAULT_MAX_RESPONSE_SIZE: u64 = 50 * 1024 * 1024;
+
+/// Delay before the first retry in seconds, doubled with every further retry
+const INITIAL_RETRY_DELAY: u64 = 1;
+
+const MAX_RETRY_DELAY: u64 = 60;
+
+/// With fewer remaining reads, GET requests are delayed to not be throttled
+const PACING_THRESHOLD: u64 = 100;
+
+/// Delay in milliseconds for each remaining read below the threshold
+const PACING_DELAY: u64 = 50;
+
+/// Number of requests listed in the `--timing` summary
+const SLOWEST_REQUESTS: usize = 10;
+
 #[derive(Debug)]
 pub struct Header {
   name: &'static str,
@@ -40,38 +86,181 @@ impl Header {
   pub fn auth_bearer(token: &str) -> Self {
     Self::new("Authorization", format!("Bearer {}", token))
   }
+
+  pub fn if_none_match(etag: &str) -> Self {
+    Self::new("If-None-Match", etag.to_owned())
+  }
+}
+
+/// The lowest remaining request quota seen in the `x-ms-ratelimit-remaining-*` headers
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimit {
+  pub subscription: Option<String>,
+  pub quota: String,
+  pub remaining: u64,
+}
+
+/// Method, status and duration of a request including retries and redirects, for `--timing`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestTiming {
+  pub method: &'static str,
+  pub url: String,
+  /// Missing if no response was received
+  pub status: Option<u16>,
+  pub duration_ms: u64,
+}
+
+/// Number and total duration of the requests and the slowest of them
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingSummary {
+  pub command_ms: u64,
+  pub requests: usize,
+  pub total_ms: u64,
+  pub failed: usize,
+  pub slowest: Vec<RequestTiming>,
+}
+
+impl TimingSummary {
+  pub fn new(mut timings: Vec<RequestTiming>, elapsed: Duration) -> Self {
+    timings.sort_by_key(|timing| Reverse(timing.duration_ms));
+    TimingSummary {
+      command_ms: elapsed.as_millis() as u64,
+      requests: timings.len(),
+      total_ms: timings.iter().map(|timing| timing.duration_ms).sum(),
+      failed: timings
+        .iter()
+        .filter(|timing| !matches!(timing.status, Some(status) if status < 400))
+        .count(),
+      slowest: timings.into_iter().take(SLOWEST_REQUESTS).collect(),
+    }
+  }
+}
+
+/// Status and headers of a response, like the `x-ms-correlation-request-id`
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseHeaders {
+  pub status: u16,
+  pub headers: BTreeMap<String, String>,
+}
+
+impl ResponseHeaders {
+  fn of(response: &ureq::Response) -> Self {
+    ResponseHeaders {
+      status: response.status(),
+      headers: response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+          let value = response.header(&name)?.to_owned();
+          Some((name.to_lowercase(), value))
+        })
+        .collect(),
+    }
+  }
+}
+
+/// Responses to subscription-scoped requests and how many of them were denied with 403,
+/// to detect a principal without any role on the subscriptions
+#[derive(Debug, Clone, Default)]
+pub struct AccessStats {
+  pub responses: u64,
+  pub denied: u64,
+  /// The scope from the error message of the last denied request
+  pub scope: Option<String>,
+}
+
+impl AccessStats {
+  pub fn all_denied(&self) -> bool {
+    self.denied > 0 && self.denied == self.responses
+  }
+}
+
+/// Connect and read timeouts of the agents, from `--connect-timeout` and `--read-timeout`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timeouts {
+  pub connect: Option<Duration>,
+  pub read: Option<Duration>,
+}
+
+impl Timeouts {
+  fn agent_builder(&self) -> AgentBuilder {
+    let mut builder = AgentBuilder::new().redirects(0);
+    if let Some(connect) = self.connect {
+      builder = builder.timeout_connect(connect);
+    }
+    if let Some(read) = self.read {
+      builder = builder.timeout_read(read);
+    }
+    builder
+  }
 }
 
 pub struct Http {
   agent: Agent,
   url: Option<String>,
   headers: Option<Vec<Header>>,
+  rate_limits: Mutex<Vec<RateLimit>>,
+  last_response: Mutex<Option<ResponseHeaders>>,
+  access_stats: Mutex<AccessStats>,
+  /// The remaining reads of the last response, to pace further requests
+  remaining_reads: AtomicU64,
+  timings: Mutex<Vec<RequestTiming>>,
+  /// The client request ID of all requests from `--request-id`, otherwise a new one per request
+  request_id: Option<String>,
+  failed_request_id: Mutex<Option<String>>,
+  max_retries: u32,
+  /// With `--offline`, every request fails without touching the network
+  offline: bool,
+  /// Larger responses are truncated, unless `None` with `--no-truncate`
+  max_response_size: Option<u64>,
 }
 
 impl Http {
-  pub fn new() -> Self {
-    Self::for_agent(AgentBuilder::new().build())
+  pub fn new(timeouts: Timeouts) -> Self {
+    Self::for_agent(timeouts.agent_builder().build())
   }
 
-  pub fn for_certificate_authority(ca: &str) -> Result<Self> {
+  /// Trusts the certificates in the PEM file in addition to the built-in roots,
+  /// e.g. those of a TLS-inspecting proxy
+  pub fn for_ca_bundle(ca_bundle: &str, timeouts: Timeouts) -> Result<Self> {
     let mut root_store = RootCertStore::empty();
-    for item in read_all(&mut ca.as_bytes())? {
-      match item {
-        Item::X509Certificate(cert) => root_store
-          .add(&Certificate(cert))
-          .or_else(|_| Err(InvalidCertificate(ca.to_owned())).into())?,
-        _ => (),
-      }
+    root_store.add_server_trust_anchors(TLS_SERVER_ROOTS.0.iter().map(|anchor| {
+      OwnedTrustAnchor::from_subject_spki_name_constraints(
+        anchor.subject,
+        anchor.spki,
+        anchor.name_constraints,
+      )
+    }));
+    add_ca_bundle(&mut root_store, ca_bundle)?;
+    Ok(Self::for_root_store(root_store, timeouts))
+  }
+
+  pub fn for_certificate_authority(
+    ca: &str,
+    ca_bundle: Option<&str>,
+    timeouts: Timeouts,
+  ) -> Result<Self> {
+    let mut root_store = RootCertStore::empty();
+    add_certificates(&mut root_store, ca, ca)?;
+    if let Some(ca_bundle) = ca_bundle {
+      add_ca_bundle(&mut root_store, ca_bundle)?;
     }
+    Ok(Self::for_root_store(root_store, timeouts))
+  }
+
+  fn for_root_store(root_store: RootCertStore, timeouts: Timeouts) -> Self {
     let client_config = ClientConfig::builder()
       .with_safe_defaults()
       .with_root_certificates(root_store)
       .with_no_client_auth();
-    Ok(Self::for_agent(
-      AgentBuilder::new()
+    Self::for_agent(
+      timeouts
+        .agent_builder()
         .tls_config(Arc::new(client_config))
         .build(),
-    ))
+    )
   }
 
   pub fn for_agent(agent: Agent) -> Self {
@@ -79,6 +268,16 @@ impl Http {
       agent,
       url: None,
       headers: None,
+      rate_limits: Mutex::new(vec![]),
+      last_response: Mutex::new(None),
+      access_stats: Mutex::new(AccessStats::default()),
+      remaining_reads: AtomicU64::new(u64::MAX),
+      timings: Mutex::new(vec![]),
+      request_id: None,
+      failed_request_id: Mutex::new(None),
+      max_retries: DEFAULT_MAX_RETRIES,
+      offline: false,
+      max_response_size: Some(DEFAULT_MAX_RESPONSE_SIZE),
     }
   }
 
@@ -87,6 +286,16 @@ impl Http {
       agent: self.agent,
       url: Some(url),
       headers: self.headers,
+      rate_limits: self.rate_limits,
+      last_response: self.last_response,
+      access_stats: self.access_stats,
+      remaining_reads: self.remaining_reads,
+      timings: self.timings,
+      request_id: self.request_id,
+      failed_request_id: self.failed_request_id,
+      max_retries: self.max_retries,
+      offline: self.offline,
+      max_response_size: self.max_response_size,
     }
   }
 
@@ -95,15 +304,160 @@ impl Http {
       agent: self.agent,
       url: self.url,
       headers: Some(headers),
+      rate_limits: self.rate_limits,
+      last_response: self.last_response,
+      access_stats: self.access_stats,
+      remaining_reads: self.remaining_reads,
+      timings: self.timings,
+      request_id: self.request_id,
+      failed_request_id: self.failed_request_id,
+      max_retries: self.max_retries,
+      offline: self.offline,
+      max_response_size: self.max_response_size,
     }
   }
 
-  pub fn get(&self, url: &str) -> Result<Response> {
-    self.execute(url, None, Option::None)
+  pub fn with_max_retries(self, max_retries: u32) -> Self {
+    Http {
+      max_retries,
+      ..self
+    }
+  }
+
+  pub fn with_request_id(self, request_id: String) -> Self {
+    Http {
+      request_id: Some(request_id),
+      ..self
+    }
+  }
+
+  pub fn with_offline(self, offline: bool) -> Self {
+    Http { offline, ..self }
+  }
+
+  pub fn with_max_response_size(self, max_response_size: Option<u64>) -> Self {
+    Http {
+      max_response_size,
+      ..self
+    }
+  }
+
+  pub fn max_response_size(&self) -> Option<u64> {
+    self.max_response_size
+  }
+
+  /// The client request ID of the last request which failed or returned an error status
+  pub fn failed_request_id(&self) -> Option<String> {
+    match self.failed_request_id.lock() {
+      Ok(failed_request_id) => failed_request_id.clone(),
+      Err(_) => None,
+    }
+  }
+
+  fn record_failure(&self, request_id: &str) {
+    if let Ok(mut failed_request_id) = self.failed_request_id.lock() {
+      *failed_request_id = Some(request_id.to_owned());
+    }
+  }
+
+  pub fn rate_limits(&self) -> Vec<RateLimit> {
+    match self.rate_limits.lock() {
+      Ok(rate_limits) => rate_limits.clone(),
+      Err(_) => vec![],
+    }
+  }
+
+  /// Returns the status and headers of the last response received
+  pub fn last_response(&self) -> Option<ResponseHeaders> {
+    match self.last_response.lock() {
+      Ok(last_response) => last_response.clone(),
+      Err(_) => None,
+    }
+  }
+
+  /// All requests sent so far, in the order they completed
+  pub fn timings(&self) -> Vec<RequestTiming> {
+    match self.timings.lock() {
+      Ok(timings) => timings.clone(),
+      Err(_) => vec![],
+    }
+  }
+
+  fn record_timing(&self, method: &'static str, url: &str, status: Option<u16>, start: Instant) {
+    let duration = start.elapsed();
+    debug!("Request took {:?}: {}", duration, url);
+    if let Ok(mut timings) = self.timings.lock() {
+      timings.push(RequestTiming {
+        method,
+        url: url.to_owned(),
+        status,
+        duration_ms: duration.as_millis() as u64,
+      });
+    }
   }
 
-  pub fn post(&self, url: &str, body: &str) -> Result<Response> {
-    self.execute(url, None, Some(body))
+  pub fn access_stats(&self) -> AccessStats {
+    match self.access_stats.lock() {
+      Ok(access_stats) => access_stats.clone(),
+      Err(_) => AccessStats::default(),
+    }
+  }
+
+  fn record_access(&self, url: &str, status: u16, json: &Value) {
+    let subscription = match subscription_id(url) {
+      Some(subscription) => subscription,
+      None => return,
+    };
+    if let Ok(mut access_stats) = self.access_stats.lock() {
+      access_stats.responses += 1;
+      if status == 403 {
+        access_stats.denied += 1;
+        access_stats.scope =
+          denied_scope(json).or_else(|| Some(format!("/subscriptions/{}", subscription)));
+      }
+    }
+  }
+
+  fn record_response(&self, url: &str, response: &ureq::Response) {
+    self.record_rate_limits(url, response);
+    if let Ok(mut last_response) = self.last_response.lock() {
+      *last_response = Some(ResponseHeaders::of(response));
+    }
+  }
+
+  fn record_rate_limits(&self, url: &str, response: &ureq::Response) {
+    let subscription = subscription_id(url);
+    for name in response.headers_names() {
+      let quota = match name.to_lowercase().strip_prefix(RATE_LIMIT_PREFIX) {
+        Some(quota) => quota.to_owned(),
+        None => continue,
+      };
+      let remaining = match response.header(&name).and_then(|value| value.parse().ok()) {
+        Some(remaining) => remaining,
+        None => continue,
+      };
+      debug!("Remaining {} requests: {}", quota, remaining);
+      if quota.ends_with("-reads") {
+        self.remaining_reads.store(remaining, Ordering::Relaxed);
+      }
+      if let Ok(mut rate_limits) = self.rate_limits.lock() {
+        let existing = rate_limits
+          .iter_mut()
+          .find(|limit| limit.subscription == subscription && limit.quota == quota);
+        match existing {
+          Some(limit) => limit.remaining = limit.remaining.min(remaining),
+          None => rate_limits.push(RateLimit {
+            subscription: subscription.clone(),
+            quota,
+            remaining,
+          }),
+        }
+      }
+    }
+  }
+
+  pub fn get(&self, url: &str) -> Result<Response> {
+    self.execute(url, None, Option::None)
   }
 
   pub fn execute(
@@ -112,56 +466,162 @@ impl Http {
     headers: Option<&Vec<Header>>,
     body: Option<&str>,
   ) -> Result<Response> {
-    let url = match &self.url {
+    Ok(self.execute_with_etag(url, headers, body)?.0)
+  }
+
+  /// Like `execute`, also returning the `ETag` header of a successful response
+  pub fn execute_with_etag(
+    &self,
+    url: &str,
+    headers: Option<&Vec<Header>>,
+    body: Option<&str>,
+  ) -> Result<(Response, Option<String>)> {
+    let mut url = match &self.url {
       Some(base) => format!("{}{}", base, url),
       None => url.to_owned(),
     };
+    if self.offline {
+      return Err(Offline(url).into());
+    }
+    let request_id = match &self.request_id {
+      Some(request_id) => request_id.clone(),
+      None => new_request_id()?,
+    };
+    debug!("Client request ID: {}", request_id);
+    let request_id_header = Header::new(CLIENT_REQUEST_ID, request_id.clone());
+    let mut headers: Vec<&Header> = self
+      .headers
+      .iter()
+      .flatten()
+      .chain(headers.into_iter().flatten())
+      .chain(Some(&request_id_header))
+      .collect();
+    let mut body = body;
+    let method = if body.is_some() { "POST" } else { "GET" };
+    let start = Instant::now();
 
-    debug!("Requesting: {}", url);
+    let mut redirects = 0;
+    let mut retries = 0;
+    let result = loop {
+      debug!("Requesting: {}", url);
 
-    trace!("Request headers: {:?}", &headers);
-    trace!("Request body: {:?}", &body);
+      trace!("Request headers: {:?}", &headers);
+      trace!("Request body: {:?}", &body);
 
-    if url.starts_with("http://") {
-      warn!("Plain HTTP requested!");
-      return Err(HttpClientError.into());
-    }
+      if url.starts_with("http://") {
+        warn!("Plain HTTP requested!");
+        return Err(HttpClientError.into());
+      }
 
-    let mut request = if body.is_some() {
-      self.agent.post(&url)
-    } else {
-      self.agent.get(&url)
-    };
+      if body.is_none() {
+        let remaining = self.remaining_reads.load(Ordering::Relaxed);
+        if let Some(delay) = pacing_delay(remaining) {
+          debug!("Only {} reads remaining, waiting {:?}", remaining, delay);
+          sleep(delay);
+        }
+      }
+
+      let mut request = if body.is_some() {
+        self.agent.post(&url)
+      } else {
+        self.agent.get(&url)
+      };
 
-    if let Some(headers) = &self.headers {
-      for header in headers {
+      for header in &headers {
         request = request.set(header.name, &header.value);
       }
-    }
 
-    if let Some(headers) = headers {
-      for header in headers {
-        request = request.set(header.name, &header.value);
+      let result = if let Some(body) = body {
+        request.send_string(body)
+      } else {
+        request.call()
+      };
+
+      if let Err(ureq::Error::Status(status, response)) = &result {
+        if retries < self.max_retries && is_retryable(*status, body.is_none()) {
+          let delay = retry_delay(response.header("Retry-After"), retries);
+          retries += 1;
+          debug!(
+            "Request failed with {}, retry {} in {:?}",
+            status, retries, delay
+          );
+          self.record_rate_limits(&url, response);
+          sleep(delay);
+          continue;
+        }
       }
-    }
 
-    let result = if let Some(body) = body {
-      request.send_string(body)
-    } else {
-      request.call()
+      let redirect = match &result {
+        Ok(response) if is_redirect(response.status()) => response
+          .header("Location")
+          .map(|location| (response.status(), location.to_owned())),
+        _ => None,
+      };
+      let (status, location) = match redirect {
+        Some(redirect) => redirect,
+        None => break result,
+      };
+      if redirects == MAX_REDIRECTS {
+        warn!("Too many redirects!");
+        return Err(HttpError(status, Value::Null).into());
+      }
+      redirects += 1;
+
+      let current = Url::parse(&url)?;
+      let location = current.join(&location)?;
+      debug!("Redirected to: {}", location);
+      if current.host_str() != location.host_str() {
+        // Never send the token to another host
+        headers.retain(|header| !header.name.eq_ignore_ascii_case("Authorization"));
+      }
+      // Like browsers, only 307 and 308 repeat the POST
+      if status != 307 && status != 308 {
+        body = None;
+      }
+      url = location.to_string();
     };
 
     match result {
       Ok(response) => {
         trace!("Response: {}", response.status());
-        Ok(Response::Success(to_json(response)))
+        self.record_response(&url, &response);
+        let status = response.status();
+        let location = response.header("Location").map(str::to_owned);
+        let result = match location {
+          _ if status == 304 => {
+            debug!("Not modified: {}", url);
+            (Response::NotModified, None)
+          }
+          Some(location) if status == 202 => {
+            debug!("Request accepted, result at: {}", location);
+            (Response::Accepted(location), None)
+          }
+          _ => {
+            let etag = response.header("ETag").map(str::to_owned);
+            let json = to_json(response, &url, self.max_response_size);
+            self.record_access(&url, status, &json);
+            (Response::Success(json), etag)
+          }
+        };
+        self.record_timing(method, &url, Some(status), start);
+        Ok(result)
       }
       Err(ureq::Error::Status(status, response)) => {
-        debug!("Request not successful: {}", status);
-        Ok(Response::Error(status, to_json(response)))
+        debug!(
+          "Request not successful: {} (client request ID {})",
+          status, request_id
+        );
+        self.record_failure(&request_id);
+        self.record_response(&url, &response);
+        let json = to_json(response, &url, self.max_response_size);
+        self.record_access(&url, status, &json);
+        self.record_timing(method, &url, Some(status), start);
+        Ok((Response::Error(status, json), None))
       }
       Err(err) => {
-        debug!("Request failed!");
+        debug!("Request failed! (client request ID {})", request_id);
+        self.record_failure(&request_id);
+        self.record_timing(method, &url, None, start);
         Err(err.into())
       }
     }
@@ -170,6 +630,9 @@ impl Http {
 
 pub enum Response {
   Success(Value),
+  Accepted(String),
+  /// The resource still matches the ETag sent with `If-None-Match`
+  NotModified,
   Error(u16, Value),
 }
 
@@ -177,14 +640,202 @@ impl Response {
   pub fn success(self) -> Result<Value> {
     match self {
       Response::Success(json) => Ok(json),
+      Response::Accepted(_) => Ok(Value::Null),
+      Response::NotModified => Err(HttpError(304, Value::Null).into()),
       Response::Error(status, json) => Err(HttpError(status, json).into()),
     }
   }
 }
 
-fn to_json(response: ureq::Response) -> Value {
-  match from_reader::<_, Value>(response.into_reader()) {
-    Ok(json) => {
+/// Minimal HTTP server on localhost, receiving the redirect of an OAuth authorization
+pub struct RedirectListener {
+  listener: TcpListener,
+  port: u16,
+}
+
+impl RedirectListener {
+  pub fn new() -> Result<Self> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+    Ok(Self { listener, port })
+  }
+
+  pub fn redirect_uri(&self) -> String {
+    format!("http://localhost:{}", self.port)
+  }
+
+  /// Waits for a request with a query string and returns its parameters
+  pub fn receive(&self) -> Result<Vec<(String, String)>> {
+    for stream in self.listener.incoming() {
+      let mut stream = stream?;
+
+      let mut request_line = String::new();
+      BufReader::new(&stream).read_line(&mut request_line)?;
+      debug!("Redirect received: {}", request_line.trim());
+
+      let path = request_line.split(' ').nth(1).unwrap_or("/");
+      let url = Url::parse(&format!("{}{}", self.redirect_uri(), path))?;
+      if url.query().is_none() {
+        stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")?;
+        continue;
+      }
+
+      let body = "Authentication finished, you can close this window.";
+      write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+      )?;
+
+      return Ok(
+        url
+          .query_pairs()
+          .map(|(key, value)| (key.into_owned(), value.into_owned()))
+          .collect(),
+      );
+    }
+    Err(HttpClientError.into())
+  }
+}
+
+/// Adds the certificates of the PEM data, returning their number
+fn add_certificates(root_store: &mut RootCertStore, pem: &str, source: &str) -> Result<usize> {
+  let mut count = 0;
+  for item in read_all(&mut pem.as_bytes())? {
+    if let Item::X509Certificate(cert) = item {
+      root_store
+        .add(&Certificate(cert))
+        .map_err(|_| InvalidCertificate(source.to_owned()))?;
+      count += 1;
+    }
+  }
+  Ok(count)
+}
+
+fn add_ca_bundle(root_store: &mut RootCertStore, path: &str) -> Result<()> {
+  let pem = read_to_string(path)?;
+  if add_certificates(root_store, &pem, path)? == 0 {
+    return Err(InvalidCertificate(path.to_owned()).into());
+  }
+  debug!("Added CA certificates from {}", path);
+  Ok(())
+}
+
+/// Throttled requests were not processed and can always be retried,
+/// server errors only for idempotent requests
+fn is_retryable(status: u16, idempotent: bool) -> bool {
+  status == 429 || (idempotent && matches!(status, 500 | 502 | 503 | 504))
+}
+
+/// Returns the delay from the `Retry-After` header in seconds, or an exponential backoff
+fn retry_delay(retry_after: Option<&str>, retries: u32) -> Duration {
+  let seconds = match retry_after.and_then(|value| value.trim().parse().ok()) {
+    Some(seconds) => seconds,
+    None => INITIAL_RETRY_DELAY
+      .checked_shl(retries)
+      .unwrap_or(MAX_RETRY_DELAY),
+  };
+  Duration::from_secs(seconds.min(MAX_RETRY_DELAY))
+}
+
+/// Slows down requests the closer the remaining reads get to zero
+fn pacing_delay(remaining_reads: u64) -> Option<Duration> {
+  if remaining_reads >= PACING_THRESHOLD {
+    None
+  } else {
+    Some(Duration::from_millis(
+      (PACING_THRESHOLD - remaining_reads) * PACING_DELAY,
+    ))
+  }
+}
+
+/// Generates a random UUID (version 4) as client request ID
+pub fn new_request_id() -> Result<String> {
+  let mut bytes = [0u8; 16];
+  SystemRandom::new()
+    .fill(&mut bytes)
+    .or(Err(HttpClientError))?;
+  bytes[6] = (bytes[6] & 0x0f) | 0x40;
+  bytes[8] = (bytes[8] & 0x3f) | 0x80;
+  let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+  Ok(format!(
+    "{}-{}-{}-{}-{}",
+    &hex[..8],
+    &hex[8..12],
+    &hex[12..16],
+    &hex[16..20],
+    &hex[20..]
+  ))
+}
+
+/// Whether the ID has the form of a UUID, like `01234567-89ab-cdef-0123-456789abcdef`
+pub fn is_request_id(id: &str) -> bool {
+  let groups: Vec<&str> = id.split('-').collect();
+  groups.len() == 5
+    && groups
+      .iter()
+      .zip([8, 4, 4, 4, 12].iter())
+      .all(|(group, len)| group.len() == *len && group.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_redirect(status: u16) -> bool {
+  matches!(status, 301 | 302 | 303 | 307 | 308)
+}
+
+fn subscription_id(url: &str) -> Option<String> {
+  let path = Url::parse(url).ok()?.path().to_lowercase();
+  let start = path.find("/subscriptions/")? + "/subscriptions/".len();
+  let id = path[start..].split('/').next()?;
+  if id.is_empty() {
+    None
+  } else {
+    Some(id.to_owned())
+  }
+}
+
+/// Extracts the scope from messages like "The client '...' with object id '...' does not
+/// have authorization to perform action '...' over scope '/subscriptions/...' or the scope is invalid."
+fn denied_scope(json: &Value) -> Option<String> {
+  let message = json["error"]["message"].as_str()?;
+  let start = message.find("over scope '")? + "over scope '".len();
+  let end = message[start..].find('\'')?;
+  Some(message[start..start + end].to_owned())
+}
+
+thread_local! {
+  /// Responses truncated on this thread, requests and their pages are executed on one thread
+  static TRUNCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The number of responses truncated on the current thread so far, to leave the results
+/// of truncated responses out of the cache
+pub fn truncated_responses() -> usize {
+  TRUNCATED.with(Cell::get)
+}
+
+fn to_json(response: ureq::Response, url: &str, max_size: Option<u64>) -> Value {
+  match parse_truncated(response.into_reader(), max_size) {
+    Ok(truncated) => {
+      let mut json = truncated.value;
+      if truncated.skipped > 0 {
+        TRUNCATED.with(|count| count.set(count.get() + 1));
+        warn!(
+          "Response of {} has {} MB, more than {} MB, kept {} of {} items. Narrow the query with \
+          -s/--subscription or -f/--filter, with --select and --top for get, or show all \
+          items with --no-truncate",
+          url,
+          truncated.size / 1024 / 1024,
+          max_size.unwrap_or_default() / 1024 / 1024,
+          truncated.kept,
+          truncated.kept + truncated.skipped
+        );
+        // Further pages would exceed the size as well
+        if let Value::Object(object) = &mut json {
+          object.remove("nextLink");
+          object.remove("@odata.nextLink");
+        }
+      }
       match to_string_pretty(&json) {
         Ok(s) => debug!("Response JSON: {}", s),
         Err(_) => debug!("Response JSON: {:?}", json),
@@ -197,3 +848,178 @@ fn to_json(response: ureq::Response) -> Value {
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use std::io::Read;
+  use std::io::Write;
+  use std::net::TcpStream;
+  use std::thread::spawn;
+
+  use std::time::Duration;
+
+  use rustls::RootCertStore;
+
+  use serde_json::json;
+
+  use super::add_certificates;
+  use super::denied_scope;
+  use super::is_request_id;
+  use super::is_retryable;
+  use super::new_request_id;
+  use super::pacing_delay;
+  use super::retry_delay;
+  use super::subscription_id;
+  use super::to_json;
+  use super::truncated_responses;
+  use super::RedirectListener;
+  use super::RequestTiming;
+  use super::TimingSummary;
+
+  #[test]
+  fn test_redirect_listener() {
+    let listener = RedirectListener::new().unwrap();
+    let address = format!("127.0.0.1:{}", listener.port);
+    let client = spawn(move || {
+      let mut stream = TcpStream::connect(address).unwrap();
+      stream
+        .write_all(b"GET /?code=abc&state=x%3D HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+      let mut response = String::new();
+      stream.read_to_string(&mut response).unwrap();
+      response
+    });
+    let params = listener.receive().unwrap();
+    assert_eq!(
+      vec![
+        ("code".to_owned(), "abc".to_owned()),
+        ("state".to_owned(), "x=".to_owned())
+      ],
+      params
+    );
+    assert!(client.join().unwrap().starts_with("HTTP/1.1 200 OK"));
+  }
+
+  #[test]
+  fn test_subscription_id() {
+    assert_eq!(
+      Some("123".to_owned()),
+      subscription_id(
+        "https://management.azure.com/subscriptions/123/resourceGroups?api-version=1"
+      )
+    );
+    assert_eq!(
+      None,
+      subscription_id("https://management.azure.com/subscriptions?api-version=1")
+    );
+  }
+
+  #[test]
+  fn test_denied_scope() {
+    let json = json!({
+      "error": {
+        "code": "AuthorizationFailed",
+        "message": "The client 'a@b.c' with object id '1' does not have authorization to perform action 'Microsoft.Resources/subscriptions/resourceGroups/read' over scope '/subscriptions/123' or the scope is invalid. If access was recently granted, please refresh your credentials."
+      }
+    });
+    assert_eq!(Some("/subscriptions/123".to_owned()), denied_scope(&json));
+    assert_eq!(
+      None,
+      denied_scope(&json!({ "error": { "message": "denied" } }))
+    );
+  }
+
+  #[test]
+  fn test_to_json_truncated() {
+    let items: Vec<_> = (0..1000).map(|i| json!({ "id": i })).collect();
+    let body = json!({ "value": items, "nextLink": "https://next" }).to_string();
+    let response = |body: &str| ureq::Response::new(200, "OK", body).unwrap();
+
+    let truncated = truncated_responses();
+    assert_eq!(
+      1000,
+      to_json(response(&body), "url", None)["value"]
+        .as_array()
+        .unwrap()
+        .len()
+    );
+    assert_eq!(truncated, truncated_responses());
+
+    let json = to_json(response(&body), "url", Some(1000));
+    assert!(json["value"].as_array().unwrap().len() < 1000);
+    assert!(json["nextLink"].is_null());
+    assert_eq!(truncated + 1, truncated_responses());
+  }
+
+  #[test]
+  fn test_add_certificates() {
+    let mut root_store = RootCertStore::empty();
+    assert_eq!(0, add_certificates(&mut root_store, "", "empty").unwrap());
+    let pem = "-----BEGIN CERTIFICATE-----\nMA==\n-----END CERTIFICATE-----\n";
+    assert!(add_certificates(&mut root_store, pem, "invalid").is_err());
+  }
+
+  #[test]
+  fn test_is_retryable() {
+    assert!(is_retryable(429, false));
+    assert!(is_retryable(503, true));
+    assert!(!is_retryable(503, false));
+    assert!(!is_retryable(404, true));
+  }
+
+  #[test]
+  fn test_retry_delay() {
+    assert_eq!(Duration::from_secs(1), retry_delay(None, 0));
+    assert_eq!(Duration::from_secs(8), retry_delay(None, 3));
+    assert_eq!(Duration::from_secs(60), retry_delay(None, 10));
+    assert_eq!(Duration::from_secs(60), retry_delay(None, 100));
+    assert_eq!(Duration::from_secs(17), retry_delay(Some("17"), 0));
+    assert_eq!(Duration::from_secs(2), retry_delay(Some("invalid"), 1));
+  }
+
+  #[test]
+  fn test_pacing_delay() {
+    assert_eq!(None, pacing_delay(u64::MAX));
+    assert_eq!(None, pacing_delay(100));
+    assert_eq!(Some(Duration::from_millis(50)), pacing_delay(99));
+    assert_eq!(Some(Duration::from_secs(5)), pacing_delay(0));
+  }
+
+  #[test]
+  fn test_new_request_id() {
+    let id = new_request_id().unwrap();
+    assert!(is_request_id(&id));
+    assert_eq!(Some('4'), id.chars().nth(14));
+    assert_ne!(id, new_request_id().unwrap());
+  }
+
+  #[test]
+  fn test_is_request_id() {
+    assert!(is_request_id("01234567-89ab-cdef-0123-456789ABCDEF"));
+    assert!(!is_request_id("01234567-89ab-cdef-0123"));
+    assert!(!is_request_id("0123456789ab-cdef-0123-456789abcdef-"));
+    assert!(!is_request_id("0123456g-89ab-cdef-0123-456789abcdef"));
+  }
+
+  #[test]
+  fn test_timing_summary() {
+    let timing = |status, duration_ms| RequestTiming {
+      method: "GET",
+      url: format!("https://management.azure.com/{}", duration_ms),
+      status,
+      duration_ms,
+    };
+    let timings = (1..=12)
+      .map(|i| timing(Some(200), i * 100))
+      .chain(vec![timing(Some(429), 50), timing(None, 30)])
+      .collect();
+    let summary = TimingSummary::new(timings, Duration::from_secs(3));
+    assert_eq!(3000, summary.command_ms);
+    assert_eq!(14, summary.requests);
+    assert_eq!(7880, summary.total_ms);
+    assert_eq!(2, summary.failed);
+    assert_eq!(10, summary.slowest.len());
+    assert_eq!(1200, summary.slowest[0].duration_ms);
+    assert_eq!(300, summary.slowest[9].duration_ms);
+  }
+}
@@ -0,0 +1,122 @@
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
+
+use crate::error::AppError::ParseError;
+use crate::utils::Result;
+
+/// Languages writing `1.234,56`
+const DECIMAL_COMMA_LANGUAGES: &[&str] = &[
+    "da", "de", "el", "es", "id", "it", "nl", "pt", "ro", "sl", "tr",
+];
+
+/// Languages writing `1 234,56`, with a no-break space
+const SPACE_GROUPING_LANGUAGES: &[&str] = &[
+    "cs", "fi", "fr", "hu", "nb", "no", "pl", "ru", "sk", "sv", "uk",
+];
+
+/// Number format of the text output, from `--locale` or `AZI_LOCALE`.
+/// Without a locale, numbers are printed like `1234.56` as before.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Locale {
+    grouping: Option<char>,
+    decimal: char,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            grouping: None,
+            decimal: '.',
+        }
+    }
+}
+
+impl Locale {
+    /// Parses names like `de`, `de-CH` or `de_DE.UTF-8`, only the language is relevant
+    pub fn parse(name: &str) -> Result<Locale> {
+        let language = name
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let (grouping, decimal) = match language.as_str() {
+            "c" | "posix" => (None, '.'),
+            "en" | "ja" | "ko" | "zh" => (Some(','), '.'),
+            l if DECIMAL_COMMA_LANGUAGES.contains(&l) => (Some('.'), ','),
+            l if SPACE_GROUPING_LANGUAGES.contains(&l) => (Some('\u{a0}'), ','),
+            _ => return Err(ParseError(format!("unsupported locale: {}", name)).into()),
+        };
+        Ok(Locale { grouping, decimal })
+    }
+
+    /// Formats the digits before and after the decimal separator, an empty fraction
+    /// is printed without the separator
+    pub fn format_digits(&self, negative: bool, integer: &str, fraction: &str) -> String {
+        let mut result = String::new();
+        if negative {
+            result.push('-');
+        }
+        for (i, c) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                result.extend(self.grouping);
+            }
+            result.push(c);
+        }
+        if !fraction.is_empty() {
+            result.push(self.decimal);
+            result.push_str(fraction);
+        }
+        result
+    }
+}
+
+/// Normalizes an RFC 3339 timestamp like `2023-01-01T10:00:00.1234567+00:00`
+/// to ISO 8601 in UTC with seconds, `2023-01-01T10:00:00Z`
+pub fn iso_timestamp(timestamp: &str) -> String {
+    match DateTime::parse_from_rfc3339(timestamp) {
+        Ok(time) => time
+            .with_timezone(&Utc)
+            .to_rfc3339_opts(SecondsFormat::Secs, true),
+        Err(_) => timestamp.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::iso_timestamp;
+    use super::Locale;
+
+    #[test]
+    fn test_format_digits() {
+        let default = Locale::default();
+        assert_eq!("1234567.89", default.format_digits(false, "1234567", "89"));
+        assert_eq!("-0.50", default.format_digits(true, "0", "50"));
+
+        let en = Locale::parse("en_US.UTF-8").unwrap();
+        assert_eq!("1,234,567.89", en.format_digits(false, "1234567", "89"));
+        assert_eq!("123.00", en.format_digits(false, "123", "00"));
+        assert_eq!("-1,000", en.format_digits(true, "1000", ""));
+
+        let de = Locale::parse("de-CH").unwrap();
+        assert_eq!("1.234,50", de.format_digits(false, "1234", "50"));
+
+        let fr = Locale::parse("fr").unwrap();
+        assert_eq!("12\u{a0}345,60", fr.format_digits(false, "12345", "60"));
+
+        assert!(Locale::parse("xx").is_err());
+    }
+
+    #[test]
+    fn test_iso_timestamp() {
+        assert_eq!(
+            "2023-01-01T10:00:00Z",
+            iso_timestamp("2023-01-01T10:00:00.1234567Z")
+        );
+        assert_eq!(
+            "2023-01-01T09:00:00Z",
+            iso_timestamp("2023-01-01T10:00:00+01:00")
+        );
+        assert_eq!("unknown", iso_timestamp("unknown"));
+    }
+}
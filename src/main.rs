@@ -5,19 +5,32 @@ extern crate lazy_static;
 extern crate log;
 
 mod auth;
+mod cache;
 mod cli;
 mod client;
 mod commands;
+mod config;
 mod error;
 mod http;
+mod locale;
+mod money;
 mod object;
+mod operations;
 mod output;
+#[cfg(unix)]
+mod pager;
+mod policy;
+mod query;
+mod schema;
 mod service;
 mod tenant;
+mod truncate;
 mod utils;
 
+use std::process::exit;
+
 use cli::run;
 
 fn main() {
-    run();
+    exit(run());
 }
@@ -0,0 +1,148 @@
+use std::cmp::Ordering;
+use std::iter::Sum;
+use std::ops::Add;
+use std::ops::AddAssign;
+
+use crate::error::AppError::ParseError;
+use crate::locale::Locale;
+use crate::utils::Result;
+
+/// Decimals of the minor unit amounts are counted in. Finer than cents, so that the
+/// costs of small resource groups are not rounded away before they are summed up
+pub const MINOR_UNIT_DECIMALS: usize = 6;
+
+const MINOR_UNITS: i64 = 1_000_000;
+
+/// How amounts are rounded to the decimals of `--precision`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    /// Halves are rounded away from zero, `0.125` is `0.13`
+    HalfUp,
+    /// Banker's rounding, halves are rounded to the even digit, `0.125` is `0.12`
+    HalfEven,
+}
+
+impl Rounding {
+    pub fn parse(name: &str) -> Result<Rounding> {
+        match name.to_lowercase().as_str() {
+            "half-up" => Ok(Rounding::HalfUp),
+            "half-even" | "bankers" => Ok(Rounding::HalfEven),
+            _ => Err(ParseError(format!("invalid rounding: {}", name)).into()),
+        }
+    }
+}
+
+/// Decimals and rounding of the amounts in the text output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Precision {
+    pub decimals: usize,
+    pub rounding: Rounding,
+}
+
+impl Default for Precision {
+    fn default() -> Self {
+        Precision {
+            decimals: 2,
+            rounding: Rounding::HalfUp,
+        }
+    }
+}
+
+/// An amount of money in millionths of the currency, summed up without floating point drift
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Money(i64);
+
+impl Money {
+    pub fn from_f64(amount: f64) -> Money {
+        Money((amount * MINOR_UNITS as f64).round() as i64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / MINOR_UNITS as f64
+    }
+
+    /// Formats the amount with the decimals and rounding of the precision,
+    /// at most `MINOR_UNIT_DECIMALS`
+    pub fn format(self, locale: &Locale, precision: &Precision) -> String {
+        let decimals = precision.decimals.min(MINOR_UNIT_DECIMALS);
+        let divisor = 10_i64.pow((MINOR_UNIT_DECIMALS - decimals) as u32);
+        let units = self.0.abs();
+        let (mut quotient, remainder) = (units / divisor, units % divisor);
+        let round_up = match (remainder * 2).cmp(&divisor) {
+            Ordering::Greater => true,
+            Ordering::Equal => match precision.rounding {
+                Rounding::HalfUp => true,
+                Rounding::HalfEven => quotient % 2 == 1,
+            },
+            Ordering::Less => false,
+        };
+        if round_up {
+            quotient += 1;
+        }
+
+        let scale = 10_i64.pow(decimals as u32);
+        let integer = (quotient / scale).to_string();
+        let fraction = match decimals {
+            0 => String::new(),
+            _ => format!("{:01$}", quotient % scale, decimals),
+        };
+        locale.format_digits(self.0 < 0 && quotient > 0, &integer, &fraction)
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0 + other.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        self.0 += other.0;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::default(), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Locale;
+    use super::Money;
+    use super::Precision;
+    use super::Rounding;
+
+    #[test]
+    fn test_sum() {
+        let sum: Money = vec![0.1; 10].into_iter().map(Money::from_f64).sum();
+        assert_eq!(Money::from_f64(1.0), sum);
+        assert_eq!(1.0, sum.to_f64());
+    }
+
+    #[test]
+    fn test_format() {
+        let locale = Locale::default();
+        let format = |amount, decimals, rounding| {
+            Money::from_f64(amount).format(&locale, &Precision { decimals, rounding })
+        };
+        assert_eq!("0.13", format(0.125, 2, Rounding::HalfUp));
+        assert_eq!("0.12", format(0.125, 2, Rounding::HalfEven));
+        assert_eq!("0.14", format(0.135, 2, Rounding::HalfEven));
+        assert_eq!("-0.13", format(-0.125, 2, Rounding::HalfUp));
+        assert_eq!("0.00", format(-0.001, 2, Rounding::HalfUp));
+        assert_eq!("2", format(2.5, 0, Rounding::HalfEven));
+        assert_eq!("1.500000", format(1.5, 6, Rounding::HalfUp));
+
+        let de = Locale::parse("de").unwrap();
+        let precision = Precision::default();
+        assert_eq!(
+            "1.234,57",
+            Money::from_f64(1234.565).format(&de, &precision)
+        );
+    }
+}
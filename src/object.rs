@@ -4,8 +4,10 @@ use std::net::IpAddr;
 use regex::Regex;
 use serde_derive::Deserialize;
 use serde_derive::Serialize;
+use serde_json::Value;
 
 use crate::error::AppError::ParseError;
+use crate::utils::matches_wildcard;
 use crate::utils::Result;
 
 pub trait Named {
@@ -47,7 +49,22 @@ macro_rules! object {
     )
 }
 
-object!(Subscription, ResourceGroup, Resource, IpAddress, DnsRecord);
+object!(
+    Subscription,
+    ResourceGroup,
+    Resource,
+    IpAddress,
+    DnsRecord,
+    ManagedEnvironment,
+    ContainerApp,
+    WebApp
+);
+
+impl Identifiable for TrafficManagerProfile {
+    fn id(&self) -> &String {
+        &self.id
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Subscription {
@@ -56,6 +73,52 @@ pub struct Subscription {
     pub subscription_id: String,
     #[serde(rename = "displayName")]
     pub name: String,
+    /// Only set when querying multiple tenants
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(default, skip_serializing)]
+    pub tags: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing)]
+    pub state: Option<String>,
+    #[serde(rename = "tenantId", default, skip_serializing)]
+    pub tenant_id: Option<String>,
+    #[serde(rename = "subscriptionPolicies", default, skip_serializing)]
+    pub policies: Option<SubscriptionPolicies>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubscriptionPolicies {
+    #[serde(rename = "spendingLimit")]
+    pub spending_limit: Option<String>,
+    #[serde(rename = "quotaId")]
+    pub quota_id: Option<String>,
+}
+
+/// A subscription from the `ResourceContainers` table of the Resource Graph
+#[derive(Debug, Deserialize)]
+pub struct SubscriptionContainer {
+    /// The management groups above the subscription, starting with its parent
+    #[serde(rename = "managementGroupAncestorsChain", default)]
+    pub management_groups: Vec<ManagementGroupAncestor>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ManagementGroupAncestor {
+    pub name: String,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AzureTenant {
+    #[serde(rename = "tenantId")]
+    pub tenant_id: String,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    #[serde(rename = "defaultDomain")]
+    pub default_domain: Option<String>,
+    #[serde(default)]
+    pub domains: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +126,12 @@ pub struct ResourceGroup {
     pub id: String,
     pub location: String,
     pub name: String,
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub protection: Vec<String>,
+    #[serde(default, skip_serializing)]
+    pub tags: Option<HashMap<String, String>>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub region: Option<RegionInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -72,6 +141,98 @@ pub struct Resource {
     pub resource_type: String,
     pub location: String,
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<ResourceIdentity>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<Value>,
+    #[serde(
+        rename = "createdTime",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub created_time: Option<String>,
+    #[serde(
+        rename = "changedTime",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub changed_time: Option<String>,
+    #[serde(skip_deserializing, skip_serializing_if = "Vec::is_empty")]
+    pub protection: Vec<String>,
+    #[serde(default, skip_serializing)]
+    pub tags: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub zones: Vec<String>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub region: Option<RegionInfo>,
+}
+
+/// Availability zone support and paired region of a location
+#[derive(Debug, Clone, Serialize)]
+pub struct RegionInfo {
+    #[serde(rename = "zonesSupported")]
+    pub zones_supported: bool,
+    #[serde(rename = "pairedRegion", skip_serializing_if = "Option::is_none")]
+    pub paired_region: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Location {
+    pub name: String,
+    pub metadata: Option<LocationMetadata>,
+    #[serde(rename = "availabilityZoneMappings", default)]
+    pub availability_zone_mappings: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocationMetadata {
+    #[serde(rename = "pairedRegion", default)]
+    pub paired_region: Vec<PairedRegion>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PairedRegion {
+    pub name: String,
+}
+
+impl Location {
+    pub fn region_info(&self) -> RegionInfo {
+        RegionInfo {
+            zones_supported: !self.availability_zone_mappings.is_empty(),
+            paired_region: self
+                .metadata
+                .as_ref()
+                .and_then(|metadata| metadata.paired_region.first())
+                .map(|region| region.name.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceIdentity {
+    #[serde(rename = "type")]
+    pub identity_type: String,
+    #[serde(rename = "principalId", skip_serializing_if = "Option::is_none")]
+    pub principal_id: Option<String>,
+    #[serde(
+        rename = "userAssignedIdentities",
+        default,
+        skip_serializing_if = "HashMap::is_empty"
+    )]
+    pub user_assigned_identities: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserAssignedIdentity {
+    pub id: String,
+    pub name: String,
+    pub properties: UserAssignedIdentityProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserAssignedIdentityProperties {
+    #[serde(rename = "principalId")]
+    pub principal_id: String,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -88,6 +249,44 @@ pub struct ManagedClusterProperties {
     pub kubernetes_version: String,
     #[serde(rename = "agentPoolProfiles")]
     pub agent_pool_profiles: Vec<AgentPoolProfile>,
+    #[serde(rename = "servicePrincipalProfile")]
+    pub service_principal_profile: Option<ServicePrincipalProfile>,
+    #[serde(rename = "aadProfile")]
+    pub aad_profile: Option<AadProfile>,
+    #[serde(rename = "nodeResourceGroup")]
+    pub node_resource_group: Option<String>,
+}
+
+/// Kubernetes cluster outside of Azure, connected with Azure Arc
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectedCluster {
+    pub id: String,
+    pub name: String,
+    pub properties: ConnectedClusterProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectedClusterProperties {
+    #[serde(rename = "kubernetesVersion")]
+    pub kubernetes_version: Option<String>,
+    /// Like `k3s`, `openshift` or `aks_hci`
+    pub distribution: Option<String>,
+    #[serde(rename = "connectivityStatus")]
+    pub connectivity_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServicePrincipalProfile {
+    #[serde(rename = "clientId")]
+    pub client_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AadProfile {
+    #[serde(rename = "clientAppID")]
+    pub client_app_id: Option<String>,
+    #[serde(rename = "serverAppID")]
+    pub server_app_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -134,6 +333,17 @@ pub enum KubernetesObject {
     },
 }
 
+/// The restarts of a container, from the status of its pod
+#[derive(Debug, Clone)]
+pub struct ContainerRestarts {
+    pub namespace: String,
+    pub restart_count: u64,
+    /// When the container last terminated, the only restart whose time is known
+    pub last_restart: Option<String>,
+    /// Waiting in `CrashLoopBackOff` after failing repeatedly
+    pub crash_loop: bool,
+}
+
 impl KubernetesObject {
     pub fn metadata(&self) -> &KubernetesMetadata {
         match self {
@@ -151,71 +361,1174 @@ impl KubernetesObject {
     }
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct IpAddress {
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubResource {
+    pub id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkSecurityGroup {
     pub id: String,
     pub name: String,
-    #[serde(rename = "ipAddress")]
-    pub ip_address: String,
+    pub properties: NetworkSecurityGroupProperties,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct DnsRecord {
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkSecurityGroupProperties {
+    #[serde(rename = "securityRules", default)]
+    pub security_rules: Vec<SecurityRule>,
+    #[serde(rename = "defaultSecurityRules", default)]
+    pub default_security_rules: Vec<SecurityRule>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityRule {
+    pub properties: SecurityRuleProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SecurityRuleProperties {
+    pub access: String,
+    pub direction: String,
+    pub priority: u32,
+    pub protocol: String,
+    #[serde(rename = "sourceAddressPrefix")]
+    pub source_address_prefix: Option<String>,
+    #[serde(rename = "sourceAddressPrefixes", default)]
+    pub source_address_prefixes: Vec<String>,
+    #[serde(rename = "destinationPortRange")]
+    pub destination_port_range: Option<String>,
+    #[serde(rename = "destinationPortRanges", default)]
+    pub destination_port_ranges: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkInterface {
     pub id: String,
     pub name: String,
-    pub fqdn: String,
-    pub entry: DnsRecordEntry,
+    pub properties: NetworkInterfaceProperties,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub enum DnsRecordEntry {
-    A(Vec<String>),
-    CNAME(String),
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkInterfaceProperties {
+    #[serde(rename = "networkSecurityGroup")]
+    pub network_security_group: Option<SubResource>,
+    #[serde(rename = "ipConfigurations", default)]
+    pub ip_configurations: Vec<NetworkInterfaceIpConfiguration>,
+    #[serde(rename = "virtualMachine")]
+    pub virtual_machine: Option<SubResource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkInterfaceIpConfiguration {
+    pub id: String,
+    pub properties: NetworkInterfaceIpConfigurationProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NetworkInterfaceIpConfigurationProperties {
+    pub subnet: Option<SubResource>,
+    #[serde(rename = "publicIPAddress")]
+    pub public_ip_address: Option<SubResource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VirtualNetwork {
+    pub id: String,
+    pub properties: VirtualNetworkProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VirtualNetworkProperties {
+    #[serde(default)]
+    pub subnets: Vec<Subnet>,
+    #[serde(rename = "virtualNetworkPeerings", default)]
+    pub virtual_network_peerings: Vec<VirtualNetworkPeering>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VirtualNetworkPeering {
+    pub properties: VirtualNetworkPeeringProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VirtualNetworkPeeringProperties {
+    #[serde(rename = "remoteVirtualNetwork")]
+    pub remote_virtual_network: SubResource,
+    #[serde(rename = "peeringState")]
+    pub peering_state: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Subnet {
+    pub id: String,
+    pub properties: SubnetProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubnetProperties {
+    #[serde(rename = "networkSecurityGroup")]
+    pub network_security_group: Option<SubResource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BastionHost {
+    pub id: String,
+    pub name: String,
+    pub sku: Option<Sku>,
+    pub properties: BastionHostProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BastionHostProperties {
+    #[serde(rename = "ipConfigurations", default)]
+    pub ip_configurations: Vec<NetworkInterfaceIpConfiguration>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Costs {
-    #[serde(rename = "resourceGroup")]
-    pub resource_group: String,
-    pub costs: f64,
-    pub currency: String,
+pub struct Sku {
+    pub name: String,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::Identifiable;
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppServicePlan {
+    pub id: String,
+    pub name: String,
+    pub sku: Option<AppServicePlanSku>,
+}
 
-    struct TestIdentifiable {
-        id: String,
-    }
+#[derive(Debug, Clone, Deserialize)]
+pub struct AppServicePlanSku {
+    pub name: String,
+    pub tier: Option<String>,
+    #[serde(default)]
+    pub capacity: u32,
+}
 
-    impl Identifiable for TestIdentifiable {
-        fn id(&self) -> &String {
-            &self.id
-        }
-    }
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebApp {
+    pub id: String,
+    pub name: String,
+    pub kind: Option<String>,
+    pub properties: WebAppProperties,
+}
 
-    #[test]
-    fn test_subscription_id() {
-        assert_eq!(
-            "123",
-            TestIdentifiable {
-                id: "/subscriptions/123/test".to_owned()
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebAppProperties {
+    #[serde(rename = "serverFarmId")]
+    pub server_farm_id: Option<String>,
+    pub state: Option<String>,
+    #[serde(rename = "defaultHostName")]
+    pub default_host_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrafficManagerProfile {
+    pub id: String,
+    pub properties: TrafficManagerProfileProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrafficManagerProfileProperties {
+    #[serde(rename = "dnsConfig")]
+    pub dns_config: Option<TrafficManagerDnsConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrafficManagerDnsConfig {
+    pub fqdn: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManagedEnvironment {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    #[serde(default)]
+    pub properties: ManagedEnvironmentProperties,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ManagedEnvironmentProperties {
+    #[serde(rename = "defaultDomain")]
+    pub default_domain: Option<String>,
+    #[serde(rename = "staticIp")]
+    pub static_ip: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerApp {
+    pub id: String,
+    pub name: String,
+    pub properties: ContainerAppProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppProperties {
+    #[serde(rename = "managedEnvironmentId")]
+    pub managed_environment_id: Option<String>,
+    pub configuration: Option<ContainerAppConfiguration>,
+    pub template: Option<ContainerAppTemplate>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppConfiguration {
+    pub ingress: Option<ContainerAppIngress>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppIngress {
+    pub fqdn: Option<String>,
+    #[serde(default)]
+    pub external: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppTemplate {
+    pub scale: Option<ContainerAppScale>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppScale {
+    #[serde(rename = "minReplicas")]
+    pub min_replicas: Option<u32>,
+    #[serde(rename = "maxReplicas")]
+    pub max_replicas: Option<u32>,
+    #[serde(default)]
+    pub rules: Vec<ContainerAppScaleRule>,
+}
+
+/// Only one of `custom`, `http`, `tcp` and `azureQueue` is set
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppScaleRule {
+    pub name: String,
+    pub custom: Option<Value>,
+    pub http: Option<Value>,
+    pub tcp: Option<Value>,
+    #[serde(rename = "azureQueue")]
+    pub azure_queue: Option<Value>,
+}
+
+impl ContainerAppScaleRule {
+    /// The type of the rule, for custom rules the KEDA scaler like `cron`
+    pub fn rule_type(&self) -> String {
+        if let Some(custom) = &self.custom {
+            if let Some(scaler) = custom["type"].as_str() {
+                return scaler.to_owned();
             }
-            .subscription_id()
-            .unwrap()
-        );
+        }
+        if self.http.is_some() {
+            "http".to_owned()
+        } else if self.tcp.is_some() {
+            "tcp".to_owned()
+        } else if self.azure_queue.is_some() {
+            "azure-queue".to_owned()
+        } else {
+            "custom".to_owned()
+        }
     }
+}
 
-    #[test]
-    fn test_resource_group() {
-        assert_eq!(
-            "test",
-            TestIdentifiable {
-                id: "/subscriptions/abc/resourceGroups/test".to_owned()
-            }
-            .resource_group()
-            .unwrap()
-        );
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppRevision {
+    pub name: String,
+    pub properties: ContainerAppRevisionProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ContainerAppRevisionProperties {
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub replicas: u32,
+    #[serde(rename = "trafficWeight", default)]
+    pub traffic_weight: u32,
+    #[serde(rename = "runningState")]
+    pub running_state: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageAccount {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    pub kind: Option<String>,
+    pub sku: Option<StorageAccountSku>,
+    #[serde(default)]
+    pub properties: StorageAccountProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageAccountSku {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StorageAccountProperties {
+    #[serde(rename = "accessTier")]
+    pub access_tier: Option<String>,
+    /// Missing for keys not rotated since the property was introduced
+    #[serde(rename = "keyCreationTime")]
+    pub key_creation_time: Option<StorageKeyCreationTime>,
+    #[serde(rename = "keyPolicy")]
+    pub key_policy: Option<StorageKeyPolicy>,
+    /// Shared key access is allowed unless this is false
+    #[serde(rename = "allowSharedKeyAccess")]
+    pub allow_shared_key_access: Option<bool>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageKeyCreationTime {
+    pub key1: Option<String>,
+    pub key2: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageKeyPolicy {
+    #[serde(rename = "keyExpirationPeriodInDays")]
+    pub key_expiration_period_in_days: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedisCache {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    pub properties: RedisCacheProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedisCacheProperties {
+    pub sku: RedisCacheSku,
+    #[serde(rename = "redisVersion")]
+    pub redis_version: Option<String>,
+    #[serde(rename = "minimumTlsVersion")]
+    pub minimum_tls_version: Option<String>,
+    #[serde(rename = "enableNonSslPort", default)]
+    pub enable_non_ssl_port: bool,
+    #[serde(rename = "publicNetworkAccess")]
+    pub public_network_access: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RedisCacheSku {
+    /// `Basic`, `Standard` or `Premium`
+    pub name: String,
+    /// `C` or `P` for Premium
+    pub family: String,
+    pub capacity: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SqlServer {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SqlDatabase {
+    pub id: String,
+    pub name: String,
+    pub sku: Option<SqlDatabaseSku>,
+    #[serde(default)]
+    pub properties: SqlDatabaseProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SqlDatabaseSku {
+    pub name: String,
+    pub tier: Option<String>,
+    pub capacity: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SqlDatabaseProperties {
+    #[serde(rename = "maxSizeBytes")]
+    pub max_size_bytes: Option<u64>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Gallery {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GalleryImage {
+    pub id: String,
+    pub name: String,
+    pub properties: GalleryImageProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GalleryImageProperties {
+    #[serde(rename = "osType")]
+    pub os_type: Option<String>,
+    pub identifier: Option<GalleryImageIdentifier>,
+    #[serde(rename = "endOfLifeDate")]
+    pub end_of_life_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GalleryImageIdentifier {
+    pub publisher: String,
+    pub offer: String,
+    pub sku: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GalleryImageVersion {
+    pub name: String,
+    pub properties: GalleryImageVersionProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GalleryImageVersionProperties {
+    #[serde(rename = "publishingProfile")]
+    pub publishing_profile: Option<PublishingProfile>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublishingProfile {
+    #[serde(rename = "targetRegions", default)]
+    pub target_regions: Vec<TargetRegion>,
+    #[serde(rename = "endOfLifeDate")]
+    pub end_of_life_date: Option<String>,
+    #[serde(rename = "publishedDate")]
+    pub published_date: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TargetRegion {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManagedImage {
+    pub id: String,
+    pub name: String,
+    pub location: String,
+    pub properties: ManagedImageProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManagedImageProperties {
+    #[serde(rename = "storageProfile")]
+    pub storage_profile: Option<ImageStorageProfile>,
+    #[serde(rename = "sourceVirtualMachine")]
+    pub source_virtual_machine: Option<SubResource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageStorageProfile {
+    #[serde(rename = "osDisk")]
+    pub os_disk: Option<ImageOsDisk>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageOsDisk {
+    #[serde(rename = "osType")]
+    pub os_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyVault {
+    pub id: String,
+    pub name: String,
+    pub properties: KeyVaultProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyVaultProperties {
+    #[serde(rename = "accessPolicies", default)]
+    pub access_policies: Vec<KeyVaultAccessPolicy>,
+    #[serde(rename = "enableRbacAuthorization", default)]
+    pub enable_rbac_authorization: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyVaultAccessPolicy {
+    #[serde(rename = "objectId")]
+    pub object_id: String,
+    #[serde(rename = "applicationId")]
+    pub application_id: Option<String>,
+    pub permissions: KeyVaultPermissions,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyVaultPermissions {
+    #[serde(default)]
+    pub secrets: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleAssignment {
+    pub properties: RoleAssignmentProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleAssignmentProperties {
+    #[serde(rename = "principalId")]
+    pub principal_id: String,
+    #[serde(rename = "principalType")]
+    pub principal_type: Option<String>,
+    #[serde(rename = "roleDefinitionId")]
+    pub role_definition_id: String,
+    pub scope: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleDefinition {
+    pub properties: RoleDefinitionProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleDefinitionProperties {
+    #[serde(rename = "roleName")]
+    pub role_name: String,
+    #[serde(default)]
+    pub permissions: Vec<RoleDefinitionPermission>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoleDefinitionPermission {
+    #[serde(rename = "dataActions", default)]
+    pub data_actions: Vec<String>,
+    #[serde(rename = "notDataActions", default)]
+    pub not_data_actions: Vec<String>,
+}
+
+impl RoleDefinition {
+    pub fn allows_data_action(&self, action: &str) -> bool {
+        self.properties.permissions.iter().any(|permission| {
+            permission
+                .data_actions
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, action))
+                && !permission
+                    .not_data_actions
+                    .iter()
+                    .any(|pattern| matches_wildcard(pattern, action))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManagementLock {
+    pub id: String,
+    pub properties: ManagementLockProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManagementLockProperties {
+    pub level: String,
+}
+
+impl ManagementLock {
+    /// The ID of the subscription, resource group or resource the lock applies to
+    pub fn scope(&self) -> &str {
+        match self
+            .id
+            .to_lowercase()
+            .rfind("/providers/microsoft.authorization/locks/")
+        {
+            Some(pos) => &self.id[..pos],
+            None => &self.id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenyAssignment {
+    pub properties: DenyAssignmentProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenyAssignmentProperties {
+    #[serde(rename = "denyAssignmentName")]
+    pub deny_assignment_name: String,
+    pub scope: String,
+    #[serde(default)]
+    pub permissions: Vec<DenyAssignmentPermission>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DenyAssignmentPermission {
+    #[serde(default)]
+    pub actions: Vec<String>,
+    #[serde(rename = "notActions", default)]
+    pub not_actions: Vec<String>,
+}
+
+impl DenyAssignment {
+    pub fn denies_action(&self, action: &str) -> bool {
+        self.properties.permissions.iter().any(|permission| {
+            permission
+                .actions
+                .iter()
+                .any(|pattern| matches_wildcard(pattern, action))
+                && !permission
+                    .not_actions
+                    .iter()
+                    .any(|pattern| matches_wildcard(pattern, action))
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryObject {
+    pub id: String,
+    #[serde(rename = "@odata.type")]
+    pub object_type: Option<String>,
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+    #[serde(rename = "appId", skip_serializing_if = "Option::is_none")]
+    pub app_id: Option<String>,
+    #[serde(rename = "userPrincipalName", skip_serializing_if = "Option::is_none")]
+    pub user_principal_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateEndpoint {
+    pub id: String,
+    pub name: String,
+    pub properties: PrivateEndpointProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateEndpointProperties {
+    #[serde(rename = "privateLinkServiceConnections", default)]
+    pub private_link_service_connections: Vec<PrivateLinkServiceConnection>,
+    #[serde(rename = "manualPrivateLinkServiceConnections", default)]
+    pub manual_private_link_service_connections: Vec<PrivateLinkServiceConnection>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateLinkServiceConnection {
+    pub properties: PrivateLinkServiceConnectionProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateLinkServiceConnectionProperties {
+    #[serde(rename = "privateLinkServiceId")]
+    pub private_link_service_id: String,
+    #[serde(rename = "groupIds", default)]
+    pub group_ids: Vec<String>,
+    #[serde(rename = "privateLinkServiceConnectionState")]
+    pub state: PrivateLinkServiceConnectionState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrivateLinkServiceConnectionState {
+    pub status: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateDnsZoneGroup {
+    pub properties: PrivateDnsZoneGroupProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateDnsZoneGroupProperties {
+    #[serde(rename = "privateDnsZoneConfigs", default)]
+    pub private_dns_zone_configs: Vec<PrivateDnsZoneConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateDnsZoneConfig {
+    pub properties: PrivateDnsZoneConfigProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrivateDnsZoneConfigProperties {
+    #[serde(rename = "privateDnsZoneId")]
+    pub private_dns_zone_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpressRouteCircuit {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub properties: ExpressRouteCircuitProperties,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExpressRouteCircuitProperties {
+    #[serde(rename = "serviceProviderProvisioningState")]
+    pub service_provider_state: Option<String>,
+    #[serde(rename = "serviceProviderProperties")]
+    pub service_provider: Option<ExpressRouteServiceProvider>,
+    #[serde(default)]
+    pub peerings: Vec<ExpressRoutePeering>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpressRouteServiceProvider {
+    #[serde(rename = "serviceProviderName")]
+    pub name: Option<String>,
+    #[serde(rename = "peeringLocation")]
+    pub peering_location: Option<String>,
+    #[serde(rename = "bandwidthInMbps")]
+    pub bandwidth_mbps: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpressRoutePeering {
+    pub name: String,
+    pub properties: ExpressRoutePeeringProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpressRoutePeeringProperties {
+    pub state: Option<String>,
+    #[serde(rename = "peerASN")]
+    pub peer_asn: Option<u64>,
+}
+
+/// BGP neighbor summary of an ExpressRoute peering
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpressRouteRouteSummary {
+    pub neighbor: Option<String>,
+    #[serde(rename = "as")]
+    pub asn: Option<u64>,
+    /// Number of received prefixes, or the BGP state when not established
+    #[serde(rename = "statePfxRcd")]
+    pub state_prefixes_received: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VirtualNetworkGateway {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub properties: VirtualNetworkGatewayProperties,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VirtualNetworkGatewayProperties {
+    #[serde(rename = "gatewayType")]
+    pub gateway_type: Option<String>,
+    #[serde(rename = "enableBgp", default)]
+    pub enable_bgp: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VirtualNetworkGatewayConnection {
+    pub name: String,
+    #[serde(default)]
+    pub properties: VirtualNetworkGatewayConnectionProperties,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct VirtualNetworkGatewayConnectionProperties {
+    #[serde(rename = "connectionType")]
+    pub connection_type: Option<String>,
+    #[serde(rename = "connectionStatus")]
+    pub connection_status: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BgpPeerStatus {
+    pub neighbor: Option<String>,
+    pub asn: Option<u64>,
+    pub state: Option<String>,
+    #[serde(rename = "routesReceived")]
+    pub routes_received: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AzureFirewall {
+    pub id: String,
+    pub name: String,
+    pub properties: AzureFirewallProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AzureFirewallProperties {
+    #[serde(rename = "firewallPolicy")]
+    pub firewall_policy: Option<SubResource>,
+    #[serde(rename = "networkRuleCollections", default)]
+    pub network_rule_collections: Vec<FirewallRuleCollection>,
+    #[serde(rename = "applicationRuleCollections", default)]
+    pub application_rule_collections: Vec<FirewallRuleCollection>,
+    #[serde(rename = "natRuleCollections", default)]
+    pub nat_rule_collections: Vec<FirewallRuleCollection>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallRuleCollection {
+    pub name: String,
+    pub properties: FirewallRuleCollectionProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallRuleCollectionProperties {
+    pub priority: u32,
+    pub action: Option<FirewallAction>,
+    #[serde(default)]
+    pub rules: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallAction {
+    #[serde(rename = "type")]
+    pub action_type: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallPolicyRuleCollectionGroup {
+    pub name: String,
+    pub properties: FirewallPolicyRuleCollectionGroupProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallPolicyRuleCollectionGroupProperties {
+    pub priority: u32,
+    #[serde(rename = "ruleCollections", default)]
+    pub rule_collections: Vec<FirewallPolicyRuleCollection>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FirewallPolicyRuleCollection {
+    pub name: String,
+    pub priority: u32,
+    pub action: Option<FirewallAction>,
+    #[serde(default)]
+    pub rules: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteTable {
+    pub id: String,
+    pub name: String,
+    pub properties: RouteTableProperties,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteTableProperties {
+    #[serde(default)]
+    pub routes: Vec<Route>,
+    #[serde(default)]
+    pub subnets: Vec<SubResource>,
+    #[serde(rename = "disableBgpRoutePropagation", default)]
+    pub disable_bgp_route_propagation: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Route {
+    pub name: String,
+    pub properties: RouteProperties,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteProperties {
+    #[serde(rename = "addressPrefix")]
+    pub address_prefix: String,
+    #[serde(rename = "nextHopType")]
+    pub next_hop_type: String,
+    #[serde(rename = "nextHopIpAddress", skip_serializing_if = "Option::is_none")]
+    pub next_hop_ip_address: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EffectiveRoute {
+    pub source: String,
+    pub state: String,
+    #[serde(rename = "addressPrefix", default)]
+    pub address_prefix: Vec<String>,
+    #[serde(rename = "nextHopType")]
+    pub next_hop_type: String,
+    #[serde(rename = "nextHopIpAddress", default)]
+    pub next_hop_ip_address: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IpAddress {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "ipAddress")]
+    pub ip_address: String,
+    #[serde(rename = "ipConfiguration", skip_serializing_if = "Option::is_none")]
+    pub ip_configuration: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub protection: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DnsRecord {
+    pub id: String,
+    pub name: String,
+    pub fqdn: String,
+    pub entry: DnsRecordEntry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum DnsRecordEntry {
+    A(Vec<String>),
+    CNAME(String),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutoscaleSetting {
+    pub id: String,
+    pub name: String,
+    pub properties: AutoscaleSettingProperties,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutoscaleSettingProperties {
+    #[serde(rename = "targetResourceUri")]
+    pub target_resource_uri: String,
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub profiles: Vec<AutoscaleProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutoscaleProfile {
+    pub name: String,
+    pub capacity: AutoscaleCapacity,
+    #[serde(default)]
+    pub rules: Vec<AutoscaleRule>,
+}
+
+/// Instance counts, which are returned as strings by the API
+#[derive(Debug, Deserialize)]
+pub struct AutoscaleCapacity {
+    pub minimum: String,
+    pub maximum: String,
+    pub default: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AutoscaleRule {
+    #[serde(rename = "metricTrigger")]
+    pub metric_trigger: MetricTrigger,
+    #[serde(rename = "scaleAction")]
+    pub scale_action: ScaleAction,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MetricTrigger {
+    #[serde(rename = "metricName")]
+    pub metric_name: String,
+    pub operator: String,
+    pub threshold: f64,
+    #[serde(rename = "timeAggregation")]
+    pub time_aggregation: String,
+    #[serde(rename = "timeWindow")]
+    pub time_window: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScaleAction {
+    pub direction: String,
+    #[serde(rename = "type")]
+    pub action_type: String,
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ActivityLogEvent {
+    pub caller: Option<String>,
+    #[serde(rename = "resourceId")]
+    pub resource_id: String,
+    #[serde(rename = "operationName")]
+    pub operation_name: LocalizableString,
+    pub status: LocalizableString,
+    #[serde(rename = "eventTimestamp")]
+    pub event_timestamp: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LocalizableString {
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VirtualMachineStatus {
+    pub id: String,
+    pub properties: VirtualMachineStatusProperties,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VirtualMachineStatusProperties {
+    #[serde(rename = "instanceView")]
+    pub instance_view: Option<InstanceView>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstanceView {
+    #[serde(default)]
+    pub statuses: Vec<InstanceViewStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InstanceViewStatus {
+    pub code: String,
+}
+
+impl VirtualMachineStatus {
+    /// The power state like `running` or `deallocated`, from the `PowerState/...` status code
+    pub fn power_state(&self) -> Option<&str> {
+        self.properties
+            .instance_view
+            .as_ref()?
+            .statuses
+            .iter()
+            .find_map(|status| status.code.strip_prefix("PowerState/"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Provider {
+    pub namespace: String,
+    /// Like `Registered`, `NotRegistered` or `Registering`
+    #[serde(rename = "registrationState", default)]
+    pub registration_state: String,
+    #[serde(rename = "resourceTypes")]
+    pub resource_types: Vec<ProviderResourceType>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProviderResourceType {
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    #[serde(rename = "apiVersions", default)]
+    pub api_versions: Vec<String>,
+}
+
+impl ProviderResourceType {
+    /// The latest stable API version, or the latest preview version if there is none
+    pub fn latest_api_version(&self) -> Option<&String> {
+        self.api_versions
+            .iter()
+            .find(|version| !version.contains("preview"))
+            .or_else(|| self.api_versions.first())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Costs {
+    #[serde(rename = "resourceGroup")]
+    pub resource_group: String,
+    pub costs: f64,
+    pub currency: String,
+    /// First day of the month the costs were accrued in, like `2019-03-01`
+    pub date: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::from_value;
+    use serde_json::json;
+
+    use super::ContainerAppScale;
+    use super::Identifiable;
+    use super::Location;
+    use super::ProviderResourceType;
+    use super::VirtualMachineStatus;
+
+    struct TestIdentifiable {
+        id: String,
+    }
+
+    impl Identifiable for TestIdentifiable {
+        fn id(&self) -> &String {
+            &self.id
+        }
+    }
+
+    #[test]
+    fn test_subscription_id() {
+        assert_eq!(
+            "123",
+            TestIdentifiable {
+                id: "/subscriptions/123/test".to_owned()
+            }
+            .subscription_id()
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_latest_api_version() {
+        let resource_type = ProviderResourceType {
+            resource_type: "test".to_owned(),
+            api_versions: vec![
+                "2022-01-01-preview".to_owned(),
+                "2021-06-01".to_owned(),
+                "2020-01-01".to_owned(),
+            ],
+        };
+        assert_eq!(
+            Some(&"2021-06-01".to_owned()),
+            resource_type.latest_api_version()
+        );
+    }
+
+    #[test]
+    fn test_resource_group() {
+        assert_eq!(
+            "test",
+            TestIdentifiable {
+                id: "/subscriptions/abc/resourceGroups/test".to_owned()
+            }
+            .resource_group()
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_region_info() {
+        let location: Location = from_value(json!({
+            "name": "westeurope",
+            "metadata": { "pairedRegion": [{ "name": "northeurope" }] },
+            "availabilityZoneMappings": [{ "logicalZone": "1", "physicalZone": "westeurope-az1" }]
+        }))
+        .unwrap();
+        let region = location.region_info();
+        assert!(region.zones_supported);
+        assert_eq!(Some("northeurope".to_owned()), region.paired_region);
+
+        let location: Location = from_value(json!({ "name": "westcentralus" })).unwrap();
+        assert!(!location.region_info().zones_supported);
+    }
+
+    #[test]
+    fn test_power_state() {
+        let status: VirtualMachineStatus = from_value(json!({
+            "id": "/subscriptions/123/resourceGroups/rg/providers/Microsoft.Compute/virtualMachines/vm",
+            "properties": {
+                "instanceView": {
+                    "statuses": [
+                        { "code": "ProvisioningState/succeeded" },
+                        { "code": "PowerState/deallocated" }
+                    ]
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(Some("deallocated"), status.power_state());
+    }
+
+    #[test]
+    fn test_scale_rule_type() {
+        let scale: ContainerAppScale = from_value(json!({
+            "minReplicas": 1,
+            "maxReplicas": 10,
+            "rules": [
+                { "name": "http-rule", "http": { "metadata": { "concurrentRequests": "50" } } },
+                { "name": "nightly", "custom": { "type": "cron", "metadata": {} } },
+                { "name": "queue", "azureQueue": { "queueName": "jobs", "queueLength": 5 } }
+            ]
+        }))
+        .unwrap();
+        let types: Vec<String> = scale.rules.iter().map(|rule| rule.rule_type()).collect();
+        assert_eq!(vec!["http", "cron", "azure-queue"], types);
     }
 }
@@ -0,0 +1,672 @@
+/// A REST operation: method, path, API version and the required RBAC action.
+/// Paths starting with `https://` are not Resource Manager operations, their
+/// action is the required Microsoft Graph permission instead
+pub type Operation = (&'static str, &'static str, &'static str, &'static str);
+
+const SUBSCRIPTIONS: Operation = (
+    "GET",
+    "/subscriptions",
+    "2020-01-01",
+    "Microsoft.Resources/subscriptions/read",
+);
+const TENANTS: Operation = (
+    "GET",
+    "/tenants",
+    "2020-01-01",
+    "Microsoft.Resources/tenants/read",
+);
+const RESOURCE_GROUPS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/resourcegroups",
+    "2018-05-01",
+    "Microsoft.Resources/subscriptions/resourceGroups/read",
+);
+const LOCATIONS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/locations",
+    "2022-12-01",
+    "Microsoft.Resources/subscriptions/locations/read",
+);
+const RESOURCES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/resources",
+    "2018-05-01",
+    "Microsoft.Resources/subscriptions/resources/read",
+);
+const RESOURCE_GRAPH: Operation = (
+    "POST",
+    "/providers/Microsoft.ResourceGraph/resources",
+    "2021-03-01",
+    "Microsoft.ResourceGraph/resources/read",
+);
+const RESOURCE_GROUP_RESOURCES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/resourceGroups/{resourceGroup}/resources",
+    "2018-05-01",
+    "Microsoft.Resources/subscriptions/resourceGroups/resources/read",
+);
+const VALIDATE_MOVE_RESOURCES: Operation = (
+    "POST",
+    "/subscriptions/{subscriptionId}/resourceGroups/{resourceGroup}/validateMoveResources",
+    "2021-04-01",
+    "Microsoft.Resources/subscriptions/resourceGroups/validateMoveResources/action",
+);
+const PROVIDERS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers",
+    "2021-04-01",
+    "Microsoft.Resources/subscriptions/providers/read",
+);
+const RESOURCE: Operation = ("GET", "/{resourceId}", "latest", "*/read");
+const ACTIVITY_LOG: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Insights/eventtypes/management/values",
+    "2015-04-01",
+    "Microsoft.Insights/eventtypes/values/read",
+);
+const VIRTUAL_MACHINES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Compute/virtualMachines",
+    "2023-03-01",
+    "Microsoft.Compute/virtualMachines/read",
+);
+const CLUSTERS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.ContainerService/managedClusters",
+    "2021-03-01",
+    "Microsoft.ContainerService/managedClusters/read",
+);
+const AGENT_POOLS: Operation = (
+    "GET",
+    "/{clusterId}/agentPools",
+    "2021-03-01",
+    "Microsoft.ContainerService/managedClusters/agentPools/read",
+);
+const CLUSTER_USER_CREDENTIAL: Operation = (
+    "POST",
+    "/{clusterId}/listClusterUserCredential",
+    "2021-03-01",
+    "Microsoft.ContainerService/managedClusters/listClusterUserCredential/action",
+);
+const CONNECTED_CLUSTERS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Kubernetes/connectedClusters",
+    "2021-10-01",
+    "Microsoft.Kubernetes/connectedClusters/read",
+);
+const CONNECTED_CLUSTER_USER_CREDENTIAL: Operation = (
+    "POST",
+    "/{connectedClusterId}/listClusterUserCredential",
+    "2021-10-01",
+    "Microsoft.Kubernetes/connectedClusters/listClusterUserCredential/action",
+);
+const PUBLIC_IP_ADDRESSES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/publicIPAddresses",
+    "2018-11-01",
+    "Microsoft.Network/publicIPAddresses/read",
+);
+const NETWORK_SECURITY_GROUPS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/networkSecurityGroups",
+    "2021-05-01",
+    "Microsoft.Network/networkSecurityGroups/read",
+);
+const NETWORK_INTERFACES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/networkInterfaces",
+    "2021-05-01",
+    "Microsoft.Network/networkInterfaces/read",
+);
+const VIRTUAL_NETWORKS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/virtualNetworks",
+    "2021-05-01",
+    "Microsoft.Network/virtualNetworks/read",
+);
+const PRIVATE_ENDPOINTS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/privateEndpoints",
+    "2021-05-01",
+    "Microsoft.Network/privateEndpoints/read",
+);
+const PRIVATE_DNS_ZONE_GROUPS: Operation = (
+    "GET",
+    "/{privateEndpointId}/privateDnsZoneGroups",
+    "2021-05-01",
+    "Microsoft.Network/privateEndpoints/privateDnsZoneGroups/read",
+);
+const BASTION_HOSTS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/bastionHosts",
+    "2021-05-01",
+    "Microsoft.Network/bastionHosts/read",
+);
+const SERVER_FARMS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Web/serverfarms",
+    "2022-03-01",
+    "Microsoft.Web/serverfarms/read",
+);
+const SITES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Web/sites",
+    "2022-03-01",
+    "Microsoft.Web/sites/read",
+);
+const TRAFFIC_MANAGER_PROFILES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/trafficmanagerprofiles",
+    "2022-04-01",
+    "Microsoft.Network/trafficManagerProfiles/read",
+);
+const MANAGED_ENVIRONMENTS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.App/managedEnvironments",
+    "2023-05-01",
+    "Microsoft.App/managedEnvironments/read",
+);
+const CONTAINER_APPS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.App/containerApps",
+    "2023-05-01",
+    "Microsoft.App/containerApps/read",
+);
+const CONTAINER_APP_REVISIONS: Operation = (
+    "GET",
+    "/{containerAppId}/revisions",
+    "2023-05-01",
+    "Microsoft.App/containerApps/revisions/read",
+);
+const STORAGE_ACCOUNTS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Storage/storageAccounts",
+    "2022-09-01",
+    "Microsoft.Storage/storageAccounts/read",
+);
+const REDIS_CACHES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Cache/redis",
+    "2023-08-01",
+    "Microsoft.Cache/redis/read",
+);
+const SQL_SERVERS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Sql/servers",
+    "2021-11-01",
+    "Microsoft.Sql/servers/read",
+);
+const SQL_DATABASES: Operation = (
+    "GET",
+    "/{serverId}/databases",
+    "2021-11-01",
+    "Microsoft.Sql/servers/databases/read",
+);
+const EXPRESS_ROUTE_CIRCUITS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/expressRouteCircuits",
+    "2022-07-01",
+    "Microsoft.Network/expressRouteCircuits/read",
+);
+const EXPRESS_ROUTE_ROUTE_SUMMARY: Operation = (
+    "POST",
+    "/{circuitId}/peerings/{peeringName}/routeTablesSummary/{devicePath}",
+    "2022-07-01",
+    "Microsoft.Network/expressRouteCircuits/peerings/routeTablesSummary/action",
+);
+const VIRTUAL_NETWORK_GATEWAY: Operation = (
+    "GET",
+    "/{gatewayId}",
+    "2022-07-01",
+    "Microsoft.Network/virtualNetworkGateways/read",
+);
+const GATEWAY_CONNECTIONS: Operation = (
+    "GET",
+    "/{gatewayId}/connections",
+    "2022-07-01",
+    "Microsoft.Network/virtualNetworkGateways/connections/read",
+);
+const BGP_PEER_STATUS: Operation = (
+    "POST",
+    "/{gatewayId}/getBgpPeerStatus",
+    "2022-07-01",
+    "Microsoft.Network/virtualNetworkGateways/getBgpPeerStatus/action",
+);
+const ADVERTISED_ROUTES: Operation = (
+    "POST",
+    "/{gatewayId}/getAdvertisedRoutes",
+    "2022-07-01",
+    "Microsoft.Network/virtualNetworkGateways/getAdvertisedRoutes/action",
+);
+const GALLERIES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Compute/galleries",
+    "2022-03-03",
+    "Microsoft.Compute/galleries/read",
+);
+const GALLERY_IMAGES: Operation = (
+    "GET",
+    "/{galleryId}/images",
+    "2022-03-03",
+    "Microsoft.Compute/galleries/images/read",
+);
+const GALLERY_IMAGE_VERSIONS: Operation = (
+    "GET",
+    "/{galleryImageId}/versions",
+    "2022-03-03",
+    "Microsoft.Compute/galleries/images/versions/read",
+);
+const IMAGES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Compute/images",
+    "2022-08-01",
+    "Microsoft.Compute/images/read",
+);
+const ROUTE_TABLES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/routeTables",
+    "2021-05-01",
+    "Microsoft.Network/routeTables/read",
+);
+const EFFECTIVE_ROUTE_TABLE: Operation = (
+    "POST",
+    "/{networkInterfaceId}/effectiveRouteTable",
+    "2021-05-01",
+    "Microsoft.Network/networkInterfaces/effectiveRouteTable/action",
+);
+const AZURE_FIREWALLS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Network/azureFirewalls",
+    "2021-05-01",
+    "Microsoft.Network/azureFirewalls/read",
+);
+const RULE_COLLECTION_GROUPS: Operation = (
+    "GET",
+    "/{firewallPolicyId}/ruleCollectionGroups",
+    "2021-05-01",
+    "Microsoft.Network/firewallPolicies/ruleCollectionGroups/read",
+);
+const KEY_VAULTS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.KeyVault/vaults",
+    "2021-10-01",
+    "Microsoft.KeyVault/vaults/read",
+);
+const USER_ASSIGNED_IDENTITIES: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.ManagedIdentity/userAssignedIdentities",
+    "2018-11-30",
+    "Microsoft.ManagedIdentity/userAssignedIdentities/read",
+);
+const AUTOSCALE_SETTINGS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Insights/autoscalesettings",
+    "2022-10-01",
+    "Microsoft.Insights/autoscalesettings/read",
+);
+const METRICS: Operation = (
+    "GET",
+    "/{resourceId}/providers/Microsoft.Insights/metrics",
+    "2018-01-01",
+    "Microsoft.Insights/metrics/read",
+);
+const LOCKS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Authorization/locks",
+    "2016-09-01",
+    "Microsoft.Authorization/locks/read",
+);
+const DENY_ASSIGNMENTS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.Authorization/denyAssignments",
+    "2022-04-01",
+    "Microsoft.Authorization/denyAssignments/read",
+);
+const ROLE_ASSIGNMENTS: Operation = (
+    "GET",
+    "/{scope}/providers/Microsoft.Authorization/roleAssignments",
+    "2022-04-01",
+    "Microsoft.Authorization/roleAssignments/read",
+);
+const ROLE_DEFINITION: Operation = (
+    "GET",
+    "/{roleDefinitionId}",
+    "2022-04-01",
+    "Microsoft.Authorization/roleDefinitions/read",
+);
+const DNS_RECORD_SETS: Operation = (
+    "GET",
+    "/subscriptions/{subscriptionId}/resourceGroups/{resourceGroup}/providers/Microsoft.Network/dnsZones/{zone}/recordsets",
+    "2018-03-01-preview",
+    "Microsoft.Network/dnsZones/recordsets/read",
+);
+const COST_QUERY: Operation = (
+    "POST",
+    "/subscriptions/{subscriptionId}/providers/Microsoft.CostManagement/query",
+    "2019-01-01",
+    "Microsoft.CostManagement/query/action",
+);
+const DIRECTORY_OBJECTS: Operation = (
+    "POST",
+    "https://graph.microsoft.com/v1.0/directoryObjects/getByIds",
+    "v1.0",
+    "Directory.Read.All",
+);
+const SERVICE_PRINCIPALS: Operation = (
+    "GET",
+    "https://graph.microsoft.com/v1.0/servicePrincipals",
+    "v1.0",
+    "Application.Read.All",
+);
+const SERVICE_PRINCIPAL_OWNERS: Operation = (
+    "GET",
+    "https://graph.microsoft.com/v1.0/servicePrincipals/{id}/owners",
+    "v1.0",
+    "Application.Read.All",
+);
+
+/// The operations each command may invoke, depending on its flags
+pub const COMMAND_OPERATIONS: &[(&str, &[Operation])] = &[
+    (
+        "list",
+        &[
+            SUBSCRIPTIONS,
+            RESOURCE_GROUPS,
+            RESOURCES,
+            RESOURCE_GRAPH,
+            PROVIDERS,
+            RESOURCE,
+            LOCKS,
+            DENY_ASSIGNMENTS,
+            LOCATIONS,
+        ],
+    ),
+    (
+        "resources",
+        &[SUBSCRIPTIONS, RESOURCES, PROVIDERS, RESOURCE],
+    ),
+    (
+        "clusters",
+        &[
+            SUBSCRIPTIONS,
+            CLUSTERS,
+            AGENT_POOLS,
+            CLUSTER_USER_CREDENTIAL,
+            CONNECTED_CLUSTERS,
+            CONNECTED_CLUSTER_USER_CREDENTIAL,
+            COST_QUERY,
+        ],
+    ),
+    (
+        "domains",
+        &[
+            SUBSCRIPTIONS,
+            RESOURCE_GROUPS,
+            RESOURCES,
+            PUBLIC_IP_ADDRESSES,
+            DNS_RECORD_SETS,
+            MANAGED_ENVIRONMENTS,
+            CONTAINER_APPS,
+            SITES,
+            TRAFFIC_MANAGER_PROFILES,
+        ],
+    ),
+    ("dns", &[SUBSCRIPTIONS, RESOURCES, DNS_RECORD_SETS]),
+    (
+        "resolve",
+        &[
+            SUBSCRIPTIONS,
+            RESOURCE_GROUPS,
+            RESOURCES,
+            PUBLIC_IP_ADDRESSES,
+            DNS_RECORD_SETS,
+            MANAGED_ENVIRONMENTS,
+            CONTAINER_APPS,
+        ],
+    ),
+    (
+        "ip",
+        &[
+            SUBSCRIPTIONS,
+            RESOURCE_GROUPS,
+            PUBLIC_IP_ADDRESSES,
+            LOCKS,
+            DENY_ASSIGNMENTS,
+        ],
+    ),
+    (
+        "endpoints",
+        &[SUBSCRIPTIONS, PRIVATE_ENDPOINTS, PRIVATE_DNS_ZONE_GROUPS],
+    ),
+    (
+        "routing",
+        &[
+            SUBSCRIPTIONS,
+            ROUTE_TABLES,
+            NETWORK_INTERFACES,
+            EFFECTIVE_ROUTE_TABLE,
+        ],
+    ),
+    (
+        "firewall",
+        &[SUBSCRIPTIONS, AZURE_FIREWALLS, RULE_COLLECTION_GROUPS],
+    ),
+    (
+        "bastion",
+        &[
+            SUBSCRIPTIONS,
+            BASTION_HOSTS,
+            PUBLIC_IP_ADDRESSES,
+            NETWORK_INTERFACES,
+            VIRTUAL_NETWORKS,
+        ],
+    ),
+    (
+        "autoscale",
+        &[SUBSCRIPTIONS, AUTOSCALE_SETTINGS, RESOURCES, METRICS],
+    ),
+    ("plans", &[SUBSCRIPTIONS, SERVER_FARMS, SITES]),
+    (
+        "containerapps",
+        &[
+            SUBSCRIPTIONS,
+            MANAGED_ENVIRONMENTS,
+            CONTAINER_APPS,
+            CONTAINER_APP_REVISIONS,
+        ],
+    ),
+    (
+        "images",
+        &[
+            SUBSCRIPTIONS,
+            GALLERIES,
+            GALLERY_IMAGES,
+            GALLERY_IMAGE_VERSIONS,
+            IMAGES,
+        ],
+    ),
+    ("storage", &[SUBSCRIPTIONS, STORAGE_ACCOUNTS, METRICS]),
+    ("sql", &[SUBSCRIPTIONS, SQL_SERVERS, SQL_DATABASES, METRICS]),
+    ("redis", &[SUBSCRIPTIONS, REDIS_CACHES]),
+    (
+        "peers",
+        &[
+            SUBSCRIPTIONS,
+            EXPRESS_ROUTE_CIRCUITS,
+            EXPRESS_ROUTE_ROUTE_SUMMARY,
+            RESOURCES,
+            VIRTUAL_NETWORK_GATEWAY,
+            GATEWAY_CONNECTIONS,
+            BGP_PEER_STATUS,
+            ADVERTISED_ROUTES,
+        ],
+    ),
+    (
+        "identity",
+        &[
+            SUBSCRIPTIONS,
+            USER_ASSIGNED_IDENTITIES,
+            ROLE_ASSIGNMENTS,
+            ROLE_DEFINITION,
+            RESOURCES,
+        ],
+    ),
+    (
+        "sp-usage",
+        &[
+            SUBSCRIPTIONS,
+            ROLE_ASSIGNMENTS,
+            KEY_VAULTS,
+            CLUSTERS,
+            DIRECTORY_OBJECTS,
+            SERVICE_PRINCIPALS,
+            SERVICE_PRINCIPAL_OWNERS,
+        ],
+    ),
+    ("changes", &[SUBSCRIPTIONS, ACTIVITY_LOG, RESOURCES]),
+    (
+        "stale",
+        &[
+            SUBSCRIPTIONS,
+            RESOURCE_GROUPS,
+            RESOURCES,
+            ACTIVITY_LOG,
+            VIRTUAL_MACHINES,
+            PUBLIC_IP_ADDRESSES,
+        ],
+    ),
+    (
+        "move-check",
+        &[
+            SUBSCRIPTIONS,
+            RESOURCE_GROUPS,
+            RESOURCE_GROUP_RESOURCES,
+            VALIDATE_MOVE_RESOURCES,
+        ],
+    ),
+    ("costs", &[SUBSCRIPTIONS, COST_QUERY]),
+    ("subs", &[SUBSCRIPTIONS, RESOURCE_GRAPH]),
+    ("providers", &[SUBSCRIPTIONS, PROVIDERS]),
+    ("tenants", &[TENANTS]),
+    (
+        "audit",
+        &[
+            SUBSCRIPTIONS,
+            PUBLIC_IP_ADDRESSES,
+            NETWORK_INTERFACES,
+            NETWORK_SECURITY_GROUPS,
+            VIRTUAL_NETWORKS,
+            KEY_VAULTS,
+            ROLE_ASSIGNMENTS,
+            ROLE_DEFINITION,
+            DIRECTORY_OBJECTS,
+            STORAGE_ACCOUNTS,
+            RESOURCES,
+        ],
+    ),
+];
+
+/// Returns the operations of the command, or `None` if it doesn't call any REST APIs
+pub fn command_operations(command: &str) -> Option<&'static [Operation]> {
+    COMMAND_OPERATIONS
+        .iter()
+        .find(|(name, _)| *name == command)
+        .map(|(_, operations)| *operations)
+}
+
+/// Returns the sorted RBAC actions of all Resource Manager operations
+pub fn rbac_actions(operations: &[Operation]) -> Vec<String> {
+    let mut actions: Vec<String> = operations
+        .iter()
+        .filter(|(_, path, _, _)| path.starts_with('/'))
+        .map(|(_, _, _, action)| action.to_string())
+        .collect();
+    actions.sort();
+    actions.dedup();
+    actions
+}
+
+/// Returns the resource provider of the operation, like `Microsoft.Network`
+pub fn provider(operation: &Operation) -> &'static str {
+    if operation.1.starts_with("https://graph.microsoft.com/") {
+        "Microsoft.Graph"
+    } else {
+        match operation.3.find('/') {
+            Some(pos) => &operation.3[..pos],
+            None => operation.3,
+        }
+    }
+}
+
+/// Returns the commands with operations of the resource provider, like `clusters`
+/// for `Microsoft.ContainerService`, ignoring the case
+pub fn provider_commands(namespace: &str) -> Vec<&'static str> {
+    COMMAND_OPERATIONS
+        .iter()
+        .filter(|(_, operations)| {
+            operations
+                .iter()
+                .any(|operation| provider(operation).eq_ignore_ascii_case(namespace))
+        })
+        .map(|(command, _)| *command)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::provider;
+    use super::provider_commands;
+    use super::rbac_actions;
+    use super::COMMAND_OPERATIONS;
+    use super::DIRECTORY_OBJECTS;
+    use super::METRICS;
+    use super::RESOURCE;
+    use super::SUBSCRIPTIONS;
+
+    #[test]
+    fn test_provider() {
+        assert_eq!("Microsoft.Insights", provider(&METRICS));
+        assert_eq!("Microsoft.Graph", provider(&DIRECTORY_OBJECTS));
+        assert_eq!("*", provider(&RESOURCE));
+    }
+
+    #[test]
+    fn test_provider_commands() {
+        let commands = provider_commands("microsoft.containerservice");
+        assert!(commands.contains(&"clusters"));
+        assert!(!commands.contains(&"costs"));
+        assert!(provider_commands("Microsoft.CostManagement").contains(&"costs"));
+        assert!(provider_commands("Microsoft.Unknown").is_empty());
+    }
+
+    #[test]
+    fn test_rbac_actions() {
+        assert_eq!(
+            vec!(
+                "Microsoft.Insights/metrics/read",
+                "Microsoft.Resources/subscriptions/read"
+            ),
+            rbac_actions(&[SUBSCRIPTIONS, METRICS, DIRECTORY_OBJECTS, SUBSCRIPTIONS])
+        );
+    }
+
+    #[test]
+    fn test_operations_match_service() {
+        let service = include_str!("service.rs");
+        for (_, operations) in COMMAND_OPERATIONS {
+            for (_, path, api_version, _) in operations.iter() {
+                if path.starts_with("https://") {
+                    assert!(service.contains(path.replace("{id}", "{}").as_str()));
+                } else if *api_version != "latest" {
+                    let last = path.rsplit('/').next().unwrap();
+                    let suffix = if last.starts_with('{') {
+                        format!("?api-version={}", api_version)
+                    } else {
+                        format!("{}?api-version={}", last, api_version)
+                    };
+                    assert!(service.contains(&suffix), "{}", suffix);
+                }
+            }
+        }
+    }
+}
@@ -1,317 +1,3467 @@
+use std::cell::RefCell;
+use std::io::stdout;
+use std::io::Write;
 use std::net::IpAddr;
+use std::sync::Mutex;
 
+use colored::ColoredString;
 use colored::Colorize;
+use env_logger::Logger;
+use log::set_boxed_logger;
+use log::set_max_level;
+use log::Level;
+use log::Log;
+use log::Metadata;
+use log::Record;
+use serde::Serialize;
+use serde_derive::Serialize;
 use serde_json::to_string;
 use serde_json::to_string_pretty;
+use serde_json::to_value;
 use serde_json::Value;
 
+use crate::cache::CacheStats;
+use crate::commands::AccountResult;
+use crate::commands::AutoscaleResult;
+use crate::commands::BastionResult;
+use crate::commands::BgpPeerResult;
+use crate::commands::ChangesResult;
 use crate::commands::ClusterResult;
+use crate::commands::ContainerAppsResult;
+use crate::commands::CostNode;
 use crate::commands::CostResult;
 use crate::commands::DnsResult;
 use crate::commands::Domain;
+use crate::commands::EffectiveRoutesResult;
+use crate::commands::EndpointResult;
+use crate::commands::ExplainResult;
+use crate::commands::FirewallResult;
+use crate::commands::IdentityResult;
+use crate::commands::ImagesResult;
 use crate::commands::IpResult;
+use crate::commands::KeyVaultAccessResult;
 use crate::commands::ListResult;
+use crate::commands::LoginResult;
+use crate::commands::LogoutResult;
+use crate::commands::MoveCheckResult;
+use crate::commands::PeersResult;
+use crate::commands::PlansResult;
+use crate::commands::PortsResult;
+use crate::commands::PrincipalUsage;
+use crate::commands::ProvidersResult;
+use crate::commands::RedisResult;
+use crate::commands::ResolveResult;
+use crate::commands::ResourcesResult;
+use crate::commands::RoleDefinitionResult;
+use crate::commands::RoutingResult;
+use crate::commands::SqlResult;
+use crate::commands::StaleResult;
+use crate::commands::StorageKeysResult;
+use crate::commands::StorageResult;
+use crate::commands::Stream;
+use crate::commands::SubscriptionInfo;
+use crate::commands::TagsResult;
+use crate::commands::TenantResult;
+use crate::commands::TokenResult;
+use crate::commands::UseResult;
+use crate::config::ConfigEntry;
+use crate::http::RateLimit;
+use crate::http::ResponseHeaders;
+use crate::http::TimingSummary;
+use crate::locale::Locale;
+use crate::money::Money;
+use crate::money::Precision;
 use crate::object::DnsRecordEntry;
 use crate::object::Identifiable;
 use crate::object::KubernetesObject;
+use crate::object::RegionInfo;
 use crate::object::Subscription;
+use crate::query::Query;
+use crate::service::Warning;
+use crate::utils::domain_to_unicode;
 use crate::utils::Result;
 
 pub trait Output {
-    fn print_list_results(&self, results: &Vec<ListResult>, id: bool) -> Result<()>;
+    fn print_list_results(
+        &self,
+        results: &Vec<ListResult>,
+        id: bool,
+        fields: Option<&[String]>,
+    ) -> Result<()>;
 
-    fn print_clusters(&self, results: &Vec<ClusterResult>, id: bool) -> Result<()>;
+    fn print_resources_results(&self, results: &Vec<ResourcesResult>, id: bool) -> Result<()>;
 
-    fn print_domains(&self, domains: &Vec<Domain>) -> Result<()>;
+    fn print_clusters(
+        &self,
+        results: &Vec<ClusterResult>,
+        id: bool,
+        fields: Option<&[String]>,
+    ) -> Result<()>;
 
-    fn print_dns_results(&self, results: &Vec<DnsResult>) -> Result<()>;
+    fn print_domains(&self, domains: &Vec<Domain>, ascii: bool, owners: bool) -> Result<()>;
 
-    fn print_ip_results(&self, results: &Vec<IpResult>) -> Result<()>;
+    fn print_dns_results(&self, results: &Vec<DnsResult>, ascii: bool) -> Result<()>;
+
+    /// Prints a single result, as one line of JSON for the JSON output
+    fn print_resolve_result(&self, result: &ResolveResult) -> Result<()>;
+
+    fn print_ip_results(&self, results: &Vec<IpResult>, fields: Option<&[String]>) -> Result<()>;
 
     fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()>;
 
-    fn print_value(&self, value: &Value) -> Result<()>;
-}
+    fn print_cost_tree(&self, nodes: &Vec<CostNode>) -> Result<()>;
+
+    fn print_endpoint_results(&self, results: &Vec<EndpointResult>, id: bool) -> Result<()>;
+
+    fn print_routing_results(&self, results: &Vec<RoutingResult>, id: bool) -> Result<()>;
+
+    fn print_effective_routes(&self, result: &EffectiveRoutesResult) -> Result<()>;
+
+    fn print_bastion_results(&self, results: &Vec<BastionResult>, id: bool) -> Result<()>;
+
+    fn print_autoscale_results(&self, results: &Vec<AutoscaleResult>, id: bool) -> Result<()>;
+
+    fn print_plans_results(&self, results: &Vec<PlansResult>, id: bool) -> Result<()>;
+
+    fn print_containerapps_results(
+        &self,
+        results: &Vec<ContainerAppsResult>,
+        id: bool,
+    ) -> Result<()>;
+
+    fn print_images_results(&self, results: &Vec<ImagesResult>, id: bool) -> Result<()>;
+
+    fn print_storage_results(&self, results: &Vec<StorageResult>, id: bool) -> Result<()>;
+
+    fn print_sql_results(&self, results: &Vec<SqlResult>, id: bool) -> Result<()>;
+
+    fn print_redis_results(&self, results: &Vec<RedisResult>, id: bool) -> Result<()>;
+
+    fn print_peers_results(&self, results: &Vec<PeersResult>, id: bool) -> Result<()>;
+
+    fn print_firewall_results(&self, results: &Vec<FirewallResult>) -> Result<()>;
+
+    fn print_identity_results(&self, results: &Vec<IdentityResult>, id: bool) -> Result<()>;
+
+    fn print_port_results(&self, results: &Vec<PortsResult>) -> Result<()>;
+
+    fn print_principal_usages(&self, usages: &Vec<PrincipalUsage>) -> Result<()>;
+
+    fn print_key_vault_access_results(&self, results: &Vec<KeyVaultAccessResult>) -> Result<()>;
+
+    fn print_storage_keys_results(&self, results: &Vec<StorageKeysResult>) -> Result<()>;
+
+    fn print_tags_results(&self, results: &Vec<TagsResult>) -> Result<()>;
+
+    fn print_changes(&self, results: &Vec<ChangesResult>) -> Result<()>;
+
+    fn print_stale_results(&self, results: &Vec<StaleResult>, id: bool) -> Result<()>;
+
+    fn print_move_check(&self, result: &MoveCheckResult, id: bool) -> Result<()>;
+
+    fn print_account(&self, result: &AccountResult) -> Result<()>;
+
+    fn print_subscriptions(&self, subscriptions: &Vec<SubscriptionInfo>) -> Result<()>;
+
+    fn print_providers(&self, results: &Vec<ProvidersResult>) -> Result<()>;
+
+    fn print_tenants(&self, tenants: &Vec<TenantResult>) -> Result<()>;
+
+    fn print_token(&self, result: &TokenResult) -> Result<()>;
+
+    fn print_use_result(&self, result: &UseResult) -> Result<()>;
+
+    fn print_explain_result(&self, result: &ExplainResult) -> Result<()>;
+
+    /// Always prints JSON, to pass to `az role definition create`
+    fn print_role_definition(&self, result: &RoleDefinitionResult) -> Result<()>;
+
+    fn print_login_result(&self, result: &LoginResult) -> Result<()>;
+
+    fn print_logout_result(&self, result: &LogoutResult) -> Result<()>;
+
+    /// Prints to stderr, so the output of the command itself is not affected
+    fn print_rate_limits(&self, rate_limits: &Vec<RateLimit>) -> Result<()>;
+
+    fn print_timings(&self, summary: &TimingSummary) -> Result<()>;
+
+    /// Prints the items left out of the results, on stderr
+    fn print_warnings(&self, warnings: &[Warning]) -> Result<()>;
+
+    fn print_config_entries(&self, entries: &Vec<ConfigEntry>) -> Result<()>;
+
+    fn print_cache_stats(&self, stats: &CacheStats) -> Result<()>;
+
+    fn print_value(&self, value: &Value) -> Result<()>;
+
+    /// Prints the result of a raw request together with the response status and headers
+    fn print_response(&self, response: Option<ResponseHeaders>, value: &Value) -> Result<()>;
+
+    /// Sets the tenants and filters the results are queried with, once they are known
+    fn set_scope(&self, scope: Scope);
+}
+
+lazy_static! {
+    static ref WARNINGS: Mutex<Vec<String>> = Mutex::new(vec![]);
+}
+
+/// Logger which keeps the logged warnings to include them in the envelope
+pub struct WarningLogger {
+    inner: Logger,
+}
+
+impl WarningLogger {
+    pub fn init(inner: Logger) -> Result<()> {
+        let max_level = inner.filter();
+        set_boxed_logger(Box::new(WarningLogger { inner }))?;
+        set_max_level(max_level);
+        Ok(())
+    }
+}
+
+impl Log for WarningLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if record.level() == Level::Warn && self.inner.matches(record) {
+            if let Ok(mut warnings) = WARNINGS.lock() {
+                warnings.push(record.args().to_string());
+            }
+        }
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Metadata wrapped around the JSON output with `--envelope`,
+/// to keep archived results self-describing
+#[derive(Debug, Serialize)]
+pub struct Envelope {
+    pub version: String,
+    pub timestamp: String,
+    pub arguments: Vec<String>,
+    /// Not set for commands which don't query Azure
+    pub scope: Option<Scope>,
+    pub warnings: Vec<String>,
+    /// Only set with `--include-headers`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<ResponseHeaders>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Scope {
+    pub tenants: Vec<String>,
+    pub subscriptions: Vec<String>,
+    pub filters: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct Enveloped<'a, T: Serialize + ?Sized> {
+    metadata: &'a Envelope,
+    result: &'a T,
+}
+
+pub struct JsonOutput {
+    envelope: Option<RefCell<Envelope>>,
+    query: Option<Query>,
+}
+
+impl JsonOutput {
+    pub fn new() -> JsonOutput {
+        JsonOutput {
+            envelope: None,
+            query: None,
+        }
+    }
+
+    pub fn with_envelope(envelope: Envelope) -> JsonOutput {
+        JsonOutput {
+            envelope: Some(RefCell::new(envelope)),
+            query: None,
+        }
+    }
+
+    /// Applies the `--query` to each result, inside the envelope
+    pub fn with_query(mut self, query: Option<Query>) -> JsonOutput {
+        self.query = query;
+        self
+    }
+
+    /// Applies the query and wraps the result in the envelope, if enabled
+    fn wrap<T: Serialize + ?Sized>(&self, result: &T) -> Result<Value> {
+        let result = match &self.query {
+            Some(query) => query.apply(&to_value(result)?),
+            None => to_value(result)?,
+        };
+        match &self.envelope {
+            Some(envelope) => {
+                let mut envelope = envelope.try_borrow_mut()?;
+                if let Ok(warnings) = WARNINGS.lock() {
+                    envelope.warnings = warnings.clone();
+                }
+                Ok(to_value(Enveloped {
+                    metadata: &envelope,
+                    result: &result,
+                })?)
+            }
+            None => Ok(result),
+        }
+    }
+}
+
+impl Output for JsonOutput {
+    fn print_list_results(
+        &self,
+        results: &Vec<ListResult>,
+        _: bool,
+        _: Option<&[String]>,
+    ) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_resources_results(&self, results: &Vec<ResourcesResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_clusters(
+        &self,
+        results: &Vec<ClusterResult>,
+        _: bool,
+        _: Option<&[String]>,
+    ) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_domains(&self, domains: &Vec<Domain>, _: bool, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(domains)?)?);
+        return Ok(());
+    }
+
+    fn print_dns_results(&self, results: &Vec<DnsResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_resolve_result(&self, result: &ResolveResult) -> Result<()> {
+        println!("{}", to_string(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_ip_results(&self, results: &Vec<IpResult>, _: Option<&[String]>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_cost_tree(&self, nodes: &Vec<CostNode>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(nodes)?)?);
+        return Ok(());
+    }
+
+    fn print_endpoint_results(&self, results: &Vec<EndpointResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_routing_results(&self, results: &Vec<RoutingResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_effective_routes(&self, result: &EffectiveRoutesResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_bastion_results(&self, results: &Vec<BastionResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_autoscale_results(&self, results: &Vec<AutoscaleResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_plans_results(&self, results: &Vec<PlansResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_containerapps_results(
+        &self,
+        results: &Vec<ContainerAppsResult>,
+        _: bool,
+    ) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_images_results(&self, results: &Vec<ImagesResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_storage_results(&self, results: &Vec<StorageResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_sql_results(&self, results: &Vec<SqlResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_redis_results(&self, results: &Vec<RedisResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_peers_results(&self, results: &Vec<PeersResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_firewall_results(&self, results: &Vec<FirewallResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_identity_results(&self, results: &Vec<IdentityResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_port_results(&self, results: &Vec<PortsResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_key_vault_access_results(&self, results: &Vec<KeyVaultAccessResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_storage_keys_results(&self, results: &Vec<StorageKeysResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_tags_results(&self, results: &Vec<TagsResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_principal_usages(&self, usages: &Vec<PrincipalUsage>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(usages)?)?);
+        return Ok(());
+    }
+
+    fn print_changes(&self, results: &Vec<ChangesResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_stale_results(&self, results: &Vec<StaleResult>, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_move_check(&self, result: &MoveCheckResult, _: bool) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_account(&self, result: &AccountResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_subscriptions(&self, subscriptions: &Vec<SubscriptionInfo>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(subscriptions)?)?);
+        return Ok(());
+    }
+
+    fn print_providers(&self, results: &Vec<ProvidersResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(results)?)?);
+        return Ok(());
+    }
+
+    fn print_tenants(&self, tenants: &Vec<TenantResult>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(tenants)?)?);
+        return Ok(());
+    }
+
+    fn print_token(&self, result: &TokenResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_use_result(&self, result: &UseResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_explain_result(&self, result: &ExplainResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_role_definition(&self, result: &RoleDefinitionResult) -> Result<()> {
+        println!("{}", to_string_pretty(result)?);
+        return Ok(());
+    }
+
+    fn print_login_result(&self, result: &LoginResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_logout_result(&self, result: &LogoutResult) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(result)?)?);
+        return Ok(());
+    }
+
+    fn print_rate_limits(&self, rate_limits: &Vec<RateLimit>) -> Result<()> {
+        eprintln!("{}", to_string_pretty(rate_limits)?);
+        return Ok(());
+    }
+
+    fn print_timings(&self, summary: &TimingSummary) -> Result<()> {
+        eprintln!("{}", to_string_pretty(summary)?);
+        return Ok(());
+    }
+
+    fn print_warnings(&self, warnings: &[Warning]) -> Result<()> {
+        eprintln!("{}", to_string_pretty(warnings)?);
+        return Ok(());
+    }
+
+    fn print_config_entries(&self, entries: &Vec<ConfigEntry>) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(entries)?)?);
+        return Ok(());
+    }
+
+    fn print_cache_stats(&self, stats: &CacheStats) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(stats)?)?);
+        return Ok(());
+    }
+
+    fn print_value(&self, value: &Value) -> Result<()> {
+        println!("{}", to_string_pretty(&self.wrap(value)?)?);
+        return Ok(());
+    }
+
+    fn print_response(&self, response: Option<ResponseHeaders>, value: &Value) -> Result<()> {
+        if let Some(envelope) = &self.envelope {
+            if let Ok(mut envelope) = envelope.try_borrow_mut() {
+                envelope.response = response;
+            }
+        }
+        return self.print_value(value);
+    }
+
+    fn set_scope(&self, scope: Scope) {
+        if let Some(envelope) = &self.envelope {
+            if let Ok(mut envelope) = envelope.try_borrow_mut() {
+                envelope.scope = Some(scope);
+            }
+        }
+    }
+}
+
+/// Prints each item of a result as one line of JSON for `-o jsonl`, like one line per
+/// subscription, flushing every line so pipelines can start before all lines are written.
+/// The `--query` is applied to each line, lines for which it returns null are left out
+pub struct JsonLinesOutput {
+    query: Option<Query>,
+}
+
+impl JsonLinesOutput {
+    pub fn new(query: Option<Query>) -> JsonLinesOutput {
+        JsonLinesOutput { query }
+    }
+
+    fn print_lines<T: Serialize>(&self, items: &[T]) -> Result<()> {
+        let stdout = stdout();
+        let mut stdout = stdout.lock();
+        for item in items {
+            let line = match &self.query {
+                Some(query) => match query.apply(&to_value(item)?) {
+                    Value::Null => continue,
+                    value => to_string(&value)?,
+                },
+                None => to_string(item)?,
+            };
+            writeln!(stdout, "{}", line)?;
+            stdout.flush()?;
+        }
+        return Ok(());
+    }
+
+    fn print_line<T: Serialize>(&self, item: &T) -> Result<()> {
+        self.print_lines(&[item])
+    }
+}
+
+impl Stream for JsonLinesOutput {
+    fn print_result(&self, result: &Value) -> Result<()> {
+        self.print_line(result)
+    }
+}
+
+impl Output for JsonLinesOutput {
+    fn print_list_results(
+        &self,
+        results: &Vec<ListResult>,
+        _: bool,
+        _: Option<&[String]>,
+    ) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_resources_results(&self, results: &Vec<ResourcesResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_clusters(
+        &self,
+        results: &Vec<ClusterResult>,
+        _: bool,
+        _: Option<&[String]>,
+    ) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_domains(&self, domains: &Vec<Domain>, _: bool, _: bool) -> Result<()> {
+        self.print_lines(domains)
+    }
+
+    fn print_dns_results(&self, results: &Vec<DnsResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_resolve_result(&self, result: &ResolveResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_ip_results(&self, results: &Vec<IpResult>, _: Option<&[String]>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_cost_tree(&self, nodes: &Vec<CostNode>) -> Result<()> {
+        self.print_lines(nodes)
+    }
+
+    fn print_endpoint_results(&self, results: &Vec<EndpointResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_routing_results(&self, results: &Vec<RoutingResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_effective_routes(&self, result: &EffectiveRoutesResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_bastion_results(&self, results: &Vec<BastionResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_autoscale_results(&self, results: &Vec<AutoscaleResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_plans_results(&self, results: &Vec<PlansResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_containerapps_results(
+        &self,
+        results: &Vec<ContainerAppsResult>,
+        _: bool,
+    ) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_images_results(&self, results: &Vec<ImagesResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_storage_results(&self, results: &Vec<StorageResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_sql_results(&self, results: &Vec<SqlResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_redis_results(&self, results: &Vec<RedisResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_peers_results(&self, results: &Vec<PeersResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_firewall_results(&self, results: &Vec<FirewallResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_identity_results(&self, results: &Vec<IdentityResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_port_results(&self, results: &Vec<PortsResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_principal_usages(&self, usages: &Vec<PrincipalUsage>) -> Result<()> {
+        self.print_lines(usages)
+    }
+
+    fn print_key_vault_access_results(&self, results: &Vec<KeyVaultAccessResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_storage_keys_results(&self, results: &Vec<StorageKeysResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_tags_results(&self, results: &Vec<TagsResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_changes(&self, results: &Vec<ChangesResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_stale_results(&self, results: &Vec<StaleResult>, _: bool) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_move_check(&self, result: &MoveCheckResult, _: bool) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_account(&self, result: &AccountResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_subscriptions(&self, subscriptions: &Vec<SubscriptionInfo>) -> Result<()> {
+        self.print_lines(subscriptions)
+    }
+
+    fn print_providers(&self, results: &Vec<ProvidersResult>) -> Result<()> {
+        self.print_lines(results)
+    }
+
+    fn print_tenants(&self, tenants: &Vec<TenantResult>) -> Result<()> {
+        self.print_lines(tenants)
+    }
+
+    fn print_token(&self, result: &TokenResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_use_result(&self, result: &UseResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_explain_result(&self, result: &ExplainResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_login_result(&self, result: &LoginResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_logout_result(&self, result: &LogoutResult) -> Result<()> {
+        self.print_line(result)
+    }
+
+    fn print_config_entries(&self, entries: &Vec<ConfigEntry>) -> Result<()> {
+        self.print_lines(entries)
+    }
+
+    fn print_cache_stats(&self, stats: &CacheStats) -> Result<()> {
+        self.print_line(stats)
+    }
+
+    fn print_role_definition(&self, result: &RoleDefinitionResult) -> Result<()> {
+        println!("{}", to_string_pretty(result)?);
+        return Ok(());
+    }
+
+    fn print_rate_limits(&self, rate_limits: &Vec<RateLimit>) -> Result<()> {
+        for rate_limit in rate_limits {
+            eprintln!("{}", to_string(rate_limit)?);
+        }
+        return Ok(());
+    }
+
+    fn print_timings(&self, summary: &TimingSummary) -> Result<()> {
+        eprintln!("{}", to_string(summary)?);
+        return Ok(());
+    }
+
+    fn print_warnings(&self, warnings: &[Warning]) -> Result<()> {
+        for warning in warnings {
+            eprintln!("{}", to_string(warning)?);
+        }
+        return Ok(());
+    }
+
+    /// Arrays, like the items of a list, are printed with one item per line
+    fn print_value(&self, value: &Value) -> Result<()> {
+        match value {
+            Value::Array(items) => self.print_lines(items),
+            value => self.print_line(value),
+        }
+    }
+
+    fn print_response(&self, _: Option<ResponseHeaders>, value: &Value) -> Result<()> {
+        self.print_value(value)
+    }
+
+    fn set_scope(&self, _: Scope) {}
+}
+
+pub struct TextOutput {
+    locale: Locale,
+    precision: Precision,
+}
+
+impl TextOutput {
+    pub fn new(locale: Locale, precision: Precision) -> Self {
+        TextOutput { locale, precision }
+    }
+
+    fn amount(&self, costs: Money) -> String {
+        costs.format(&self.locale, &self.precision)
+    }
+
+    fn costs(&self, costs: Money, currency: &str) -> String {
+        format!("{} {}", self.amount(costs), currency)
+    }
+
+    /// Prints the fields selected with `--fields` in aligned columns, without a header
+    fn print_rows(&self, rows: &FieldRows) -> Result<()> {
+        if !rows.rows.is_empty() {
+            println!("{}", rows.table().without_header().render());
+        }
+        return Ok(());
+    }
+
+    /// Internationalized domain names in Unicode, unless punycode is requested with `--ascii`
+    fn domain_name(&self, name: &str, ascii: bool) -> String {
+        if ascii {
+            name.to_owned()
+        } else {
+            domain_to_unicode(name)
+        }
+    }
+
+    fn protection(&self, protection: &[String]) -> String {
+        if protection.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", format!("[{}]", protection.join(", ")).yellow())
+        }
+    }
+
+    fn region(&self, location: &str, region: Option<&RegionInfo>) -> String {
+        let mut info = vec![location.to_owned()];
+        if let Some(region) = region {
+            if region.zones_supported {
+                info.push("zones".to_owned());
+            }
+            if let Some(paired_region) = &region.paired_region {
+                info.push(format!("pair {}", paired_region));
+            }
+        }
+        format!(" {}", info.join(", ").dimmed())
+    }
+
+    fn zones(&self, zones: &[String], region: Option<&RegionInfo>) -> String {
+        match region {
+            Some(_) if !zones.is_empty() => {
+                format!(" {}", format!("[zones {}]", zones.join(", ")).cyan())
+            }
+            Some(region) if region.zones_supported => format!(" {}", "[no zones]".yellow()),
+            _ => String::new(),
+        }
+    }
+
+    fn state(&self, state: &str, disconnected: bool) -> ColoredString {
+        if disconnected {
+            state.red()
+        } else {
+            state.green()
+        }
+    }
+
+    fn print_bgp_peer(&self, peer: &BgpPeerResult) {
+        print!("    {} {}", "bgp".dimmed(), peer.name);
+        if let Some(neighbor) = peer.neighbor.as_ref().filter(|n| **n != peer.name) {
+            print!(" {}", neighbor);
+        }
+        if let Some(asn) = peer.asn {
+            print!(" {}", format!("AS{}", asn).dimmed());
+        }
+        if let Some(state) = &peer.state {
+            print!(" {}", self.state(state, peer.disconnected));
+        }
+        if let Some(learned) = peer.learned_routes {
+            print!(" {}", format!("{} learned", learned).dimmed());
+        }
+        if let Some(advertised) = peer.advertised_routes {
+            print!(" {}", format!("{} advertised", advertised).dimmed());
+        }
+        println!();
+    }
+
+    fn print_subscription(&self, subscription: &Subscription, id: bool) {
+        print!("{}", subscription.name.red());
+        if let Some(tenant) = &subscription.tenant {
+            print!(" {}", tenant.cyan());
+        }
+        if id {
+            print!(
+                " {}",
+                format!("({})", subscription.subscription_id).dimmed()
+            );
+        }
+        println!();
+    }
+}
+
+impl Output for TextOutput {
+    fn print_list_results(
+        &self,
+        results: &Vec<ListResult>,
+        id: bool,
+        fields: Option<&[String]>,
+    ) -> Result<()> {
+        if fields.is_some() {
+            return self.print_rows(&list_rows(results, id, fields)?);
+        }
+
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for resource_group in &result.resource_groups {
+                println!(
+                    "  {}{}{}",
+                    resource_group.name.blue(),
+                    self.region(&resource_group.location, resource_group.region.as_ref()),
+                    self.protection(&resource_group.protection)
+                );
+
+                for resource in &result.resources {
+                    if resource.resource_group()? == resource_group.name {
+                        let zones = self.zones(&resource.zones, resource.region.as_ref());
+                        if id {
+                            println!(
+                                "    {} {} {} {}{}{}",
+                                resource.name,
+                                format!("({})", resource.resource_type).dimmed(),
+                                format!("({})", resource.id).dimmed(),
+                                resource.location.dimmed(),
+                                zones,
+                                self.protection(&resource.protection)
+                            );
+                        } else {
+                            println!(
+                                "    {} {} {}{}{}",
+                                resource.name,
+                                format!("({})", resource.resource_type).dimmed(),
+                                resource.location.dimmed(),
+                                zones,
+                                self.protection(&resource.protection)
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_resources_results(&self, results: &Vec<ResourcesResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for resource in &result.resources {
+                print!(
+                    "  {} {} {}",
+                    resource.name.blue(),
+                    resource.resource_group,
+                    resource.location.dimmed()
+                );
+                let mut tags: Vec<String> = resource
+                    .tags
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                tags.sort();
+                if !tags.is_empty() {
+                    print!(" {}", tags.join(" ").cyan());
+                }
+                if id {
+                    print!(" {}", format!("({})", resource.id).dimmed());
+                }
+                println!();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_clusters(
+        &self,
+        results: &Vec<ClusterResult>,
+        id: bool,
+        fields: Option<&[String]>,
+    ) -> Result<()> {
+        if fields.is_some() {
+            let rows = cluster_rows(results, id, fields, |costs, currency| {
+                self.costs(Money::from_f64(costs), currency)
+            });
+            return self.print_rows(&rows);
+        }
+
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for cluster in &result.clusters {
+                print!("  {} {}", cluster.name.blue(), cluster.version.cyan());
+                if let Some(distribution) = &cluster.distribution {
+                    print!(" {}", format!("({})", distribution).dimmed());
+                }
+                match &cluster.costs {
+                    Some(costs) => {
+                        let costs = self.costs(Money::from_f64(costs.costs), &costs.currency);
+                        println!(" {}", costs.yellow())
+                    }
+                    None => println!(),
+                }
+
+                if let Some(agent_pools) = &cluster.agent_pools {
+                    for pool in agent_pools {
+                        print!("    {}", format!("{}", pool.count).yellow());
+                        if let (Some(min), Some(max)) = (pool.min_count, pool.max_count) {
+                            print!(" {}", format!("[{}-{}]", min, max).dimmed());
+                        }
+                        print!(" {}", pool.vm_size);
+                        if id {
+                            print!(" {}", format!("({})", pool.name).dimmed());
+                        }
+                        println!();
+                    }
+                }
+
+                if let Some(objects) = &cluster.objects {
+                    for object in objects {
+                        match object {
+                            KubernetesObject::Service {
+                                metadata,
+                                service_type: _,
+                                ip_addresses,
+                            } => {
+                                let namespace = format!("{}/", metadata.namespace).dimmed();
+                                print!("    {}{}", namespace, metadata.name);
+                                for ip in ip_addresses {
+                                    let private = match ip {
+                                        IpAddr::V4(ip) => ip.is_private(),
+                                        IpAddr::V6(ip) => ip.is_loopback(),
+                                    };
+                                    if private {
+                                        print!(" {}", ip.to_string().dimmed());
+                                    } else {
+                                        print!(" {}", ip.to_string().green());
+                                    }
+                                }
+                                println!();
+                            }
+                            KubernetesObject::Deployment {
+                                metadata,
+                                target,
+                                ready,
+                            } => {
+                                let pods = format!("{}/{}", ready, target);
+                                let pods = if ready >= target {
+                                    pods.green()
+                                } else {
+                                    pods.red()
+                                };
+                                let namespace = format!("{}/", metadata.namespace).dimmed();
+                                println!("    {}{} {}", namespace, metadata.name, pods);
+                            }
+                        };
+                    }
+                }
+
+                if let Some(restarts) = &cluster.restarts {
+                    if restarts.is_empty() {
+                        println!("    {}", "no restarts".dimmed());
+                    }
+                    for namespace in restarts {
+                        let name = if namespace.storm {
+                            namespace.namespace.red()
+                        } else {
+                            namespace.namespace.normal()
+                        };
+                        print!(
+                            "    {} {} {}",
+                            "restarts".dimmed(),
+                            name,
+                            format!(
+                                "{} total, {} last hour, {} last day",
+                                namespace.total, namespace.last_hour, namespace.last_day
+                            )
+                            .yellow()
+                        );
+                        if namespace.crash_looping > 0 {
+                            print!(
+                                " {}",
+                                format!("{} crash looping", namespace.crash_looping).red()
+                            );
+                        }
+                        println!();
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_domains(&self, domains: &Vec<Domain>, ascii: bool, owners: bool) -> Result<()> {
+        for domain in domains {
+            let name = self.domain_name(&domain.name, ascii).cyan();
+            match &domain.owner {
+                Some(owner) => println!("{} {}", name, format!("[{}]", owner).yellow()),
+                None if owners => println!("{} {}", name, "[no owner]".red()),
+                None => println!("{}", name),
+            }
+
+            let arrow = "->".dimmed();
+
+            let mut depth = 0;
+            for entry in &domain.entries {
+                match entry {
+                    Some(DnsRecordEntry::CNAME(cname)) => {
+                        let cname = self.domain_name(cname, ascii);
+                        println!("{0:1$} {2} {3}", "", depth * 4, arrow, cname);
+                        depth += 1;
+                    }
+                    None => println!(
+                        "{0:1$} {2} {3}",
+                        "",
+                        depth * 4,
+                        arrow,
+                        "[recursion depth exceeded]".red()
+                    ),
+                    _ => (),
+                }
+            }
+
+            if let Some(resource_group) = &domain.resource_group {
+                println!(
+                    "{0:1$} {2} {3}",
+                    "",
+                    depth * 4,
+                    arrow,
+                    resource_group.name.blue()
+                );
+            }
+
+            for ip_address in &domain.ip_addresses {
+                println!(
+                    "{0:1$} {2} {3}",
+                    "",
+                    depth * 4,
+                    arrow,
+                    ip_address.ip_address
+                );
+
+                if let Some(resource_group) = ip_address.resource_group.as_ref() {
+                    println!(
+                        "{0:1$}     {2} {3}",
+                        "",
+                        depth * 4,
+                        arrow,
+                        resource_group.name.blue()
+                    );
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_resolve_result(&self, result: &ResolveResult) -> Result<()> {
+        print!("{}", result.hostname.cyan());
+
+        let arrow = "->".dimmed();
+        for entry in &result.entries {
+            match entry {
+                Some(DnsRecordEntry::CNAME(cname)) => print!(" {} {}", arrow, cname),
+                None => print!(" {} {}", arrow, "[recursion depth exceeded]".red()),
+                _ => (),
+            }
+        }
+
+        if result.ip_addresses.is_empty() {
+            print!(" {}", "not resolved".dimmed());
+        }
+        for ip_address in &result.ip_addresses {
+            print!(" {} {}", arrow, ip_address.ip_address);
+            if let Some(resource_group) = ip_address.resource_group.as_ref() {
+                print!(" {}", resource_group.name.blue());
+            }
+        }
+
+        if !result.zone {
+            print!(" {}", "(external)".dimmed());
+        }
+        println!();
+
+        return Ok(());
+    }
+
+    fn print_dns_results(&self, results: &Vec<DnsResult>, ascii: bool) -> Result<()> {
+        for result in results {
+            println!("{}", self.domain_name(&result.zone.name, ascii).blue());
+
+            for record in &result.records {
+                println!("  {}", self.domain_name(&record.name, ascii).cyan());
+                match &record.entry {
+                    DnsRecordEntry::A(ip_addresses) => {
+                        for ip in ip_addresses {
+                            println!("    {} {}", "A".dimmed(), ip);
+                        }
+                    }
+                    DnsRecordEntry::CNAME(cname) => println!(
+                        "    {} {}",
+                        "CNAME".dimmed(),
+                        self.domain_name(cname, ascii)
+                    ),
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_ip_results(&self, results: &Vec<IpResult>, fields: Option<&[String]>) -> Result<()> {
+        if fields.is_some() {
+            return self.print_rows(&ip_rows(results, fields));
+        }
+
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for resource_group in &result.resource_groups {
+                println!(
+                    "  {}{}",
+                    resource_group.resource_group.name.blue(),
+                    self.protection(&resource_group.resource_group.protection)
+                );
+
+                for ip in &resource_group.ip_addresses {
+                    println!("    {}{}", ip.ip_address, self.protection(&ip.protection));
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
+        let mut total = Money::default();
+        let mut total_currency = None;
+
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            let mut sum = Money::default();
+            let mut sum_currency = None;
+
+            for item in &result.costs {
+                if item.costs >= 0.01 {
+                    let name = if item.resource_group.is_empty() {
+                        "unknown".dimmed()
+                    } else {
+                        item.resource_group.blue()
+                    };
+                    let costs = self.costs(Money::from_f64(item.costs), &item.currency);
+                    println!("  {}  {}", name, costs);
+                }
+                sum += Money::from_f64(item.costs);
+                if sum_currency == None {
+                    sum_currency = Some(&item.currency);
+                }
+            }
+
+            if let Some(currency) = sum_currency {
+                println!("  {}  {}", "sum".cyan(), self.costs(sum, currency));
+                total += sum;
+                total_currency = Some(currency.clone());
+            }
+        }
+
+        if let Some(currency) = total_currency {
+            println!("{}  {}", "total".cyan(), self.costs(total, &currency));
+        }
+
+        return Ok(());
+    }
+
+    fn print_cost_tree(&self, nodes: &Vec<CostNode>) -> Result<()> {
+        fn print_nodes(output: &TextOutput, nodes: &[CostNode], depth: usize) {
+            for node in nodes {
+                let name = match node.subscription_id {
+                    Some(_) => node.name.normal(),
+                    None => node.name.blue(),
+                };
+                let costs = match &node.currency {
+                    Some(currency) => output.costs(Money::from_f64(node.costs), currency),
+                    None => "no costs".to_owned(),
+                };
+                println!("{0:1$}{2}  {3}", "", depth * 2, name, costs.yellow());
+                print_nodes(output, &node.children, depth + 1);
+            }
+        }
+
+        print_nodes(self, nodes, 0);
+        return Ok(());
+    }
+
+    fn print_endpoint_results(&self, results: &Vec<EndpointResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for endpoint in &result.endpoints {
+                if id {
+                    println!(
+                        "  {} {}",
+                        endpoint.name.blue(),
+                        format!("({})", endpoint.id).dimmed()
+                    );
+                } else {
+                    println!("  {}", endpoint.name.blue());
+                }
+
+                for connection in &endpoint.connections {
+                    let status = if connection.is_approved() {
+                        connection.status.green()
+                    } else {
+                        connection.status.red()
+                    };
+                    let target = if id {
+                        connection.target.as_str()
+                    } else {
+                        connection.target.rsplit('/').next().unwrap_or("")
+                    };
+                    print!("    {} {}", target, status);
+                    if !connection.group_ids.is_empty() {
+                        print!(
+                            " {}",
+                            format!("({})", connection.group_ids.join(", ")).dimmed()
+                        );
+                    }
+                    println!();
+                }
+
+                if endpoint.private_dns_zones.is_empty() {
+                    println!("    {}", "no private DNS zone".yellow());
+                }
+                for zone in &endpoint.private_dns_zones {
+                    let zone = if id {
+                        zone.as_str()
+                    } else {
+                        zone.rsplit('/').next().unwrap_or("")
+                    };
+                    println!("    {} {}", "->".dimmed(), zone.cyan());
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_routing_results(&self, results: &Vec<RoutingResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for table in &result.route_tables {
+                if id {
+                    println!(
+                        "  {} {}",
+                        table.name.blue(),
+                        format!("({})", table.id).dimmed()
+                    );
+                } else {
+                    println!("  {}", table.name.blue());
+                }
+                if !table.bgp_route_propagation {
+                    println!("    {}", "BGP route propagation disabled".yellow());
+                }
+
+                for route in &table.routes {
+                    print!(
+                        "    {} {} {}",
+                        route.properties.address_prefix,
+                        "->".dimmed(),
+                        route.properties.next_hop_type.cyan()
+                    );
+                    if let Some(ip) = &route.properties.next_hop_ip_address {
+                        print!(" {}", ip);
+                    }
+                    println!(" {}", format!("({})", route.name).dimmed());
+                }
+
+                for subnet in &table.subnets {
+                    let subnet = if id {
+                        subnet.as_str()
+                    } else {
+                        subnet.rsplit('/').next().unwrap_or("")
+                    };
+                    println!("    {} {}", "subnet".dimmed(), subnet);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_effective_routes(&self, result: &EffectiveRoutesResult) -> Result<()> {
+        println!("{}", result.network_interface.blue());
+
+        for route in &result.routes {
+            let state = if route.state.eq_ignore_ascii_case("Active") {
+                route.state.green()
+            } else {
+                route.state.dimmed()
+            };
+            println!(
+                "  {} {} {} {} {} {}",
+                route.address_prefix.join(","),
+                "->".dimmed(),
+                route.next_hop_type.cyan(),
+                route.next_hop_ip_address.join(","),
+                state,
+                format!("({})", route.source).dimmed()
+            );
+        }
+
+        return Ok(());
+    }
+
+    fn print_bastion_results(&self, results: &Vec<BastionResult>, id: bool) -> Result<()> {
+        fn name(id: &str) -> &str {
+            id.rsplit('/').next().unwrap_or("")
+        }
+
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for bastion in &result.bastions {
+                print!("  {}", bastion.name.blue());
+                if let Some(sku) = &bastion.sku {
+                    print!(" {}", sku.cyan());
+                }
+                if id {
+                    print!(" {}", format!("({})", bastion.id).dimmed());
+                }
+                println!();
+                for network in &bastion.virtual_networks {
+                    let network = if id { network.as_str() } else { name(network) };
+                    println!("    {} {}", "->".dimmed(), network);
+                }
+            }
+
+            for machine in &result.exposed_machines {
+                let virtual_machine = if id {
+                    machine.virtual_machine.as_str()
+                } else {
+                    name(&machine.virtual_machine)
+                };
+                print!("  {}", virtual_machine.red());
+                if let Some(ip) = &machine.ip_address {
+                    print!(" {}", ip);
+                }
+                println!(" {}", "not covered by Bastion".dimmed());
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_autoscale_results(&self, results: &Vec<AutoscaleResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for setting in &result.settings {
+                let target = if id {
+                    setting.target.as_str()
+                } else {
+                    setting.target.rsplit('/').next().unwrap_or("")
+                };
+                print!("  {} {} {}", setting.name.blue(), "->".dimmed(), target);
+                if !setting.enabled {
+                    print!(" {}", "disabled".yellow());
+                }
+                if id {
+                    print!(" {}", format!("({})", setting.id).dimmed());
+                }
+                println!();
+
+                for profile in &setting.profiles {
+                    println!(
+                        "    {} {}",
+                        profile.name,
+                        format!(
+                            "(min {}, max {}, default {})",
+                            profile.minimum, profile.maximum, profile.default
+                        )
+                        .cyan()
+                    );
+                    for rule in &profile.rules {
+                        println!("      {}", rule.dimmed());
+                    }
+                }
+            }
+
+            for resource in &result.unscaled {
+                print!("  {}", resource.name.red());
+                if let (Some(min), Some(max)) = (resource.cpu_min, resource.cpu_max) {
+                    print!(" {}", format!("cpu {:.0}%-{:.0}%", min, max).dimmed());
+                }
+                if resource.variable {
+                    print!(" {}", "highly variable".yellow());
+                }
+                if id {
+                    print!(" {}", format!("({})", resource.id).dimmed());
+                }
+                println!(" {}", "no autoscale".dimmed());
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_plans_results(&self, results: &Vec<PlansResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for plan in &result.plans {
+                print!("  {}", plan.name.blue());
+                if let Some(sku) = &plan.sku {
+                    print!(" {}", sku.cyan());
+                }
+                print!(
+                    " {}",
+                    format!("({} instances, {} apps)", plan.instances, plan.apps.len()).dimmed()
+                );
+                if plan.underused {
+                    print!(" {}", "underused".yellow());
+                }
+                if id {
+                    print!(" {}", format!("({})", plan.id).dimmed());
+                }
+                println!();
+
+                for app in &plan.apps {
+                    match &app.state {
+                        Some(state) if state != "Running" => {
+                            println!("    {} {}", app.name, state.to_lowercase().dimmed())
+                        }
+                        _ => println!("    {}", app.name),
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_containerapps_results(
+        &self,
+        results: &Vec<ContainerAppsResult>,
+        id: bool,
+    ) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for environment in &result.environments {
+                print!(
+                    "  {} {}",
+                    environment.name.blue(),
+                    environment.location.cyan()
+                );
+                if let Some(static_ip) = &environment.static_ip {
+                    print!(" {}", static_ip);
+                }
+                if id {
+                    print!(" {}", format!("({})", environment.id).dimmed());
+                }
+                println!();
+
+                for app in &environment.apps {
+                    print!("    {}", app.name.blue());
+                    if let Some(fqdn) = &app.fqdn {
+                        print!(" {}", fqdn);
+                        if !app.external {
+                            print!(" {}", "internal".dimmed());
+                        }
+                    }
+                    print!(
+                        " {}",
+                        format!(
+                            "({}-{} replicas)",
+                            app.min_replicas.unwrap_or(0),
+                            app.max_replicas
+                                .map_or("?".to_owned(), |max| max.to_string())
+                        )
+                        .dimmed()
+                    );
+                    for rule in &app.scale_rules {
+                        print!(" {}", format!("{}:{}", rule.name, rule.rule_type).yellow());
+                    }
+                    if id {
+                        print!(" {}", format!("({})", app.id).dimmed());
+                    }
+                    println!();
+
+                    for revision in &app.revisions {
+                        print!(
+                            "      {} {} {}",
+                            revision.name,
+                            format!("{} replicas", revision.replicas).green(),
+                            format!("{}%", revision.traffic_weight).dimmed()
+                        );
+                        match &revision.running_state {
+                            Some(state) if state != "Running" && state != "RunningAtMaxScale" => {
+                                print!(" {}", state.to_lowercase().yellow())
+                            }
+                            _ => (),
+                        }
+                        println!();
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_storage_results(&self, results: &Vec<StorageResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for account in &result.accounts {
+                print!("  {}", account.name.blue());
+                if let Some(sku) = &account.sku {
+                    print!(" {}", sku.cyan());
+                }
+                if let Some(access_tier) = &account.access_tier {
+                    print!(" {}", access_tier.to_lowercase().dimmed());
+                }
+                if let Some(used_gb) = account.used_gb {
+                    print!(" {}", format!("{:.2} GB", used_gb).green());
+                }
+                if let Some(transactions) = account.transactions {
+                    print!(
+                        " {}",
+                        format!("({:.0} transactions)", transactions).dimmed()
+                    );
+                }
+                if id {
+                    print!(" {}", format!("({})", account.id).dimmed());
+                }
+                println!();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_peers_results(&self, results: &Vec<PeersResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for circuit in &result.circuits {
+                print!("  {}", circuit.name.blue());
+                if let Some(provider) = &circuit.provider {
+                    print!(" {}", provider.cyan());
+                }
+                if let Some(location) = &circuit.peering_location {
+                    print!(" {}", location);
+                }
+                if let Some(bandwidth) = circuit.bandwidth_mbps {
+                    print!(" {}", format!("{} Mbps", bandwidth).dimmed());
+                }
+                if let Some(state) = &circuit.state {
+                    print!(" {}", self.state(state, circuit.disconnected));
+                }
+                if id {
+                    print!(" {}", format!("({})", circuit.id).dimmed());
+                }
+                println!();
+                for peer in &circuit.peers {
+                    self.print_bgp_peer(peer);
+                }
+            }
+
+            for gateway in &result.gateways {
+                print!("  {}", gateway.name.blue());
+                if let Some(gateway_type) = &gateway.gateway_type {
+                    print!(" {}", gateway_type.cyan());
+                }
+                if id {
+                    print!(" {}", format!("({})", gateway.id).dimmed());
+                }
+                println!();
+                for connection in &gateway.connections {
+                    print!("    {}", connection.name);
+                    if let Some(connection_type) = &connection.connection_type {
+                        print!(" {}", format!("({})", connection_type).dimmed());
+                    }
+                    if let Some(status) = &connection.status {
+                        print!(" {}", self.state(status, connection.disconnected));
+                    }
+                    println!();
+                }
+                for peer in &gateway.peers {
+                    self.print_bgp_peer(peer);
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_sql_results(&self, results: &Vec<SqlResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for database in &result.databases {
+                print!("  {}/{}", database.server, database.name.blue());
+                if let Some(sku) = &database.sku {
+                    print!(" {}", sku.cyan());
+                }
+                match (database.used_gb, database.max_size_gb) {
+                    (Some(used), Some(max)) => print!(" {:.2}/{:.0} GB", used, max),
+                    (Some(used), None) => print!(" {:.2} GB", used),
+                    _ => (),
+                }
+                if let Some(utilization) = database.utilization_max {
+                    print!(" {}", format!("(max {:.0}%)", utilization).dimmed());
+                }
+                if database.overprovisioned {
+                    print!(" {}", "over-provisioned".yellow());
+                }
+                if id {
+                    print!(" {}", format!("({})", database.id).dimmed());
+                }
+                println!();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_redis_results(&self, results: &Vec<RedisResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for cache in &result.caches {
+                print!(
+                    "  {} {} {}",
+                    cache.name.blue(),
+                    cache.sku.cyan(),
+                    cache.size
+                );
+                if let Some(size_gb) = cache.size_gb {
+                    print!(" {}", format!("({} GB)", size_gb).dimmed());
+                }
+                if let Some(version) = &cache.version {
+                    print!(" {}", version);
+                }
+                if let Some(tls) = &cache.minimum_tls_version {
+                    print!(" {}", format!("TLS {}", tls).dimmed());
+                }
+                if cache.non_ssl_port {
+                    print!(" {}", "non-SSL port".yellow());
+                }
+                if cache.public_network_access.as_deref() == Some("Enabled") {
+                    print!(" {}", "public".dimmed());
+                }
+                if !cache.retiring.is_empty() {
+                    print!(
+                        " {}",
+                        format!("retiring: {}", cache.retiring.join(", ")).red()
+                    );
+                }
+                if id {
+                    print!(" {}", format!("({})", cache.id).dimmed());
+                }
+                println!();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_images_results(&self, results: &Vec<ImagesResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for gallery in &result.galleries {
+                print!("  {}", gallery.name.blue());
+                if id {
+                    print!(" {}", format!("({})", gallery.id).dimmed());
+                }
+                println!();
+
+                for definition in &gallery.definitions {
+                    print!("    {}", definition.name);
+                    if let Some(identifier) = &definition.identifier {
+                        print!(" {}", identifier.cyan());
+                    }
+                    if let Some(os_type) = &definition.os_type {
+                        print!(" {}", format!("({})", os_type).dimmed());
+                    }
+                    if definition.expired {
+                        print!(" {}", "end of life".red());
+                    }
+                    println!();
+
+                    for version in &definition.versions {
+                        print!("      {}", version.name);
+                        if !version.regions.is_empty() {
+                            print!(" {}", version.regions.join(", ").dimmed());
+                        }
+                        if let Some(end_of_life) = &version.end_of_life {
+                            let end_of_life = format!("(end of life {})", end_of_life);
+                            if version.expired {
+                                print!(" {}", end_of_life.red());
+                            } else {
+                                print!(" {}", end_of_life.dimmed());
+                            }
+                        }
+                        println!();
+                    }
+                }
+            }
+
+            for image in &result.managed_images {
+                print!("  {} {}", image.name.blue(), image.location.dimmed());
+                if let Some(os_type) = &image.os_type {
+                    print!(" {}", format!("({})", os_type).dimmed());
+                }
+                if id {
+                    print!(" {}", format!("({})", image.id).dimmed());
+                }
+                println!();
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_firewall_results(&self, results: &Vec<FirewallResult>) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for firewall in &result.firewalls {
+                match &firewall.policy {
+                    Some(policy) => println!(
+                        "  {} {}",
+                        firewall.name.blue(),
+                        format!("({})", policy.rsplit('/').next().unwrap_or("")).dimmed()
+                    ),
+                    None => println!("  {}", firewall.name.blue()),
+                }
+
+                let mut collection = None;
+                for rule in &firewall.rules {
+                    let current = (&rule.group, &rule.collection);
+                    if collection != Some(current) {
+                        let action = if rule.action.eq_ignore_ascii_case("Deny") {
+                            rule.action.red()
+                        } else {
+                            rule.action.green()
+                        };
+                        println!(
+                            "    {} {} {}",
+                            format!("[{}]", rule.priority).dimmed(),
+                            rule.collection.cyan(),
+                            action
+                        );
+                        collection = Some(current);
+                    }
+                    println!(
+                        "      {} {} {} {} {}",
+                        rule.name,
+                        rule.sources.join(",").dimmed(),
+                        "->".dimmed(),
+                        rule.destinations.join(","),
+                        rule.ports.join(",").yellow()
+                    );
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_identity_results(&self, results: &Vec<IdentityResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for identity in &result.identities {
+                print!(
+                    "  {} {}",
+                    identity.name.blue(),
+                    format!("({})", identity.identity_type).dimmed()
+                );
+                if id {
+                    print!(" {}", format!("({})", identity.principal_id).dimmed());
+                }
+                println!();
+
+                for role in &identity.roles {
+                    println!("    {} {}", role.role.cyan(), role.scope.dimmed());
+                }
+
+                if identity.identity_type == "UserAssigned" {
+                    if identity.resources.is_empty() {
+                        println!("    {}", "not used by any resource".yellow());
+                    }
+                    for resource in &identity.resources {
+                        let resource = if id {
+                            resource.as_str()
+                        } else {
+                            resource.rsplit('/').next().unwrap_or("")
+                        };
+                        println!("    {} {}", "->".dimmed(), resource);
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_port_results(&self, results: &Vec<PortsResult>) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for exposure in &result.exposures {
+                println!(
+                    "  {} {}",
+                    exposure.ip_address.ip_address.blue(),
+                    format!("({})", exposure.network_interface).dimmed()
+                );
+                if exposure.network_security_groups.is_empty() {
+                    println!("    {}", "no network security group".dimmed());
+                }
+                println!("    {}", exposure.ports.join(" ").yellow());
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_key_vault_access_results(&self, results: &Vec<KeyVaultAccessResult>) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for vault in &result.key_vaults {
+                if vault.rbac_authorization {
+                    println!("  {} {}", vault.name.blue(), "(RBAC)".dimmed());
+                } else {
+                    println!("  {}", vault.name.blue());
+                }
+
+                for principal in &vault.principals {
+                    let name = match &principal.display_name {
+                        Some(name) => name.normal(),
+                        None => principal.id.dimmed(),
+                    };
+                    print!("    {}", name);
+                    if let Some(principal_type) = &principal.principal_type {
+                        print!(" {}", format!("({})", principal_type).dimmed());
+                    }
+                    println!(" {}", principal.source.cyan());
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_storage_keys_results(&self, results: &Vec<StorageKeysResult>) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for account in &result.accounts {
+                if account.shared_key_access {
+                    println!(
+                        "  {} {}",
+                        account.name.blue(),
+                        format!("(max. {} days)", account.max_age_days).dimmed()
+                    );
+                } else {
+                    println!(
+                        "  {} {}",
+                        account.name.blue(),
+                        "(shared key access disabled)".green()
+                    );
+                }
+
+                for key in &account.keys {
+                    match (&key.creation_time, key.age_days) {
+                        (Some(created), Some(age)) => {
+                            let age = format!("{} days", age);
+                            let age = if key.expired { age.red() } else { age.normal() };
+                            println!("    {} {} {}", key.name, age, created.dimmed());
+                        }
+                        _ => println!("    {} {}", key.name, "creation time unknown".yellow()),
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_tags_results(&self, results: &Vec<TagsResult>) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for owner in &result.owners {
+                match &owner.owner {
+                    Some(name) => println!("  {}", name.blue()),
+                    None => println!("  {}", "no owner".red()),
+                }
+                for resource in &owner.resources {
+                    println!(
+                        "    {} {}",
+                        resource.name,
+                        format!("({})", resource.resource_type).dimmed()
+                    );
+                    for violation in &resource.violations {
+                        println!("      {}", violation.yellow());
+                    }
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_principal_usages(&self, usages: &Vec<PrincipalUsage>) -> Result<()> {
+        for usage in usages {
+            let name = match &usage.display_name {
+                Some(name) => name.blue(),
+                None => "unknown".red(),
+            };
+            match &usage.app_id {
+                Some(app_id) => println!("{} {}", name, format!("({})", app_id).dimmed()),
+                None => println!("{}", name),
+            }
+
+            if !usage.owners.is_empty() {
+                println!("  {} {}", "owners".dimmed(), usage.owners.join(", ").cyan());
+            }
+
+            for reference in &usage.references {
+                println!("  {} {}", reference.source.dimmed(), reference.resource);
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_changes(&self, results: &Vec<ChangesResult>) -> Result<()> {
+        for result in results {
+            println!("{}", result.caller.blue());
+            for change in &result.changes {
+                let name = change.resource.rsplit('/').next().unwrap_or("");
+                let prefix = match change.change_type.as_str() {
+                    "created" => "+".green(),
+                    "deleted" => "-".red(),
+                    _ => "~".yellow(),
+                };
+                println!(
+                    "  {} {} {} {}",
+                    prefix,
+                    name,
+                    format!("({}, {})", change.resource_type, change.subscription).dimmed(),
+                    change.last_change.dimmed()
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_stale_results(&self, results: &Vec<StaleResult>, id: bool) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, id);
+
+            for resource in &result.resources {
+                print!(
+                    "  {} {} {}",
+                    resource.name.blue(),
+                    format!("({})", resource.resource_type).dimmed(),
+                    resource.last_modified.yellow()
+                );
+                if id {
+                    print!(" {}", format!("({})", resource.id).dimmed());
+                }
+                println!();
+                for hint in &resource.hints {
+                    println!("    {}", hint.red());
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_move_check(&self, result: &MoveCheckResult, id: bool) -> Result<()> {
+        println!(
+            "{} {} {}",
+            result.source.blue(),
+            "->".dimmed(),
+            result.target.blue()
+        );
+
+        for error in &result.errors {
+            println!("  {}", error.red());
+        }
+
+        for resource in &result.resources {
+            let name = if id {
+                resource.id.as_str()
+            } else {
+                resource.name.as_str()
+            };
+            if resource.movable {
+                println!(
+                    "  {} {} {}",
+                    "ok".green(),
+                    name,
+                    format!("({})", resource.resource_type).dimmed()
+                );
+            } else {
+                println!(
+                    "  {} {} {}",
+                    "blocked".red(),
+                    name,
+                    format!("({})", resource.resource_type).dimmed()
+                );
+                for reason in &resource.reasons {
+                    println!("    {}", reason.dimmed());
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_account(&self, result: &AccountResult) -> Result<()> {
+        match &result.user {
+            Some(user) => println!("{} {}", "user".blue(), user),
+            None => println!("{} {}", "user".blue(), "not logged in".yellow()),
+        }
+        println!("{} {}", "tenant".blue(), result.tenant);
+        if let Some(expires_on) = &result.expires_on {
+            if result.expired {
+                println!("{} {} {}", "expires".blue(), expires_on, "expired".yellow());
+            } else {
+                println!("{} {}", "expires".blue(), expires_on);
+            }
+        }
+        if let Some(subscription) = &result.subscription {
+            println!(
+                "{} {} {}",
+                "subscription".blue(),
+                subscription.name,
+                format!("({})", subscription.id).dimmed()
+            );
+        }
+        return Ok(());
+    }
+
+    fn print_subscriptions(&self, subscriptions: &Vec<SubscriptionInfo>) -> Result<()> {
+        for subscription in subscriptions {
+            print!("{}", subscription.name.red());
+            if let Some(tenant) = &subscription.tenant {
+                print!(" {}", tenant.cyan());
+            }
+            print!(
+                " {}",
+                format!("({})", subscription.subscription_id).dimmed()
+            );
+            match subscription.state.as_deref() {
+                Some("Enabled") | None => (),
+                Some(state) => print!(" {}", state.yellow()),
+            }
+            println!();
+
+            if !subscription.management_groups.is_empty() {
+                println!(
+                    "  {} {}",
+                    "management groups".blue(),
+                    subscription.management_groups.join(" / ")
+                );
+            }
+            if let Some(tenant_id) = &subscription.tenant_id {
+                println!("  {} {}", "tenant".blue(), tenant_id);
+            }
+            if let Some(spending_limit) = &subscription.spending_limit {
+                println!("  {} {}", "spending limit".blue(), spending_limit);
+            }
+            if let Some(quota_id) = &subscription.quota_id {
+                println!("  {} {}", "offer".blue(), quota_id);
+            }
+            if !subscription.tags.is_empty() {
+                let tags: Vec<String> = subscription
+                    .tags
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect();
+                println!("  {} {}", "tags".blue(), tags.join(" ").cyan());
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_providers(&self, results: &Vec<ProvidersResult>) -> Result<()> {
+        for result in results {
+            self.print_subscription(&result.subscription, false);
+
+            for provider in &result.providers {
+                let state = &provider.registration_state;
+                let state = match state.as_str() {
+                    "Registered" => state.green(),
+                    _ if provider.missing => state.red(),
+                    "Registering" => state.yellow(),
+                    _ => state.dimmed(),
+                };
+                if provider.required_by.is_empty() {
+                    println!("  {} {}", provider.namespace.blue(), state);
+                } else {
+                    let commands = format!("({})", provider.required_by.join(", "));
+                    println!(
+                        "  {} {} {}",
+                        provider.namespace.blue(),
+                        state,
+                        commands.dimmed()
+                    );
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_tenants(&self, tenants: &Vec<TenantResult>) -> Result<()> {
+        for tenant in tenants {
+            print!("{}", tenant.id);
+            if let Some(name) = &tenant.name {
+                print!(" {}", name.blue());
+            }
+            if let Some(domain) = &tenant.default_domain {
+                print!(" {}", domain.dimmed());
+            }
+            if tenant.current {
+                print!(" {}", "current".cyan());
+            }
+            println!();
+        }
+        return Ok(());
+    }
+
+    /// Prints only the token, so it can be used in scripts
+    fn print_token(&self, result: &TokenResult) -> Result<()> {
+        println!("{}", result.access_token);
+        return Ok(());
+    }
+
+    fn print_use_result(&self, result: &UseResult) -> Result<()> {
+        println!(
+            "{} {}",
+            result.name.blue(),
+            format!("({})", result.id).dimmed()
+        );
+        return Ok(());
+    }
+
+    fn print_explain_result(&self, result: &ExplainResult) -> Result<()> {
+        if result.operations.is_empty() {
+            println!("{}", "No REST operations".dimmed());
+            return Ok(());
+        }
+
+        for operation in &result.operations {
+            println!(
+                "{} {} {}",
+                operation.method.cyan(),
+                operation.path,
+                format!("({})", operation.api_version).dimmed()
+            );
+            println!("  {}", operation.action.blue());
+        }
+
+        if !result.actions.is_empty() {
+            println!();
+            println!("{}", "Required actions".red());
+            for action in &result.actions {
+                println!("  {}", action);
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn print_role_definition(&self, result: &RoleDefinitionResult) -> Result<()> {
+        println!("{}", to_string_pretty(result)?);
+        return Ok(());
+    }
+
+    fn print_login_result(&self, result: &LoginResult) -> Result<()> {
+        println!(
+            "{} {} {}",
+            result.user.blue(),
+            format!("({})", result.tenant).dimmed(),
+            format!("valid until {}", result.expires_on).dimmed()
+        );
+        return Ok(());
+    }
+
+    fn print_logout_result(&self, result: &LogoutResult) -> Result<()> {
+        let tenant = match &result.tenant {
+            Some(tenant) => tenant.as_str(),
+            None => "all tenants",
+        };
+        println!(
+            "Removed {} tokens {}",
+            result.removed,
+            format!("({})", tenant).dimmed()
+        );
+        return Ok(());
+    }
+
+    fn print_rate_limits(&self, rate_limits: &Vec<RateLimit>) -> Result<()> {
+        let mut rate_limits = rate_limits.clone();
+        rate_limits
+            .sort_by(|a, b| (&a.subscription, a.remaining).cmp(&(&b.subscription, b.remaining)));
+
+        eprintln!("Remaining request quotas:");
+        if rate_limits.is_empty() {
+            eprintln!("  {}", "no rate limit headers received".dimmed());
+        }
+        for limit in &rate_limits {
+            let subscription = match &limit.subscription {
+                Some(subscription) => subscription.as_str(),
+                None => "tenant",
+            };
+            eprintln!(
+                "  {} {} {}",
+                subscription.red(),
+                limit.quota,
+                limit.remaining.to_string().cyan()
+            );
+        }
+        return Ok(());
+    }
+
+    fn print_timings(&self, summary: &TimingSummary) -> Result<()> {
+        eprintln!(
+            "{} requests in {} ms, {} ms total request time, {} failed",
+            summary.requests, summary.command_ms, summary.total_ms, summary.failed
+        );
+        if !summary.slowest.is_empty() {
+            eprintln!("Slowest requests:");
+        }
+        for timing in &summary.slowest {
+            let status = match timing.status {
+                Some(status) if status >= 400 => status.to_string().red(),
+                Some(status) => status.to_string().green(),
+                None => "failed".red(),
+            };
+            eprintln!(
+                "  {} {} {} {}",
+                format!("{:>6} ms", timing.duration_ms).cyan(),
+                status,
+                timing.method.dimmed(),
+                timing.url
+            );
+        }
+        return Ok(());
+    }
+
+    fn print_warnings(&self, warnings: &[Warning]) -> Result<()> {
+        for warning in warnings {
+            let item = match &warning.id {
+                Some(id) => id.to_owned(),
+                None => to_string(&warning.item)?,
+            };
+            eprintln!(
+                "{} {}, skipped {}",
+                "warning:".yellow(),
+                warning.message,
+                item.dimmed()
+            );
+        }
+        return Ok(());
+    }
+
+    fn print_config_entries(&self, entries: &Vec<ConfigEntry>) -> Result<()> {
+        for entry in entries {
+            println!("{} = {}", entry.key.blue(), entry.value);
+        }
+        return Ok(());
+    }
+
+    fn print_cache_stats(&self, stats: &CacheStats) -> Result<()> {
+        let requests = stats.hits + stats.misses;
+        let hit_rate = if requests > 0 {
+            stats.hits as f64 * 100.0 / requests as f64
+        } else {
+            0.0
+        };
+        println!("{} {}", "path".blue(), stats.path);
+        println!("{} {}", "entries".blue(), stats.entries);
+        println!("{} {} {}", "size".blue(), stats.size, "bytes".dimmed());
+        println!(
+            "{} {} {}",
+            "hits".blue(),
+            stats.hits,
+            format!("({:.1}% of {} requests)", hit_rate, requests).dimmed()
+        );
+        println!("{} {}", "misses".blue(), stats.misses);
+        return Ok(());
+    }
+
+    fn print_value(&self, value: &Value) -> Result<()> {
+        println!("{}", to_string(value)?);
+        return Ok(());
+    }
+
+    fn print_response(&self, response: Option<ResponseHeaders>, value: &Value) -> Result<()> {
+        if let Some(response) = response {
+            println!("{}", format!("HTTP {}", response.status).blue());
+            for (name, value) in &response.headers {
+                println!("{}: {}", name.cyan(), value);
+            }
+            println!();
+        }
+        return self.print_value(value);
+    }
+
+    fn set_scope(&self, _: Scope) {}
+}
+
+/// Prints one line per violation, independent of the output format
+pub fn print_tags_csv(results: &[TagsResult]) -> Result<()> {
+    println!("subscription,owner,id,name,resourceType,violation");
+    for result in results {
+        for owner in &result.owners {
+            for resource in &owner.resources {
+                for violation in &resource.violations {
+                    let fields = [
+                        result.subscription.name.as_str(),
+                        owner.owner.as_deref().unwrap_or(""),
+                        &resource.id,
+                        &resource.name,
+                        &resource.resource_type,
+                        violation,
+                    ];
+                    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+                    println!("{}", fields.join(","));
+                }
+            }
+        }
+    }
+    return Ok(());
+}
+
+/// Rows of cells, printed with aligned columns below a header
+struct Table {
+    headers: Vec<String>,
+    /// Indices of the columns aligned to the right, like amounts
+    right_aligned: Vec<usize>,
+    rows: Vec<Vec<String>>,
+    header: bool,
+}
+
+impl Table {
+    fn new(headers: Vec<&str>) -> Self {
+        Table {
+            headers: headers.into_iter().map(str::to_owned).collect(),
+            right_aligned: vec![],
+            rows: vec![],
+            header: true,
+        }
+    }
+
+    fn align_right(mut self, column: usize) -> Self {
+        self.right_aligned.push(column);
+        self
+    }
+
+    /// Only prints the rows, still aligned to the width of the headers
+    fn without_header(mut self) -> Self {
+        self.header = false;
+        self
+    }
+
+    fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    fn render(&self) -> String {
+        let header = &self.headers;
+        let mut widths: Vec<usize> = header.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+
+        let mut lines = vec![];
+        let header_rows = if self.header {
+            vec![header, &rule]
+        } else {
+            vec![]
+        };
+        for row in header_rows.into_iter().chain(&self.rows) {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, width)| {
+                    let cell = row.get(column).map(String::as_str).unwrap_or("");
+                    if self.right_aligned.contains(&column) {
+                        format!("{:>width$}", cell, width = width)
+                    } else {
+                        format!("{:width$}", cell, width = width)
+                    }
+                })
+                .collect();
+            lines.push(cells.join("  ").trim_end().to_owned());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Prints the results of `list`, `ip`, `costs` and `clusters` as tables for `-o table`,
+/// all other results like the text output
+pub struct TableOutput {
+    text: TextOutput,
+}
+
+impl TableOutput {
+    pub fn new(locale: Locale, precision: Precision) -> Self {
+        TableOutput {
+            text: TextOutput::new(locale, precision),
+        }
+    }
+}
+
+impl Output for TableOutput {
+    fn print_list_results(
+        &self,
+        results: &Vec<ListResult>,
+        id: bool,
+        fields: Option<&[String]>,
+    ) -> Result<()> {
+        println!("{}", list_rows(results, id, fields)?.table().render());
+        return Ok(());
+    }
+
+    fn print_clusters(
+        &self,
+        results: &Vec<ClusterResult>,
+        id: bool,
+        fields: Option<&[String]>,
+    ) -> Result<()> {
+        let rows = cluster_rows(results, id, fields, |costs, currency| {
+            self.text.costs(Money::from_f64(costs), currency)
+        });
+        println!("{}", rows.table().render());
+        return Ok(());
+    }
+
+    fn print_ip_results(&self, results: &Vec<IpResult>, fields: Option<&[String]>) -> Result<()> {
+        println!("{}", ip_rows(results, fields).table().render());
+        return Ok(());
+    }
+
+    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
+        let mut table =
+            Table::new(vec!["SUBSCRIPTION", "RESOURCE GROUP", "COSTS", "CURRENCY"]).align_right(2);
+        for result in results {
+            for item in &result.costs {
+                if item.costs >= 0.01 {
+                    table.add_row(vec![
+                        result.subscription.name.clone(),
+                        item.resource_group.clone(),
+                        self.text.amount(Money::from_f64(item.costs)),
+                        item.currency.clone(),
+                    ]);
+                }
+            }
+        }
+        println!("{}", table.render());
+        return Ok(());
+    }
+
+    fn print_cost_tree(&self, nodes: &Vec<CostNode>) -> Result<()> {
+        self.text.print_cost_tree(nodes)
+    }
+
+    fn print_resources_results(&self, results: &Vec<ResourcesResult>, id: bool) -> Result<()> {
+        self.text.print_resources_results(results, id)
+    }
+
+    fn print_domains(&self, domains: &Vec<Domain>, ascii: bool, owners: bool) -> Result<()> {
+        self.text.print_domains(domains, ascii, owners)
+    }
+
+    fn print_dns_results(&self, results: &Vec<DnsResult>, ascii: bool) -> Result<()> {
+        self.text.print_dns_results(results, ascii)
+    }
+
+    fn print_resolve_result(&self, result: &ResolveResult) -> Result<()> {
+        self.text.print_resolve_result(result)
+    }
+
+    fn print_endpoint_results(&self, results: &Vec<EndpointResult>, id: bool) -> Result<()> {
+        self.text.print_endpoint_results(results, id)
+    }
+
+    fn print_routing_results(&self, results: &Vec<RoutingResult>, id: bool) -> Result<()> {
+        self.text.print_routing_results(results, id)
+    }
+
+    fn print_effective_routes(&self, result: &EffectiveRoutesResult) -> Result<()> {
+        self.text.print_effective_routes(result)
+    }
+
+    fn print_bastion_results(&self, results: &Vec<BastionResult>, id: bool) -> Result<()> {
+        self.text.print_bastion_results(results, id)
+    }
+
+    fn print_autoscale_results(&self, results: &Vec<AutoscaleResult>, id: bool) -> Result<()> {
+        self.text.print_autoscale_results(results, id)
+    }
+
+    fn print_plans_results(&self, results: &Vec<PlansResult>, id: bool) -> Result<()> {
+        self.text.print_plans_results(results, id)
+    }
+
+    fn print_containerapps_results(
+        &self,
+        results: &Vec<ContainerAppsResult>,
+        id: bool,
+    ) -> Result<()> {
+        self.text.print_containerapps_results(results, id)
+    }
+
+    fn print_images_results(&self, results: &Vec<ImagesResult>, id: bool) -> Result<()> {
+        self.text.print_images_results(results, id)
+    }
+
+    fn print_storage_results(&self, results: &Vec<StorageResult>, id: bool) -> Result<()> {
+        self.text.print_storage_results(results, id)
+    }
+
+    fn print_sql_results(&self, results: &Vec<SqlResult>, id: bool) -> Result<()> {
+        self.text.print_sql_results(results, id)
+    }
+
+    fn print_redis_results(&self, results: &Vec<RedisResult>, id: bool) -> Result<()> {
+        self.text.print_redis_results(results, id)
+    }
+
+    fn print_peers_results(&self, results: &Vec<PeersResult>, id: bool) -> Result<()> {
+        self.text.print_peers_results(results, id)
+    }
+
+    fn print_firewall_results(&self, results: &Vec<FirewallResult>) -> Result<()> {
+        self.text.print_firewall_results(results)
+    }
+
+    fn print_identity_results(&self, results: &Vec<IdentityResult>, id: bool) -> Result<()> {
+        self.text.print_identity_results(results, id)
+    }
+
+    fn print_port_results(&self, results: &Vec<PortsResult>) -> Result<()> {
+        self.text.print_port_results(results)
+    }
+
+    fn print_principal_usages(&self, usages: &Vec<PrincipalUsage>) -> Result<()> {
+        self.text.print_principal_usages(usages)
+    }
+
+    fn print_key_vault_access_results(&self, results: &Vec<KeyVaultAccessResult>) -> Result<()> {
+        self.text.print_key_vault_access_results(results)
+    }
+
+    fn print_storage_keys_results(&self, results: &Vec<StorageKeysResult>) -> Result<()> {
+        self.text.print_storage_keys_results(results)
+    }
+
+    fn print_tags_results(&self, results: &Vec<TagsResult>) -> Result<()> {
+        self.text.print_tags_results(results)
+    }
+
+    fn print_changes(&self, results: &Vec<ChangesResult>) -> Result<()> {
+        self.text.print_changes(results)
+    }
+
+    fn print_stale_results(&self, results: &Vec<StaleResult>, id: bool) -> Result<()> {
+        self.text.print_stale_results(results, id)
+    }
+
+    fn print_move_check(&self, result: &MoveCheckResult, id: bool) -> Result<()> {
+        self.text.print_move_check(result, id)
+    }
+
+    fn print_account(&self, result: &AccountResult) -> Result<()> {
+        self.text.print_account(result)
+    }
+
+    fn print_subscriptions(&self, subscriptions: &Vec<SubscriptionInfo>) -> Result<()> {
+        self.text.print_subscriptions(subscriptions)
+    }
+
+    fn print_providers(&self, results: &Vec<ProvidersResult>) -> Result<()> {
+        self.text.print_providers(results)
+    }
+
+    fn print_tenants(&self, tenants: &Vec<TenantResult>) -> Result<()> {
+        self.text.print_tenants(tenants)
+    }
+
+    fn print_token(&self, result: &TokenResult) -> Result<()> {
+        self.text.print_token(result)
+    }
+
+    fn print_use_result(&self, result: &UseResult) -> Result<()> {
+        self.text.print_use_result(result)
+    }
+
+    fn print_explain_result(&self, result: &ExplainResult) -> Result<()> {
+        self.text.print_explain_result(result)
+    }
+
+    fn print_role_definition(&self, result: &RoleDefinitionResult) -> Result<()> {
+        self.text.print_role_definition(result)
+    }
+
+    fn print_login_result(&self, result: &LoginResult) -> Result<()> {
+        self.text.print_login_result(result)
+    }
+
+    fn print_logout_result(&self, result: &LogoutResult) -> Result<()> {
+        self.text.print_logout_result(result)
+    }
+
+    fn print_rate_limits(&self, rate_limits: &Vec<RateLimit>) -> Result<()> {
+        self.text.print_rate_limits(rate_limits)
+    }
+
+    fn print_timings(&self, summary: &TimingSummary) -> Result<()> {
+        self.text.print_timings(summary)
+    }
+
+    fn print_warnings(&self, warnings: &[Warning]) -> Result<()> {
+        self.text.print_warnings(warnings)
+    }
+
+    fn print_config_entries(&self, entries: &Vec<ConfigEntry>) -> Result<()> {
+        self.text.print_config_entries(entries)
+    }
+
+    fn print_cache_stats(&self, stats: &CacheStats) -> Result<()> {
+        self.text.print_cache_stats(stats)
+    }
+
+    fn print_value(&self, value: &Value) -> Result<()> {
+        self.text.print_value(value)
+    }
+
+    fn print_response(&self, response: Option<ResponseHeaders>, value: &Value) -> Result<()> {
+        self.text.print_response(response, value)
+    }
+
+    fn set_scope(&self, scope: Scope) {
+        self.text.set_scope(scope)
+    }
+}
+
+/// Prints only the names of resources, IP addresses and domains for `-q/--quiet`, one per line,
+/// all other results like the text output
+pub struct QuietOutput {
+    text: TextOutput,
+}
+
+impl QuietOutput {
+    pub fn new(locale: Locale, precision: Precision) -> Self {
+        QuietOutput {
+            text: TextOutput::new(locale, precision),
+        }
+    }
+
+    /// Without the trailing dot, to pass it on to other tools
+    fn fqdn(&self, name: &str, ascii: bool) -> String {
+        self.text
+            .domain_name(name, ascii)
+            .trim_end_matches('.')
+            .to_owned()
+    }
+}
+
+impl Output for QuietOutput {
+    fn print_list_results(
+        &self,
+        results: &Vec<ListResult>,
+        id: bool,
+        _: Option<&[String]>,
+    ) -> Result<()> {
+        let resources = results.iter().any(|result| !result.resources.is_empty());
+        for result in results {
+            if resources {
+                for resource in &result.resources {
+                    println!("{}", if id { &resource.id } else { &resource.name });
+                }
+            } else {
+                for resource_group in &result.resource_groups {
+                    println!(
+                        "{}",
+                        if id {
+                            &resource_group.id
+                        } else {
+                            &resource_group.name
+                        }
+                    );
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_clusters(
+        &self,
+        results: &Vec<ClusterResult>,
+        id: bool,
+        _: Option<&[String]>,
+    ) -> Result<()> {
+        for result in results {
+            for cluster in &result.clusters {
+                println!("{}", if id { &cluster.id } else { &cluster.name });
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_ip_results(&self, results: &Vec<IpResult>, _: Option<&[String]>) -> Result<()> {
+        for result in results {
+            for resource_group in &result.resource_groups {
+                for ip in &resource_group.ip_addresses {
+                    println!("{}", ip.ip_address);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
+        self.text.print_cost_results(results)
+    }
+
+    fn print_cost_tree(&self, nodes: &Vec<CostNode>) -> Result<()> {
+        self.text.print_cost_tree(nodes)
+    }
+
+    fn print_resources_results(&self, results: &Vec<ResourcesResult>, id: bool) -> Result<()> {
+        for result in results {
+            for resource in &result.resources {
+                println!("{}", if id { &resource.id } else { &resource.name });
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_domains(&self, domains: &Vec<Domain>, ascii: bool, _: bool) -> Result<()> {
+        for domain in domains {
+            println!("{}", self.fqdn(&domain.name, ascii));
+        }
+        return Ok(());
+    }
+
+    fn print_dns_results(&self, results: &Vec<DnsResult>, ascii: bool) -> Result<()> {
+        for result in results {
+            for record in &result.records {
+                println!("{}", self.fqdn(&record.fqdn, ascii));
+            }
+        }
+        return Ok(());
+    }
+
+    fn print_resolve_result(&self, result: &ResolveResult) -> Result<()> {
+        for ip_address in &result.ip_addresses {
+            println!("{}", ip_address.ip_address);
+        }
+        return Ok(());
+    }
+
+    fn print_endpoint_results(&self, results: &Vec<EndpointResult>, id: bool) -> Result<()> {
+        self.text.print_endpoint_results(results, id)
+    }
+
+    fn print_routing_results(&self, results: &Vec<RoutingResult>, id: bool) -> Result<()> {
+        self.text.print_routing_results(results, id)
+    }
+
+    fn print_effective_routes(&self, result: &EffectiveRoutesResult) -> Result<()> {
+        self.text.print_effective_routes(result)
+    }
+
+    fn print_bastion_results(&self, results: &Vec<BastionResult>, id: bool) -> Result<()> {
+        self.text.print_bastion_results(results, id)
+    }
+
+    fn print_autoscale_results(&self, results: &Vec<AutoscaleResult>, id: bool) -> Result<()> {
+        self.text.print_autoscale_results(results, id)
+    }
+
+    fn print_plans_results(&self, results: &Vec<PlansResult>, id: bool) -> Result<()> {
+        self.text.print_plans_results(results, id)
+    }
+
+    fn print_containerapps_results(
+        &self,
+        results: &Vec<ContainerAppsResult>,
+        id: bool,
+    ) -> Result<()> {
+        self.text.print_containerapps_results(results, id)
+    }
+
+    fn print_images_results(&self, results: &Vec<ImagesResult>, id: bool) -> Result<()> {
+        self.text.print_images_results(results, id)
+    }
+
+    fn print_storage_results(&self, results: &Vec<StorageResult>, id: bool) -> Result<()> {
+        self.text.print_storage_results(results, id)
+    }
+
+    fn print_sql_results(&self, results: &Vec<SqlResult>, id: bool) -> Result<()> {
+        self.text.print_sql_results(results, id)
+    }
 
-pub struct JsonOutput {}
+    fn print_redis_results(&self, results: &Vec<RedisResult>, id: bool) -> Result<()> {
+        self.text.print_redis_results(results, id)
+    }
 
-impl Output for JsonOutput {
-    fn print_list_results(&self, results: &Vec<ListResult>, _: bool) -> Result<()> {
-        println!("{}", to_string_pretty(results)?);
-        return Ok(());
+    fn print_peers_results(&self, results: &Vec<PeersResult>, id: bool) -> Result<()> {
+        self.text.print_peers_results(results, id)
     }
 
-    fn print_clusters(&self, results: &Vec<ClusterResult>, _: bool) -> Result<()> {
-        println!("{}", to_string_pretty(results)?);
-        return Ok(());
+    fn print_firewall_results(&self, results: &Vec<FirewallResult>) -> Result<()> {
+        self.text.print_firewall_results(results)
     }
 
-    fn print_domains(&self, domains: &Vec<Domain>) -> Result<()> {
-        println!("{}", to_string_pretty(domains)?);
-        return Ok(());
+    fn print_identity_results(&self, results: &Vec<IdentityResult>, id: bool) -> Result<()> {
+        self.text.print_identity_results(results, id)
     }
 
-    fn print_dns_results(&self, results: &Vec<DnsResult>) -> Result<()> {
-        println!("{}", to_string_pretty(results)?);
-        return Ok(());
+    fn print_port_results(&self, results: &Vec<PortsResult>) -> Result<()> {
+        self.text.print_port_results(results)
     }
 
-    fn print_ip_results(&self, results: &Vec<IpResult>) -> Result<()> {
-        println!("{}", to_string_pretty(results)?);
-        return Ok(());
+    fn print_principal_usages(&self, usages: &Vec<PrincipalUsage>) -> Result<()> {
+        self.text.print_principal_usages(usages)
     }
 
-    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
-        println!("{}", to_string_pretty(results)?);
-        return Ok(());
+    fn print_key_vault_access_results(&self, results: &Vec<KeyVaultAccessResult>) -> Result<()> {
+        self.text.print_key_vault_access_results(results)
     }
 
-    fn print_value(&self, value: &Value) -> Result<()> {
-        println!("{}", to_string_pretty(value)?);
-        return Ok(());
+    fn print_storage_keys_results(&self, results: &Vec<StorageKeysResult>) -> Result<()> {
+        self.text.print_storage_keys_results(results)
     }
-}
 
-pub struct TextOutput {}
+    fn print_tags_results(&self, results: &Vec<TagsResult>) -> Result<()> {
+        self.text.print_tags_results(results)
+    }
 
-impl TextOutput {
-    fn print_subscription(&self, subscription: &Subscription, id: bool) {
-        if id {
-            println!(
-                "{} {}",
-                subscription.name.red(),
-                format!("({})", subscription.subscription_id).dimmed()
-            );
-        } else {
-            println!("{}", subscription.name.red());
-        }
+    fn print_changes(&self, results: &Vec<ChangesResult>) -> Result<()> {
+        self.text.print_changes(results)
     }
-}
 
-impl Output for TextOutput {
-    fn print_list_results(&self, results: &Vec<ListResult>, id: bool) -> Result<()> {
-        for result in results {
-            self.print_subscription(&result.subscription, id);
+    fn print_stale_results(&self, results: &Vec<StaleResult>, id: bool) -> Result<()> {
+        self.text.print_stale_results(results, id)
+    }
 
-            for resource_group in &result.resource_groups {
-                println!("  {}", resource_group.name.blue());
+    fn print_move_check(&self, result: &MoveCheckResult, id: bool) -> Result<()> {
+        self.text.print_move_check(result, id)
+    }
 
-                for resource in &result.resources {
-                    if resource.resource_group()? == resource_group.name {
-                        if id {
-                            println!(
-                                "    {} {} {}",
-                                resource.name,
-                                format!("({})", resource.resource_type).dimmed(),
-                                format!("({})", resource.id).dimmed()
-                            );
-                        } else {
-                            println!(
-                                "    {} {}",
-                                resource.name,
-                                format!("({})", resource.resource_type).dimmed()
-                            );
-                        }
-                    }
-                }
-            }
-        }
+    fn print_account(&self, result: &AccountResult) -> Result<()> {
+        self.text.print_account(result)
+    }
 
-        return Ok(());
+    fn print_subscriptions(&self, subscriptions: &Vec<SubscriptionInfo>) -> Result<()> {
+        self.text.print_subscriptions(subscriptions)
     }
 
-    fn print_clusters(&self, results: &Vec<ClusterResult>, id: bool) -> Result<()> {
-        for result in results {
-            self.print_subscription(&result.subscription, id);
+    fn print_providers(&self, results: &Vec<ProvidersResult>) -> Result<()> {
+        self.text.print_providers(results)
+    }
 
-            for cluster in &result.clusters {
-                println!("  {} {}", cluster.name.blue(), cluster.version.cyan());
+    fn print_tenants(&self, tenants: &Vec<TenantResult>) -> Result<()> {
+        self.text.print_tenants(tenants)
+    }
 
-                if let Some(agent_pools) = &cluster.agent_pools {
-                    for pool in agent_pools {
-                        print!("    {}", format!("{}", pool.count).yellow());
-                        if let (Some(min), Some(max)) = (pool.min_count, pool.max_count) {
-                            print!(" {}", format!("[{}-{}]", min, max).dimmed());
-                        }
-                        print!(" {}", pool.vm_size);
-                        if id {
-                            print!(" {}", format!("({})", pool.name).dimmed());
-                        }
-                        println!();
-                    }
-                }
+    fn print_token(&self, result: &TokenResult) -> Result<()> {
+        self.text.print_token(result)
+    }
 
-                if let Some(objects) = &cluster.objects {
-                    for object in objects {
-                        match object {
-                            KubernetesObject::Service {
-                                metadata,
-                                service_type: _,
-                                ip_addresses,
-                            } => {
-                                let namespace = format!("{}/", metadata.namespace).dimmed();
-                                print!("    {}{}", namespace, metadata.name);
-                                for ip in ip_addresses {
-                                    let private = match ip {
-                                        IpAddr::V4(ip) => ip.is_private(),
-                                        IpAddr::V6(ip) => ip.is_loopback(),
-                                    };
-                                    if private {
-                                        print!(" {}", ip.to_string().dimmed());
-                                    } else {
-                                        print!(" {}", ip.to_string().green());
-                                    }
-                                }
-                                println!();
-                            }
-                            KubernetesObject::Deployment {
-                                metadata,
-                                target,
-                                ready,
-                            } => {
-                                let pods = format!("{}/{}", ready, target);
-                                let pods = if ready >= target {
-                                    pods.green()
-                                } else {
-                                    pods.red()
-                                };
-                                let namespace = format!("{}/", metadata.namespace).dimmed();
-                                println!("    {}{} {}", namespace, metadata.name, pods);
-                            }
-                        };
-                    }
-                }
-            }
-        }
+    fn print_use_result(&self, result: &UseResult) -> Result<()> {
+        self.text.print_use_result(result)
+    }
 
-        return Ok(());
+    fn print_explain_result(&self, result: &ExplainResult) -> Result<()> {
+        self.text.print_explain_result(result)
     }
 
-    fn print_domains(&self, domains: &Vec<Domain>) -> Result<()> {
-        for domain in domains {
-            println!("{}", domain.name.cyan());
+    fn print_role_definition(&self, result: &RoleDefinitionResult) -> Result<()> {
+        self.text.print_role_definition(result)
+    }
 
-            let arrow = "->".dimmed();
+    fn print_login_result(&self, result: &LoginResult) -> Result<()> {
+        self.text.print_login_result(result)
+    }
 
-            let mut depth = 0;
-            for entry in &domain.entries {
-                match entry {
-                    Some(DnsRecordEntry::CNAME(cname)) => {
-                        println!("{0:1$} {2} {3}", "", depth * 4, arrow, cname);
-                        depth += 1;
-                    }
-                    None => println!(
-                        "{0:1$} {2} {3}",
-                        "",
-                        depth * 4,
-                        arrow,
-                        "[recursion depth exceeded]".red()
-                    ),
-                    _ => (),
-                }
-            }
+    fn print_logout_result(&self, result: &LogoutResult) -> Result<()> {
+        self.text.print_logout_result(result)
+    }
 
-            for ip_address in &domain.ip_addresses {
-                println!(
-                    "{0:1$} {2} {3}",
-                    "",
-                    depth * 4,
-                    arrow,
-                    ip_address.ip_address
-                );
+    fn print_rate_limits(&self, rate_limits: &Vec<RateLimit>) -> Result<()> {
+        self.text.print_rate_limits(rate_limits)
+    }
 
-                if let Some(resource_group) = ip_address.resource_group.as_ref() {
-                    println!(
-                        "{0:1$}     {2} {3}",
-                        "",
-                        depth * 4,
-                        arrow,
-                        resource_group.name.blue()
-                    );
-                }
-            }
+    fn print_timings(&self, summary: &TimingSummary) -> Result<()> {
+        self.text.print_timings(summary)
+    }
+
+    fn print_warnings(&self, warnings: &[Warning]) -> Result<()> {
+        self.text.print_warnings(warnings)
+    }
+
+    fn print_config_entries(&self, entries: &Vec<ConfigEntry>) -> Result<()> {
+        self.text.print_config_entries(entries)
+    }
+
+    fn print_cache_stats(&self, stats: &CacheStats) -> Result<()> {
+        self.text.print_cache_stats(stats)
+    }
+
+    fn print_value(&self, value: &Value) -> Result<()> {
+        self.text.print_value(value)
+    }
+
+    fn print_response(&self, response: Option<ResponseHeaders>, value: &Value) -> Result<()> {
+        self.text.print_response(response, value)
+    }
+
+    fn set_scope(&self, scope: Scope) {
+        self.text.set_scope(scope)
+    }
+}
+
+/// The fields of `list` for `--fields`, `-o table` and `-o csv`, in the order of their cells.
+/// Without `--resources`, the rows are resource groups with their name as `name`
+pub const LIST_FIELD_NAMES: &[&str] = &[
+    "subscription",
+    "resourceGroup",
+    "name",
+    "type",
+    "location",
+    "zones",
+    "id",
+];
+pub const IP_FIELD_NAMES: &[&str] = &["subscription", "resourceGroup", "name", "ipAddress", "id"];
+pub const CLUSTERS_FIELD_NAMES: &[&str] = &[
+    "subscription",
+    "name",
+    "version",
+    "distribution",
+    "nodes",
+    "costs",
+    "id",
+];
+/// The fields of `costs`, only for `--sort`
+pub const COSTS_FIELD_NAMES: &[&str] = &["subscription", "resourceGroup", "date", "costs"];
+
+/// Fields aligned to the right in tables
+const NUMERIC_FIELDS: &[&str] = &["nodes", "costs"];
+
+/// Rows with one cell per field, of which only some are printed
+struct FieldRows {
+    fields: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl FieldRows {
+    fn new(fields: &[&'static str]) -> Self {
+        FieldRows {
+            fields: fields.to_vec(),
+            rows: vec![],
         }
+    }
 
-        return Ok(());
+    fn add_row(&mut self, row: Vec<String>) {
+        self.rows.push(row);
     }
 
-    fn print_dns_results(&self, results: &Vec<DnsResult>) -> Result<()> {
-        for result in results {
-            println!("{}", result.zone.name.blue());
+    /// Only keeps the cells of the given fields, in their order. Unknown fields are ignored
+    fn select<S: AsRef<str>>(self, fields: &[S]) -> Self {
+        let indices: Vec<usize> = fields
+            .iter()
+            .filter_map(|field| self.fields.iter().position(|f| *f == field.as_ref()))
+            .collect();
+        FieldRows {
+            fields: indices.iter().map(|&index| self.fields[index]).collect(),
+            rows: self
+                .rows
+                .into_iter()
+                .map(|row| indices.iter().map(|&index| row[index].clone()).collect())
+                .collect(),
+        }
+    }
 
-            for record in &result.records {
-                println!("  {}", record.name.cyan());
-                match &record.entry {
-                    DnsRecordEntry::A(ip_addresses) => {
-                        for ip in ip_addresses {
-                            println!("    {} {}", "A".dimmed(), ip);
-                        }
-                    }
-                    DnsRecordEntry::CNAME(cname) => println!("    {} {}", "CNAME".dimmed(), cname),
-                }
+    fn table(&self) -> Table {
+        let headers: Vec<String> = self
+            .fields
+            .iter()
+            .map(|field| field_header(field))
+            .collect();
+        let mut table = Table::new(headers.iter().map(String::as_str).collect());
+        for (column, field) in self.fields.iter().enumerate() {
+            if NUMERIC_FIELDS.contains(field) {
+                table = table.align_right(column);
             }
         }
+        for row in &self.rows {
+            table.add_row(row.clone());
+        }
+        table
+    }
 
-        return Ok(());
+    fn print_csv(&self) {
+        println!("{}", self.fields.join(","));
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+            println!("{}", cells.join(","));
+        }
     }
+}
 
-    fn print_ip_results(&self, results: &Vec<IpResult>) -> Result<()> {
-        for result in results {
-            println!("{}", result.subscription.name.red());
+/// The table header of a field, like `RESOURCE GROUP` for `resourceGroup`
+fn field_header(field: &str) -> String {
+    let mut header = String::new();
+    for c in field.chars() {
+        if c.is_uppercase() {
+            header.push(' ');
+        }
+        header.push(c.to_ascii_uppercase());
+    }
+    header
+}
 
+/// The rows of `list` with the selected fields, by default those of the table output
+fn list_rows(results: &[ListResult], id: bool, fields: Option<&[String]>) -> Result<FieldRows> {
+    let resources = results.iter().any(|result| !result.resources.is_empty());
+    let mut rows = FieldRows::new(LIST_FIELD_NAMES);
+    for result in results {
+        let subscription = &result.subscription.name;
+        if resources {
+            for resource in &result.resources {
+                rows.add_row(vec![
+                    subscription.clone(),
+                    resource.resource_group()?.to_owned(),
+                    resource.name.clone(),
+                    resource.resource_type.clone(),
+                    resource.location.clone(),
+                    resource.zones.join(","),
+                    resource.id.clone(),
+                ]);
+            }
+        } else {
             for resource_group in &result.resource_groups {
-                println!("  {}", resource_group.resource_group.name.blue());
-
-                for ip in &resource_group.ip_addresses {
-                    println!("    {}", ip.ip_address);
-                }
+                rows.add_row(vec![
+                    subscription.clone(),
+                    resource_group.name.clone(),
+                    resource_group.name.clone(),
+                    "Microsoft.Resources/resourceGroups".to_owned(),
+                    resource_group.location.clone(),
+                    String::new(),
+                    resource_group.id.clone(),
+                ]);
             }
         }
+    }
+    if let Some(fields) = fields {
+        return Ok(rows.select(fields));
+    }
+    let mut fields = if resources {
+        vec!["subscription", "resourceGroup", "name", "type", "location"]
+    } else {
+        vec!["subscription", "resourceGroup", "location"]
+    };
+    if id {
+        fields.push("id");
+    }
+    Ok(rows.select(&fields))
+}
 
-        return Ok(());
+/// The rows of `clusters` with the selected fields, by default those of the table output
+fn cluster_rows<F>(
+    results: &[ClusterResult],
+    id: bool,
+    fields: Option<&[String]>,
+    format_costs: F,
+) -> FieldRows
+where
+    F: Fn(f64, &str) -> String,
+{
+    let mut rows = FieldRows::new(CLUSTERS_FIELD_NAMES);
+    for result in results {
+        for cluster in &result.clusters {
+            let nodes = match &cluster.agent_pools {
+                Some(pools) => pools.iter().map(|pool| pool.count).sum::<u64>().to_string(),
+                None => String::new(),
+            };
+            let costs = match &cluster.costs {
+                Some(costs) => format_costs(costs.costs, &costs.currency),
+                None => String::new(),
+            };
+            rows.add_row(vec![
+                result.subscription.name.clone(),
+                cluster.name.clone(),
+                cluster.version.clone(),
+                cluster.distribution.clone().unwrap_or_default(),
+                nodes,
+                costs,
+                cluster.id.clone(),
+            ]);
+        }
+    }
+    if let Some(fields) = fields {
+        return rows.select(fields);
+    }
+    let mut fields = vec![
+        "subscription",
+        "name",
+        "version",
+        "distribution",
+        "nodes",
+        "costs",
+    ];
+    if id {
+        fields.push("id");
     }
+    rows.select(&fields)
+}
 
-    fn print_cost_results(&self, results: &Vec<CostResult>) -> Result<()> {
-        let mut total = 0.0;
-        let mut total_currency = None;
+/// The rows of `ip` with the selected fields, by default those of the table output
+fn ip_rows(results: &[IpResult], fields: Option<&[String]>) -> FieldRows {
+    let mut rows = FieldRows::new(IP_FIELD_NAMES);
+    for result in results {
+        for resource_group in &result.resource_groups {
+            for ip in &resource_group.ip_addresses {
+                rows.add_row(vec![
+                    result.subscription.name.clone(),
+                    resource_group.resource_group.name.clone(),
+                    ip.name.clone(),
+                    ip.ip_address.clone(),
+                    ip.id.clone(),
+                ]);
+            }
+        }
+    }
+    match fields {
+        Some(fields) => rows.select(fields),
+        None => rows.select(&["subscription", "resourceGroup", "name", "ipAddress"]),
+    }
+}
 
-        for result in results {
-            println!("{}", result.subscription.name.red());
+pub fn print_list_csv(results: &[ListResult], id: bool, fields: Option<&[String]>) -> Result<()> {
+    list_rows(results, id, fields)?.print_csv();
+    Ok(())
+}
 
-            let mut sum = 0.0;
-            let mut sum_currency = None;
+/// Costs are not localized and keep their full precision, like in `print_costs_csv`
+pub fn print_clusters_csv(
+    results: &[ClusterResult],
+    id: bool,
+    fields: Option<&[String]>,
+) -> Result<()> {
+    cluster_rows(results, id, fields, |costs, currency| {
+        format!("{} {}", costs, currency)
+    })
+    .print_csv();
+    Ok(())
+}
 
-            for item in &result.costs {
-                if item.costs >= 0.01 {
-                    let name = if item.resource_group.is_empty() {
-                        "unknown".dimmed()
-                    } else {
-                        item.resource_group.blue()
-                    };
-                    println!("  {}  {:0.2} {}", name, item.costs, item.currency);
-                }
-                sum += item.costs;
-                if sum_currency == None {
-                    sum_currency = Some(&item.currency);
-                }
-            }
+pub fn print_ip_csv(results: &[IpResult], fields: Option<&[String]>) -> Result<()> {
+    ip_rows(results, fields).print_csv();
+    Ok(())
+}
 
-            if let Some(currency) = sum_currency {
-                println!("  {}  {:0.2} {}", "sum".cyan(), sum, currency);
-                total += sum;
-                total_currency = Some(currency.clone());
-            }
+/// Prints one row per resource group and month in long format, for pivot tables.
+/// Amounts are not localized and keep their full precision
+pub fn print_costs_csv(results: &[CostResult]) -> Result<()> {
+    println!("subscription,resourceGroup,period,date,amount,currency");
+    for result in results {
+        for costs in &result.costs {
+            let amount = costs.costs.to_string();
+            let fields = [
+                result.subscription.name.as_str(),
+                &costs.resource_group,
+                &result.period,
+                costs.date.as_deref().unwrap_or(""),
+                &amount,
+                &costs.currency,
+            ];
+            let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+            println!("{}", fields.join(","));
         }
+    }
+    Ok(())
+}
 
-        if let Some(currency) = total_currency {
-            println!("{}  {:0.2} {}", "total".cyan(), total, currency);
-        }
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace("\"", "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
 
-        return Ok(());
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::csv_field;
+    use super::field_header;
+    use super::Envelope;
+    use super::FieldRows;
+    use super::JsonOutput;
+    use super::Table;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("prod", csv_field("prod"));
+        assert_eq!("\"a,b\"", csv_field("a,b"));
+        assert_eq!("\"say \"\"hi\"\"\"", csv_field("say \"hi\""));
     }
 
-    fn print_value(&self, value: &Value) -> Result<()> {
-        println!("{}", to_string(value)?);
-        return Ok(());
+    #[test]
+    fn test_table_render() {
+        let mut table = Table::new(vec!["NAME", "COSTS", "CURRENCY"]).align_right(1);
+        table.add_row(vec![
+            "prod".to_owned(),
+            "1,234.50".to_owned(),
+            "EUR".to_owned(),
+        ]);
+        table.add_row(vec!["development".to_owned(), "7.00".to_owned()]);
+        assert_eq!(
+            "NAME            COSTS  CURRENCY\n\
+             -----------  --------  --------\n\
+             prod         1,234.50  EUR\n\
+             development      7.00",
+            table.render()
+        );
+    }
+
+    #[test]
+    fn test_field_rows_select() {
+        let mut rows = FieldRows::new(&["subscription", "name", "ipAddress"]);
+        rows.add_row(vec![
+            "prod".to_owned(),
+            "gateway".to_owned(),
+            "10.0.0.1".to_owned(),
+        ]);
+        let rows = rows.select(&["ipAddress", "name"]);
+        assert_eq!(vec!["ipAddress", "name"], rows.fields);
+        assert_eq!(vec![vec!["10.0.0.1", "gateway"]], rows.rows);
+        assert_eq!(
+            "IP ADDRESS  NAME
+\
+             ----------  -------
+\
+             10.0.0.1    gateway",
+            rows.table().render()
+        );
+        assert_eq!(
+            "10.0.0.1    gateway",
+            rows.table().without_header().render()
+        );
+    }
+
+    #[test]
+    fn test_field_header() {
+        assert_eq!("NAME", field_header("name"));
+        assert_eq!("RESOURCE GROUP", field_header("resourceGroup"));
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(json!([1, 2]), JsonOutput::new().wrap(&vec![1, 2]).unwrap());
+
+        let output = JsonOutput::with_envelope(Envelope {
+            version: "1.0.0".to_owned(),
+            timestamp: "2023-01-01T00:00:00Z".to_owned(),
+            arguments: vec!["list".to_owned()],
+            scope: None,
+            response: None,
+            warnings: vec![],
+        });
+        let value = output.wrap(&vec![1, 2]).unwrap();
+        assert_eq!(json!("1.0.0"), value["metadata"]["version"]);
+        assert_eq!(json!(["list"]), value["metadata"]["arguments"]);
+        assert_eq!(json!([1, 2]), value["result"]);
     }
 }
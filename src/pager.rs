@@ -0,0 +1,78 @@
+use std::env::var;
+use std::io::stdout;
+use std::io::IsTerminal;
+use std::io::Write;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::io::RawFd;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+
+/// Pipes stdout through `$AZI_PAGER`, `$PAGER` or `less`, like git does.
+/// Unless `LESS` is set, less keeps the colors and quits right away when the
+/// output fits on the screen. Stdout is restored and the pager waited for on drop
+pub struct Pager {
+    child: Child,
+    stdout: RawFd,
+}
+
+impl Pager {
+    /// Starts the pager when stdout is a terminal and the pager is not set to `cat`
+    /// or an empty value
+    pub fn start() -> Option<Pager> {
+        if !stdout().is_terminal() {
+            return None;
+        }
+        let command = var("AZI_PAGER")
+            .or_else(|_| var("PAGER"))
+            .unwrap_or_else(|_| "less".to_owned());
+        let command = command.trim();
+        if command.is_empty() || command == "cat" {
+            return None;
+        }
+
+        let mut pager = Command::new("sh");
+        pager.arg("-c").arg(command).stdin(Stdio::piped());
+        if var("LESS").is_err() {
+            pager.env("LESS", "FRX");
+        }
+        if var("LV").is_err() {
+            pager.env("LV", "-c");
+        }
+        let mut child = match pager.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                debug!("Failed to start the pager {}: {}", command, err);
+                return None;
+            }
+        };
+
+        // The pipe only stays open as stdout, so the pager sees its end when stdout is restored
+        let stdin = child.stdin.take()?;
+        let stdout = unsafe { libc::dup(libc::STDOUT_FILENO) };
+        if stdout < 0 || unsafe { libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            debug!("Failed to redirect stdout to the pager");
+            if stdout >= 0 {
+                unsafe { libc::close(stdout) };
+            }
+            drop(stdin);
+            let _ = child.wait();
+            return None;
+        }
+        // Quitting the pager before all output is written ends azi quietly, like a command
+        // in a shell pipeline, instead of the next println! failing with a broken pipe
+        unsafe { libc::signal(libc::SIGPIPE, libc::SIG_DFL) };
+        Some(Pager { child, stdout })
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = stdout().flush();
+        unsafe {
+            libc::dup2(self.stdout, libc::STDOUT_FILENO);
+            libc::close(self.stdout);
+        }
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+
+use crate::error::AppError::ParseError;
+use crate::utils::Result;
+
+pub const DEFAULT_OWNER_TAG: &'static str = "owner";
+const ALL_TYPES: &'static str = "*";
+
+/// A tag policy file like `tags.toml`, with one section per resource type (or `"*"`
+/// for all types) listing the required tag keys and their allowed values:
+///
+/// ```toml
+/// owner-tag = "owner"
+///
+/// ["*"]
+/// owner = []
+/// environment = ["prod", "test", "dev"]
+///
+/// ["Microsoft.Compute/virtualMachines"]
+/// cost-center = []
+/// ```
+///
+/// An empty list allows any value.
+#[derive(Debug, PartialEq)]
+pub struct TagPolicy {
+    pub owner_tag: String,
+    rules: Vec<TagRule>,
+}
+
+#[derive(Debug, PartialEq)]
+struct TagRule {
+    resource_type: String,
+    key: String,
+    allowed: Vec<String>,
+}
+
+impl TagPolicy {
+    pub fn parse(content: &str) -> Result<TagPolicy> {
+        let mut owner_tag = DEFAULT_OWNER_TAG.to_owned();
+        let mut rules = vec![];
+        let mut section: Option<String> = None;
+
+        for (i, line) in content.lines().enumerate() {
+            let invalid = || ParseError(format!("invalid policy file, line {}: {}", i + 1, line));
+
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix("[") {
+                let header = header.strip_suffix("]").ok_or_else(invalid)?.trim();
+                section = Some(unquote(header).ok_or_else(invalid)?.to_lowercase());
+                continue;
+            }
+
+            let (key, value) = match line.find("=") {
+                Some(pos) => (line[..pos].trim(), line[pos + 1..].trim()),
+                None => return Err(invalid().into()),
+            };
+            let key = unquote(key).ok_or_else(invalid)?;
+
+            match &section {
+                Some(resource_type) => rules.push(TagRule {
+                    resource_type: resource_type.clone(),
+                    key: key.to_owned(),
+                    allowed: parse_list(value).ok_or_else(invalid)?,
+                }),
+                None if key == "owner-tag" => {
+                    owner_tag = unquote(value).ok_or_else(invalid)?.to_owned();
+                }
+                None => return Err(invalid().into()),
+            }
+        }
+
+        Ok(TagPolicy { owner_tag, rules })
+    }
+
+    /// Returns the policy violations of a resource with the given type and tags
+    pub fn check(&self, resource_type: &str, tags: &HashMap<String, String>) -> Vec<String> {
+        let resource_type = resource_type.to_lowercase();
+        let mut violations = vec![];
+        for rule in &self.rules {
+            if rule.resource_type != ALL_TYPES && rule.resource_type != resource_type {
+                continue;
+            }
+            match find_tag(tags, &rule.key) {
+                None => violations.push(format!("missing tag: {}", rule.key)),
+                Some(value) if !rule.allowed.is_empty() && !rule.allowed.contains(value) => {
+                    violations.push(format!("invalid value for {}: {}", rule.key, value))
+                }
+                Some(_) => (),
+            }
+        }
+        violations
+    }
+}
+
+/// Tag keys are case-insensitive in Azure
+pub fn find_tag<'a>(tags: &'a HashMap<String, String>, key: &str) -> Option<&'a String> {
+    tags.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut quoted = false;
+    for (pos, c) in line.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '#' if !quoted => return &line[..pos],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn unquote(s: &str) -> Option<&str> {
+    match s.strip_prefix("\"") {
+        Some(quoted) => quoted.strip_suffix("\""),
+        None if !s.is_empty() && !s.contains(|c: char| c == '"' || c.is_whitespace()) => Some(s),
+        None => None,
+    }
+}
+
+/// Parses a list of strings like `["a", "b"]`
+fn parse_list(s: &str) -> Option<Vec<String>> {
+    let inner = s.strip_prefix("[")?.strip_suffix("]")?;
+    let mut values = vec![];
+    for (i, part) in inner.split('"').enumerate() {
+        if i % 2 == 1 {
+            values.push(part.to_owned());
+        } else if !part.chars().all(|c| c == ',' || c.is_whitespace()) {
+            return None;
+        }
+    }
+    Some(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::TagPolicy;
+
+    const POLICY: &str = r#"
+owner-tag = "team"
+
+["*"]
+team = []
+environment = ["prod", "dev"] # no test environment
+
+[Microsoft.Compute/virtualMachines]
+"cost-center" = []
+"#;
+
+    #[test]
+    fn test_parse() {
+        let policy = TagPolicy::parse(POLICY).unwrap();
+        assert_eq!("team", policy.owner_tag);
+        assert_eq!(3, policy.rules.len());
+        assert_eq!(vec!["prod", "dev"], policy.rules[1].allowed);
+        assert_eq!(
+            "microsoft.compute/virtualmachines",
+            policy.rules[2].resource_type
+        );
+
+        assert!(TagPolicy::parse("team = []").is_err());
+        assert!(TagPolicy::parse("[\"*\"]\nteam = [\"a\" \"b\"").is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let policy = TagPolicy::parse(POLICY).unwrap();
+        let mut tags = HashMap::new();
+        tags.insert("Team".to_owned(), "platform".to_owned());
+        tags.insert("environment".to_owned(), "test".to_owned());
+
+        assert_eq!(
+            vec!["invalid value for environment: test"],
+            policy.check("Microsoft.Storage/storageAccounts", &tags)
+        );
+        assert_eq!(
+            vec![
+                "invalid value for environment: test",
+                "missing tag: cost-center"
+            ],
+            policy.check("Microsoft.Compute/virtualMachines", &tags)
+        );
+
+        tags.insert("environment".to_owned(), "prod".to_owned());
+        assert!(policy
+            .check("Microsoft.Storage/storageAccounts", &tags)
+            .is_empty());
+    }
+}
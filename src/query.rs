@@ -0,0 +1,728 @@
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use serde_json::from_str;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::error::AppError::ParseError;
+use crate::utils::Result;
+
+/// A JMESPath expression for `--query`, applied to the JSON output.
+/// Supports fields, indexes, slices, `[*]`, `*` and `[]` projections, filters like
+/// `[?state == 'Enabled']`, multiselect lists and hashes, pipes, `&&`, `||`, `!`
+/// and the functions `length`, `keys`, `contains` and `starts_with`
+#[derive(Debug, Clone)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.expression(0)?;
+        match parser.peek() {
+            Token::Eof => Ok(Query { expr }),
+            token => Err(parser.error(&format!("unexpected {:?}", token))),
+        }
+    }
+
+    pub fn apply(&self, value: &Value) -> Value {
+        self.expr.eval(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Literal(Value),
+    Number(i64),
+    Dot,
+    Star,
+    At,
+    Comma,
+    Colon,
+    Pipe,
+    Or,
+    And,
+    Not,
+    Compare(Comparator),
+    LBracket,
+    RBracket,
+    Flatten,
+    Filter,
+    LBrace,
+    RBrace,
+    LParen,
+    RParen,
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Current,
+    Field(String),
+    Literal(Value),
+    Index(Box<Expr>, i64),
+    Slice(Box<Expr>, Option<i64>, Option<i64>, Option<i64>),
+    Subexpr(Box<Expr>, Box<Expr>),
+    /// Applies the right side to each item of the list
+    Project(Box<Expr>, Box<Expr>),
+    /// Applies the right side to each value of the object
+    ValueProject(Box<Expr>, Box<Expr>),
+    Flatten(Box<Expr>),
+    Filter(Box<Expr>, Box<Expr>, Box<Expr>),
+    MultiList(Vec<Expr>),
+    MultiHash(Vec<(String, Expr)>),
+    Pipe(Box<Expr>, Box<Expr>),
+    Compare(Comparator, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Function(String, Vec<Expr>),
+}
+
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("length", 1),
+    ("keys", 1),
+    ("contains", 2),
+    ("starts_with", 2),
+];
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let error = |message: String| ParseError(format!("invalid query: {}", message));
+    let mut tokens = vec![];
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            ' ' | '\t' | '\n' | '\r' => continue,
+            '.' => Token::Dot,
+            '*' => Token::Star,
+            '@' => Token::At,
+            ',' => Token::Comma,
+            ':' => Token::Colon,
+            ']' => Token::RBracket,
+            '{' => Token::LBrace,
+            '}' => Token::RBrace,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => match chars.peek() {
+                Some(']') => {
+                    chars.next();
+                    Token::Flatten
+                }
+                Some('?') => {
+                    chars.next();
+                    Token::Filter
+                }
+                _ => Token::LBracket,
+            },
+            '|' if chars.peek() == Some(&'|') => {
+                chars.next();
+                Token::Or
+            }
+            '|' => Token::Pipe,
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                Token::And
+            }
+            '!' if chars.peek() == Some(&'=') => {
+                chars.next();
+                Token::Compare(Comparator::Ne)
+            }
+            '!' => Token::Not,
+            '=' if chars.peek() == Some(&'=') => {
+                chars.next();
+                Token::Compare(Comparator::Eq)
+            }
+            '<' | '>' => {
+                let equal = chars.peek() == Some(&'=');
+                if equal {
+                    chars.next();
+                }
+                Token::Compare(match (c, equal) {
+                    ('<', false) => Comparator::Lt,
+                    ('<', true) => Comparator::Le,
+                    ('>', false) => Comparator::Gt,
+                    _ => Comparator::Ge,
+                })
+            }
+            '\'' => Token::Literal(Value::String(
+                delimited(&mut chars, '\'').ok_or_else(|| error("unclosed string".to_owned()))?,
+            )),
+            '`' => {
+                let json = delimited(&mut chars, '`')
+                    .ok_or_else(|| error("unclosed literal".to_owned()))?;
+                Token::Literal(
+                    from_str(&json).map_err(|_| error(format!("invalid literal: {}", json)))?,
+                )
+            }
+            '"' => Token::Identifier(
+                delimited(&mut chars, '"').ok_or_else(|| error("unclosed field".to_owned()))?,
+            ),
+            '-' | '0'..='9' => {
+                let mut number = c.to_string();
+                while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                    number.push(*digit);
+                    chars.next();
+                }
+                Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| error(format!("invalid number: {}", number)))?,
+                )
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut identifier = c.to_string();
+                while let Some(c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    identifier.push(*c);
+                    chars.next();
+                }
+                Token::Identifier(identifier)
+            }
+            c => return Err(error(format!("unexpected character: {}", c)).into()),
+        };
+        tokens.push(token);
+    }
+    tokens.push(Token::Eof);
+    Ok(tokens)
+}
+
+/// Reads up to the closing delimiter, which can be escaped with a backslash
+fn delimited(chars: &mut Peekable<Chars>, delimiter: char) -> Option<String> {
+    let mut result = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&delimiter) => result.push(chars.next()?),
+            c if c == delimiter => return Some(result),
+            c => result.push(c),
+        }
+    }
+    None
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos]
+    }
+
+    fn next(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<()> {
+        match self.next() {
+            token if token == expected => Ok(()),
+            token => Err(self.error(&format!("expected {:?}, found {:?}", expected, token))),
+        }
+    }
+
+    fn error(&self, message: &str) -> Box<dyn std::error::Error> {
+        ParseError(format!("invalid query: {}", message)).into()
+    }
+
+    /// The binding power of the token after an expression, like in the JMESPath reference
+    fn binding_power(token: &Token) -> u8 {
+        match token {
+            Token::Pipe => 1,
+            Token::Or => 2,
+            Token::And => 3,
+            Token::Compare(_) => 5,
+            Token::Flatten => 9,
+            Token::Star => 20,
+            Token::Filter => 21,
+            Token::Dot => 40,
+            Token::Not => 45,
+            Token::LBrace => 50,
+            Token::LBracket => 55,
+            Token::LParen => 60,
+            _ => 0,
+        }
+    }
+
+    fn expression(&mut self, power: u8) -> Result<Expr> {
+        let mut left = self.prefix()?;
+        while power < Parser::binding_power(self.peek()) {
+            left = self.infix(left)?;
+        }
+        Ok(left)
+    }
+
+    fn prefix(&mut self) -> Result<Expr> {
+        match self.next() {
+            Token::Identifier(name) if self.peek() == &Token::LParen => self.function(name),
+            Token::Identifier(name) => Ok(Expr::Field(name)),
+            Token::Literal(value) => Ok(Expr::Literal(value)),
+            Token::At => Ok(Expr::Current),
+            Token::Star => {
+                let right = self.projection(20)?;
+                Ok(Expr::ValueProject(Box::new(Expr::Current), Box::new(right)))
+            }
+            Token::Flatten => {
+                let right = self.projection(9)?;
+                let left = Expr::Flatten(Box::new(Expr::Current));
+                Ok(Expr::Project(Box::new(left), Box::new(right)))
+            }
+            Token::Filter => self.filter(Expr::Current),
+            Token::LBracket => match self.peek() {
+                Token::Number(_) | Token::Colon => self.index(Expr::Current),
+                Token::Star if self.tokens.get(self.pos + 1) == Some(&Token::RBracket) => {
+                    self.pos += 2;
+                    let right = self.projection(20)?;
+                    Ok(Expr::Project(Box::new(Expr::Current), Box::new(right)))
+                }
+                _ => self.multi_list(),
+            },
+            Token::LBrace => self.multi_hash(),
+            Token::Not => Ok(Expr::Not(Box::new(self.expression(45)?))),
+            Token::LParen => {
+                let expr = self.expression(0)?;
+                self.expect(Token::RParen)?;
+                Ok(expr)
+            }
+            token => Err(self.error(&format!("unexpected {:?}", token))),
+        }
+    }
+
+    fn infix(&mut self, left: Expr) -> Result<Expr> {
+        let left = Box::new(left);
+        match self.next() {
+            Token::Dot if self.peek() == &Token::Star => {
+                self.next();
+                let right = self.projection(20)?;
+                Ok(Expr::ValueProject(left, Box::new(right)))
+            }
+            Token::Dot => Ok(Expr::Subexpr(left, Box::new(self.dot_right(40)?))),
+            Token::Pipe => Ok(Expr::Pipe(left, Box::new(self.expression(1)?))),
+            Token::Or => Ok(Expr::Or(left, Box::new(self.expression(2)?))),
+            Token::And => Ok(Expr::And(left, Box::new(self.expression(3)?))),
+            Token::Compare(comparator) => {
+                let right = self.expression(5)?;
+                Ok(Expr::Compare(comparator, left, Box::new(right)))
+            }
+            Token::Flatten => {
+                let right = self.projection(9)?;
+                Ok(Expr::Project(
+                    Box::new(Expr::Flatten(left)),
+                    Box::new(right),
+                ))
+            }
+            Token::Filter => self.filter(*left),
+            Token::LBracket => match self.peek() {
+                Token::Star => {
+                    self.next();
+                    self.expect(Token::RBracket)?;
+                    let right = self.projection(20)?;
+                    Ok(Expr::Project(left, Box::new(right)))
+                }
+                _ => self.index(*left),
+            },
+            token => Err(self.error(&format!("unexpected {:?}", token))),
+        }
+    }
+
+    /// The expression applied to each item of a projection, which ends at a pipe
+    fn projection(&mut self, power: u8) -> Result<Expr> {
+        match self.peek() {
+            token if Parser::binding_power(token) < 10 => Ok(Expr::Current),
+            Token::LBracket | Token::Filter => self.expression(power),
+            Token::Dot => {
+                self.next();
+                self.dot_right(power)
+            }
+            token => Err(self.error(&format!("unexpected {:?}", token))),
+        }
+    }
+
+    fn dot_right(&mut self, power: u8) -> Result<Expr> {
+        match self.peek() {
+            Token::LBracket => {
+                self.next();
+                self.multi_list()
+            }
+            Token::LBrace => {
+                self.next();
+                self.multi_hash()
+            }
+            _ => self.expression(power),
+        }
+    }
+
+    /// An index like `[0]` or `[-1]`, or a slice like `[1:3]` or `[::2]`
+    fn index(&mut self, left: Expr) -> Result<Expr> {
+        let mut parts = vec![None];
+        loop {
+            match self.next() {
+                Token::Number(number) => *parts.last_mut().unwrap() = Some(number),
+                Token::Colon if parts.len() < 3 => parts.push(None),
+                Token::RBracket => break,
+                token => return Err(self.error(&format!("unexpected {:?}", token))),
+            }
+        }
+        match parts.as_slice() {
+            [Some(index)] => Ok(Expr::Index(Box::new(left), *index)),
+            [None] => Err(self.error("empty index")),
+            _ => {
+                let slice = Expr::Slice(
+                    Box::new(left),
+                    parts[0],
+                    parts[1],
+                    parts.get(2).copied().flatten(),
+                );
+                let right = self.projection(20)?;
+                Ok(Expr::Project(Box::new(slice), Box::new(right)))
+            }
+        }
+    }
+
+    fn filter(&mut self, left: Expr) -> Result<Expr> {
+        let condition = self.expression(0)?;
+        self.expect(Token::RBracket)?;
+        let right = self.projection(21)?;
+        Ok(Expr::Filter(
+            Box::new(left),
+            Box::new(condition),
+            Box::new(right),
+        ))
+    }
+
+    fn multi_list(&mut self) -> Result<Expr> {
+        let mut items = vec![self.expression(0)?];
+        while self.peek() == &Token::Comma {
+            self.next();
+            items.push(self.expression(0)?);
+        }
+        self.expect(Token::RBracket)?;
+        Ok(Expr::MultiList(items))
+    }
+
+    fn multi_hash(&mut self) -> Result<Expr> {
+        let mut items = vec![];
+        loop {
+            let key = match self.next() {
+                Token::Identifier(key) => key,
+                token => return Err(self.error(&format!("expected a key, found {:?}", token))),
+            };
+            self.expect(Token::Colon)?;
+            items.push((key, self.expression(0)?));
+            match self.next() {
+                Token::Comma => continue,
+                Token::RBrace => return Ok(Expr::MultiHash(items)),
+                token => return Err(self.error(&format!("unexpected {:?}", token))),
+            }
+        }
+    }
+
+    fn function(&mut self, name: String) -> Result<Expr> {
+        self.expect(Token::LParen)?;
+        let mut args = vec![];
+        if self.peek() != &Token::RParen {
+            args.push(self.expression(0)?);
+            while self.peek() == &Token::Comma {
+                self.next();
+                args.push(self.expression(0)?);
+            }
+        }
+        self.expect(Token::RParen)?;
+        match FUNCTIONS.iter().find(|(function, _)| *function == name) {
+            Some((_, count)) if *count == args.len() => Ok(Expr::Function(name, args)),
+            Some((_, count)) => Err(self.error(&format!("{} takes {} arguments", name, count))),
+            None => Err(self.error(&format!("unknown function: {}", name))),
+        }
+    }
+}
+
+impl Expr {
+    fn eval(&self, value: &Value) -> Value {
+        match self {
+            Expr::Current => value.clone(),
+            Expr::Field(name) => value.get(name).cloned().unwrap_or(Value::Null),
+            Expr::Literal(literal) => literal.clone(),
+            Expr::Index(left, index) => match left.eval(value) {
+                Value::Array(items) => {
+                    let index = if *index < 0 {
+                        items.len() as i64 + index
+                    } else {
+                        *index
+                    };
+                    usize::try_from(index)
+                        .ok()
+                        .and_then(|index| items.get(index).cloned())
+                        .unwrap_or(Value::Null)
+                }
+                _ => Value::Null,
+            },
+            Expr::Slice(left, start, stop, step) => match left.eval(value) {
+                Value::Array(items) => Value::Array(slice(items, *start, *stop, *step)),
+                _ => Value::Null,
+            },
+            Expr::Subexpr(left, right) => match left.eval(value) {
+                Value::Null => Value::Null,
+                left => right.eval(&left),
+            },
+            Expr::Project(left, right) => match left.eval(value) {
+                Value::Array(items) => project(items.iter(), right),
+                _ => Value::Null,
+            },
+            Expr::ValueProject(left, right) => match left.eval(value) {
+                Value::Object(map) => project(map.values(), right),
+                _ => Value::Null,
+            },
+            Expr::Flatten(left) => match left.eval(value) {
+                Value::Array(items) => {
+                    let mut result = vec![];
+                    for item in items {
+                        match item {
+                            Value::Array(nested) => result.extend(nested),
+                            item => result.push(item),
+                        }
+                    }
+                    Value::Array(result)
+                }
+                _ => Value::Null,
+            },
+            Expr::Filter(left, condition, right) => match left.eval(value) {
+                Value::Array(items) => project(
+                    items.iter().filter(|item| is_true(&condition.eval(item))),
+                    right,
+                ),
+                _ => Value::Null,
+            },
+            Expr::MultiList(items) if !value.is_null() => {
+                Value::Array(items.iter().map(|item| item.eval(value)).collect())
+            }
+            Expr::MultiHash(items) if !value.is_null() => {
+                let mut map = Map::new();
+                for (key, item) in items {
+                    map.insert(key.clone(), item.eval(value));
+                }
+                Value::Object(map)
+            }
+            Expr::MultiList(_) | Expr::MultiHash(_) => Value::Null,
+            Expr::Pipe(left, right) => right.eval(&left.eval(value)),
+            Expr::Compare(comparator, left, right) => {
+                compare(*comparator, &left.eval(value), &right.eval(value))
+            }
+            Expr::And(left, right) => match left.eval(value) {
+                left if !is_true(&left) => left,
+                _ => right.eval(value),
+            },
+            Expr::Or(left, right) => match left.eval(value) {
+                left if is_true(&left) => left,
+                _ => right.eval(value),
+            },
+            Expr::Not(expr) => Value::Bool(!is_true(&expr.eval(value))),
+            Expr::Function(name, args) => {
+                let args: Vec<Value> = args.iter().map(|arg| arg.eval(value)).collect();
+                function(name, &args)
+            }
+        }
+    }
+}
+
+/// Applies the expression to each item, leaving out null results
+fn project<'a>(items: impl Iterator<Item = &'a Value>, expr: &Expr) -> Value {
+    Value::Array(
+        items
+            .map(|item| expr.eval(item))
+            .filter(|item| !item.is_null())
+            .collect(),
+    )
+}
+
+fn slice(
+    items: Vec<Value>,
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: Option<i64>,
+) -> Vec<Value> {
+    let len = items.len() as i64;
+    let step = step.filter(|step| *step != 0).unwrap_or(1);
+    let bound = |index: i64| {
+        let index = if index < 0 { index + len } else { index };
+        let (min, max) = if step > 0 { (0, len) } else { (-1, len - 1) };
+        index.clamp(min, max)
+    };
+    let (mut index, stop) = if step > 0 {
+        (bound(start.unwrap_or(0)), bound(stop.unwrap_or(len)))
+    } else {
+        (
+            bound(start.unwrap_or(len - 1)),
+            bound(stop.unwrap_or(-len - 1)),
+        )
+    };
+    let mut result = vec![];
+    while (step > 0 && index < stop) || (step < 0 && index > stop) {
+        result.push(items[index as usize].clone());
+        index += step;
+    }
+    result
+}
+
+/// False, null, empty strings, lists and objects are false, like in JMESPath
+fn is_true(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::String(value) => !value.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(map) => !map.is_empty(),
+        Value::Number(_) => true,
+    }
+}
+
+/// Numbers and, unlike JMESPath, strings can be ordered, like timestamps
+fn compare(comparator: Comparator, left: &Value, right: &Value) -> Value {
+    let ordering = match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left
+            .as_f64()
+            .and_then(|left| left.partial_cmp(&right.as_f64()?)),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        _ => None,
+    };
+    match (comparator, ordering) {
+        (Comparator::Eq, _) => Value::Bool(left == right),
+        (Comparator::Ne, _) => Value::Bool(left != right),
+        (_, None) => Value::Null,
+        (Comparator::Lt, Some(ordering)) => Value::Bool(ordering == Ordering::Less),
+        (Comparator::Le, Some(ordering)) => Value::Bool(ordering != Ordering::Greater),
+        (Comparator::Gt, Some(ordering)) => Value::Bool(ordering == Ordering::Greater),
+        (Comparator::Ge, Some(ordering)) => Value::Bool(ordering != Ordering::Less),
+    }
+}
+
+fn function(name: &str, args: &[Value]) -> Value {
+    match (name, args) {
+        ("length", [Value::String(value)]) => Value::from(value.chars().count()),
+        ("length", [Value::Array(items)]) => Value::from(items.len()),
+        ("length", [Value::Object(map)]) => Value::from(map.len()),
+        ("keys", [Value::Object(map)]) => {
+            Value::Array(map.keys().cloned().map(Value::String).collect())
+        }
+        ("contains", [Value::String(value), Value::String(search)]) => {
+            Value::Bool(value.contains(search.as_str()))
+        }
+        ("contains", [Value::Array(items), search]) => Value::Bool(items.contains(search)),
+        ("starts_with", [Value::String(value), Value::String(prefix)]) => {
+            Value::Bool(value.starts_with(prefix.as_str()))
+        }
+        _ => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use serde_json::Value;
+
+    use super::Query;
+
+    fn query(query: &str, value: &Value) -> Value {
+        Query::parse(query).unwrap().apply(value)
+    }
+
+    #[test]
+    fn test_query() {
+        let value = json!([
+            {
+                "subscription": { "displayName": "Production" },
+                "resourceGroups": [
+                    { "name": "rg-web", "location": "westeurope", "tags": { "owner": "web" } },
+                    { "name": "rg-db", "location": "northeurope" }
+                ]
+            },
+            {
+                "subscription": { "displayName": "Development" },
+                "resourceGroups": [{ "name": "rg-dev", "location": "westeurope" }]
+            }
+        ]);
+
+        assert_eq!(
+            json!(["Production", "Development"]),
+            query("[*].subscription.displayName", &value)
+        );
+        assert_eq!(
+            json!(["rg-web", "rg-db", "rg-dev"]),
+            query("[].resourceGroups[].name", &value)
+        );
+        assert_eq!(
+            json!(["rg-web", "rg-dev"]),
+            query(
+                "[].resourceGroups[] | [?location == 'westeurope'].name",
+                &value
+            )
+        );
+        assert_eq!(
+            json!("rg-dev"),
+            query("[-1].resourceGroups[0].name", &value)
+        );
+        assert_eq!(
+            json!([{ "name": "rg-web", "owner": "web" }]),
+            query(
+                "[0].resourceGroups[?tags].{name: name, owner: tags.owner}",
+                &value
+            )
+        );
+        assert_eq!(
+            json!([["Production", 2], ["Development", 1]]),
+            query(
+                "[*].[subscription.displayName, length(resourceGroups)]",
+                &value
+            )
+        );
+        assert_eq!(
+            json!(["rg-db"]),
+            query(
+                "[].resourceGroups[] | [?!starts_with(location, 'west') && name != 'x'].name",
+                &value
+            )
+        );
+        assert_eq!(json!(2), query("length(@)", &value));
+        assert_eq!(
+            json!(["web"]),
+            query("[0].resourceGroups[0].tags.*", &value)
+        );
+        assert_eq!(json!([2, 1]), query("[1:].name || `[2, 1]`", &value));
+        assert_eq!(Value::Null, query("missing.field", &value));
+    }
+
+    #[test]
+    fn test_query_slice() {
+        let value = json!([0, 1, 2, 3, 4]);
+        assert_eq!(json!([1, 2]), query("[1:3]", &value));
+        assert_eq!(json!([3, 4]), query("[-2:]", &value));
+        assert_eq!(json!([4, 2, 0]), query("[::-2]", &value));
+        assert_eq!(json!([3, 4]), query("[?@ >= `3`]", &value));
+    }
+
+    #[test]
+    fn test_query_errors() {
+        assert!(Query::parse("name[").is_err());
+        assert!(Query::parse("[?name == 'x").is_err());
+        assert!(Query::parse("unknown(@)").is_err());
+        assert!(Query::parse("length(@, @)").is_err());
+        assert!(Query::parse("a b").is_err());
+    }
+}
@@ -0,0 +1,2085 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::net::IpAddr;
+
+use serde_json::json;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::cache::CacheStats;
+use crate::commands::AccountResult;
+use crate::commands::AccountSubscription;
+use crate::commands::AgentPool;
+use crate::commands::Autoscale;
+use crate::commands::AutoscaleProfile;
+use crate::commands::AutoscaleResult;
+use crate::commands::Bastion;
+use crate::commands::BastionResult;
+use crate::commands::BgpPeerResult;
+use crate::commands::Change;
+use crate::commands::ChangesResult;
+use crate::commands::CircuitResult;
+use crate::commands::Cluster;
+use crate::commands::ClusterResult;
+use crate::commands::ContainerAppEnvironment;
+use crate::commands::ContainerAppResult;
+use crate::commands::ContainerAppRevisionResult;
+use crate::commands::ContainerAppScaleRuleResult;
+use crate::commands::ContainerAppsResult;
+use crate::commands::CostResult;
+use crate::commands::DnsResult;
+use crate::commands::Domain;
+use crate::commands::DomainIpAddress;
+use crate::commands::EffectiveRoutesResult;
+use crate::commands::Endpoint;
+use crate::commands::EndpointConnection;
+use crate::commands::EndpointResult;
+use crate::commands::ExplainOperation;
+use crate::commands::ExplainResult;
+use crate::commands::ExposedMachine;
+use crate::commands::Firewall;
+use crate::commands::FirewallResult;
+use crate::commands::FirewallRule;
+use crate::commands::GatewayConnectionResult;
+use crate::commands::GatewayResult;
+use crate::commands::Identity;
+use crate::commands::IdentityResult;
+use crate::commands::IdentityRole;
+use crate::commands::ImageDefinition;
+use crate::commands::ImageGallery;
+use crate::commands::ImageVersion;
+use crate::commands::ImagesResult;
+use crate::commands::IpResult;
+use crate::commands::IpResultResourceGroup;
+use crate::commands::KeyVaultAccess;
+use crate::commands::KeyVaultAccessResult;
+use crate::commands::KeyVaultPrincipal;
+use crate::commands::ListResult;
+use crate::commands::LoginResult;
+use crate::commands::LogoutResult;
+use crate::commands::ManagedImageResult;
+use crate::commands::MoveCheckResource;
+use crate::commands::MoveCheckResult;
+use crate::commands::NamespaceRestarts;
+use crate::commands::PeersResult;
+use crate::commands::Plan;
+use crate::commands::PlanApp;
+use crate::commands::PlansResult;
+use crate::commands::PortExposure;
+use crate::commands::PortsResult;
+use crate::commands::PrincipalReference;
+use crate::commands::PrincipalUsage;
+use crate::commands::ProviderRegistration;
+use crate::commands::ProvidersResult;
+use crate::commands::RedisCacheResult;
+use crate::commands::RedisResult;
+use crate::commands::ResolveResult;
+use crate::commands::ResourcesResult;
+use crate::commands::RoleDefinitionResult;
+use crate::commands::RoutingResult;
+use crate::commands::RoutingTable;
+use crate::commands::SqlDatabaseResult;
+use crate::commands::SqlResult;
+use crate::commands::StaleResource;
+use crate::commands::StaleResult;
+use crate::commands::StorageAccountKeys;
+use crate::commands::StorageAccountResult;
+use crate::commands::StorageKey;
+use crate::commands::StorageKeysResult;
+use crate::commands::StorageResult;
+use crate::commands::SubscriptionInfo;
+use crate::commands::TagsOwner;
+use crate::commands::TagsResult;
+use crate::commands::TagsViolation;
+use crate::commands::TenantResult;
+use crate::commands::TokenResult;
+use crate::commands::TypedResource;
+use crate::commands::UnscaledResource;
+use crate::commands::UseResult;
+use crate::config::ConfigEntry;
+use crate::object::Costs;
+use crate::object::DnsRecord;
+use crate::object::DnsRecordEntry;
+use crate::object::EffectiveRoute;
+use crate::object::IpAddress;
+use crate::object::KubernetesMetadata;
+use crate::object::KubernetesObject;
+use crate::object::RegionInfo;
+use crate::object::Resource;
+use crate::object::ResourceGroup;
+use crate::object::ResourceIdentity;
+use crate::object::Route;
+use crate::object::RouteProperties;
+use crate::object::Subscription;
+
+const SCHEMA_VERSION: &'static str = "http://json-schema.org/draft-07/schema#";
+
+/// JSON Schema of the serialized form of a type
+pub trait Schema {
+    fn schema() -> Value;
+
+    /// Whether the property is always present in the serialized object
+    fn required() -> bool {
+        true
+    }
+}
+
+/// Returns the JSON Schema document for the given output type
+pub fn output_schema<T: Schema>(title: &str) -> Value {
+    let mut schema = T::schema();
+    schema["$schema"] = json!(SCHEMA_VERSION);
+    schema["title"] = json!(title);
+    schema
+}
+
+fn object(properties: Vec<(&str, Value, bool)>) -> Value {
+    let mut map = Map::new();
+    let mut required = vec![];
+    for (name, schema, is_required) in properties {
+        map.insert(name.to_owned(), schema);
+        if is_required {
+            required.push(json!(name));
+        }
+    }
+    json!({
+        "type": "object",
+        "properties": map,
+        "required": required,
+        "additionalProperties": false
+    })
+}
+
+macro_rules! schema_object {
+    ($type:ty { $($name:literal: $field:ty),* $(,)? }) => {
+        impl Schema for $type {
+            fn schema() -> Value {
+                object(vec![$(($name, <$field>::schema(), <$field>::required())),*])
+            }
+        }
+    };
+}
+
+/// Used for properties which are skipped in the output when empty
+struct Skipped<T>(PhantomData<T>);
+
+impl<T: Schema> Schema for Skipped<T> {
+    fn schema() -> Value {
+        T::schema()
+    }
+
+    fn required() -> bool {
+        false
+    }
+}
+
+impl Schema for String {
+    fn schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl Schema for bool {
+    fn schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl Schema for u32 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl Schema for u64 {
+    fn schema() -> Value {
+        json!({ "type": "integer", "minimum": 0 })
+    }
+}
+
+impl Schema for i64 {
+    fn schema() -> Value {
+        json!({ "type": "integer" })
+    }
+}
+
+impl Schema for f64 {
+    fn schema() -> Value {
+        json!({ "type": "number" })
+    }
+}
+
+impl Schema for IpAddr {
+    fn schema() -> Value {
+        json!({ "type": "string", "anyOf": [{ "format": "ipv4" }, { "format": "ipv6" }] })
+    }
+}
+
+impl Schema for Value {
+    fn schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: Schema> Schema for Option<T> {
+    fn schema() -> Value {
+        json!({ "anyOf": [T::schema(), { "type": "null" }] })
+    }
+}
+
+impl<T: Schema> Schema for Vec<T> {
+    fn schema() -> Value {
+        json!({ "type": "array", "items": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for HashMap<String, T> {
+    fn schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::schema() })
+    }
+}
+
+impl<T: Schema> Schema for BTreeMap<String, T> {
+    fn schema() -> Value {
+        HashMap::<String, T>::schema()
+    }
+}
+
+impl Schema for KubernetesObject {
+    fn schema() -> Value {
+        json!({
+            "oneOf": [
+                object(vec![(
+                    "Service",
+                    object(vec![
+                        ("metadata", KubernetesMetadata::schema(), true),
+                        ("service_type", String::schema(), true),
+                        ("ip_addresses", Vec::<IpAddr>::schema(), true),
+                    ]),
+                    true,
+                )]),
+                object(vec![(
+                    "Deployment",
+                    object(vec![
+                        ("metadata", KubernetesMetadata::schema(), true),
+                        ("target", u64::schema(), true),
+                        ("ready", u64::schema(), true),
+                    ]),
+                    true,
+                )]),
+            ]
+        })
+    }
+}
+
+impl Schema for DnsRecordEntry {
+    fn schema() -> Value {
+        json!({
+            "oneOf": [
+                object(vec![("A", Vec::<String>::schema(), true)]),
+                object(vec![("CNAME", String::schema(), true)]),
+            ]
+        })
+    }
+}
+
+schema_object!(Subscription {
+    "id": String,
+    "subscriptionId": String,
+    "displayName": String,
+    "tenant": Skipped<String>,
+});
+
+schema_object!(ResourceGroup {
+    "id": String,
+    "location": String,
+    "name": String,
+    "protection": Skipped<Vec<String>>,
+    "region": Skipped<RegionInfo>,
+});
+
+schema_object!(RegionInfo {
+    "zonesSupported": bool,
+    "pairedRegion": Skipped<String>,
+});
+
+schema_object!(Resource {
+    "id": String,
+    "type": String,
+    "location": String,
+    "name": String,
+    "identity": Skipped<ResourceIdentity>,
+    "properties": Skipped<Value>,
+    "createdTime": Skipped<String>,
+    "changedTime": Skipped<String>,
+    "protection": Skipped<Vec<String>>,
+    "zones": Skipped<Vec<String>>,
+    "region": Skipped<RegionInfo>,
+});
+
+schema_object!(ResourceIdentity {
+    "type": String,
+    "principalId": Skipped<String>,
+    "userAssignedIdentities": Skipped<HashMap<String, Value>>,
+});
+
+schema_object!(KubernetesMetadata {
+    "name": String,
+    "namespace": String,
+    "labels": HashMap<String, String>,
+});
+
+schema_object!(IpAddress {
+    "id": String,
+    "name": String,
+    "ipAddress": String,
+    "ipConfiguration": Skipped<String>,
+    "protection": Skipped<Vec<String>>,
+});
+
+schema_object!(DnsRecord {
+    "id": String,
+    "name": String,
+    "fqdn": String,
+    "entry": DnsRecordEntry,
+});
+
+schema_object!(Costs {
+    "resourceGroup": String,
+    "costs": f64,
+    "currency": String,
+    "date": Option<String>,
+});
+
+schema_object!(Route {
+    "name": String,
+    "properties": RouteProperties,
+});
+
+schema_object!(RouteProperties {
+    "addressPrefix": String,
+    "nextHopType": String,
+    "nextHopIpAddress": Skipped<String>,
+});
+
+schema_object!(EffectiveRoute {
+    "source": String,
+    "state": String,
+    "addressPrefix": Vec<String>,
+    "nextHopType": String,
+    "nextHopIpAddress": Vec<String>,
+});
+
+schema_object!(ListResult {
+    "subscription": Subscription,
+    "resourceGroups": Vec<ResourceGroup>,
+    "resources": Vec<Resource>,
+});
+
+schema_object!(ResourcesResult {
+    "subscription": Subscription,
+    "resources": Vec<TypedResource>,
+});
+
+schema_object!(TypedResource {
+    "id": String,
+    "name": String,
+    "resourceGroup": String,
+    "location": String,
+    "tags": HashMap<String, String>,
+    "raw": Skipped<Value>,
+});
+
+schema_object!(ClusterResult {
+    "subscription": Subscription,
+    "clusters": Vec<Cluster>,
+});
+
+schema_object!(Cluster {
+    "id": String,
+    "name": String,
+    "version": String,
+    "distribution": Option<String>,
+    "agent_pools": Option<Vec<AgentPool>>,
+    "objects": Option<Vec<KubernetesObject>>,
+    "restarts": Option<Vec<NamespaceRestarts>>,
+    "costs": Option<Costs>,
+});
+
+schema_object!(NamespaceRestarts {
+    "namespace": String,
+    "total": u64,
+    "lastHour": u64,
+    "lastDay": u64,
+    "crashLooping": u64,
+    "storm": bool,
+});
+
+schema_object!(AgentPool {
+    "name": String,
+    "count": u64,
+    "min_count": Option<u64>,
+    "max_count": Option<u64>,
+    "vm_size": String,
+});
+
+schema_object!(Domain {
+    "name": String,
+    "entries": Vec<Option<DnsRecordEntry>>,
+    "ipAddresses": Vec<DomainIpAddress>,
+    "resourceGroup": Skipped<ResourceGroup>,
+    "owner": Skipped<String>,
+});
+
+schema_object!(ResolveResult {
+    "hostname": String,
+    "zone": bool,
+    "entries": Vec<Option<DnsRecordEntry>>,
+    "ipAddresses": Vec<DomainIpAddress>,
+});
+
+schema_object!(DomainIpAddress {
+    "ipAddress": String,
+    "resourceGroup": Option<ResourceGroup>,
+});
+
+schema_object!(DnsResult {
+    "zone": Resource,
+    "records": Vec<DnsRecord>,
+});
+
+schema_object!(IpResult {
+    "subscription": Subscription,
+    "resourceGroups": Vec<IpResultResourceGroup>,
+});
+
+schema_object!(IpResultResourceGroup {
+    "resourceGroup": ResourceGroup,
+    "ipAddresses": Vec<IpAddress>,
+});
+
+schema_object!(CostResult {
+    "subscription": Subscription,
+    "period": String,
+    "costs": Vec<Costs>,
+});
+
+schema_object!(EndpointResult {
+    "subscription": Subscription,
+    "endpoints": Vec<Endpoint>,
+});
+
+schema_object!(Endpoint {
+    "id": String,
+    "name": String,
+    "connections": Vec<EndpointConnection>,
+    "privateDnsZones": Vec<String>,
+});
+
+schema_object!(EndpointConnection {
+    "target": String,
+    "groupIds": Vec<String>,
+    "status": String,
+    "description": Option<String>,
+});
+
+schema_object!(RoutingResult {
+    "subscription": Subscription,
+    "routeTables": Vec<RoutingTable>,
+});
+
+schema_object!(RoutingTable {
+    "id": String,
+    "name": String,
+    "bgpRoutePropagation": bool,
+    "routes": Vec<Route>,
+    "subnets": Vec<String>,
+});
+
+schema_object!(EffectiveRoutesResult {
+    "networkInterface": String,
+    "routes": Vec<EffectiveRoute>,
+});
+
+schema_object!(BastionResult {
+    "subscription": Subscription,
+    "bastions": Vec<Bastion>,
+    "exposedMachines": Vec<ExposedMachine>,
+});
+
+schema_object!(Bastion {
+    "id": String,
+    "name": String,
+    "sku": Option<String>,
+    "virtualNetworks": Vec<String>,
+});
+
+schema_object!(ExposedMachine {
+    "virtualMachine": String,
+    "networkInterface": String,
+    "ipAddress": Option<String>,
+    "virtualNetwork": Option<String>,
+});
+
+schema_object!(AutoscaleResult {
+    "subscription": Subscription,
+    "settings": Vec<Autoscale>,
+    "unscaled": Vec<UnscaledResource>,
+});
+
+schema_object!(Autoscale {
+    "id": String,
+    "name": String,
+    "target": String,
+    "enabled": bool,
+    "profiles": Vec<AutoscaleProfile>,
+});
+
+schema_object!(AutoscaleProfile {
+    "name": String,
+    "minimum": u32,
+    "maximum": u32,
+    "default": u32,
+    "rules": Vec<String>,
+});
+
+schema_object!(UnscaledResource {
+    "id": String,
+    "name": String,
+    "resourceType": String,
+    "cpuMin": Option<f64>,
+    "cpuMax": Option<f64>,
+    "variable": bool,
+});
+
+schema_object!(PlansResult {
+    "subscription": Subscription,
+    "plans": Vec<Plan>,
+});
+
+schema_object!(Plan {
+    "id": String,
+    "name": String,
+    "sku": Option<String>,
+    "tier": Option<String>,
+    "instances": u32,
+    "apps": Vec<PlanApp>,
+    "underused": bool,
+});
+
+schema_object!(PlanApp {
+    "name": String,
+    "kind": Option<String>,
+    "state": Option<String>,
+});
+
+schema_object!(ContainerAppsResult {
+    "subscription": Subscription,
+    "environments": Vec<ContainerAppEnvironment>,
+});
+
+schema_object!(ContainerAppEnvironment {
+    "id": String,
+    "name": String,
+    "location": String,
+    "defaultDomain": Option<String>,
+    "staticIp": Option<String>,
+    "apps": Vec<ContainerAppResult>,
+});
+
+schema_object!(ContainerAppResult {
+    "id": String,
+    "name": String,
+    "fqdn": Option<String>,
+    "external": bool,
+    "minReplicas": Option<u32>,
+    "maxReplicas": Option<u32>,
+    "scaleRules": Vec<ContainerAppScaleRuleResult>,
+    "revisions": Vec<ContainerAppRevisionResult>,
+});
+
+schema_object!(ContainerAppScaleRuleResult {
+    "name": String,
+    "type": String,
+});
+
+schema_object!(ContainerAppRevisionResult {
+    "name": String,
+    "replicas": u32,
+    "trafficWeight": u32,
+    "runningState": Option<String>,
+});
+
+schema_object!(StorageResult {
+    "subscription": Subscription,
+    "accounts": Vec<StorageAccountResult>,
+});
+
+schema_object!(StorageAccountResult {
+    "id": String,
+    "name": String,
+    "location": String,
+    "kind": Option<String>,
+    "sku": Option<String>,
+    "accessTier": Option<String>,
+    "usedGb": Skipped<f64>,
+    "transactions": Skipped<f64>,
+});
+
+schema_object!(SqlResult {
+    "subscription": Subscription,
+    "databases": Vec<SqlDatabaseResult>,
+});
+
+schema_object!(SqlDatabaseResult {
+    "id": String,
+    "name": String,
+    "server": String,
+    "sku": Option<String>,
+    "tier": Option<String>,
+    "capacity": Option<u32>,
+    "status": Option<String>,
+    "maxSizeGb": Option<f64>,
+    "usedGb": Skipped<f64>,
+    "utilizationMax": Skipped<f64>,
+    "overprovisioned": bool,
+});
+
+schema_object!(RedisResult {
+    "subscription": Subscription,
+    "caches": Vec<RedisCacheResult>,
+});
+
+schema_object!(RedisCacheResult {
+    "id": String,
+    "name": String,
+    "location": String,
+    "sku": String,
+    "size": String,
+    "sizeGb": Option<f64>,
+    "version": Option<String>,
+    "minimumTlsVersion": Option<String>,
+    "nonSslPort": bool,
+    "publicNetworkAccess": Option<String>,
+    "retiring": Vec<String>,
+});
+
+schema_object!(PeersResult {
+    "subscription": Subscription,
+    "circuits": Vec<CircuitResult>,
+    "gateways": Vec<GatewayResult>,
+});
+
+schema_object!(CircuitResult {
+    "id": String,
+    "name": String,
+    "provider": Option<String>,
+    "peeringLocation": Option<String>,
+    "bandwidthMbps": Option<u32>,
+    "state": Option<String>,
+    "peers": Vec<BgpPeerResult>,
+    "disconnected": bool,
+});
+
+schema_object!(GatewayResult {
+    "id": String,
+    "name": String,
+    "gatewayType": Option<String>,
+    "connections": Vec<GatewayConnectionResult>,
+    "peers": Vec<BgpPeerResult>,
+});
+
+schema_object!(GatewayConnectionResult {
+    "name": String,
+    "connectionType": Option<String>,
+    "status": Option<String>,
+    "disconnected": bool,
+});
+
+schema_object!(BgpPeerResult {
+    "name": String,
+    "neighbor": Option<String>,
+    "asn": Option<u64>,
+    "state": Option<String>,
+    "learnedRoutes": Option<u64>,
+    "advertisedRoutes": Option<u64>,
+    "disconnected": bool,
+});
+
+schema_object!(ImagesResult {
+    "subscription": Subscription,
+    "galleries": Vec<ImageGallery>,
+    "managedImages": Vec<ManagedImageResult>,
+});
+
+schema_object!(ImageGallery {
+    "id": String,
+    "name": String,
+    "definitions": Vec<ImageDefinition>,
+});
+
+schema_object!(ImageDefinition {
+    "id": String,
+    "name": String,
+    "osType": Option<String>,
+    "identifier": Option<String>,
+    "endOfLife": Option<String>,
+    "expired": bool,
+    "versions": Vec<ImageVersion>,
+});
+
+schema_object!(ImageVersion {
+    "name": String,
+    "regions": Vec<String>,
+    "published": Option<String>,
+    "endOfLife": Option<String>,
+    "expired": bool,
+});
+
+schema_object!(ManagedImageResult {
+    "id": String,
+    "name": String,
+    "location": String,
+    "osType": Option<String>,
+    "source": Option<String>,
+});
+
+schema_object!(FirewallResult {
+    "subscription": Subscription,
+    "firewalls": Vec<Firewall>,
+});
+
+schema_object!(Firewall {
+    "id": String,
+    "name": String,
+    "policy": Option<String>,
+    "rules": Vec<FirewallRule>,
+});
+
+schema_object!(FirewallRule {
+    "group": Option<String>,
+    "collection": String,
+    "priority": u32,
+    "action": String,
+    "name": String,
+    "ruleType": String,
+    "sources": Vec<String>,
+    "destinations": Vec<String>,
+    "ports": Vec<String>,
+});
+
+schema_object!(PortsResult {
+    "subscription": Subscription,
+    "exposures": Vec<PortExposure>,
+});
+
+schema_object!(PortExposure {
+    "ipAddress": IpAddress,
+    "networkInterface": String,
+    "networkSecurityGroups": Vec<String>,
+    "ports": Vec<String>,
+});
+
+schema_object!(IdentityResult {
+    "subscription": Subscription,
+    "identities": Vec<Identity>,
+});
+
+schema_object!(Identity {
+    "id": String,
+    "name": String,
+    "identityType": String,
+    "principalId": String,
+    "resources": Vec<String>,
+    "roles": Vec<IdentityRole>,
+});
+
+schema_object!(IdentityRole {
+    "role": String,
+    "scope": String,
+});
+
+schema_object!(PrincipalUsage {
+    "id": String,
+    "appId": Option<String>,
+    "displayName": Option<String>,
+    "owners": Vec<String>,
+    "references": Vec<PrincipalReference>,
+});
+
+schema_object!(PrincipalReference {
+    "source": String,
+    "resource": String,
+});
+
+schema_object!(KeyVaultAccessResult {
+    "subscription": Subscription,
+    "keyVaults": Vec<KeyVaultAccess>,
+});
+
+schema_object!(KeyVaultAccess {
+    "id": String,
+    "name": String,
+    "rbacAuthorization": bool,
+    "principals": Vec<KeyVaultPrincipal>,
+});
+
+schema_object!(KeyVaultPrincipal {
+    "id": String,
+    "displayName": Option<String>,
+    "principalType": Option<String>,
+    "source": String,
+});
+
+schema_object!(StorageKeysResult {
+    "subscription": Subscription,
+    "accounts": Vec<StorageAccountKeys>,
+});
+
+schema_object!(StorageAccountKeys {
+    "id": String,
+    "name": String,
+    "sharedKeyAccess": bool,
+    "maxAgeDays": u32,
+    "keys": Vec<StorageKey>,
+});
+
+schema_object!(StorageKey {
+    "name": String,
+    "creationTime": Option<String>,
+    "ageDays": Option<i64>,
+    "expired": bool,
+});
+
+schema_object!(TagsResult {
+    "subscription": Subscription,
+    "owners": Vec<TagsOwner>,
+});
+
+schema_object!(TagsOwner {
+    "owner": Option<String>,
+    "resources": Vec<TagsViolation>,
+});
+
+schema_object!(TagsViolation {
+    "id": String,
+    "name": String,
+    "resourceType": String,
+    "violations": Vec<String>,
+});
+
+schema_object!(ChangesResult {
+    "caller": String,
+    "changes": Vec<Change>,
+});
+
+schema_object!(Change {
+    "changeType": String,
+    "resource": String,
+    "resourceType": String,
+    "subscription": String,
+    "operations": u32,
+    "lastChange": String,
+});
+
+schema_object!(StaleResult {
+    "subscription": Subscription,
+    "resources": Vec<StaleResource>,
+});
+
+schema_object!(StaleResource {
+    "id": String,
+    "name": String,
+    "resourceType": String,
+    "lastModified": String,
+    "hints": Vec<String>,
+});
+
+schema_object!(AccountResult {
+    "user": Option<String>,
+    "tenant": String,
+    "expiresOn": Option<String>,
+    "expired": bool,
+    "subscription": Option<AccountSubscription>,
+});
+
+schema_object!(AccountSubscription {
+    "id": String,
+    "name": String,
+});
+
+schema_object!(ProvidersResult {
+    "subscription": Subscription,
+    "providers": Vec<ProviderRegistration>,
+});
+
+schema_object!(ProviderRegistration {
+    "namespace": String,
+    "registrationState": String,
+    "requiredBy": Vec<String>,
+    "missing": bool,
+});
+
+schema_object!(SubscriptionInfo {
+    "id": String,
+    "subscriptionId": String,
+    "displayName": String,
+    "state": Option<String>,
+    "tenantId": Option<String>,
+    "tenant": Skipped<String>,
+    "managementGroups": Vec<String>,
+    "spendingLimit": Option<String>,
+    "quotaId": Option<String>,
+    "tags": BTreeMap<String, String>,
+});
+
+schema_object!(TenantResult {
+    "id": String,
+    "name": Option<String>,
+    "defaultDomain": Option<String>,
+    "domains": Vec<String>,
+    "current": bool,
+});
+
+schema_object!(UseResult {
+    "id": String,
+    "name": String,
+    "tenant": Option<String>,
+});
+
+schema_object!(ExplainResult {
+    "command": String,
+    "operations": Vec<ExplainOperation>,
+    "actions": Vec<String>,
+});
+
+schema_object!(ExplainOperation {
+    "method": String,
+    "provider": String,
+    "path": String,
+    "apiVersion": String,
+    "action": String,
+});
+
+schema_object!(RoleDefinitionResult {
+    "Name": String,
+    "IsCustom": bool,
+    "Description": String,
+    "Actions": Vec<String>,
+    "NotActions": Vec<String>,
+    "DataActions": Vec<String>,
+    "NotDataActions": Vec<String>,
+    "AssignableScopes": Vec<String>,
+});
+
+schema_object!(TokenResult {
+    "accessToken": String,
+    "expiresOn": String,
+    "resource": String,
+    "tenant": String,
+});
+
+schema_object!(LoginResult {
+    "tenant": String,
+    "user": String,
+    "expiresOn": String,
+});
+
+schema_object!(LogoutResult {
+    "tenant": Option<String>,
+    "removed": u64,
+});
+
+schema_object!(MoveCheckResult {
+    "source": String,
+    "target": String,
+    "resources": Vec<MoveCheckResource>,
+    "errors": Vec<String>,
+});
+
+schema_object!(MoveCheckResource {
+    "id": String,
+    "name": String,
+    "resourceType": String,
+    "movable": bool,
+    "reasons": Vec<String>,
+});
+
+schema_object!(ConfigEntry {
+    "key": String,
+    "value": String,
+});
+
+schema_object!(CacheStats {
+    "path": String,
+    "entries": u64,
+    "size": u64,
+    "hits": u64,
+    "misses": u64,
+});
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::collections::BTreeSet;
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use serde_json::to_value;
+    use serde_json::Value;
+
+    use super::Schema;
+    use crate::cache::CacheStats;
+    use crate::commands::AccountResult;
+    use crate::commands::AccountSubscription;
+    use crate::commands::AgentPool;
+    use crate::commands::Autoscale;
+    use crate::commands::AutoscaleProfile;
+    use crate::commands::AutoscaleResult;
+    use crate::commands::Bastion;
+    use crate::commands::BastionResult;
+    use crate::commands::BgpPeerResult;
+    use crate::commands::Change;
+    use crate::commands::ChangesResult;
+    use crate::commands::CircuitResult;
+    use crate::commands::Cluster;
+    use crate::commands::ClusterResult;
+    use crate::commands::ContainerAppEnvironment;
+    use crate::commands::ContainerAppResult;
+    use crate::commands::ContainerAppRevisionResult;
+    use crate::commands::ContainerAppScaleRuleResult;
+    use crate::commands::ContainerAppsResult;
+    use crate::commands::CostResult;
+    use crate::commands::DnsResult;
+    use crate::commands::Domain;
+    use crate::commands::DomainIpAddress;
+    use crate::commands::EffectiveRoutesResult;
+    use crate::commands::Endpoint;
+    use crate::commands::EndpointConnection;
+    use crate::commands::EndpointResult;
+    use crate::commands::ExplainOperation;
+    use crate::commands::ExplainResult;
+    use crate::commands::ExposedMachine;
+    use crate::commands::Firewall;
+    use crate::commands::FirewallResult;
+    use crate::commands::FirewallRule;
+    use crate::commands::GatewayConnectionResult;
+    use crate::commands::GatewayResult;
+    use crate::commands::Identity;
+    use crate::commands::IdentityResult;
+    use crate::commands::IdentityRole;
+    use crate::commands::ImageDefinition;
+    use crate::commands::ImageGallery;
+    use crate::commands::ImageVersion;
+    use crate::commands::ImagesResult;
+    use crate::commands::IpResult;
+    use crate::commands::IpResultResourceGroup;
+    use crate::commands::KeyVaultAccess;
+    use crate::commands::KeyVaultAccessResult;
+    use crate::commands::KeyVaultPrincipal;
+    use crate::commands::ListResult;
+    use crate::commands::LoginResult;
+    use crate::commands::LogoutResult;
+    use crate::commands::ManagedImageResult;
+    use crate::commands::MoveCheckResource;
+    use crate::commands::MoveCheckResult;
+    use crate::commands::NamespaceRestarts;
+    use crate::commands::PeersResult;
+    use crate::commands::Plan;
+    use crate::commands::PlanApp;
+    use crate::commands::PlansResult;
+    use crate::commands::PortExposure;
+    use crate::commands::PortsResult;
+    use crate::commands::PrincipalReference;
+    use crate::commands::PrincipalUsage;
+    use crate::commands::ProviderRegistration;
+    use crate::commands::ProvidersResult;
+    use crate::commands::RedisCacheResult;
+    use crate::commands::RedisResult;
+    use crate::commands::ResolveResult;
+    use crate::commands::ResourcesResult;
+    use crate::commands::RoleDefinitionResult;
+    use crate::commands::RoutingResult;
+    use crate::commands::RoutingTable;
+    use crate::commands::SqlDatabaseResult;
+    use crate::commands::SqlResult;
+    use crate::commands::StaleResource;
+    use crate::commands::StaleResult;
+    use crate::commands::StorageAccountKeys;
+    use crate::commands::StorageAccountResult;
+    use crate::commands::StorageKey;
+    use crate::commands::StorageKeysResult;
+    use crate::commands::StorageResult;
+    use crate::commands::SubscriptionInfo;
+    use crate::commands::TagsOwner;
+    use crate::commands::TagsResult;
+    use crate::commands::TagsViolation;
+    use crate::commands::TenantResult;
+    use crate::commands::TokenResult;
+    use crate::commands::TypedResource;
+    use crate::commands::UnscaledResource;
+    use crate::commands::UseResult;
+    use crate::config::ConfigEntry;
+    use crate::object::Costs;
+    use crate::object::DnsRecord;
+    use crate::object::DnsRecordEntry;
+    use crate::object::EffectiveRoute;
+    use crate::object::IpAddress;
+    use crate::object::KubernetesMetadata;
+    use crate::object::KubernetesObject;
+    use crate::object::RegionInfo;
+    use crate::object::Resource;
+    use crate::object::ResourceGroup;
+    use crate::object::ResourceIdentity;
+    use crate::object::Route;
+    use crate::object::RouteProperties;
+    use crate::object::Subscription;
+
+    /// Validates the value against the subset of JSON Schema the schemas use, and collects
+    /// the paths of the properties it has, with `[]` for the items of arrays
+    fn validate(
+        schema: &Value,
+        value: &Value,
+        path: &str,
+        seen: &mut BTreeSet<String>,
+    ) -> Result<(), String> {
+        let error = |message: &str| Err(format!("{}: {} in {}", path, message, value));
+        if let Some(schemas) = schema["anyOf"].as_array().or(schema["oneOf"].as_array()) {
+            let mut matches = 0;
+            for schema in schemas {
+                let mut branch_seen = BTreeSet::new();
+                if validate(schema, value, path, &mut branch_seen).is_ok() {
+                    seen.append(&mut branch_seen);
+                    matches += 1;
+                }
+            }
+            return match matches {
+                0 => error("no schema matches"),
+                1 => Ok(()),
+                _ if schema["anyOf"].is_array() => Ok(()),
+                _ => error("more than one schema matches"),
+            };
+        }
+        let valid = match schema["type"].as_str() {
+            None => true,
+            Some("string") => value.is_string(),
+            Some("boolean") => value.is_boolean(),
+            Some("number") => value.is_number(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("null") => value.is_null(),
+            Some("array") => value.is_array(),
+            Some("object") => value.is_object(),
+            Some(other) => return error(&format!("unknown type {}", other)),
+        };
+        if !valid {
+            return error(&format!("expected {}", schema["type"]));
+        }
+        if schema["minimum"] == 0 && value.is_i64() && !value.is_u64() {
+            return error("negative");
+        }
+        if let Some(items) = value.as_array() {
+            for item in items {
+                validate(&schema["items"], item, &format!("{}[]", path), seen)?;
+            }
+        }
+        if let Some(object) = value.as_object() {
+            for required in schema["required"].as_array().into_iter().flatten() {
+                if !object.contains_key(required.as_str().unwrap()) {
+                    return error(&format!("missing {}", required));
+                }
+            }
+            for (name, value) in object {
+                let path = format!("{}.{}", path, name);
+                match (&schema["properties"][name], &schema["additionalProperties"]) {
+                    (Value::Null, Value::Bool(false)) => {
+                        return Err(format!("{}: not in the schema", path))
+                    }
+                    (Value::Null, Value::Null) => {}
+                    (Value::Null, additional) => validate(additional, value, &path, seen)?,
+                    (property, _) => {
+                        validate(property, value, &path, seen)?;
+                        seen.insert(path);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The paths of all properties of the schema, like `validate` collects them
+    fn schema_paths(schema: &Value, path: &str, paths: &mut BTreeSet<String>) {
+        let schemas = schema["anyOf"].as_array().or(schema["oneOf"].as_array());
+        for schema in schemas.into_iter().flatten() {
+            schema_paths(schema, path, paths);
+        }
+        if schema["items"].is_object() {
+            schema_paths(&schema["items"], &format!("{}[]", path), paths);
+        }
+        for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+            let path = format!("{}.{}", path, name);
+            schema_paths(property, &path, paths);
+            paths.insert(path);
+        }
+    }
+
+    /// `Some` in the instance with all optional properties, `None` in the one without
+    fn optional<T>(full: bool, value: T) -> Option<T> {
+        if full {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    /// One item in the instance with all optional properties, none in the one without
+    fn list<T>(full: bool, value: T) -> Vec<T> {
+        optional(full, value).into_iter().collect()
+    }
+
+    fn subscription(full: bool) -> Subscription {
+        Subscription {
+            id: "/subscriptions/1".to_owned(),
+            subscription_id: "1".to_owned(),
+            name: "test".to_owned(),
+            tenant: optional(full, "contoso.com".to_owned()),
+            tags: None,
+            state: None,
+            tenant_id: None,
+            policies: None,
+        }
+    }
+
+    fn region(full: bool) -> RegionInfo {
+        RegionInfo {
+            zones_supported: true,
+            paired_region: optional(full, "northeurope".to_owned()),
+        }
+    }
+
+    fn resource_group(full: bool) -> ResourceGroup {
+        ResourceGroup {
+            id: "/subscriptions/1/resourceGroups/web".to_owned(),
+            location: "westeurope".to_owned(),
+            name: "web".to_owned(),
+            protection: list(full, "CanNotDelete".to_owned()),
+            tags: None,
+            region: optional(full, region(full)),
+        }
+    }
+
+    fn resource(full: bool) -> Resource {
+        let identity = ResourceIdentity {
+            identity_type: "SystemAssigned, UserAssigned".to_owned(),
+            principal_id: optional(full, "2".to_owned()),
+            user_assigned_identities: list(full, ("/identities/app".to_owned(), json!({})))
+                .into_iter()
+                .collect(),
+        };
+        Resource {
+            id: "/subscriptions/1/resourceGroups/web/providers/Microsoft.Web/sites/app".to_owned(),
+            resource_type: "Microsoft.Web/sites".to_owned(),
+            location: "westeurope".to_owned(),
+            name: "app".to_owned(),
+            identity: optional(full, identity),
+            properties: optional(full, json!({ "state": "Running" })),
+            created_time: optional(full, "2024-01-01T00:00:00Z".to_owned()),
+            changed_time: optional(full, "2024-02-01T00:00:00Z".to_owned()),
+            protection: list(full, "CanNotDelete".to_owned()),
+            tags: None,
+            zones: list(full, "1".to_owned()),
+            region: optional(full, region(full)),
+        }
+    }
+
+    fn ip_address(full: bool) -> IpAddress {
+        IpAddress {
+            id: "/publicIPAddresses/ip".to_owned(),
+            name: "ip".to_owned(),
+            ip_address: "20.0.0.1".to_owned(),
+            ip_configuration: optional(full, "/ipConfigurations/default".to_owned()),
+            protection: list(full, "CanNotDelete".to_owned()),
+        }
+    }
+
+    fn domain_ip_address(full: bool) -> DomainIpAddress {
+        DomainIpAddress {
+            ip_address: "20.0.0.1".to_owned(),
+            resource_group: optional(full, resource_group(full)),
+        }
+    }
+
+    fn dns_entries() -> Vec<Option<DnsRecordEntry>> {
+        vec![
+            Some(DnsRecordEntry::A(vec!["20.0.0.1".to_owned()])),
+            Some(DnsRecordEntry::CNAME("app.azurewebsites.net".to_owned())),
+            None,
+        ]
+    }
+
+    fn costs(full: bool) -> Costs {
+        Costs {
+            resource_group: "web".to_owned(),
+            costs: 12.5,
+            currency: "EUR".to_owned(),
+            date: optional(full, "2024-01-01".to_owned()),
+        }
+    }
+
+    fn bgp_peer(full: bool) -> BgpPeerResult {
+        BgpPeerResult {
+            name: "peer".to_owned(),
+            neighbor: optional(full, "10.0.0.1".to_owned()),
+            asn: optional(full, 65515),
+            state: optional(full, "Connected".to_owned()),
+            learned_routes: optional(full, 10),
+            advertised_routes: optional(full, 2),
+            disconnected: false,
+        }
+    }
+
+    /// The schema of a result type and a function serializing an instance of it, with or
+    /// without the optional properties
+    type ResultType = (&'static str, Value, fn(bool) -> Value);
+
+    fn result_types() -> Vec<ResultType> {
+        vec![
+            ("list", ListResult::schema(), |full| {
+                to_value(ListResult {
+                    subscription: subscription(full),
+                    resource_groups: vec![resource_group(full)],
+                    resources: vec![resource(full)],
+                })
+                .unwrap()
+            }),
+            ("resources", ResourcesResult::schema(), |full| {
+                to_value(ResourcesResult {
+                    subscription: subscription(full),
+                    resources: vec![TypedResource {
+                        id: "/subscriptions/1/resourceGroups/web/providers/Microsoft.Web/sites/app"
+                            .to_owned(),
+                        name: "app".to_owned(),
+                        resource_group: "web".to_owned(),
+                        location: "westeurope".to_owned(),
+                        tags: HashMap::from([("owner".to_owned(), "team".to_owned())]),
+                        raw: if full {
+                            json!({ "kind": "app" })
+                        } else {
+                            Value::Null
+                        },
+                    }],
+                })
+                .unwrap()
+            }),
+            ("clusters", ClusterResult::schema(), |full| {
+                let metadata = KubernetesMetadata {
+                    name: "web".to_owned(),
+                    namespace: "default".to_owned(),
+                    labels: HashMap::new(),
+                };
+                let objects = vec![
+                    KubernetesObject::Service {
+                        metadata: metadata.clone(),
+                        service_type: "LoadBalancer".to_owned(),
+                        ip_addresses: vec!["20.0.0.1".parse().unwrap()],
+                    },
+                    KubernetesObject::Deployment {
+                        metadata,
+                        target: 2,
+                        ready: 1,
+                    },
+                ];
+                to_value(ClusterResult {
+                    subscription: subscription(full),
+                    clusters: vec![Cluster {
+                        id: "/managedClusters/aks".to_owned(),
+                        name: "aks".to_owned(),
+                        version: "1.29".to_owned(),
+                        distribution: optional(full, "aks".to_owned()),
+                        agent_pools: optional(
+                            full,
+                            vec![AgentPool {
+                                name: "system".to_owned(),
+                                count: 3,
+                                min_count: optional(full, 1),
+                                max_count: optional(full, 5),
+                                vm_size: "Standard_D4s_v5".to_owned(),
+                            }],
+                        ),
+                        objects: optional(full, objects),
+                        restarts: optional(
+                            full,
+                            vec![NamespaceRestarts {
+                                namespace: "default".to_owned(),
+                                total: 12,
+                                last_hour: 1,
+                                last_day: 4,
+                                crash_looping: 1,
+                                storm: false,
+                            }],
+                        ),
+                        costs: optional(full, costs(full)),
+                        kubeconfig: optional(full, "apiVersion: v1".to_owned()),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("domains", Domain::schema(), |full| {
+                to_value(Domain {
+                    name: "www.example.com".to_owned(),
+                    entries: dns_entries(),
+                    ip_addresses: vec![domain_ip_address(full)],
+                    resource_group: optional(full, resource_group(full)),
+                    owner: optional(full, "team".to_owned()),
+                })
+                .unwrap()
+            }),
+            ("dns", DnsResult::schema(), |full| {
+                let record = |entry| DnsRecord {
+                    id: "/dnszones/example.com/A/www".to_owned(),
+                    name: "www".to_owned(),
+                    fqdn: "www.example.com".to_owned(),
+                    entry,
+                };
+                to_value(DnsResult {
+                    zone: resource(full),
+                    records: dns_entries().into_iter().flatten().map(record).collect(),
+                })
+                .unwrap()
+            }),
+            ("resolve", ResolveResult::schema(), |full| {
+                to_value(ResolveResult {
+                    hostname: "www.example.com".to_owned(),
+                    zone: true,
+                    entries: dns_entries(),
+                    ip_addresses: vec![domain_ip_address(full)],
+                })
+                .unwrap()
+            }),
+            ("ip", IpResult::schema(), |full| {
+                to_value(IpResult {
+                    subscription: subscription(full),
+                    resource_groups: vec![IpResultResourceGroup {
+                        resource_group: resource_group(full),
+                        ip_addresses: vec![ip_address(full)],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("endpoints", EndpointResult::schema(), |full| {
+                to_value(EndpointResult {
+                    subscription: subscription(full),
+                    endpoints: vec![Endpoint {
+                        id: "/privateEndpoints/pe".to_owned(),
+                        name: "pe".to_owned(),
+                        connections: vec![EndpointConnection {
+                            target: "/storageAccounts/data".to_owned(),
+                            group_ids: vec!["blob".to_owned()],
+                            status: "Approved".to_owned(),
+                            description: optional(full, "Auto-approved".to_owned()),
+                        }],
+                        private_dns_zones: vec!["privatelink.blob.core.windows.net".to_owned()],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("routing", RoutingResult::schema(), |full| {
+                to_value(RoutingResult {
+                    subscription: subscription(full),
+                    route_tables: vec![RoutingTable {
+                        id: "/routeTables/default".to_owned(),
+                        name: "default".to_owned(),
+                        bgp_route_propagation: true,
+                        routes: vec![Route {
+                            name: "internet".to_owned(),
+                            properties: RouteProperties {
+                                address_prefix: "0.0.0.0/0".to_owned(),
+                                next_hop_type: "VirtualAppliance".to_owned(),
+                                next_hop_ip_address: optional(full, "10.0.0.4".to_owned()),
+                            },
+                        }],
+                        subnets: vec!["/subnets/default".to_owned()],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("routing effective", EffectiveRoutesResult::schema(), |_| {
+                to_value(EffectiveRoutesResult {
+                    network_interface: "/networkInterfaces/vm".to_owned(),
+                    routes: vec![EffectiveRoute {
+                        source: "Default".to_owned(),
+                        state: "Active".to_owned(),
+                        address_prefix: vec!["10.0.0.0/16".to_owned()],
+                        next_hop_type: "VnetLocal".to_owned(),
+                        next_hop_ip_address: vec![],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("firewall", FirewallResult::schema(), |full| {
+                to_value(FirewallResult {
+                    subscription: subscription(full),
+                    firewalls: vec![Firewall {
+                        id: "/azureFirewalls/fw".to_owned(),
+                        name: "fw".to_owned(),
+                        policy: optional(full, "/firewallPolicies/default".to_owned()),
+                        rules: vec![FirewallRule {
+                            group: optional(full, "DefaultNetworkRuleCollectionGroup".to_owned()),
+                            collection: "allow-web".to_owned(),
+                            priority: 100,
+                            action: "Allow".to_owned(),
+                            name: "https".to_owned(),
+                            rule_type: "NetworkRule".to_owned(),
+                            sources: vec!["10.0.0.0/16".to_owned()],
+                            destinations: vec!["*".to_owned()],
+                            ports: vec!["443".to_owned()],
+                        }],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("bastion", BastionResult::schema(), |full| {
+                to_value(BastionResult {
+                    subscription: subscription(full),
+                    bastions: vec![Bastion {
+                        id: "/bastionHosts/bastion".to_owned(),
+                        name: "bastion".to_owned(),
+                        sku: optional(full, "Standard".to_owned()),
+                        virtual_networks: vec!["/virtualNetworks/hub".to_owned()],
+                    }],
+                    exposed_machines: vec![ExposedMachine {
+                        virtual_machine: "/virtualMachines/vm".to_owned(),
+                        network_interface: "/networkInterfaces/vm".to_owned(),
+                        ip_address: optional(full, "20.0.0.1".to_owned()),
+                        virtual_network: optional(full, "/virtualNetworks/spoke".to_owned()),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("autoscale", AutoscaleResult::schema(), |full| {
+                to_value(AutoscaleResult {
+                    subscription: subscription(full),
+                    settings: vec![Autoscale {
+                        id: "/autoscaleSettings/web".to_owned(),
+                        name: "web".to_owned(),
+                        target: "/serverfarms/web".to_owned(),
+                        enabled: true,
+                        profiles: vec![AutoscaleProfile {
+                            name: "default".to_owned(),
+                            minimum: 1,
+                            maximum: 4,
+                            default: 2,
+                            rules: vec!["CpuPercentage > 70: +1".to_owned()],
+                        }],
+                    }],
+                    unscaled: vec![UnscaledResource {
+                        id: "/serverfarms/api".to_owned(),
+                        name: "api".to_owned(),
+                        resource_type: "Microsoft.Web/serverfarms".to_owned(),
+                        cpu_min: optional(full, 5.0),
+                        cpu_max: optional(full, 95.0),
+                        variable: true,
+                    }],
+                })
+                .unwrap()
+            }),
+            ("plans", PlansResult::schema(), |full| {
+                to_value(PlansResult {
+                    subscription: subscription(full),
+                    plans: vec![Plan {
+                        id: "/serverfarms/web".to_owned(),
+                        name: "web".to_owned(),
+                        sku: optional(full, "P1v3".to_owned()),
+                        tier: optional(full, "PremiumV3".to_owned()),
+                        instances: 1,
+                        apps: vec![PlanApp {
+                            name: "app".to_owned(),
+                            kind: optional(full, "app".to_owned()),
+                            state: optional(full, "Running".to_owned()),
+                        }],
+                        underused: false,
+                    }],
+                })
+                .unwrap()
+            }),
+            ("containerapps", ContainerAppsResult::schema(), |full| {
+                to_value(ContainerAppsResult {
+                    subscription: subscription(full),
+                    environments: vec![ContainerAppEnvironment {
+                        id: "/managedEnvironments/env".to_owned(),
+                        name: "env".to_owned(),
+                        location: "westeurope".to_owned(),
+                        default_domain: optional(full, "env.azurecontainerapps.io".to_owned()),
+                        static_ip: optional(full, "20.0.0.1".to_owned()),
+                        apps: vec![ContainerAppResult {
+                            id: "/containerApps/api".to_owned(),
+                            name: "api".to_owned(),
+                            fqdn: optional(full, "api.env.azurecontainerapps.io".to_owned()),
+                            external: true,
+                            min_replicas: optional(full, 1),
+                            max_replicas: optional(full, 10),
+                            scale_rules: vec![ContainerAppScaleRuleResult {
+                                name: "http".to_owned(),
+                                rule_type: "http".to_owned(),
+                            }],
+                            revisions: vec![ContainerAppRevisionResult {
+                                name: "api--1".to_owned(),
+                                replicas: 1,
+                                traffic_weight: 100,
+                                running_state: optional(full, "Running".to_owned()),
+                            }],
+                        }],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("images", ImagesResult::schema(), |full| {
+                to_value(ImagesResult {
+                    subscription: subscription(full),
+                    galleries: vec![ImageGallery {
+                        id: "/galleries/images".to_owned(),
+                        name: "images".to_owned(),
+                        definitions: vec![ImageDefinition {
+                            id: "/galleries/images/images/ubuntu".to_owned(),
+                            name: "ubuntu".to_owned(),
+                            os_type: optional(full, "Linux".to_owned()),
+                            identifier: optional(full, "Canonical/Ubuntu/22.04".to_owned()),
+                            end_of_life: optional(full, "2027-04-01".to_owned()),
+                            expired: false,
+                            versions: vec![ImageVersion {
+                                name: "1.0.0".to_owned(),
+                                regions: vec!["westeurope".to_owned()],
+                                published: optional(full, "2024-01-01".to_owned()),
+                                end_of_life: optional(full, "2025-01-01".to_owned()),
+                                expired: true,
+                            }],
+                        }],
+                    }],
+                    managed_images: vec![ManagedImageResult {
+                        id: "/images/legacy".to_owned(),
+                        name: "legacy".to_owned(),
+                        location: "westeurope".to_owned(),
+                        os_type: optional(full, "Windows".to_owned()),
+                        source: optional(full, "/virtualMachines/vm".to_owned()),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("storage", StorageResult::schema(), |full| {
+                to_value(StorageResult {
+                    subscription: subscription(full),
+                    accounts: vec![StorageAccountResult {
+                        id: "/storageAccounts/data".to_owned(),
+                        name: "data".to_owned(),
+                        location: "westeurope".to_owned(),
+                        kind: optional(full, "StorageV2".to_owned()),
+                        sku: optional(full, "Standard_LRS".to_owned()),
+                        access_tier: optional(full, "Hot".to_owned()),
+                        used_gb: optional(full, 1.5),
+                        transactions: optional(full, 1000.0),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("sql", SqlResult::schema(), |full| {
+                to_value(SqlResult {
+                    subscription: subscription(full),
+                    databases: vec![SqlDatabaseResult {
+                        id: "/servers/sql/databases/app".to_owned(),
+                        name: "app".to_owned(),
+                        server: "sql".to_owned(),
+                        sku: optional(full, "GP_Gen5_2".to_owned()),
+                        tier: optional(full, "GeneralPurpose".to_owned()),
+                        capacity: optional(full, 2),
+                        status: optional(full, "Online".to_owned()),
+                        max_size_gb: optional(full, 32.0),
+                        used_gb: optional(full, 4.5),
+                        utilization_max: optional(full, 12.5),
+                        overprovisioned: true,
+                    }],
+                })
+                .unwrap()
+            }),
+            ("redis", RedisResult::schema(), |full| {
+                to_value(RedisResult {
+                    subscription: subscription(full),
+                    caches: vec![RedisCacheResult {
+                        id: "/redis/cache".to_owned(),
+                        name: "cache".to_owned(),
+                        location: "westeurope".to_owned(),
+                        sku: "Standard".to_owned(),
+                        size: "C1".to_owned(),
+                        size_gb: optional(full, 1.0),
+                        version: optional(full, "6.0".to_owned()),
+                        minimum_tls_version: optional(full, "1.2".to_owned()),
+                        non_ssl_port: false,
+                        public_network_access: optional(full, "Disabled".to_owned()),
+                        retiring: list(full, "Redis 4".to_owned()),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("peers", PeersResult::schema(), |full| {
+                to_value(PeersResult {
+                    subscription: subscription(full),
+                    circuits: vec![CircuitResult {
+                        id: "/expressRouteCircuits/er".to_owned(),
+                        name: "er".to_owned(),
+                        provider: optional(full, "Equinix".to_owned()),
+                        peering_location: optional(full, "Amsterdam".to_owned()),
+                        bandwidth_mbps: optional(full, 1000),
+                        state: optional(full, "Provisioned".to_owned()),
+                        peers: vec![bgp_peer(full)],
+                        disconnected: false,
+                    }],
+                    gateways: vec![GatewayResult {
+                        id: "/virtualNetworkGateways/vpn".to_owned(),
+                        name: "vpn".to_owned(),
+                        gateway_type: optional(full, "Vpn".to_owned()),
+                        connections: vec![GatewayConnectionResult {
+                            name: "office".to_owned(),
+                            connection_type: optional(full, "IPsec".to_owned()),
+                            status: optional(full, "Connected".to_owned()),
+                            disconnected: false,
+                        }],
+                        peers: vec![bgp_peer(full)],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("identity", IdentityResult::schema(), |full| {
+                to_value(IdentityResult {
+                    subscription: subscription(full),
+                    identities: vec![Identity {
+                        id: "/userAssignedIdentities/app".to_owned(),
+                        name: "app".to_owned(),
+                        identity_type: "UserAssigned".to_owned(),
+                        principal_id: "2".to_owned(),
+                        resources: vec!["/sites/app".to_owned()],
+                        roles: vec![IdentityRole {
+                            role: "Reader".to_owned(),
+                            scope: "/subscriptions/1".to_owned(),
+                        }],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("sp-usage", PrincipalUsage::schema(), |full| {
+                to_value(PrincipalUsage {
+                    id: "2".to_owned(),
+                    app_id: optional(full, "3".to_owned()),
+                    display_name: optional(full, "deploy".to_owned()),
+                    owners: vec!["admin@contoso.com".to_owned()],
+                    references: vec![PrincipalReference {
+                        source: "roleAssignment".to_owned(),
+                        resource: "/subscriptions/1".to_owned(),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("changes", ChangesResult::schema(), |_| {
+                to_value(ChangesResult {
+                    caller: "admin@contoso.com".to_owned(),
+                    changes: vec![Change {
+                        change_type: "Update".to_owned(),
+                        resource: "/sites/app".to_owned(),
+                        resource_type: "Microsoft.Web/sites".to_owned(),
+                        subscription: "1".to_owned(),
+                        operations: 2,
+                        last_change: "2024-01-01T00:00:00Z".to_owned(),
+                    }],
+                })
+                .unwrap()
+            }),
+            ("stale", StaleResult::schema(), |full| {
+                to_value(StaleResult {
+                    subscription: subscription(full),
+                    resources: vec![StaleResource {
+                        id: "/disks/old".to_owned(),
+                        name: "old".to_owned(),
+                        resource_type: "Microsoft.Compute/disks".to_owned(),
+                        last_modified: "2022-01-01T00:00:00Z".to_owned(),
+                        hints: vec!["unattached".to_owned()],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("move-check", MoveCheckResult::schema(), |_| {
+                to_value(MoveCheckResult {
+                    source: "/subscriptions/1/resourceGroups/web".to_owned(),
+                    target: "/subscriptions/2/resourceGroups/web".to_owned(),
+                    resources: vec![MoveCheckResource {
+                        id: "/sites/app".to_owned(),
+                        name: "app".to_owned(),
+                        resource_type: "Microsoft.Web/sites".to_owned(),
+                        movable: false,
+                        reasons: vec!["not supported".to_owned()],
+                    }],
+                    errors: vec![],
+                })
+                .unwrap()
+            }),
+            ("costs", CostResult::schema(), |full| {
+                to_value(CostResult {
+                    subscription: subscription(full),
+                    period: "2024-01".to_owned(),
+                    costs: vec![costs(full)],
+                })
+                .unwrap()
+            }),
+            ("audit ports", PortsResult::schema(), |full| {
+                to_value(PortsResult {
+                    subscription: subscription(full),
+                    exposures: vec![PortExposure {
+                        ip_address: ip_address(full),
+                        network_interface: "/networkInterfaces/vm".to_owned(),
+                        network_security_groups: vec!["/networkSecurityGroups/vm".to_owned()],
+                        ports: vec!["22".to_owned()],
+                    }],
+                })
+                .unwrap()
+            }),
+            (
+                "audit keyvault-access",
+                KeyVaultAccessResult::schema(),
+                |full| {
+                    to_value(KeyVaultAccessResult {
+                        subscription: subscription(full),
+                        key_vaults: vec![KeyVaultAccess {
+                            id: "/vaults/secrets".to_owned(),
+                            name: "secrets".to_owned(),
+                            rbac_authorization: false,
+                            principals: vec![KeyVaultPrincipal {
+                                id: "2".to_owned(),
+                                display_name: optional(full, "deploy".to_owned()),
+                                principal_type: optional(full, "ServicePrincipal".to_owned()),
+                                source: "accessPolicy".to_owned(),
+                            }],
+                        }],
+                    })
+                    .unwrap()
+                },
+            ),
+            ("audit storage-keys", StorageKeysResult::schema(), |full| {
+                to_value(StorageKeysResult {
+                    subscription: subscription(full),
+                    accounts: vec![StorageAccountKeys {
+                        id: "/storageAccounts/data".to_owned(),
+                        name: "data".to_owned(),
+                        shared_key_access: true,
+                        max_age_days: 90,
+                        keys: vec![StorageKey {
+                            name: "key1".to_owned(),
+                            creation_time: optional(full, "2024-01-01T00:00:00Z".to_owned()),
+                            age_days: optional(full, 120),
+                            expired: true,
+                        }],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("audit tags", TagsResult::schema(), |full| {
+                to_value(TagsResult {
+                    subscription: subscription(full),
+                    owners: vec![TagsOwner {
+                        owner: optional(full, "team".to_owned()),
+                        resources: vec![TagsViolation {
+                            id: "/sites/app".to_owned(),
+                            name: "app".to_owned(),
+                            resource_type: "Microsoft.Web/sites".to_owned(),
+                            violations: vec!["missing tag cost-center".to_owned()],
+                        }],
+                    }],
+                })
+                .unwrap()
+            }),
+            ("account", AccountResult::schema(), |full| {
+                to_value(AccountResult {
+                    user: optional(full, "admin@contoso.com".to_owned()),
+                    tenant: "contoso.com".to_owned(),
+                    expires_on: optional(full, "2024-01-01 12:00:00".to_owned()),
+                    expired: false,
+                    subscription: optional(
+                        full,
+                        AccountSubscription {
+                            id: "1".to_owned(),
+                            name: "test".to_owned(),
+                        },
+                    ),
+                })
+                .unwrap()
+            }),
+            ("subs", SubscriptionInfo::schema(), |full| {
+                to_value(SubscriptionInfo {
+                    id: "/subscriptions/1".to_owned(),
+                    subscription_id: "1".to_owned(),
+                    name: "test".to_owned(),
+                    state: optional(full, "Enabled".to_owned()),
+                    tenant_id: optional(full, "4".to_owned()),
+                    tenant: optional(full, "contoso.com".to_owned()),
+                    management_groups: vec!["root".to_owned()],
+                    spending_limit: optional(full, "Off".to_owned()),
+                    quota_id: optional(full, "EnterpriseAgreement_2014-09-01".to_owned()),
+                    tags: BTreeMap::from([("owner".to_owned(), "team".to_owned())]),
+                })
+                .unwrap()
+            }),
+            ("providers", ProvidersResult::schema(), |full| {
+                to_value(ProvidersResult {
+                    subscription: subscription(full),
+                    providers: vec![ProviderRegistration {
+                        namespace: "Microsoft.Web".to_owned(),
+                        registration_state: "Registered".to_owned(),
+                        required_by: vec!["Microsoft.Web/sites".to_owned()],
+                        missing: false,
+                    }],
+                })
+                .unwrap()
+            }),
+            ("tenants", TenantResult::schema(), |full| {
+                to_value(TenantResult {
+                    id: "4".to_owned(),
+                    name: optional(full, "Contoso".to_owned()),
+                    default_domain: optional(full, "contoso.com".to_owned()),
+                    domains: vec!["contoso.com".to_owned()],
+                    current: true,
+                })
+                .unwrap()
+            }),
+            ("use", UseResult::schema(), |full| {
+                to_value(UseResult {
+                    id: "1".to_owned(),
+                    name: "test".to_owned(),
+                    tenant: optional(full, "contoso.com".to_owned()),
+                })
+                .unwrap()
+            }),
+            ("explain", ExplainResult::schema(), |_| {
+                to_value(ExplainResult {
+                    command: "list".to_owned(),
+                    operations: vec![ExplainOperation {
+                        method: "GET".to_owned(),
+                        provider: "Microsoft.Resources".to_owned(),
+                        path: "/subscriptions/{id}/resources".to_owned(),
+                        api_version: "2021-04-01".to_owned(),
+                        action: "Microsoft.Resources/subscriptions/resources/read".to_owned(),
+                    }],
+                    actions: vec!["Microsoft.Resources/subscriptions/resources/read".to_owned()],
+                })
+                .unwrap()
+            }),
+            ("rbac-minrole", RoleDefinitionResult::schema(), |_| {
+                to_value(RoleDefinitionResult {
+                    name: "azi reader".to_owned(),
+                    is_custom: true,
+                    description: "Read access for azi".to_owned(),
+                    actions: vec!["Microsoft.Resources/subscriptions/read".to_owned()],
+                    not_actions: vec![],
+                    data_actions: vec![],
+                    not_data_actions: vec![],
+                    assignable_scopes: vec!["/subscriptions/1".to_owned()],
+                })
+                .unwrap()
+            }),
+            ("token", TokenResult::schema(), |_| {
+                to_value(TokenResult {
+                    access_token: "eyJ0".to_owned(),
+                    expires_on: "2024-01-01 12:00:00".to_owned(),
+                    resource: "https://management.azure.com/".to_owned(),
+                    tenant: "4".to_owned(),
+                })
+                .unwrap()
+            }),
+            ("login", LoginResult::schema(), |_| {
+                to_value(LoginResult {
+                    tenant: "4".to_owned(),
+                    user: "admin@contoso.com".to_owned(),
+                    expires_on: "2024-01-01 12:00:00".to_owned(),
+                })
+                .unwrap()
+            }),
+            ("logout", LogoutResult::schema(), |full| {
+                to_value(LogoutResult {
+                    tenant: optional(full, "4".to_owned()),
+                    removed: 1,
+                })
+                .unwrap()
+            }),
+            ("config", ConfigEntry::schema(), |_| {
+                to_value(ConfigEntry {
+                    key: "output".to_owned(),
+                    value: "json".to_owned(),
+                })
+                .unwrap()
+            }),
+            ("cache", CacheStats::schema(), |_| {
+                to_value(CacheStats {
+                    path: "/home/user/.azi/cache".to_owned(),
+                    entries: 2,
+                    size: 1024,
+                    hits: 3,
+                    misses: 1,
+                })
+                .unwrap()
+            }),
+        ]
+    }
+
+    /// Every result type is valid without its optional properties, and with them it has
+    /// every property of its schema
+    #[test]
+    fn test_result_types() {
+        for (name, schema, serialize) in result_types() {
+            let mut seen = BTreeSet::new();
+            for full in [false, true] {
+                if let Err(err) = validate(&schema, &serialize(full), "", &mut seen) {
+                    panic!("{} does not match its schema: {}", name, err);
+                }
+            }
+            let mut paths = BTreeSet::new();
+            schema_paths(&schema, "", &mut paths);
+            let missing: Vec<_> = paths.difference(&seen).collect();
+            assert!(missing.is_empty(), "{} never has {:?}", name, missing);
+        }
+    }
+
+    fn property_names(schema: &serde_json::Value) -> Vec<String> {
+        let mut names: Vec<String> = schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_schema_matches_serialization() {
+        let subscription = Subscription {
+            id: "/subscriptions/1".to_owned(),
+            subscription_id: "1".to_owned(),
+            name: "test".to_owned(),
+            tenant: Some("contoso.com".to_owned()),
+            tags: None,
+            state: None,
+            tenant_id: None,
+            policies: None,
+        };
+        let json = to_value(&subscription).unwrap();
+        let mut keys: Vec<String> = json.as_object().unwrap().keys().cloned().collect();
+        keys.sort();
+        assert_eq!(keys, property_names(&Subscription::schema()));
+    }
+
+    #[test]
+    fn test_required() {
+        assert_eq!(
+            json!(["id", "type", "location", "name"]),
+            Resource::schema()["required"]
+        );
+        assert_eq!(
+            json!({ "anyOf": [{ "type": "string" }, { "type": "null" }] }),
+            EndpointConnection::schema()["properties"]["description"]
+        );
+    }
+}
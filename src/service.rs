@@ -1,38 +1,131 @@
+use std::collections::HashMap;
 use std::net::IpAddr;
 use std::str::from_utf8;
+use std::sync::Mutex;
+use std::time::SystemTime;
 
 use base64::decode;
+use chrono::Datelike;
+use chrono::Utc;
+use regex::Regex;
+use regex::RegexBuilder;
+use serde::de::DeserializeOwned;
 use serde_derive::Deserialize;
+use serde_derive::Serialize;
+use serde_json::from_value;
 use serde_json::json;
 use serde_json::Value;
+use url::form_urlencoded::byte_serialize;
 use url::Url;
+use yaml_rust::yaml::Hash;
 use yaml_rust::Yaml;
+use yaml_rust::YamlEmitter;
 use yaml_rust::YamlLoader;
 
+use crate::auth::TokenSet;
 use crate::client::Client;
 use crate::client::Request;
+use crate::error::AppError;
+use crate::error::AppError::HttpError;
+use crate::error::AppError::ParseError;
 use crate::error::AppError::ServiceError;
+use crate::http::AccessStats;
 use crate::http::Header;
 use crate::http::Http;
+use crate::http::RateLimit;
+use crate::http::RequestTiming;
+use crate::http::ResponseHeaders;
+use crate::object::ActivityLogEvent;
 use crate::object::AgentPool;
+use crate::object::AppServicePlan;
+use crate::object::AutoscaleSetting;
+use crate::object::AzureFirewall;
+use crate::object::AzureTenant;
+use crate::object::BastionHost;
+use crate::object::BgpPeerStatus;
+use crate::object::ConnectedCluster;
+use crate::object::ContainerApp;
+use crate::object::ContainerAppRevision;
+use crate::object::ContainerRestarts;
 use crate::object::Costs;
+use crate::object::DenyAssignment;
+use crate::object::DirectoryObject;
 use crate::object::DnsRecord;
 use crate::object::DnsRecordEntry;
+use crate::object::EffectiveRoute;
+use crate::object::ExpressRouteCircuit;
+use crate::object::ExpressRouteRouteSummary;
+use crate::object::FirewallPolicyRuleCollectionGroup;
+use crate::object::Gallery;
+use crate::object::GalleryImage;
+use crate::object::GalleryImageVersion;
+use crate::object::Identifiable;
 use crate::object::IpAddress;
+use crate::object::KeyVault;
 use crate::object::KubernetesMetadata;
 use crate::object::KubernetesObject;
+use crate::object::Location;
 use crate::object::ManagedCluster;
+use crate::object::ManagedEnvironment;
+use crate::object::ManagedImage;
+use crate::object::ManagementLock;
+use crate::object::NetworkInterface;
+use crate::object::NetworkSecurityGroup;
+use crate::object::PrivateDnsZoneGroup;
+use crate::object::PrivateEndpoint;
+use crate::object::Provider;
+use crate::object::RedisCache;
 use crate::object::Resource;
 use crate::object::ResourceGroup;
+use crate::object::RoleAssignment;
+use crate::object::RoleDefinition;
+use crate::object::RouteTable;
+use crate::object::SqlDatabase;
+use crate::object::SqlServer;
+use crate::object::StorageAccount;
 use crate::object::Subscription;
+use crate::object::SubscriptionContainer;
+use crate::object::TrafficManagerProfile;
+use crate::object::UserAssignedIdentity;
+use crate::object::VirtualMachineStatus;
+use crate::object::VirtualNetwork;
+use crate::object::VirtualNetworkGateway;
+use crate::object::VirtualNetworkGatewayConnection;
+use crate::object::WebApp;
+use crate::policy::find_tag;
+use crate::tenant::Tenant;
 use crate::utils::Result;
 use crate::utils::ValueExt;
 
 pub const TYPE_DNS_ZONE: &'static str = "Microsoft.Network/dnsZones";
+pub const TYPE_MANAGED_ENVIRONMENT: &'static str = "Microsoft.App/managedEnvironments";
+pub const TYPE_WEB_APP: &'static str = "Microsoft.Web/sites";
+pub const TYPE_TRAFFIC_MANAGER_PROFILE: &'static str = "Microsoft.Network/trafficmanagerprofiles";
 
 pub struct Service {
     client: Client,
     filter: Filter,
+    tenant_label: Option<String>,
+    warnings: Mutex<Vec<Warning>>,
+}
+
+/// An item of a response which was left out of the result, because it could not be parsed
+#[derive(Debug, Clone, Serialize)]
+pub struct Warning {
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub item: Value,
+}
+
+impl Warning {
+    fn skipped(message: String, item: &Value) -> Warning {
+        Warning {
+            message,
+            id: item["id"].as_str().map(str::to_owned),
+            item: item.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -41,68 +134,301 @@ pub enum Timeframe {
     Custom { from: String, to: String },
 }
 
+impl Timeframe {
+    /// Returns the period as ISO 8601 interval, like `2019-03-01/2019-03-31`
+    pub fn period(&self) -> String {
+        match self {
+            Timeframe::MonthToDate => {
+                let today = Utc::today();
+                format!(
+                    "{:04}-{:02}-01/{}",
+                    today.year(),
+                    today.month(),
+                    today.format("%Y-%m-%d")
+                )
+            }
+            Timeframe::Custom { from, to } => format!("{}/{}", from, to),
+        }
+    }
+}
+
 pub struct Filter {
-    filter: Option<String>,
+    filters: Vec<Pattern>,
+    excludes: Vec<Pattern>,
+    subscriptions: Vec<String>,
+    tags: Vec<(String, Option<String>)>,
 }
 
-impl Filter {
-    pub fn new(filter: Option<&str>) -> Self {
-        Filter {
-            filter: filter.map(&str::to_lowercase),
+enum Pattern {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Result<Pattern> {
+        match pattern.strip_prefix("re:") {
+            Some(re) => RegexBuilder::new(re)
+                .case_insensitive(true)
+                .build()
+                .map(Pattern::Regex)
+                .map_err(|err| ParseError(format!("invalid filter regex: {}", err)).into()),
+            None => Ok(Pattern::Substring(pattern.to_lowercase())),
         }
     }
 
-    pub fn matches(&self, s: &Subscription) -> bool {
-        match &self.filter {
-            Some(filter) => {
+    fn matches(&self, s: &Subscription) -> bool {
+        match self {
+            Pattern::Substring(filter) => {
                 &s.subscription_id.to_lowercase() == filter
-                    || s.name.to_lowercase().contains(filter)
+                    || s.name.to_lowercase().contains(filter.as_str())
+            }
+            Pattern::Regex(re) => re.is_match(&s.subscription_id) || re.is_match(&s.name),
+        }
+    }
+}
+
+impl Filter {
+    /// Subscriptions match if any filter matches, filters starting with `!` exclude subscriptions
+    /// and filters starting with `re:` are regular expressions
+    pub fn new(filters: &[&str]) -> Result<Self> {
+        let mut include = vec![];
+        let mut exclude = vec![];
+        for filter in filters {
+            match filter.strip_prefix('!') {
+                Some(negated) => exclude.push(Pattern::parse(negated)?),
+                None => include.push(Pattern::parse(filter)?),
+            }
+        }
+        Ok(Filter {
+            filters: include,
+            excludes: exclude,
+            subscriptions: vec![],
+            tags: vec![],
+        })
+    }
+
+    /// Only use subscriptions, resource groups and resources with all the given tags,
+    /// either `key=value` or just `key` to allow any value
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags
+            .iter()
+            .map(|tag| match tag.find('=') {
+                Some(pos) => (tag[..pos].to_owned(), Some(tag[pos + 1..].to_owned())),
+                None => (tag.to_string(), None),
+            })
+            .collect();
+        return self;
+    }
+
+    pub fn matches_tags(&self, tags: Option<&HashMap<String, String>>) -> bool {
+        self.tags.iter().all(|(key, value)| {
+            match (tags.and_then(|tags| find_tag(tags, key)), value) {
+                (Some(actual), Some(value)) => actual == value,
+                (Some(_), None) => true,
+                (None, _) => false,
             }
-            None => true,
+        })
+    }
+
+    /// Only use the subscriptions with the given IDs or names
+    pub fn with_subscriptions(mut self, subscriptions: &[&str]) -> Self {
+        self.subscriptions = subscriptions.iter().map(|s| s.to_lowercase()).collect();
+        return self;
+    }
+
+    /// Returns the selected subscription IDs, if all selected subscriptions are given by ID
+    fn subscription_ids(&self) -> Option<&[String]> {
+        let is_id =
+            |s: &String| s.len() == 36 && s.chars().all(|c| c == '-' || c.is_ascii_hexdigit());
+        if !self.subscriptions.is_empty() && self.subscriptions.iter().all(is_id) {
+            Some(&self.subscriptions)
+        } else {
+            None
+        }
+    }
+
+    pub fn matches(&self, s: &Subscription) -> bool {
+        if !self.subscriptions.is_empty()
+            && !self.subscriptions.iter().any(|selected| {
+                &s.subscription_id.to_lowercase() == selected || &s.name.to_lowercase() == selected
+            })
+        {
+            return false;
         }
+        if self.excludes.iter().any(|filter| filter.matches(s)) {
+            return false;
+        }
+        self.filters.is_empty() || self.filters.iter().any(|filter| filter.matches(s))
     }
 }
 
-const DEFAULT_PREFIX: &'static str = "https://management.azure.com/";
-const DEFAULT_RESOURCE: &'static str = "https://management.core.windows.net/";
+const GRAPH_RESOURCE: &'static str = "https://graph.microsoft.com/";
+const GRAPH_HOST: &str = "graph.microsoft.com";
 
 impl Service {
     pub fn new(client: Client, filter: Filter) -> Service {
-        return Service { client, filter };
+        return Service {
+            client,
+            filter,
+            tenant_label: None,
+            warnings: Mutex::new(vec![]),
+        };
     }
 
-    pub fn get(&self, request: &str, resource: &str) -> Result<Value> {
-        let url = &Service::to_url(request);
-        if Service::is_azure(url)? {
-            self.with_request(url, resource, |request| request.get_raw())
-        } else {
-            self.client.http().get(url)?.success()
+    /// Marks all subscriptions with the given label, to group the output of multiple tenants
+    pub fn with_tenant_label(mut self, label: String) -> Service {
+        self.tenant_label = Some(label);
+        return self;
+    }
+
+    /// Returns `true` if the tags match the `--tag` filter, or if there is none
+    pub fn matches_tags(&self, tags: Option<&HashMap<String, String>>) -> bool {
+        self.filter.matches_tags(tags)
+    }
+
+    /// Requests the URL or path with the additional query parameters, like `$filter`
+    pub fn get(&self, request: &str, resource: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let url = &to_url(self.client.arm_endpoint(), request);
+        match self.token_resource(url, resource)? {
+            Some(resource) => self.with_request(url, resource, |request| {
+                query
+                    .iter()
+                    .fold(request.uncached(), |request, (name, value)| {
+                        request.query(name, value)
+                    })
+                    .get_raw()
+            }),
+            None => {
+                let mut url = Url::parse(url)?;
+                url.query_pairs_mut().extend_pairs(query);
+                self.client.http().get(url.as_str())?.success()
+            }
         }
     }
 
-    pub fn post(&self, request: &str, resource: &str, body: &str) -> Result<Value> {
-        let url = &Service::to_url(request);
-        if Service::is_azure(url)? {
-            self.with_request(url, resource, |request| request.body(body).post_raw())
-        } else {
-            self.client.http().post(url, body)?.success()
+    pub fn post(
+        &self,
+        request: &str,
+        resource: &str,
+        body: &str,
+        content_type: Option<&str>,
+    ) -> Result<Value> {
+        let url = &to_url(self.client.arm_endpoint(), request);
+        let content_type = content_type.unwrap_or("application/json");
+        match self.token_resource(url, resource)? {
+            Some(resource) => self.with_request(url, resource, |request| {
+                request.body(body).content_type(content_type).post_raw()
+            }),
+            None => {
+                let headers = vec![Header::new("Content-Type", content_type.to_owned())];
+                self.client
+                    .http()
+                    .execute(url, Some(&headers), Some(body))?
+                    .success()
+            }
         }
     }
 
-    fn to_url(request: &str) -> String {
-        if request.starts_with("https://") {
-            request.to_owned()
-        } else {
-            format!("{}{}", DEFAULT_PREFIX, request)
+    pub fn tenant(&self) -> Result<Tenant> {
+        self.client.tenant()
+    }
+
+    pub fn rate_limits(&self) -> Vec<RateLimit> {
+        self.client.rate_limits()
+    }
+
+    pub fn failed_request_id(&self) -> Option<String> {
+        self.client.http().failed_request_id()
+    }
+
+    pub fn timings(&self) -> Vec<RequestTiming> {
+        self.client.http().timings()
+    }
+
+    pub fn access_stats(&self) -> AccessStats {
+        self.client.http().access_stats()
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.client.is_offline()
+    }
+
+    /// When the oldest of the cached responses used so far was requested
+    pub fn oldest_cached(&self) -> Option<SystemTime> {
+        self.client.oldest_cached()
+    }
+
+    /// Counts a failed request whose error was only warned about
+    pub fn record_failure(&self) {
+        self.client.record_failure()
+    }
+
+    /// The number of failed or denied requests which were only warned about
+    pub fn failed_requests(&self) -> usize {
+        self.client.failed_requests()
+    }
+
+    /// The items left out of the results so far
+    pub fn warnings(&self) -> Vec<Warning> {
+        match self.warnings.lock() {
+            Ok(warnings) => warnings.clone(),
+            Err(_) => vec![],
         }
     }
 
-    fn is_azure(url: &str) -> Result<bool> {
-        Url::parse(url).map_err(|err| err.into()).map(|url| {
-            url.host_str()
-                .map(|host| host == "azure.com" || host.ends_with(".azure.com"))
-                .unwrap_or(false)
-        })
+    fn add_warnings(&self, mut warnings: Vec<Warning>) {
+        if let Ok(mut all) = self.warnings.lock() {
+            all.append(&mut warnings);
+        }
+    }
+
+    /// Status and headers of the last response, for `--include-headers`
+    pub fn last_response(&self) -> Option<ResponseHeaders> {
+        self.client.http().last_response()
+    }
+
+    pub fn token_set(&self, resource: Option<&str>) -> Result<TokenSet> {
+        self.client
+            .token_set(resource.unwrap_or(self.client.arm_resource()))
+    }
+
+    pub fn cached_token_set(&self) -> Result<Option<TokenSet>> {
+        self.client.cached_token_set(self.client.arm_resource())
+    }
+
+    pub fn login(&self) -> Result<TokenSet> {
+        self.client.login(self.client.arm_resource())
+    }
+
+    pub fn logout(&self) -> Result<usize> {
+        self.client.logout()
+    }
+
+    /// Returns the resource to request a token for, like Microsoft Graph for its
+    /// `@odata.nextLink`, or `None` for URLs outside of Azure
+    fn token_resource<'a>(&self, url: &str, resource: &'a str) -> Result<Option<&'a str>> {
+        let arm_host = Url::parse(self.client.arm_endpoint())?
+            .host_str()
+            .map(str::to_owned);
+        let url = Url::parse(url)?;
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return Ok(None),
+        };
+        if host == GRAPH_HOST {
+            Ok(Some(if resource.is_empty() {
+                GRAPH_RESOURCE
+            } else {
+                resource
+            }))
+        } else if host == "azure.com"
+            || host.ends_with(".azure.com")
+            || Some(host) == arm_host.as_deref()
+        {
+            Ok(Some(resource))
+        } else {
+            Ok(None)
+        }
     }
 
     fn with_request(
@@ -112,7 +438,7 @@ impl Service {
         function: impl Fn(Request) -> Result<Value>,
     ) -> Result<Value> {
         let resource = if resource.is_empty() {
-            DEFAULT_RESOURCE
+            self.client.arm_resource()
         } else {
             resource
         };
@@ -120,25 +446,75 @@ impl Service {
     }
 
     pub fn get_subscriptions(&self) -> Result<Vec<Subscription>> {
-        let url = "https://management.azure.com/subscriptions?api-version=2016-06-01";
-        let mut subscriptions: Vec<Subscription> = self
-            .client
-            .new_request(url, DEFAULT_RESOURCE)
-            .get_list()?
+        let subscriptions: Vec<Subscription> = match self.filter.subscription_ids() {
+            // With multiple tenants, each ID only exists in one of them
+            Some(ids) if self.tenant_label.is_none() => ids
+                .iter()
+                .map(|id| self.get_subscription(id))
+                .collect::<Result<_>>()?,
+            _ => {
+                let url = &format!(
+                    "{}/subscriptions?api-version=2020-01-01",
+                    self.client.arm_endpoint()
+                );
+                self.client
+                    .new_request(url, self.client.arm_resource())
+                    .get_list()?
+            }
+        };
+        let mut subscriptions: Vec<Subscription> = subscriptions
             .into_iter()
             .filter(|subscription| self.filter.matches(&subscription))
             .collect();
         subscriptions.sort_by(|a, b| a.name.cmp(&b.name));
+        for subscription in &mut subscriptions {
+            subscription.tenant = self.tenant_label.clone();
+        }
         Ok(subscriptions)
     }
 
+    fn get_subscription(&self, subscription_id: &str) -> Result<Subscription> {
+        let url = format!(
+            "{}/subscriptions/{}?api-version=2020-01-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_raw()?
+            .to()
+    }
+
+    /// Returns all tenants the signed-in identity has access to
+    pub fn get_tenants(&self) -> Result<Vec<AzureTenant>> {
+        let url = format!(
+            "{}/tenants?api-version=2020-01-01",
+            self.client.arm_endpoint()
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_locations(&self, subscription_id: &str) -> Result<Vec<Location>> {
+        let url = format!(
+            "{}/subscriptions/{}/locations?api-version=2022-12-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
     pub fn get_resource_groups(&self, subscription_id: &str) -> Result<Vec<ResourceGroup>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/resourcegroups?api-version=2018-05-01",
+            "{}/subscriptions/{}/resourcegroups?api-version=2018-05-01",
+            self.client.arm_endpoint(),
             subscription_id
         );
         self.client
-            .new_request(&url, DEFAULT_RESOURCE)
+            .new_request(&url, self.client.arm_resource())
             .get_list()
             .map(|mut list: Vec<ResourceGroup>| {
                 list.sort_by(|a, b| a.name.cmp(&b.name));
@@ -148,10 +524,312 @@ impl Service {
 
     pub fn get_resources(&self, subscription_id: &str) -> Result<Vec<Resource>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/resources?api-version=2018-05-01",
+            "{}/subscriptions/{}/resources?api-version=2018-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    /// Resource groups of all given subscriptions from a single Resource Graph query,
+    /// by lowercase subscription ID
+    pub fn get_graph_resource_groups(
+        &self,
+        subscription_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<ResourceGroup>>> {
+        let query = "ResourceContainers \
+            | where type =~ 'microsoft.resources/subscriptions/resourcegroups' \
+            | project id, name, location, tags, subscriptionId";
+        let mut resource_groups: HashMap<String, Vec<ResourceGroup>> =
+            self.query_resource_graph(subscription_ids, query)?;
+        for list in resource_groups.values_mut() {
+            list.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        Ok(resource_groups)
+    }
+
+    /// Display names of the management groups above each subscription, starting with
+    /// the root, by lowercase subscription ID
+    pub fn get_graph_management_groups(
+        &self,
+        subscription_ids: &[&str],
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let query = "ResourceContainers \
+            | where type =~ 'microsoft.resources/subscriptions' \
+            | project subscriptionId, \
+                managementGroupAncestorsChain = properties.managementGroupAncestorsChain";
+        let containers: HashMap<String, Vec<SubscriptionContainer>> =
+            self.query_resource_graph(subscription_ids, query)?;
+        Ok(containers
+            .into_iter()
+            .map(|(subscription_id, containers)| {
+                let path = containers
+                    .into_iter()
+                    .flat_map(|container| container.management_groups)
+                    .rev()
+                    .map(|group| group.display_name.unwrap_or(group.name))
+                    .collect();
+                (subscription_id, path)
+            })
+            .collect())
+    }
+
+    /// Resources of all given subscriptions from a single Resource Graph query,
+    /// by lowercase subscription ID. The resource types are returned in lowercase.
+    pub fn get_graph_resources(
+        &self,
+        subscription_ids: &[&str],
+        resource_type: Option<&str>,
+    ) -> Result<HashMap<String, Vec<Resource>>> {
+        let mut query = "Resources".to_owned();
+        if let Some(resource_type) = resource_type {
+            query.push_str(&format!(
+                " | where type =~ '{}'",
+                resource_type.replace('\'', "\\'")
+            ));
+        }
+        query
+            .push_str(" | project id, name, type, location, tags, identity, zones, subscriptionId");
+        self.query_resource_graph(subscription_ids, &query)
+    }
+
+    /// Runs a Resource Graph query in batches of 1000 subscriptions, the maximum per query,
+    /// following the `$skipToken` until all rows are returned
+    fn query_resource_graph<T>(
+        &self,
+        subscription_ids: &[&str],
+        query: &str,
+    ) -> Result<HashMap<String, Vec<T>>>
+    where
+        T: DeserializeOwned,
+    {
+        let url = format!(
+            "{}/providers/Microsoft.ResourceGraph/resources?api-version=2021-03-01",
+            self.client.arm_endpoint()
+        );
+
+        let mut result: HashMap<String, Vec<T>> = HashMap::new();
+        for batch in subscription_ids.chunks(1000) {
+            let mut skip_token: Option<String> = None;
+            loop {
+                let mut options = json!({ "$top": 1000, "resultFormat": "objectArray" });
+                if let Some(skip_token) = &skip_token {
+                    options["$skipToken"] = json!(skip_token);
+                }
+                let body = json!({ "subscriptions": batch, "query": query, "options": options });
+                let body = body.to_string();
+                let json = self
+                    .client
+                    .new_request(&url, self.client.arm_resource())
+                    .body(&body)
+                    .post_raw()?;
+
+                for row in json["data"].as_array().into_iter().flatten() {
+                    let subscription_id = row["subscriptionId"].as_str().unwrap_or_default();
+                    // Missing columns are returned as null, which serde defaults don't accept
+                    let row: serde_json::Map<String, Value> = match row.as_object() {
+                        Some(row) => row
+                            .iter()
+                            .filter(|(_, value)| !value.is_null())
+                            .map(|(key, value)| (key.clone(), value.clone()))
+                            .collect(),
+                        None => continue,
+                    };
+                    result
+                        .entry(subscription_id.to_lowercase())
+                        .or_default()
+                        .push(from_value(Value::Object(row))?);
+                }
+
+                skip_token = json["$skipToken"].as_str().map(str::to_owned);
+                if skip_token.is_none() {
+                    break;
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn get_resource_group_resources(
+        &self,
+        subscription_id: &str,
+        resource_group: &str,
+    ) -> Result<Vec<Resource>> {
+        let url = format!(
+            "{}/subscriptions/{}/resourceGroups/{}/resources?api-version=2018-05-01",
+            self.client.arm_endpoint(),
+            subscription_id,
+            resource_group
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    /// Validates moving the resources to the target resource group, returning the error
+    /// when the validation failed
+    pub fn validate_move_resources(
+        &self,
+        source_resource_group: &str,
+        resource_ids: &[String],
+        target_resource_group: &str,
+    ) -> Result<Option<Value>> {
+        let url = format!(
+            "{}{}/validateMoveResources?api-version=2021-04-01",
+            self.client.arm_endpoint(),
+            source_resource_group
+        );
+        let body = json!({
+            "resources": resource_ids,
+            "targetResourceGroup": target_resource_group,
+        })
+        .to_string();
+        match self
+            .client
+            .new_request(&url, self.client.arm_resource())
+            .body(&body)
+            .post_raw()
+        {
+            Ok(_) => Ok(None),
+            Err(err) => match err.downcast::<AppError>() {
+                Ok(app_err) => match *app_err {
+                    HttpError(_, json) if json["error"].is_object() => {
+                        Ok(Some(json["error"].clone()))
+                    }
+                    app_err => Err(app_err.into()),
+                },
+                Err(err) => Err(err),
+            },
+        }
+    }
+
+    pub fn get_resources_with_timestamps(&self, subscription_id: &str) -> Result<Vec<Resource>> {
+        let url = format!(
+            "{}/subscriptions/{}/resources?api-version=2018-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .query("$expand", "createdTime,changedTime")
+            .get_list()
+    }
+
+    pub fn get_activity_log(
+        &self,
+        subscription_id: &str,
+        from: &str,
+    ) -> Result<Vec<ActivityLogEvent>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Insights/eventtypes/management/values?api-version=2015-04-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .query("$filter", &format!("eventTimestamp ge '{}'", from))
+            .get_list()
+    }
+
+    pub fn get_virtual_machine_statuses(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Vec<VirtualMachineStatus>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Compute/virtualMachines?api-version=2023-03-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .query("statusOnly", "true")
+            .get_list()
+    }
+
+    pub fn get_providers(&self, subscription_id: &str) -> Result<Vec<Provider>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers?api-version=2021-04-01",
+            self.client.arm_endpoint(),
             subscription_id
         );
-        self.client.new_request(&url, DEFAULT_RESOURCE).get_list()
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    /// Returns the latest API version of each resource type, by lowercase type name
+    pub fn get_api_versions(&self, subscription_id: &str) -> Result<HashMap<String, String>> {
+        let mut api_versions = HashMap::new();
+        for provider in self.get_providers(subscription_id)? {
+            for resource_type in &provider.resource_types {
+                if let Some(api_version) = resource_type.latest_api_version() {
+                    api_versions.insert(
+                        format!("{}/{}", provider.namespace, resource_type.resource_type)
+                            .to_lowercase(),
+                        api_version.clone(),
+                    );
+                }
+            }
+        }
+        Ok(api_versions)
+    }
+
+    /// Returns the complete properties of each resource, or `Value::Null` if unavailable
+    pub fn get_resource_properties(
+        &self,
+        subscription_id: &str,
+        resources: &[Resource],
+        parallelism: usize,
+    ) -> Result<Vec<Value>> {
+        let values = self.get_resource_values(subscription_id, resources, None, parallelism)?;
+        Ok(values
+            .into_iter()
+            .map(|mut value| value["properties"].take())
+            .collect())
+    }
+
+    /// Returns each resource as returned by its provider, or `Value::Null` if unavailable.
+    /// Without `api_version`, the latest API version of each resource type is used.
+    pub fn get_resource_values(
+        &self,
+        subscription_id: &str,
+        resources: &[Resource],
+        api_version: Option<&str>,
+        parallelism: usize,
+    ) -> Result<Vec<Value>> {
+        let api_versions = match api_version {
+            Some(_) => HashMap::new(),
+            None => self.get_api_versions(subscription_id)?,
+        };
+
+        let mut urls = vec![];
+        let mut indices = vec![];
+        for (i, resource) in resources.iter().enumerate() {
+            let latest = api_versions.get(&resource.resource_type.to_lowercase());
+            match api_version.or(latest.map(String::as_str)) {
+                Some(api_version) => {
+                    urls.push(format!(
+                        "{}{}?api-version={}",
+                        self.client.arm_endpoint(),
+                        resource.id,
+                        api_version
+                    ));
+                    indices.push(i);
+                }
+                None => debug!("No API version found for {}", resource.resource_type),
+            }
+        }
+
+        let mut result = vec![Value::Null; resources.len()];
+        let values = self
+            .client
+            .get_all(&urls, self.client.arm_resource(), parallelism)?;
+        for (i, value) in indices.into_iter().zip(values) {
+            result[i] = value;
+        }
+        Ok(result)
     }
 
     pub fn get_resources_by_type(
@@ -160,29 +838,80 @@ impl Service {
         resource_type: &str,
     ) -> Result<Vec<Resource>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/resources?api-version=2018-05-01",
+            "{}/subscriptions/{}/resources?api-version=2018-05-01",
+            self.client.arm_endpoint(),
             subscription_id
         );
         self.client
-            .new_request(&url, DEFAULT_RESOURCE)
+            .new_request(&url, self.client.arm_resource())
             .query("$filter", &format!("resourceType eq '{}'", resource_type))
             .get_list()
     }
 
+    /// Resources of the given type in each of the subscriptions, requested in batches
+    pub fn get_resources_by_type_batch(
+        &self,
+        subscription_ids: &[&str],
+        resource_type: &str,
+    ) -> Result<Vec<Vec<Resource>>> {
+        let urls: Vec<String> = subscription_ids
+            .iter()
+            .map(|subscription_id| {
+                format!(
+                    "{}/subscriptions/{}/resources?api-version=2018-05-01",
+                    self.client.arm_endpoint(),
+                    subscription_id
+                )
+            })
+            .collect();
+        let filter = format!("resourceType eq '{}'", resource_type);
+        let requests: Vec<Request> = urls
+            .iter()
+            .map(|url| {
+                self.client
+                    .new_request(url, self.client.arm_resource())
+                    .query("$filter", &filter)
+            })
+            .collect();
+
+        let mut resources = vec![];
+        for json in self.client.get_batch(&requests)? {
+            resources.push(from_value(json?)?);
+        }
+        Ok(resources)
+    }
+
     pub fn get_clusters(&self, subscription_id: &str) -> Result<Vec<ManagedCluster>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/providers/Microsoft.ContainerService/managedClusters?api-version=2021-03-01",
+            "{}/subscriptions/{}/providers/Microsoft.ContainerService/managedClusters?api-version=2021-03-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_connected_clusters(&self, subscription_id: &str) -> Result<Vec<ConnectedCluster>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Kubernetes/connectedClusters?api-version=2021-10-01",
+            self.client.arm_endpoint(),
             subscription_id
         );
-        self.client.new_request(&url, DEFAULT_RESOURCE).get_list()
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
     }
 
     pub fn get_agent_pools(&self, cluster_id: &str) -> Result<Vec<AgentPool>> {
         let url = format!(
-            "https://management.azure.com{}/agentPools?api-version=2021-03-01",
+            "{}{}/agentPools?api-version=2021-03-01",
+            self.client.arm_endpoint(),
             cluster_id
         );
-        self.client.new_request(&url, DEFAULT_RESOURCE).get_list()
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
     }
 
     pub fn get_cluster_kubeconfig(&self, cluster_id: &str) -> Result<String> {
@@ -199,10 +928,13 @@ impl Service {
 
         let credentials: ClusterCredentials = {
             let url = format!(
-                "https://management.azure.com{}/listClusterUserCredential?api-version=2021-03-01",
+                "{}{}/listClusterUserCredential?api-version=2021-03-01",
+                self.client.arm_endpoint(),
                 cluster_id
             );
-            self.client.new_request(&url, DEFAULT_RESOURCE).post()?
+            self.client
+                .new_request(&url, self.client.arm_resource())
+                .post()?
         };
 
         let entry = credentials
@@ -217,15 +949,118 @@ impl Service {
         Ok(kubeconfig)
     }
 
+    /// Returns a kubeconfig for the cluster connect proxy of an Arc-enabled cluster,
+    /// which fails if the cluster-connect feature is not enabled
+    pub fn get_connected_cluster_kubeconfig(&self, cluster_id: &str) -> Result<String> {
+        let url = format!(
+            "{}{}/listClusterUserCredential?api-version=2021-10-01",
+            self.client.arm_endpoint(),
+            cluster_id
+        );
+        let body = json!({ "authenticationMethod": "AAD", "clientProxy": false }).to_string();
+        let json = self
+            .client
+            .new_request(&url, self.client.arm_resource())
+            .body(&body)
+            .post_raw()?;
+
+        let value = json["kubeconfigs"][0]["value"]
+            .as_str()
+            .ok_or(ServiceError("no kubeconfig returned"))?;
+        let kubeconfig = from_utf8(&decode(value)?)?.to_owned();
+        debug!("kubeconfig: {}", kubeconfig);
+
+        Ok(kubeconfig)
+    }
+
     pub fn get_kubernetes_objects(
         &self,
         kubeconfig: &str,
         all_resources: bool,
     ) -> Result<Vec<KubernetesObject>> {
+        let http = self.kubernetes_http(kubeconfig)?;
+
+        let mut objects = vec![];
+        Self::get_kubernetes_services(&http, &mut objects)?;
+        Self::get_kubernetes_deployments(&http, &mut objects)?;
+
+        if !all_resources {
+            objects.retain(|object| {
+                let metadata = object.metadata();
+                metadata.namespace != "kube-system"
+                    && metadata
+                        .labels
+                        .get("provider")
+                        .filter(|p| p.as_str() == "kubernetes")
+                        .is_none()
+            });
+        }
+
+        Ok(objects)
+    }
+
+    /// Returns the restarts of all containers, from the status of all pods
+    pub fn get_kubernetes_restarts(
+        &self,
+        kubeconfig: &str,
+        all_resources: bool,
+    ) -> Result<Vec<ContainerRestarts>> {
+        let http = self.kubernetes_http(kubeconfig)?;
+
+        let mut restarts = vec![];
+        let mut path = "/api/v1/pods?limit=500".to_owned();
+        loop {
+            let json = http.execute(&path, None, None)?.success()?;
+            for item in json["items"].to_array()? {
+                let namespace = item["metadata"]["namespace"].string()?;
+                if !all_resources && namespace == "kube-system" {
+                    continue;
+                }
+                if let Some(statuses) = item["status"]["containerStatuses"].as_array() {
+                    for status in statuses {
+                        restarts.push(ContainerRestarts {
+                            namespace: namespace.clone(),
+                            restart_count: status["restartCount"].as_u64().unwrap_or(0),
+                            last_restart: status["lastState"]["terminated"]["finishedAt"]
+                                .as_str()
+                                .map(str::to_owned),
+                            crash_loop: status["state"]["waiting"]["reason"].as_str()
+                                == Some("CrashLoopBackOff"),
+                        });
+                    }
+                }
+            }
+            match json["metadata"]["continue"].as_str() {
+                Some(token) if !token.is_empty() => {
+                    let token: String = byte_serialize(token.as_bytes()).collect();
+                    path = format!("/api/v1/pods?limit=500&continue={}", token);
+                }
+                _ => break,
+            }
+        }
+
+        Ok(restarts)
+    }
+
+    /// A client for the API server of the kubeconfig, authenticated with its token
+    fn kubernetes_http(&self, kubeconfig: &str) -> Result<Http> {
         let cluster = KubernetesCluster::parse(kubeconfig)?;
 
-        let http = Http::for_certificate_authority(&cluster.certificate_authority)?
-            .with_url(cluster.server.clone());
+        let http = match &cluster.certificate_authority {
+            Some(ca) => Http::for_certificate_authority(
+                ca,
+                self.client.ca_bundle(),
+                self.client.timeouts(),
+            )?,
+            // Like the Arc proxy, with a certificate of a public CA
+            None => match self.client.ca_bundle() {
+                Some(ca_bundle) => Http::for_ca_bundle(ca_bundle, self.client.timeouts())?,
+                None => Http::new(self.client.timeouts()),
+            },
+        }
+        .with_url(cluster.server.clone())
+        .with_offline(self.client.is_offline())
+        .with_max_response_size(self.client.max_response_size());
 
         let http = match &cluster.auth {
             KubernetesAuthentication::BearerToken(token) => {
@@ -243,23 +1078,7 @@ impl Service {
             }
         };
 
-        let mut objects = vec![];
-        Self::get_kubernetes_services(&http, &mut objects)?;
-        Self::get_kubernetes_deployments(&http, &mut objects)?;
-
-        if !all_resources {
-            objects.retain(|object| {
-                let metadata = object.metadata();
-                metadata.namespace != "kube-system"
-                    && metadata
-                        .labels
-                        .get("provider")
-                        .filter(|p| p.as_str() == "kubernetes")
-                        .is_none()
-            });
-        }
-
-        Ok(objects)
+        Ok(http)
     }
 
     fn get_kubernetes_services(http: &Http, objects: &mut Vec<KubernetesObject>) -> Result<()> {
@@ -298,139 +1117,645 @@ impl Service {
             })
         }
 
-        for item in json["items"].to_array()? {
-            objects.push(match to_service(item) {
-                Ok(service) => service,
-                Err(err) => {
-                    debug!("Failed to parse JSON: {}", item.to_string());
-                    return Err(err);
-                }
-            });
-        }
+        for item in json["items"].to_array()? {
+            objects.push(match to_service(item) {
+                Ok(service) => service,
+                Err(err) => {
+                    debug!("Failed to parse JSON: {}", item.to_string());
+                    return Err(err);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn get_kubernetes_deployments(http: &Http, objects: &mut Vec<KubernetesObject>) -> Result<()> {
+        let json = http
+            .execute("/apis/apps/v1/deployments?limit=200", None, None)?
+            .success()?;
+
+        fn to_deployment(json: &Value) -> Result<KubernetesObject> {
+            let metadata = json["metadata"].clone().to::<KubernetesMetadata>()?;
+            let target = json["status"]["replicas"].as_u64().unwrap_or(0);
+            let ready = json["status"]["readyReplicas"].as_u64().unwrap_or(0);
+            Ok(KubernetesObject::Deployment {
+                metadata,
+                target,
+                ready,
+            })
+        }
+
+        for item in json["items"].to_array()? {
+            objects.push(match to_deployment(item) {
+                Ok(deployment) => deployment,
+                Err(err) => {
+                    debug!("Failed to parse JSON: {}", item.to_string());
+                    return Err(err);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn get_ip_addresses(&self, subscription_id: &str) -> Result<Vec<IpAddress>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/publicIPAddresses?api-version=2018-11-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        let mut warnings = vec![];
+        let ip_addresses = self
+            .client
+            .new_request(&url, self.client.arm_resource())
+            .get_raw()?
+            .as_array()
+            .ok_or(ServiceError("response is not an array"))?
+            .iter()
+            .filter_map(|row| {
+                if let (Some(id), Some(name), Some(ip_address)) = (
+                    row["id"].as_str(),
+                    row["name"].as_str(),
+                    row["properties"]["ipAddress"].as_str(),
+                ) {
+                    return Some(IpAddress {
+                        id: id.to_owned(),
+                        name: name.to_owned(),
+                        ip_address: ip_address.to_owned(),
+                        ip_configuration: row["properties"]["ipConfiguration"]["id"]
+                            .as_str()
+                            .map(str::to_owned),
+                        protection: vec![],
+                    });
+                } else {
+                    warnings.push(Warning::skipped(
+                        "public IP address without id, name or IP address".to_owned(),
+                        row,
+                    ));
+                    return None;
+                }
+            })
+            .collect();
+        self.add_warnings(warnings);
+        return Ok(ip_addresses);
+    }
+
+    pub fn get_network_security_groups(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Vec<NetworkSecurityGroup>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/networkSecurityGroups?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_network_interfaces(&self, subscription_id: &str) -> Result<Vec<NetworkInterface>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/networkInterfaces?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_virtual_networks(&self, subscription_id: &str) -> Result<Vec<VirtualNetwork>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/virtualNetworks?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_private_endpoints(&self, subscription_id: &str) -> Result<Vec<PrivateEndpoint>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/privateEndpoints?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_private_dns_zone_groups(
+        &self,
+        private_endpoint_id: &str,
+    ) -> Result<Vec<PrivateDnsZoneGroup>> {
+        let url = format!(
+            "{}{}/privateDnsZoneGroups?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            private_endpoint_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_bastion_hosts(&self, subscription_id: &str) -> Result<Vec<BastionHost>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/bastionHosts?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_storage_accounts(&self, subscription_id: &str) -> Result<Vec<StorageAccount>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Storage/storageAccounts?api-version=2022-09-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_redis_caches(&self, subscription_id: &str) -> Result<Vec<RedisCache>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Cache/redis?api-version=2023-08-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_sql_servers(&self, subscription_id: &str) -> Result<Vec<SqlServer>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Sql/servers?api-version=2021-11-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_sql_databases(&self, server_id: &str) -> Result<Vec<SqlDatabase>> {
+        let url = format!(
+            "{}{}/databases?api-version=2021-11-01",
+            self.client.arm_endpoint(),
+            server_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_app_service_plans(&self, subscription_id: &str) -> Result<Vec<AppServicePlan>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Web/serverfarms?api-version=2022-03-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_web_apps(&self, subscription_id: &str) -> Result<Vec<WebApp>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Web/sites?api-version=2022-03-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_traffic_manager_profiles(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Vec<TrafficManagerProfile>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/trafficmanagerprofiles?api-version=2022-04-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_managed_environments(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Vec<ManagedEnvironment>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.App/managedEnvironments?api-version=2023-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_container_apps(&self, subscription_id: &str) -> Result<Vec<ContainerApp>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.App/containerApps?api-version=2023-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_container_app_revisions(
+        &self,
+        container_app_id: &str,
+    ) -> Result<Vec<ContainerAppRevision>> {
+        let url = format!(
+            "{}{}/revisions?api-version=2023-05-01",
+            self.client.arm_endpoint(),
+            container_app_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_galleries(&self, subscription_id: &str) -> Result<Vec<Gallery>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Compute/galleries?api-version=2022-03-03",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_gallery_images(&self, gallery_id: &str) -> Result<Vec<GalleryImage>> {
+        let url = format!(
+            "{}{}/images?api-version=2022-03-03",
+            self.client.arm_endpoint(),
+            gallery_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_gallery_image_versions(&self, image_id: &str) -> Result<Vec<GalleryImageVersion>> {
+        let url = format!(
+            "{}{}/versions?api-version=2022-03-03",
+            self.client.arm_endpoint(),
+            image_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_managed_images(&self, subscription_id: &str) -> Result<Vec<ManagedImage>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Compute/images?api-version=2022-08-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_route_tables(&self, subscription_id: &str) -> Result<Vec<RouteTable>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/routeTables?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_effective_routes(&self, network_interface_id: &str) -> Result<Vec<EffectiveRoute>> {
+        let url = format!(
+            "{}{}/effectiveRouteTable?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            network_interface_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .post()
+    }
+
+    pub fn get_express_route_circuits(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Vec<ExpressRouteCircuit>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/expressRouteCircuits?api-version=2022-07-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    /// Returns the BGP neighbors of the primary device of an ExpressRoute peering
+    pub fn get_express_route_route_summary(
+        &self,
+        circuit_id: &str,
+        peering: &str,
+    ) -> Result<Vec<ExpressRouteRouteSummary>> {
+        let url = format!(
+            "{}{}/peerings/{}/routeTablesSummary/primary?api-version=2022-07-01",
+            self.client.arm_endpoint(),
+            circuit_id,
+            peering
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .post()
+    }
+
+    pub fn get_virtual_network_gateway(&self, gateway_id: &str) -> Result<VirtualNetworkGateway> {
+        let url = format!(
+            "{}{}?api-version=2022-07-01",
+            self.client.arm_endpoint(),
+            gateway_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_raw()?
+            .to()
+    }
+
+    pub fn get_gateway_connections(
+        &self,
+        gateway_id: &str,
+    ) -> Result<Vec<VirtualNetworkGatewayConnection>> {
+        let url = format!(
+            "{}{}/connections?api-version=2022-07-01",
+            self.client.arm_endpoint(),
+            gateway_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_bgp_peer_status(&self, gateway_id: &str) -> Result<Vec<BgpPeerStatus>> {
+        let url = format!(
+            "{}{}/getBgpPeerStatus?api-version=2022-07-01",
+            self.client.arm_endpoint(),
+            gateway_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .post()
+    }
+
+    /// Returns the number of routes the gateway advertises to the given BGP peer
+    pub fn get_advertised_route_count(&self, gateway_id: &str, peer: &str) -> Result<usize> {
+        let url = format!(
+            "{}{}/getAdvertisedRoutes?api-version=2022-07-01",
+            self.client.arm_endpoint(),
+            gateway_id
+        );
+        let json = self
+            .client
+            .new_request(&url, self.client.arm_resource())
+            .query("peer", peer)
+            .post_raw()?;
+        Ok(json.to_array()?.len())
+    }
+
+    pub fn get_firewalls(&self, subscription_id: &str) -> Result<Vec<AzureFirewall>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Network/azureFirewalls?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_firewall_policy_rule_collection_groups(
+        &self,
+        policy_id: &str,
+    ) -> Result<Vec<FirewallPolicyRuleCollectionGroup>> {
+        let url = format!(
+            "{}{}/ruleCollectionGroups?api-version=2021-05-01",
+            self.client.arm_endpoint(),
+            policy_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    pub fn get_key_vaults(&self, subscription_id: &str) -> Result<Vec<KeyVault>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.KeyVault/vaults?api-version=2021-10-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
 
-        Ok(())
+    pub fn get_user_assigned_identities(
+        &self,
+        subscription_id: &str,
+    ) -> Result<Vec<UserAssignedIdentity>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.ManagedIdentity/userAssignedIdentities?api-version=2018-11-30",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
     }
 
-    fn get_kubernetes_deployments(http: &Http, objects: &mut Vec<KubernetesObject>) -> Result<()> {
-        let json = http
-            .execute("/apis/apps/v1/deployments?limit=200", None, None)?
-            .success()?;
+    /// Returns the autoscale settings of the subscription, with their target resources and profiles
+    pub fn get_autoscale_settings(&self, subscription_id: &str) -> Result<Vec<AutoscaleSetting>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Insights/autoscalesettings?api-version=2022-10-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
 
-        fn to_deployment(json: &Value) -> Result<KubernetesObject> {
-            let metadata = json["metadata"].clone().to::<KubernetesMetadata>()?;
-            let target = json["status"]["replicas"].as_u64().unwrap_or(0);
-            let ready = json["status"]["readyReplicas"].as_u64().unwrap_or(0);
-            Ok(KubernetesObject::Deployment {
-                metadata,
-                target,
-                ready,
-            })
-        }
+    /// Returns the hourly averages of the given metric over the last day
+    pub fn get_hourly_metric(&self, resource_id: &str, metric: &str) -> Result<Vec<f64>> {
+        self.get_hourly_metric_aggregation(resource_id, metric, "Average")
+    }
 
-        for item in json["items"].to_array()? {
-            objects.push(match to_deployment(item) {
-                Ok(deployment) => deployment,
-                Err(err) => {
-                    debug!("Failed to parse JSON: {}", item.to_string());
-                    return Err(err);
-                }
-            });
-        }
+    /// Returns the hourly values of the given metric and aggregation, like `Total`, over the last day
+    pub fn get_hourly_metric_aggregation(
+        &self,
+        resource_id: &str,
+        metric: &str,
+        aggregation: &str,
+    ) -> Result<Vec<f64>> {
+        let url = format!(
+            "{}{}/providers/Microsoft.Insights/metrics?api-version=2018-01-01&timespan=PT24H&interval=PT1H&aggregation={}",
+            self.client.arm_endpoint(),
+            resource_id,
+            aggregation
+        );
+        let json = self
+            .client
+            .new_request(&url, self.client.arm_resource())
+            .query("metricnames", metric)
+            .get_raw()?;
+        Ok(metric_values(&json, &aggregation.to_lowercase()))
+    }
 
-        Ok(())
+    pub fn get_locks(&self, subscription_id: &str) -> Result<Vec<ManagementLock>> {
+        let url = format!(
+            "{}/subscriptions/{}/providers/Microsoft.Authorization/locks?api-version=2016-09-01",
+            self.client.arm_endpoint(),
+            subscription_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
     }
 
-    pub fn get_ip_addresses(&self, subscription_id: &str) -> Result<Vec<IpAddress>> {
+    pub fn get_deny_assignments(&self, subscription_id: &str) -> Result<Vec<DenyAssignment>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/providers/Microsoft.Network/publicIPAddresses?api-version=2018-11-01",
+            "{}/subscriptions/{}/providers/Microsoft.Authorization/denyAssignments?api-version=2022-04-01",
+            self.client.arm_endpoint(),
             subscription_id
         );
-        return Ok(self
-            .client
-            .new_request(&url, DEFAULT_RESOURCE)
-            .get_raw()?
-            .as_array()
-            .ok_or(ServiceError("response is not an array"))?
-            .iter()
-            .filter_map(|row| {
-                if let (Some(id), Some(name), Some(ip_address)) = (
-                    row["id"].as_str(),
-                    row["name"].as_str(),
-                    row["properties"]["ipAddress"].as_str(),
-                ) {
-                    return Some(IpAddress {
-                        id: id.to_owned(),
-                        name: name.to_owned(),
-                        ip_address: ip_address.to_owned(),
-                    });
-                } else {
-                    trace!("Invalid row, missing id or name: {:?}", row);
-                    return None;
-                }
-            })
-            .collect());
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
     }
 
-    pub fn get_dns_records(
+    pub fn get_subscription_role_assignments(
         &self,
         subscription_id: &str,
-        resource_group: &str,
-        zone: &str,
-    ) -> Result<Vec<DnsRecord>> {
+    ) -> Result<Vec<RoleAssignment>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones/{}/recordsets?api-version=2018-03-01-preview",
-            subscription_id,
-            resource_group,
-            zone,
+            "{}/subscriptions/{}/providers/Microsoft.Authorization/roleAssignments?api-version=2022-04-01",
+            self.client.arm_endpoint(),
+            subscription_id
         );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_list()
+    }
+
+    /// Returns the role assignments applying to the given scope, including inherited ones
+    pub fn get_role_assignments(&self, scope: &str) -> Result<Vec<RoleAssignment>> {
+        let url = format!(
+            "{}{}/providers/Microsoft.Authorization/roleAssignments?api-version=2022-04-01",
+            self.client.arm_endpoint(),
+            scope
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .query("$filter", "atScope()")
+            .get_list()
+    }
 
-        let json = self.client.new_request(&url, DEFAULT_RESOURCE).get_raw()?;
+    pub fn get_role_definition(&self, role_definition_id: &str) -> Result<RoleDefinition> {
+        let url = format!(
+            "{}{}?api-version=2022-04-01",
+            self.client.arm_endpoint(),
+            role_definition_id
+        );
+        self.client
+            .new_request(&url, self.client.arm_resource())
+            .get_raw()?
+            .to()
+    }
 
-        let records = json
-            .as_array()
-            .ok_or(ServiceError("response is not an array"))?
+    pub fn get_directory_objects(&self, ids: &[String]) -> Result<Vec<DirectoryObject>> {
+        let url = "https://graph.microsoft.com/v1.0/directoryObjects/getByIds";
+        let mut objects = vec![];
+        for chunk in ids.chunks(1000) {
+            let body = json!({ "ids": chunk }).to_string();
+            let json = self
+                .client
+                .new_request(url, GRAPH_RESOURCE)
+                .body(&body)
+                .post_raw()?;
+            for item in json.to_array()? {
+                objects.push(item.clone().to()?);
+            }
+        }
+        Ok(objects)
+    }
+
+    pub fn get_service_principal_by_app_id(&self, app_id: &str) -> Result<Option<DirectoryObject>> {
+        let url = "https://graph.microsoft.com/v1.0/servicePrincipals";
+        let filter = format!("appId eq '{}'", app_id);
+        let mut service_principals: Vec<DirectoryObject> = self
+            .client
+            .new_request(url, GRAPH_RESOURCE)
+            .query("$filter", &filter)
+            .get_list()?;
+        Ok(service_principals.pop())
+    }
+
+    pub fn get_service_principal_owners(&self, object_id: &str) -> Result<Vec<DirectoryObject>> {
+        let url = format!(
+            "https://graph.microsoft.com/v1.0/servicePrincipals/{}/owners",
+            object_id
+        );
+        self.client.new_request(&url, GRAPH_RESOURCE).get_list()
+    }
+
+    /// Record sets of all given DNS zones, requested in batches
+    pub fn get_dns_records_batch(&self, zones: &[Resource]) -> Result<Vec<Vec<DnsRecord>>> {
+        let mut urls = vec![];
+        for zone in zones {
+            urls.push(format!(
+                "{}/subscriptions/{}/resourceGroups/{}/providers/Microsoft.Network/dnsZones/{}/recordsets?api-version=2018-03-01-preview",
+                self.client.arm_endpoint(),
+                zone.subscription_id()?,
+                zone.resource_group()?,
+                zone.name,
+            ));
+        }
+        let requests: Vec<Request> = urls
             .iter()
-            .filter_map(|row| {
-                let (id, name) =
-                    if let (Some(id), Some(name)) = (row["id"].as_str(), row["name"].as_str()) {
-                        (id.to_owned(), name.to_owned())
-                    } else {
-                        trace!("Invalid row, missing id or name: {:?}", row);
-                        return None;
-                    };
-                let fqdn = if name == "@" {
-                    zone.to_owned()
-                } else {
-                    format!("{}.{}", name, zone)
-                };
-                let entry = if let Some(a_records) = row["properties"]["ARecords"].as_array() {
-                    let ip_addresses: Vec<String> = a_records
-                        .iter()
-                        .filter_map(|row| row["ipv4Address"].as_str())
-                        .map(str::to_owned)
-                        .collect();
-                    DnsRecordEntry::A(ip_addresses)
-                } else if let Some(cname) = row["properties"]["CNAMERecord"]["cname"].as_str() {
-                    DnsRecordEntry::CNAME(cname.to_owned())
-                } else {
-                    trace!("Invalid row, unknown record type: {:?}", row);
-                    return None;
-                };
-                return Some(DnsRecord {
-                    id,
-                    name,
-                    fqdn,
-                    entry,
-                });
-            })
+            .map(|url| self.client.new_request(url, self.client.arm_resource()))
             .collect();
 
-        return Ok(records);
+        let mut records = vec![];
+        let mut warnings = vec![];
+        for (zone, json) in zones.iter().zip(self.client.get_batch(&requests)?) {
+            records.push(parse_dns_records(&json?, &zone.name, &mut warnings)?);
+        }
+        self.add_warnings(warnings);
+        Ok(records)
     }
 
     pub fn get_costs(&self, subscription_id: &str, timeframe: &Timeframe) -> Result<Vec<Costs>> {
         let url = format!(
-            "https://management.azure.com/subscriptions/{}/providers/Microsoft.CostManagement/query?api-version=2019-01-01",
+            "{}/subscriptions/{}/providers/Microsoft.CostManagement/query?api-version=2019-01-01",
+            self.client.arm_endpoint(),
             subscription_id
         );
 
@@ -463,7 +1788,7 @@ impl Service {
 
         let json = self
             .client
-            .new_request(&url, DEFAULT_RESOURCE)
+            .new_request(&url, self.client.arm_resource())
             .body(&body.to_string())
             .post_raw()?;
 
@@ -483,6 +1808,7 @@ impl Service {
         let resource_group_col = find_column(&json, "ResourceGroup")?;
         let costs_col = find_column(&json, "PreTaxCost")?;
         let currency_col = find_column(&json, "Currency")?;
+        let date_col = find_column(&json, "UsageDate").ok();
 
         let items = json["properties"]["rows"]
             .as_array()
@@ -495,10 +1821,12 @@ impl Service {
                         arr.get(costs_col).and_then(Value::as_f64),
                         arr.get(currency_col).and_then(Value::as_str),
                     ) {
+                        let date = date_col.and_then(|col| arr.get(col)).and_then(usage_date);
                         return Some(Costs {
                             resource_group: resource_group.to_owned(),
                             costs,
                             currency: currency.to_owned(),
+                            date,
                         });
                     }
                 }
@@ -514,7 +1842,8 @@ impl Service {
 pub struct KubernetesCluster {
     pub name: String,
     pub server: String,
-    pub certificate_authority: String,
+    /// Not set if the server certificate is issued by a public CA
+    pub certificate_authority: Option<String>,
     pub auth: KubernetesAuthentication,
 }
 
@@ -531,32 +1860,41 @@ impl KubernetesCluster {
         let configs = YamlLoader::load_from_str(kubeconfig)?;
         let config = configs.get(0).ok_or_else(err)?;
 
-        fn get_entry<'a>(obj: &'a Yaml, name: &Yaml) -> Result<&'a Yaml> {
-            let err = || ServiceError("cannot find kubeconfig entry");
-            let name = name.as_str().ok_or_else(err)?;
-            Ok(obj
-                .as_vec()
-                .ok_or_else(err)?
-                .iter()
-                .find(|c| c["name"].as_str() == Some(name))
-                .ok_or_else(err)?)
-        }
-
         let current_context = &config["current-context"];
-        let context = &get_entry(&config["contexts"], &current_context)?["context"];
+        let context = &kubeconfig_entry(&config["contexts"], &current_context)?["context"];
 
-        let cluster = &get_entry(&config["clusters"], &context["cluster"])?["cluster"];
-        let user = &get_entry(&config["users"], &context["user"])?["user"];
+        let cluster = &kubeconfig_entry(&config["clusters"], &context["cluster"])?["cluster"];
+        let user = &kubeconfig_entry(&config["users"], &context["user"])?["user"];
 
         let to_str = |yaml: &Yaml| yaml.as_str().ok_or_else(err).map(|s| s.to_owned());
 
-        let ca = from_utf8(&decode(&to_str(&cluster["certificate-authority-data"])?)?)?.to_owned();
+        let ca = match &cluster["certificate-authority-data"] {
+            Yaml::BadValue => None,
+            ca => Some(from_utf8(&decode(&to_str(ca)?)?)?.to_owned()),
+        };
 
         let auth = if !user["auth-provider"].is_badvalue() {
             KubernetesAuthentication::AccessToken {
                 client_id: to_str(&user["auth-provider"]["config"]["client-id"])?,
                 resource: to_str(&user["auth-provider"]["config"]["apiserver-id"])?,
             }
+        } else if !user["exec"].is_badvalue() {
+            // kubelogin, like `kubelogin get-token --server-id <id> --client-id <id>`
+            let args: Vec<&str> = match user["exec"]["args"].as_vec() {
+                Some(args) => args.iter().filter_map(Yaml::as_str).collect(),
+                None => vec![],
+            };
+            let arg = |name: &str| {
+                args.iter()
+                    .position(|arg| *arg == name)
+                    .and_then(|i| args.get(i + 1))
+                    .map(|value| value.to_string())
+                    .ok_or(ServiceError("unsupported kubeconfig exec plugin"))
+            };
+            KubernetesAuthentication::AccessToken {
+                client_id: arg("--client-id")?,
+                resource: arg("--server-id")?,
+            }
         } else {
             KubernetesAuthentication::BearerToken(to_str(&user["token"])?)
         };
@@ -570,10 +1908,341 @@ impl KubernetesCluster {
     }
 }
 
+fn kubeconfig_entry<'a>(obj: &'a Yaml, name: &Yaml) -> Result<&'a Yaml> {
+    let err = || ServiceError("cannot find kubeconfig entry");
+    let name = name.as_str().ok_or_else(err)?;
+    Ok(obj
+        .as_vec()
+        .ok_or_else(err)?
+        .iter()
+        .find(|c| c["name"].as_str() == Some(name))
+        .ok_or_else(err)?)
+}
+
+/// Merges the current contexts of the kubeconfigs into one kubeconfig, renaming each context
+/// and its cluster and user to the given name. No current context is set, so that the file
+/// can be added to `KUBECONFIG` without changing the context of kubectl
+pub fn merge_kubeconfigs(kubeconfigs: &[(String, &str)]) -> Result<String> {
+    let err = || ServiceError("invalid kubeconfig structure");
+    let key = |key: &str| Yaml::String(key.to_owned());
+    let entry = |name: &str, kind: &str, value: Yaml| {
+        let mut entry = Hash::new();
+        entry.insert(key("name"), key(name));
+        entry.insert(key(kind), value);
+        Yaml::Hash(entry)
+    };
+
+    let mut clusters = vec![];
+    let mut contexts = vec![];
+    let mut users = vec![];
+    for (name, kubeconfig) in kubeconfigs {
+        let configs = YamlLoader::load_from_str(kubeconfig)?;
+        let config = configs.first().ok_or_else(err)?;
+
+        let context =
+            &kubeconfig_entry(&config["contexts"], &config["current-context"])?["context"];
+        let cluster = &kubeconfig_entry(&config["clusters"], &context["cluster"])?["cluster"];
+        let user = &kubeconfig_entry(&config["users"], &context["user"])?["user"];
+
+        let mut context = context.as_hash().ok_or_else(err)?.clone();
+        context.insert(key("cluster"), key(name));
+        context.insert(key("user"), key(name));
+
+        clusters.push(entry(name, "cluster", cluster.clone()));
+        contexts.push(entry(name, "context", Yaml::Hash(context)));
+        users.push(entry(name, "user", user.clone()));
+    }
+
+    let mut config = Hash::new();
+    config.insert(key("apiVersion"), key("v1"));
+    config.insert(key("kind"), key("Config"));
+    config.insert(key("clusters"), Yaml::Array(clusters));
+    config.insert(key("contexts"), Yaml::Array(contexts));
+    config.insert(key("users"), Yaml::Array(users));
+
+    let mut result = String::new();
+    YamlEmitter::new(&mut result).dump(&Yaml::Hash(config))?;
+    result.push('\n');
+    Ok(result)
+}
+
+/// Accepts absolute URLs, like a `nextLink` copied from previous output, protocol-relative
+/// URLs and paths relative to the Resource Manager
+fn to_url(arm_endpoint: &str, request: &str) -> String {
+    let request = request.trim().trim_matches('"');
+    if request.starts_with("https://") || request.starts_with("http://") {
+        request.to_owned()
+    } else if request.starts_with("//") {
+        format!("https:{}", request)
+    } else {
+        format!("{}/{}", arm_endpoint, request.trim_start_matches('/'))
+    }
+}
+
+/// Returns the data points of all time series of a metrics response
+fn metric_values(json: &Value, aggregation: &str) -> Vec<f64> {
+    let mut values = vec![];
+    if let Some(metrics) = json.as_array() {
+        for metric in metrics {
+            for series in metric["timeseries"].as_array().unwrap_or(&vec![]) {
+                for data in series["data"].as_array().unwrap_or(&vec![]) {
+                    if let Some(value) = data[aggregation].as_f64() {
+                        values.push(value);
+                    }
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Parses the A and CNAME record sets of a DNS zone, other types are skipped with a warning
+fn parse_dns_records(
+    json: &Value,
+    zone: &str,
+    warnings: &mut Vec<Warning>,
+) -> Result<Vec<DnsRecord>> {
+    let records = json
+        .as_array()
+        .ok_or(ServiceError("response is not an array"))?
+        .iter()
+        .filter_map(|row| {
+            let (id, name) =
+                if let (Some(id), Some(name)) = (row["id"].as_str(), row["name"].as_str()) {
+                    (id.to_owned(), name.to_owned())
+                } else {
+                    warnings.push(Warning::skipped(
+                        "DNS record without id or name".to_owned(),
+                        row,
+                    ));
+                    return None;
+                };
+            let fqdn = if name == "@" {
+                zone.to_owned()
+            } else {
+                format!("{}.{}", name, zone)
+            };
+            let entry = if let Some(a_records) = row["properties"]["ARecords"].as_array() {
+                let ip_addresses: Vec<String> = a_records
+                    .iter()
+                    .filter_map(|row| row["ipv4Address"].as_str())
+                    .map(str::to_owned)
+                    .collect();
+                DnsRecordEntry::A(ip_addresses)
+            } else if let Some(cname) = row["properties"]["CNAMERecord"]["cname"].as_str() {
+                DnsRecordEntry::CNAME(cname.to_owned())
+            } else {
+                let record_type = row["type"].as_str().unwrap_or_default();
+                let record_type = record_type.rsplit('/').next().unwrap_or_default();
+                warnings.push(Warning::skipped(
+                    format!("unsupported DNS record type {}", record_type),
+                    row,
+                ));
+                return None;
+            };
+            return Some(DnsRecord {
+                id,
+                name,
+                fqdn,
+                entry,
+            });
+        })
+        .collect();
+
+    return Ok(records);
+}
+
+/// Converts a usage date of the cost query like `20190301` to `2019-03-01`
+fn usage_date(value: &Value) -> Option<String> {
+    let date = match value {
+        Value::Number(number) => number.as_u64()?.to_string(),
+        Value::String(date) => date.clone(),
+        _ => return None,
+    };
+    if date.len() == 8 && date.chars().all(|c| c.is_ascii_digit()) {
+        Some(format!("{}-{}-{}", &date[0..4], &date[4..6], &date[6..8]))
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use serde_json::json;
+    use yaml_rust::Yaml;
+    use yaml_rust::YamlLoader;
+
+    use super::merge_kubeconfigs;
+    use super::metric_values;
+    use super::parse_dns_records;
+    use super::to_url;
+    use super::usage_date;
+    use super::Filter;
     use super::KubernetesAuthentication;
     use super::KubernetesCluster;
+    use super::Timeframe;
+    use crate::object::Subscription;
+
+    #[test]
+    fn test_usage_date() {
+        assert_eq!(Some("2019-03-01".to_owned()), usage_date(&json!(20190301)));
+        assert_eq!(
+            Some("2019-03-01".to_owned()),
+            usage_date(&json!("20190301"))
+        );
+        assert_eq!(None, usage_date(&json!("2019-03")));
+        assert_eq!(None, usage_date(&json!(null)));
+    }
+
+    #[test]
+    fn test_period() {
+        let timeframe = Timeframe::Custom {
+            from: "2019-03-01".to_owned(),
+            to: "2019-03-31".to_owned(),
+        };
+        assert_eq!("2019-03-01/2019-03-31", timeframe.period());
+        assert!(Timeframe::MonthToDate.period().contains("-01/"));
+    }
+
+    #[test]
+    fn test_to_url() {
+        let arm = "https://management.azure.com";
+        assert_eq!(
+            "https://management.azure.com/subscriptions?api-version=2016-06-01",
+            to_url(arm, "/subscriptions?api-version=2016-06-01")
+        );
+        assert_eq!(
+            "https://management.azure.com/subscriptions?$skiptoken=abc",
+            to_url(
+                arm,
+                "\"https://management.azure.com/subscriptions?$skiptoken=abc\""
+            )
+        );
+        assert_eq!(
+            "https://graph.microsoft.com/v1.0/users",
+            to_url(arm, "//graph.microsoft.com/v1.0/users")
+        );
+    }
+
+    #[test]
+    fn test_metric_values() {
+        let json = json!([{
+            "name": { "value": "Transactions" },
+            "timeseries": [{
+                "data": [
+                    { "timeStamp": "2024-01-01T00:00:00Z", "total": 10.0 },
+                    { "timeStamp": "2024-01-01T01:00:00Z" },
+                    { "timeStamp": "2024-01-01T02:00:00Z", "total": 5.0 }
+                ]
+            }]
+        }]);
+        assert_eq!(vec![10.0, 5.0], metric_values(&json, "total"));
+        assert!(metric_values(&json, "average").is_empty());
+    }
+
+    #[test]
+    fn test_filter_subscriptions() {
+        let subscription = Subscription {
+            id: "/subscriptions/12345678-1234-1234-1234-abcdef123456".to_owned(),
+            subscription_id: "12345678-1234-1234-1234-abcdef123456".to_owned(),
+            name: "Production".to_owned(),
+            tenant: None,
+            tags: None,
+            state: None,
+            tenant_id: None,
+            policies: None,
+        };
+
+        let filter = Filter::new(&[])
+            .unwrap()
+            .with_subscriptions(&["production", "Development"]);
+        assert!(filter.matches(&subscription));
+        assert!(filter.subscription_ids().is_none());
+
+        let filter = Filter::new(&[])
+            .unwrap()
+            .with_subscriptions(&["12345678-1234-1234-1234-ABCDEF123456"]);
+        assert!(filter.matches(&subscription));
+        assert_eq!(1, filter.subscription_ids().unwrap().len());
+
+        let filter = Filter::new(&["prod"])
+            .unwrap()
+            .with_subscriptions(&["Development"]);
+        assert!(!filter.matches(&subscription));
+    }
+
+    #[test]
+    fn test_filter_multiple() {
+        let subscription = |name: &str| Subscription {
+            id: "/subscriptions/12345678-1234-1234-1234-abcdef123456".to_owned(),
+            subscription_id: "12345678-1234-1234-1234-abcdef123456".to_owned(),
+            name: name.to_owned(),
+            tenant: None,
+            tags: None,
+            state: None,
+            tenant_id: None,
+            policies: None,
+        };
+
+        let filter = Filter::new(&["prod", "dev"]).unwrap();
+        assert!(filter.matches(&subscription("Production")));
+        assert!(filter.matches(&subscription("Development")));
+        assert!(!filter.matches(&subscription("Test")));
+
+        let filter = Filter::new(&["!sandbox"]).unwrap();
+        assert!(filter.matches(&subscription("Production")));
+        assert!(!filter.matches(&subscription("Sandbox-1")));
+
+        let filter = Filter::new(&["prod", "!sandbox"]).unwrap();
+        assert!(filter.matches(&subscription("Production")));
+        assert!(!filter.matches(&subscription("Production Sandbox")));
+        assert!(!filter.matches(&subscription("Test")));
+    }
+
+    #[test]
+    fn test_filter_tags() {
+        let mut tags = HashMap::new();
+        tags.insert("Environment".to_owned(), "prod".to_owned());
+        tags.insert("team".to_owned(), "platform".to_owned());
+
+        let filter = Filter::new(&[]).unwrap();
+        assert!(filter.matches_tags(None));
+
+        let filter = Filter::new(&[])
+            .unwrap()
+            .with_tags(&["environment=prod", "team"]);
+        assert!(filter.matches_tags(Some(&tags)));
+        assert!(!filter.matches_tags(None));
+
+        let filter = Filter::new(&[]).unwrap().with_tags(&["environment=dev"]);
+        assert!(!filter.matches_tags(Some(&tags)));
+    }
+
+    #[test]
+    fn test_filter_regex() {
+        let subscription = |name: &str| Subscription {
+            id: "/subscriptions/12345678-1234-1234-1234-abcdef123456".to_owned(),
+            subscription_id: "12345678-1234-1234-1234-abcdef123456".to_owned(),
+            name: name.to_owned(),
+            tenant: None,
+            tags: None,
+            state: None,
+            tenant_id: None,
+            policies: None,
+        };
+        let filter = Filter::new(&["re:^prod-(eu|us)", "!re:-us$"]).unwrap();
+        assert!(filter.matches(&subscription("PROD-EU")));
+        assert!(filter.matches(&subscription("prod-eu-sandbox")));
+        assert!(!filter.matches(&subscription("prod-us")));
+        assert!(!filter.matches(&subscription("dev-eu")));
+
+        let filter = Filter::new(&["re:^12345678-"]).unwrap();
+        assert!(filter.matches(&subscription("Production")));
+
+        assert!(Filter::new(&["re:prod-(eu"]).is_err());
+    }
 
     #[test]
     fn test_parse_kubeconfig() {
@@ -601,7 +2270,131 @@ users:
         let cluster = parsed.unwrap();
         assert_eq!("cluster0", cluster.name);
         assert_eq!("http://localhost", cluster.server);
-        assert_eq!("CA", cluster.certificate_authority);
+        assert_eq!(Some("CA".to_owned()), cluster.certificate_authority);
+        assert_eq!(
+            KubernetesAuthentication::AccessToken {
+                client_id: "abc-def".to_owned(),
+                resource: "123-456".to_owned()
+            },
+            cluster.auth
+        );
+    }
+
+    #[test]
+    fn test_parse_dns_records() {
+        let json = json!([
+            {
+                "id": "/dnszones/example.com/A/@",
+                "name": "@",
+                "properties": { "ARecords": [{ "ipv4Address": "10.0.0.1" }] }
+            },
+            {
+                "id": "/dnszones/example.com/CNAME/www",
+                "name": "www",
+                "properties": { "CNAMERecord": { "cname": "example.com" } }
+            },
+            {
+                "id": "/dnszones/example.com/MX/@",
+                "name": "@",
+                "type": "Microsoft.Network/dnszones/MX",
+                "properties": { "MXRecords": [] }
+            },
+            { "name": "invalid" }
+        ]);
+        let mut warnings = vec![];
+        let records = parse_dns_records(&json, "example.com", &mut warnings).unwrap();
+        assert_eq!(2, records.len());
+        assert_eq!("www.example.com", records[1].fqdn);
+        assert_eq!(2, warnings.len());
+        assert_eq!("unsupported DNS record type MX", warnings[0].message);
+        assert_eq!(
+            Some("/dnszones/example.com/MX/@"),
+            warnings[0].id.as_deref()
+        );
+        assert_eq!(None, warnings[1].id);
+    }
+
+    #[test]
+    fn test_merge_kubeconfigs() {
+        let kubeconfig = |name: &str| {
+            format!(
+                r#"current-context: {0}
+contexts:
+- name: {0}
+  context:
+    cluster: {0}
+    namespace: default
+    user: clusterUser_{0}
+clusters:
+- name: {0}
+  cluster:
+    server: https://{0}.example.com
+users:
+- name: clusterUser_{0}
+  user:
+    token: secret-{0}
+"#,
+                name
+            )
+        };
+        let (aks1, aks2) = (kubeconfig("aks1"), kubeconfig("aks2"));
+        let merged = merge_kubeconfigs(&[
+            ("Production/aks1".to_owned(), aks1.as_str()),
+            ("Development/aks2".to_owned(), aks2.as_str()),
+        ])
+        .unwrap();
+
+        let config = &YamlLoader::load_from_str(&merged).unwrap()[0];
+        assert_eq!(Yaml::BadValue, config["current-context"]);
+        let context = &config["contexts"][1];
+        assert_eq!(Some("Development/aks2"), context["name"].as_str());
+        assert_eq!(
+            Some("Development/aks2"),
+            context["context"]["cluster"].as_str()
+        );
+        assert_eq!(
+            Some("Development/aks2"),
+            context["context"]["user"].as_str()
+        );
+        assert_eq!(Some("default"), context["context"]["namespace"].as_str());
+        let user = &config["users"][0];
+        assert_eq!(Some("Production/aks1"), user["name"].as_str());
+        assert_eq!(Some("secret-aks1"), user["user"]["token"].as_str());
+
+        let cluster = KubernetesCluster::parse(&format!(
+            "current-context: Production/aks1\n{}",
+            merged.trim_start_matches("---\n")
+        ))
+        .unwrap();
+        assert_eq!("https://aks1.example.com", cluster.server);
+    }
+
+    #[test]
+    fn test_parse_kubeconfig_exec() {
+        let data = r#"current-context: arc
+contexts:
+- name: arc
+  context:
+    cluster: arc
+    user: user0
+clusters:
+- name: arc
+  cluster:
+    server: https://arc.example.com
+users:
+- name: user0
+  user:
+    exec:
+      command: kubelogin
+      args:
+      - get-token
+      - --server-id
+      - 123-456
+      - --client-id
+      - abc-def
+"#;
+        let cluster = KubernetesCluster::parse(data).unwrap();
+        assert_eq!(None, cluster.certificate_authority);
         assert_eq!(
             KubernetesAuthentication::AccessToken {
                 client_id: "abc-def".to_owned(),
@@ -1,5 +1,8 @@
+use std::fs::write;
+
 use dirs::home_dir;
 use regex::Regex;
+use serde_json::to_string;
 use serde_json::Value;
 use url::Url;
 
@@ -39,7 +42,7 @@ impl Tenant {
     }
   }
 
-  pub fn from_name(name: &str, http: &Http) -> Result<Tenant> {
+  pub fn from_name(name: &str, login_endpoint: &str, http: &Http) -> Result<Tenant> {
     if Self::is_valid_id(name) {
       return Ok(Tenant {
         id: name.to_owned(),
@@ -47,8 +50,8 @@ impl Tenant {
     }
 
     let url = format!(
-      "https://login.microsoftonline.com/{}/.well-known/openid-configuration",
-      name
+      "{}/{}/.well-known/openid-configuration",
+      login_endpoint, name
     );
 
     let json = http.execute(&url, None, None)?.success()?;
@@ -84,17 +87,10 @@ impl Tenant {
   }
 
   pub fn read_default_tenant() -> Result<Option<Tenant>> {
-    if let Some(ref home_dir) = home_dir() {
-      let path = home_dir.join(AZURE_PROFILE_PATH);
-      if let Some(subscriptions) = read_file(&path)?["subscriptions"].as_array() {
-        for subscription in subscriptions {
-          if subscription["isDefault"] == Value::Bool(true) {
-            if let Some(id) = subscription["tenantId"].as_str() {
-              debug!("Read default tenant from {}: {}", path.display(), id);
-              return Ok(Some(Tenant { id: id.to_owned() }));
-            }
-          }
-        }
+    if let Some(subscription) = read_default_subscription()? {
+      if let Some(id) = subscription["tenantId"].as_str() {
+        debug!("Read default tenant from azureProfile.json: {}", id);
+        return Ok(Some(Tenant { id: id.to_owned() }));
       }
     }
 
@@ -110,8 +106,77 @@ impl Tenant {
   }
 }
 
+/// Returns the default subscription entry of the Azure CLI `azureProfile.json`
+pub fn read_default_subscription() -> Result<Option<Value>> {
+  if let Some(ref home_dir) = home_dir() {
+    let path = home_dir.join(AZURE_PROFILE_PATH);
+    if let Some(subscriptions) = read_file(&path)?["subscriptions"].as_array() {
+      for subscription in subscriptions {
+        if subscription["isDefault"] == Value::Bool(true) {
+          return Ok(Some(subscription.clone()));
+        }
+      }
+    }
+  }
+
+  Ok(None)
+}
+
+/// Makes the subscription with the given ID or name the default in `azureProfile.json`,
+/// like `az account set`, and returns its entry
+pub fn write_default_subscription(subscription: &str) -> Result<Value> {
+  let path = match home_dir() {
+    Some(home_dir) => home_dir.join(AZURE_PROFILE_PATH),
+    None => return Err(Box::from("home directory not found")),
+  };
+
+  let mut profile = read_file(&path)?;
+  let entry = set_default_subscription(&mut profile, subscription).ok_or_else(|| {
+    format!(
+      "subscription not found in {}: {}",
+      AZURE_PROFILE_PATH, subscription
+    )
+  })?;
+
+  // The Azure CLI writes the file with a byte order mark
+  write(&path, format!("\u{feff}{}", to_string(&profile)?))?;
+  debug!(
+    "Default subscription written to azureProfile.json: {}",
+    entry["id"]
+  );
+
+  Ok(entry)
+}
+
+fn set_default_subscription(profile: &mut Value, subscription: &str) -> Option<Value> {
+  let subscriptions = profile["subscriptions"].as_array_mut()?;
+
+  let matches = |entry: &Value, key: &str| {
+    entry[key]
+      .as_str()
+      .is_some_and(|value| value.eq_ignore_ascii_case(subscription))
+  };
+  let index = subscriptions
+    .iter()
+    .position(|entry| matches(entry, "id"))
+    .or_else(|| {
+      subscriptions
+        .iter()
+        .position(|entry| matches(entry, "name"))
+    })?;
+
+  for (i, entry) in subscriptions.iter_mut().enumerate() {
+    entry["isDefault"] = Value::Bool(i == index);
+  }
+
+  Some(subscriptions[index].clone())
+}
+
 #[cfg(test)]
 mod tests {
+  use serde_json::json;
+
+  use super::set_default_subscription;
   use super::Tenant;
 
   #[test]
@@ -123,4 +188,22 @@ mod tests {
     assert_eq!(true, Tenant::is_valid_id("common"));
     assert_eq!(false, Tenant::is_valid_id(""));
   }
+
+  #[test]
+  fn test_set_default_subscription() {
+    let mut profile = json!({
+      "subscriptions": [
+        { "id": "1", "name": "Production", "isDefault": true },
+        { "id": "2", "name": "Development", "isDefault": false }
+      ]
+    });
+
+    let entry = set_default_subscription(&mut profile, "development").unwrap();
+    assert_eq!("2", entry["id"]);
+    assert_eq!(false, profile["subscriptions"][0]["isDefault"]);
+    assert_eq!(true, profile["subscriptions"][1]["isDefault"]);
+
+    assert_eq!(None, set_default_subscription(&mut profile, "unknown"));
+    assert_eq!(true, profile["subscriptions"][1]["isDefault"]);
+  }
 }
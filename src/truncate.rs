@@ -0,0 +1,200 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io::BufReader;
+use std::io::Read;
+
+use serde::de::DeserializeSeed;
+use serde::de::Deserializer;
+use serde::de::IgnoredAny;
+use serde::de::MapAccess;
+use serde::de::SeqAccess;
+use serde::de::Visitor;
+use serde_json::Map;
+use serde_json::Number;
+use serde_json::Value;
+
+/// Arrays up to this depth are truncated, the root and the properties of the root like
+/// the `value` of lists, so that the items which are kept are complete
+const MAX_TRUNCATED_DEPTH: usize = 1;
+
+/// A response parsed while streaming, with the items of its top-level arrays left out
+/// once more than the maximum size was read
+#[derive(Debug)]
+pub struct Truncated {
+    pub value: Value,
+    /// Bytes read, when the items were left out
+    pub size: u64,
+    pub kept: usize,
+    pub skipped: usize,
+}
+
+/// Parses the JSON of the reader. Once more than `max_size` bytes are read, the remaining
+/// items of top-level arrays are only counted, without materializing them
+pub fn parse_truncated<R: Read>(reader: R, max_size: Option<u64>) -> serde_json::Result<Truncated> {
+    let state = State {
+        read: Cell::new(0),
+        max_size,
+        kept: Cell::new(0),
+        skipped: Cell::new(0),
+    };
+    let reader = BufReader::new(Counter {
+        inner: reader,
+        read: &state.read,
+    });
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let value = Bounded {
+        state: &state,
+        depth: 0,
+    }
+    .deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(Truncated {
+        value,
+        size: state.read.get(),
+        kept: state.kept.get(),
+        skipped: state.skipped.get(),
+    })
+}
+
+struct State {
+    read: Cell<u64>,
+    max_size: Option<u64>,
+    kept: Cell<usize>,
+    skipped: Cell<usize>,
+}
+
+impl State {
+    fn exceeded(&self) -> bool {
+        matches!(self.max_size, Some(max_size) if self.read.get() > max_size)
+    }
+}
+
+/// Counts the bytes read, ahead of the parser by at most the buffer size
+struct Counter<'s, R> {
+    inner: R,
+    read: &'s Cell<u64>,
+}
+
+impl<R: Read> Read for Counter<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.read.set(self.read.get() + read as u64);
+        Ok(read)
+    }
+}
+
+/// Builds a `Value` like its own deserializer, except for the items of truncated arrays
+struct Bounded<'s> {
+    state: &'s State,
+    depth: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for Bounded<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Bounded<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_owned()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let truncated = self.depth <= MAX_TRUNCATED_DEPTH;
+        let mut items = vec![];
+        loop {
+            if truncated && self.state.exceeded() {
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    self.state.skipped.set(self.state.skipped.get() + 1);
+                }
+                break;
+            }
+            let item = Bounded {
+                state: self.state,
+                depth: self.depth + 1,
+            };
+            match seq.next_element_seed(item)? {
+                Some(item) => items.push(item),
+                None => break,
+            }
+            if truncated {
+                self.state.kept.set(self.state.kept.get() + 1);
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut object = Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let value = map.next_value_seed(Bounded {
+                state: self.state,
+                depth: self.depth + 1,
+            })?;
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::parse_truncated;
+
+    #[test]
+    fn test_parse_truncated() {
+        let items: Vec<_> = (0..1000)
+            .map(|i| json!({ "id": i, "tags": ["a", "b", "c"] }))
+            .collect();
+        let json = json!({ "value": items, "nextLink": "https://next" }).to_string();
+
+        let parsed = parse_truncated(json.as_bytes(), None).unwrap();
+        assert_eq!(json, parsed.value.to_string());
+        assert_eq!(0, parsed.skipped);
+
+        let parsed = parse_truncated(json.as_bytes(), Some(10_000)).unwrap();
+        let kept = parsed.value["value"].as_array().unwrap();
+        assert!(!kept.is_empty() && kept.len() < 1000);
+        assert_eq!(kept.len(), parsed.kept);
+        assert_eq!(1000, parsed.kept + parsed.skipped);
+        assert_eq!(json!(["a", "b", "c"]), kept[kept.len() - 1]["tags"]);
+        assert_eq!("https://next", parsed.value["nextLink"]);
+
+        assert!(parse_truncated("[1, 2".as_bytes(), Some(1)).is_err());
+    }
+}
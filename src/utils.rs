@@ -1,15 +1,32 @@
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::ffi::OsString;
+use std::fs::DirBuilder;
 use std::fs::File;
+use std::fs::OpenOptions;
+#[cfg(unix)]
+use std::fs::Permissions;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Read;
+use std::io::Write;
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
 use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::channel;
+use std::thread::scope;
 
 use serde::de::DeserializeOwned;
 use serde_json::from_reader;
 use serde_json::Value;
 
+use crate::error::AppError;
 use crate::error::AppError::UnexpectedJsonType;
 
 const DAYS: &[u32] = &[31, 0, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
@@ -37,6 +54,33 @@ pub fn days_of_month(year: u32, month: u32) -> Result<u32> {
     }
 }
 
+/// Case-insensitive match of an RBAC style pattern, where `*` matches any sequence
+pub fn matches_wildcard(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let value = value.to_lowercase();
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == value;
+    }
+    let mut rest = &value[..];
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            if !rest.starts_with(part) {
+                return false;
+            }
+            rest = &rest[part.len()..];
+        } else if i == parts.len() - 1 {
+            return rest.ends_with(part);
+        } else {
+            match rest.find(part) {
+                Some(pos) => rest = &rest[pos + part.len()..],
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
 pub fn read_file(path: &Path) -> Result<Value> {
     if path.exists() {
         let file = File::open(&path)?;
@@ -54,6 +98,30 @@ pub fn read_file(path: &Path) -> Result<Value> {
     }
 }
 
+/// Writes a file only readable by the user, as it contains credentials or their responses
+pub fn write_private_file(path: &Path, content: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path)?;
+    // Files of earlier versions keep their mode when opened
+    #[cfg(unix)]
+    file.set_permissions(Permissions::from_mode(0o600))?;
+    file.write_all(content)?;
+    Ok(())
+}
+
+/// Creates the directory and its parents, only accessible by the user
+pub fn create_private_dir(path: &Path) -> Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    builder.mode(0o700);
+    builder.create(path)?;
+    Ok(())
+}
+
 fn skip_bom(mut reader: BufReader<File>) -> Result<BufReader<File>> {
     let buf = reader.fill_buf()?;
     if buf.len() >= 3 && buf[0] == 0xEF && buf[1] == 0xBB && buf[2] == 0xBF {
@@ -97,10 +165,281 @@ impl ValueExt for Value {
     }
 }
 
+type SendError = Box<dyn Error + Send + Sync>;
+
+/// Calls the function for all items, at most `parallelism` at a time,
+/// and returns the results in the order of the items. The first error is returned
+pub fn parallel_map<I, T, F>(items: &[I], parallelism: usize, function: F) -> Result<Vec<T>>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I) -> Result<T> + Sync,
+{
+    parallel_map_ordered(items, parallelism, true, function)
+}
+
+/// Like `parallel_map`, but unless `ordered`, the results are returned in the order
+/// the calls complete
+pub fn parallel_map_ordered<I, T, F>(
+    items: &[I],
+    parallelism: usize,
+    ordered: bool,
+    function: F,
+) -> Result<Vec<T>>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I) -> Result<T> + Sync,
+{
+    let mut results = Vec::with_capacity(items.len());
+    parallel_for_each(items, parallelism, ordered, function, |result| {
+        results.push(result);
+        Ok(())
+    })?;
+    Ok(results)
+}
+
+/// Calls the function for all items, at most `parallelism` at a time, and passes each result
+/// to `emit` on the calling thread while the remaining calls are still running. Unless
+/// `ordered`, the results are passed as the calls complete and the first error to arrive is
+/// returned. Otherwise they are passed in the order of the items, each as soon as all before
+/// it are done, and the error of the first failed item is returned, after the results before
+/// it. Each thread takes the next item as soon as it is done, so no call waits for a slower
+/// one started before it
+pub fn parallel_for_each<I, T, F, E>(
+    items: &[I],
+    parallelism: usize,
+    ordered: bool,
+    function: F,
+    mut emit: E,
+) -> Result<()>
+where
+    I: Sync,
+    T: Send,
+    F: Fn(&I) -> Result<T> + Sync,
+    E: FnMut(T) -> Result<()>,
+{
+    let function = &function;
+    let next = &AtomicUsize::new(0);
+    let (sender, receiver) = channel();
+    scope(|scope| {
+        let handles: Vec<_> = (0..parallelism.max(1).min(items.len()))
+            .map(|_| {
+                let sender = sender.clone();
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let item = match items.get(index) {
+                        Some(item) => item,
+                        None => break,
+                    };
+                    let result = function(item).map_err(to_send_error);
+                    let failed = result.is_err();
+                    if sender.send((index, result)).is_err() || failed {
+                        // No further items are started after an error
+                        next.store(items.len(), Ordering::SeqCst);
+                    }
+                })
+            })
+            .collect();
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut emitted = 0;
+        // In order, the first failed item, all items before it are already started
+        let mut failed = None;
+        let mut result = Ok(());
+        for (index, value) in receiver.iter() {
+            match value {
+                Ok(value) if !ordered => result = emit(value),
+                Ok(value) => {
+                    pending.insert(index, value);
+                }
+                Err(err) if !ordered => result = Err(err as Box<dyn Error>),
+                Err(err) => {
+                    if failed.as_ref().is_none_or(|(first, _)| index < *first) {
+                        failed = Some((index, err));
+                    }
+                }
+            }
+            if ordered {
+                // The results before the first failed item are still passed on, in order
+                let end = failed.as_ref().map_or(items.len(), |(first, _)| *first);
+                while emitted < end && result.is_ok() {
+                    match pending.remove(&emitted) {
+                        Some(value) => {
+                            emitted += 1;
+                            result = emit(value);
+                        }
+                        None => break,
+                    }
+                }
+                if emitted == end && result.is_ok() {
+                    if let Some((_, err)) = failed.take() {
+                        result = Err(err);
+                    }
+                }
+            }
+            if result.is_err() {
+                break;
+            }
+        }
+        // The running calls complete, but their results are dropped
+        next.store(items.len(), Ordering::SeqCst);
+        drop(receiver);
+
+        if handles.into_iter().any(|handle| handle.join().is_err()) {
+            return Err("thread failed".into());
+        }
+        result
+    })
+}
+
+/// Converts the punycode labels of an internationalized domain name, like `xn--bcher-kva.example`,
+/// to Unicode. Names without such labels or which are not valid are returned unchanged.
+pub fn domain_to_unicode(name: &str) -> String {
+    if !name.to_lowercase().contains("xn--") {
+        return name.to_owned();
+    }
+    match idna::domain_to_unicode(name) {
+        (unicode, Ok(())) => unicode,
+        (_, Err(_)) => name.to_owned(),
+    }
+}
+
+/// Converts a domain name with Unicode labels to punycode, or returns it unchanged if not valid
+pub fn domain_to_ascii(name: &str) -> String {
+    if name.is_ascii() {
+        return name.to_owned();
+    }
+    idna::domain_to_ascii(name).unwrap_or_else(|_| name.to_owned())
+}
+
+/// Keeps `AppError`s, so they can still be matched, other errors are only kept as message
+fn to_send_error(err: Box<dyn Error>) -> SendError {
+    match err.downcast::<AppError>() {
+        Ok(err) => err,
+        Err(err) => SendError::from(err.to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::mpsc::channel;
+    use std::sync::mpsc::Receiver;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
     use super::days_of_month;
+    use super::domain_to_ascii;
+    use super::domain_to_unicode;
+    use super::matches_wildcard;
+    use super::parallel_for_each;
+    use super::parallel_map;
+    use super::parallel_map_ordered;
+    use super::Result;
+
+    #[test]
+    fn test_parallel_map() {
+        let items: Vec<u32> = (1..=10).collect();
+        let doubled = parallel_map(&items, 3, |item| Ok(item * 2)).unwrap();
+        assert_eq!(vec![2, 4, 6, 8, 10, 12, 14, 16, 18, 20], doubled);
+
+        let failed: Result<Vec<u32>> = parallel_map(&items, 4, |item| match item {
+            5 => Err(Box::from("five")),
+            item => Ok(*item),
+        });
+        assert_eq!("five", failed.unwrap_err().to_string());
+    }
+
+    /// Runs `parallel_for_each` for `count` items, each of which completes once it is released,
+    /// so they complete in a fixed order without timing: `release[0]` are released up front and
+    /// `release[n]` once the n-th result was passed on. The items in `fail` return an error,
+    /// after releasing `released_by_failure`
+    fn run_released(
+        count: usize,
+        parallelism: usize,
+        ordered: bool,
+        release: &[&[usize]],
+        fail: &[usize],
+        released_by_failure: &[usize],
+    ) -> (Vec<usize>, Result<()>) {
+        let (senders, receivers): (Vec<_>, Vec<_>) = (0..count).map(|_| channel()).unzip();
+        let receivers: Vec<Mutex<Receiver<()>>> = receivers.into_iter().map(Mutex::new).collect();
+        let release_items = |items: &[usize]| {
+            for &item in items {
+                senders[item].send(()).unwrap();
+            }
+        };
+        release_items(release[0]);
+
+        let items: Vec<usize> = (0..count).collect();
+        let mut emitted = vec![];
+        let result = parallel_for_each(
+            &items,
+            parallelism,
+            ordered,
+            |&item| {
+                receivers[item]
+                    .lock()
+                    .unwrap()
+                    .recv_timeout(Duration::from_secs(10))
+                    .map_err(|_| format!("item {} not released", item))?;
+                if fail.contains(&item) {
+                    release_items(released_by_failure);
+                    return Err(format!("item {} failed", item).into());
+                }
+                Ok(item)
+            },
+            |item| {
+                emitted.push(item);
+                if let Some(items) = release.get(emitted.len()) {
+                    release_items(items);
+                }
+                Ok(())
+            },
+        );
+        (emitted, result)
+    }
+
+    #[test]
+    fn test_parallel_for_each_unordered() {
+        let release: &[&[usize]] = &[&[1], &[2], &[0]];
+        let (emitted, result) = run_released(3, 3, false, release, &[], &[]);
+        assert_eq!(vec![1, 2, 0], emitted);
+        assert!(result.is_ok());
+
+        // The second thread takes the third item while the first is still running
+        let (emitted, result) = run_released(3, 2, false, release, &[], &[]);
+        assert_eq!(vec![1, 2, 0], emitted);
+        assert!(result.is_ok());
+    }
 
+    #[test]
+    fn test_parallel_for_each_ordered() {
+        // The first two results are passed on before the third item completes
+        let (emitted, result) = run_released(3, 3, true, &[&[1, 0], &[], &[2]], &[], &[]);
+        assert_eq!(vec![0, 1, 2], emitted);
+        assert!(result.is_ok());
+
+        let items = [3, 1, 2];
+        assert_eq!(
+            vec![3, 1, 2],
+            parallel_map_ordered(&items, 2, true, |item| Ok(*item)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parallel_for_each_error() {
+        // Unless ordered, the results that arrive before the error are passed on
+        let (emitted, result) = run_released(2, 2, false, &[&[1], &[0]], &[0], &[]);
+        assert_eq!(vec![1], emitted);
+        assert_eq!("item 0 failed", result.unwrap_err().to_string());
+
+        // In order, the error of the first failed item, even if a later one fails first
+        let (emitted, result) = run_released(4, 4, true, &[&[0, 2, 3]], &[1, 3], &[1]);
+        assert_eq!(vec![0], emitted);
+        assert_eq!("item 1 failed", result.unwrap_err().to_string());
+    }
     #[test]
     fn test_days_of_month_feb() {
         assert_eq!(28, days_of_month(2003, 2).unwrap());
@@ -111,4 +450,42 @@ mod tests {
     fn test_days_of_month_jun() {
         assert_eq!(30, days_of_month(2002, 6).unwrap());
     }
+
+    #[test]
+    fn test_matches_wildcard() {
+        assert!(matches_wildcard("*", "Microsoft.KeyVault/vaults/read"));
+        assert!(matches_wildcard(
+            "Microsoft.KeyVault/vaults/secrets/*",
+            "microsoft.keyvault/vaults/secrets/getSecret/action"
+        ));
+        assert!(matches_wildcard(
+            "Microsoft.*/read",
+            "Microsoft.Network/read"
+        ));
+        assert!(!matches_wildcard(
+            "Microsoft.*/read",
+            "Microsoft.Network/write"
+        ));
+        assert!(!matches_wildcard(
+            "Microsoft.Network/read",
+            "Microsoft.Network"
+        ));
+    }
+
+    #[test]
+    fn test_domain_to_unicode() {
+        assert_eq!(
+            "bücher.example.",
+            domain_to_unicode("xn--bcher-kva.example.")
+        );
+        assert_eq!("bücher", domain_to_unicode("xn--bcher-kva"));
+        assert_eq!("WWW.example.com", domain_to_unicode("WWW.example.com"));
+        assert_eq!("@", domain_to_unicode("@"));
+    }
+
+    #[test]
+    fn test_domain_to_ascii() {
+        assert_eq!("xn--bcher-kva.example", domain_to_ascii("bücher.example"));
+        assert_eq!("WWW.example.com", domain_to_ascii("WWW.example.com"));
+    }
 }